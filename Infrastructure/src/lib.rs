@@ -5,5 +5,6 @@
 pub mod platform;
 pub mod scheduler;
 pub mod timer;
+pub mod watchdog;
 
 pub use platform::PlatformAbstraction;
@@ -0,0 +1,129 @@
+//! Embedded platform implementation
+//!
+//! Timing is derived from a monotonic tick counter supplied by the board
+//! (e.g. SysTick or a hardware timer) instead of `std::time::Instant`, so the
+//! same code can run on targets without an OS clock. Only `core`/`alloc`
+//! types are used here.
+
+extern crate alloc;
+
+use super::{PlatformAbstraction, PlatformCapabilities, PlatformError};
+use crate::timer::Timer;
+use alloc::sync::Arc;
+use core::time::Duration;
+
+/// Source of monotonic ticks
+///
+/// Implemented for any `Fn() -> u64` closure so tests can inject a mock.
+pub trait TickSource: Send + Sync {
+    fn now_ticks(&self) -> u64;
+}
+
+impl<F> TickSource for F
+where
+    F: Fn() -> u64 + Send + Sync,
+{
+    fn now_ticks(&self) -> u64 {
+        self()
+    }
+}
+
+/// Convert a tick count to a duration at `tick_hz` ticks per second
+fn ticks_to_duration(ticks: u64, tick_hz: u32) -> Duration {
+    let nanos = (ticks as u128 * 1_000_000_000) / tick_hz as u128;
+    Duration::from_nanos(nanos.min(u64::MAX as u128) as u64)
+}
+
+/// Embedded timer driven by a tick source
+pub struct EmbeddedTimer {
+    ticks: Arc<dyn TickSource>,
+    tick_hz: u32,
+    start_tick: Option<u64>,
+}
+
+impl EmbeddedTimer {
+    /// `tick_hz` must be non-zero
+    pub fn new(ticks: Arc<dyn TickSource>, tick_hz: u32) -> Self {
+        EmbeddedTimer {
+            ticks,
+            tick_hz: tick_hz.max(1),
+            start_tick: None,
+        }
+    }
+}
+
+impl Timer for EmbeddedTimer {
+    fn start(&mut self) -> Result<(), PlatformError> {
+        self.start_tick = Some(self.ticks.now_ticks());
+        Ok(())
+    }
+
+    fn elapsed(&self) -> Duration {
+        // wrapping_sub keeps elapsed correct across counter rollover
+        self.start_tick
+            .map(|start| {
+                ticks_to_duration(self.ticks.now_ticks().wrapping_sub(start), self.tick_hz)
+            })
+            .unwrap_or(Duration::ZERO)
+    }
+
+    fn stop(&mut self) -> Result<(), PlatformError> {
+        self.start_tick = None;
+        Ok(())
+    }
+
+    fn is_running(&self) -> bool {
+        self.start_tick.is_some()
+    }
+}
+
+/// Embedded platform implementation
+pub struct EmbeddedPlatform {
+    ticks: Arc<dyn TickSource>,
+    tick_hz: u32,
+}
+
+impl EmbeddedPlatform {
+    pub fn new(ticks: Arc<dyn TickSource>, tick_hz: u32) -> Self {
+        EmbeddedPlatform { ticks, tick_hz }
+    }
+
+    pub fn tick_hz(&self) -> u32 {
+        self.tick_hz
+    }
+
+    /// Timer sharing this platform's tick source
+    pub fn timer(&self) -> EmbeddedTimer {
+        EmbeddedTimer::new(Arc::clone(&self.ticks), self.tick_hz)
+    }
+}
+
+impl PlatformAbstraction for EmbeddedPlatform {
+    fn platform_name(&self) -> &'static str {
+        "Embedded"
+    }
+
+    fn start(&mut self) -> Result<(), PlatformError> {
+        if self.tick_hz == 0 {
+            return Err(PlatformError::InitializationFailed(
+                "tick frequency must be non-zero".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), PlatformError> {
+        Ok(())
+    }
+
+    /// Single core with cooperative scheduling and no async runtime; timer
+    /// resolution follows the tick rate
+    fn capabilities(&self) -> PlatformCapabilities {
+        PlatformCapabilities {
+            high_res_timer: self.tick_hz >= 1_000_000,
+            preemptive_scheduling: false,
+            num_cpus: 1,
+            supports_async: false,
+        }
+    }
+}
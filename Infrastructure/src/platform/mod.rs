@@ -2,13 +2,45 @@
 //!
 //! Defines the core traits that all platform implementations must provide.
 
+use crate::timer::{DesktopTimer, Timer};
 use std::time::Duration;
 
+#[cfg(feature = "embedded")]
+pub mod embedded;
+
+#[cfg(feature = "embedded")]
+pub use embedded::{EmbeddedPlatform, EmbeddedTimer, TickSource};
+
 /// Platform abstraction trait
 pub trait PlatformAbstraction: Send + Sync {
     fn platform_name(&self) -> &'static str;
     fn start(&mut self) -> Result<(), PlatformError>;
     fn stop(&mut self) -> Result<(), PlatformError>;
+    /// What the platform supports, for choosing timer and scheduler
+    /// implementations at runtime
+    ///
+    /// Defaults to the least a platform can offer: one CPU, cooperative
+    /// scheduling, a coarse timer and no async runtime.
+    fn capabilities(&self) -> PlatformCapabilities {
+        PlatformCapabilities {
+            high_res_timer: false,
+            preemptive_scheduling: false,
+            num_cpus: 1,
+            supports_async: false,
+        }
+    }
+}
+
+/// Features and resources reported by a platform
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlatformCapabilities {
+    /// Timer resolution of a microsecond or better
+    pub high_res_timer: bool,
+    /// Tasks can be preempted, so a long task does not stall the others
+    pub preemptive_scheduling: bool,
+    pub num_cpus: usize,
+    /// An async runtime is available
+    pub supports_async: bool,
 }
 
 /// Platform error type
@@ -45,8 +77,161 @@ pub trait SchedulerBackend: Send + Sync {
     fn current_task_id(&self) -> u32;
 }
 
+/// Desktop timer backend built on `DesktopTimer`
+pub struct DesktopTimerBackend {
+    timer: DesktopTimer,
+    duration: Duration,
+}
+
+impl DesktopTimerBackend {
+    pub fn new() -> Self {
+        DesktopTimerBackend {
+            timer: DesktopTimer::new(),
+            duration: Duration::ZERO,
+        }
+    }
+
+    /// Duration requested by the last call to `start`
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+impl Default for DesktopTimerBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimerBackend for DesktopTimerBackend {
+    fn start(&mut self, duration: Duration) -> Result<(), PlatformError> {
+        self.duration = duration;
+        self.timer.start()
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.timer.elapsed()
+    }
+
+    fn stop(&mut self) -> Result<(), PlatformError> {
+        self.timer.stop()
+    }
+}
+
+/// Desktop scheduler backend tracking the currently scheduled task
+pub struct DesktopSchedulerBackend {
+    current_task_id: u32,
+}
+
+impl DesktopSchedulerBackend {
+    pub fn new() -> Self {
+        DesktopSchedulerBackend { current_task_id: 0 }
+    }
+}
+
+impl Default for DesktopSchedulerBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SchedulerBackend for DesktopSchedulerBackend {
+    fn schedule_task(&mut self, task_id: u32) -> Result<(), PlatformError> {
+        self.current_task_id = task_id;
+        Ok(())
+    }
+
+    fn yield_cpu(&self) {
+        std::thread::yield_now();
+    }
+
+    fn current_task_id(&self) -> u32 {
+        self.current_task_id
+    }
+}
+
+/// Scheduler backend for code running on a tokio runtime
+///
+/// `yield_now` hands control back to the runtime so other tasks on the same
+/// worker can make progress. The blocking `yield_cpu` cannot await and
+/// falls back to yielding the OS thread.
+#[cfg(feature = "async")]
+#[derive(Debug, Default)]
+pub struct AsyncSchedulerBackend {
+    current_task_id: u32,
+}
+
+#[cfg(feature = "async")]
+impl AsyncSchedulerBackend {
+    pub fn new() -> Self {
+        AsyncSchedulerBackend { current_task_id: 0 }
+    }
+
+    /// Yield to the tokio scheduler
+    pub async fn yield_now(&self) {
+        tokio::task::yield_now().await;
+    }
+}
+
+#[cfg(feature = "async")]
+impl SchedulerBackend for AsyncSchedulerBackend {
+    fn schedule_task(&mut self, task_id: u32) -> Result<(), PlatformError> {
+        self.current_task_id = task_id;
+        Ok(())
+    }
+
+    fn yield_cpu(&self) {
+        std::thread::yield_now();
+    }
+
+    fn current_task_id(&self) -> u32 {
+        self.current_task_id
+    }
+}
+
+/// Lifecycle state of a platform
+///
+/// `start` and `stop` complete synchronously, so there are no transitional
+/// states to observe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlatformState {
+    #[default]
+    Stopped,
+    Running,
+}
+
 /// Default desktop platform implementation
-pub struct DesktopPlatform;
+#[derive(Debug, Default)]
+pub struct DesktopPlatform {
+    state: PlatformState,
+}
+
+impl DesktopPlatform {
+    pub fn new() -> Self {
+        DesktopPlatform {
+            state: PlatformState::Stopped,
+        }
+    }
+
+    /// Current lifecycle state
+    pub fn state(&self) -> PlatformState {
+        self.state
+    }
+
+    /// Timer backend for this platform
+    pub fn timer_backend(&self) -> DesktopTimerBackend {
+        DesktopTimerBackend::new()
+    }
+
+    /// Scheduler backend for this platform
+    pub fn scheduler_backend(&self) -> DesktopSchedulerBackend {
+        DesktopSchedulerBackend::new()
+    }
+}
+
+fn invalid_transition(action: &str, state: PlatformState) -> PlatformError {
+    PlatformError::OperationFailed(format!("cannot {} platform while {:?}", action, state))
+}
 
 impl PlatformAbstraction for DesktopPlatform {
     fn platform_name(&self) -> &'static str {
@@ -54,10 +239,29 @@ impl PlatformAbstraction for DesktopPlatform {
     }
 
     fn start(&mut self) -> Result<(), PlatformError> {
+        if self.state != PlatformState::Stopped {
+            return Err(invalid_transition("start", self.state));
+        }
+        // Nothing to initialise yet; backends are created on demand
+        self.state = PlatformState::Running;
         Ok(())
     }
 
     fn stop(&mut self) -> Result<(), PlatformError> {
+        if self.state != PlatformState::Running {
+            return Err(invalid_transition("stop", self.state));
+        }
+        self.state = PlatformState::Stopped;
         Ok(())
     }
+
+    /// OS threads on a monotonic `Instant` clock, with tokio available
+    fn capabilities(&self) -> PlatformCapabilities {
+        PlatformCapabilities {
+            high_res_timer: true,
+            preemptive_scheduling: true,
+            num_cpus: num_cpus::get(),
+            supports_async: true,
+        }
+    }
 }
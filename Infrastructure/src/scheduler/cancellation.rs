@@ -0,0 +1,31 @@
+//! Cancellation tokens
+//!
+//! Let another thread (e.g. a signal handler) stop a blocking scheduler loop
+//! such as `DefaultScheduler::run_for`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared flag that asks a running loop to stop
+///
+/// Clones share the flag, so one clone can be handed to the loop and another
+/// kept to cancel it. Cancellation cannot be undone.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask every loop holding a clone of this token to stop
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+}
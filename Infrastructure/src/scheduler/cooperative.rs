@@ -0,0 +1,101 @@
+//! Cooperative scheduler
+//!
+//! Wraps `DefaultScheduler` so long task tables give other threads (or other
+//! async tasks) a chance to run part-way through a pass.
+
+use super::{DefaultScheduler, Scheduler, Task};
+use crate::platform::{PlatformError, SchedulerBackend};
+
+#[cfg(feature = "async")]
+use crate::platform::AsyncSchedulerBackend;
+
+/// `DefaultScheduler` that yields through a `SchedulerBackend` between tasks
+///
+/// After every `yield_every` executed handlers, `run` calls `yield_cpu`
+/// before starting the next one. No yield follows the last task of a pass.
+pub struct CooperativeScheduler<B: SchedulerBackend> {
+    inner: DefaultScheduler,
+    backend: B,
+    yield_every: usize,
+}
+
+impl<B: SchedulerBackend> CooperativeScheduler<B> {
+    /// Yield after every task
+    pub fn new(inner: DefaultScheduler, backend: B) -> Self {
+        CooperativeScheduler {
+            inner,
+            backend,
+            yield_every: 1,
+        }
+    }
+
+    /// Yield after every `n` executed tasks (at least one)
+    pub fn with_yield_every(mut self, n: usize) -> Self {
+        self.yield_every = n.max(1);
+        self
+    }
+
+    pub fn yield_every(&self) -> usize {
+        self.yield_every
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn inner(&self) -> &DefaultScheduler {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut DefaultScheduler {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> DefaultScheduler {
+        self.inner
+    }
+}
+
+#[cfg(feature = "async")]
+impl CooperativeScheduler<AsyncSchedulerBackend> {
+    /// Run one pass, awaiting `tokio::task::yield_now` between tasks
+    pub async fn run_async(&mut self) -> Result<(), PlatformError> {
+        let mut since_yield = 0;
+        for index in self.inner.execution_order() {
+            if since_yield >= self.yield_every && self.inner.will_run(index) {
+                self.backend.yield_now().await;
+                since_yield = 0;
+            }
+            if self.inner.run_entry(index) {
+                since_yield += 1;
+            }
+        }
+        self.inner.end_pass();
+        Ok(())
+    }
+}
+
+impl<B: SchedulerBackend> Scheduler for CooperativeScheduler<B> {
+    fn add_task(&mut self, task: Task) -> Result<(), PlatformError> {
+        self.inner.add_task(task)
+    }
+
+    fn remove_task(&mut self, task_id: u32) -> Result<(), PlatformError> {
+        self.inner.remove_task(task_id)
+    }
+
+    fn run(&mut self) -> Result<(), PlatformError> {
+        let mut since_yield = 0;
+        for index in self.inner.execution_order() {
+            if since_yield >= self.yield_every && self.inner.will_run(index) {
+                self.backend.yield_cpu();
+                since_yield = 0;
+            }
+            if self.inner.run_entry(index) {
+                since_yield += 1;
+            }
+        }
+        self.inner.end_pass();
+        Ok(())
+    }
+}
@@ -3,13 +3,115 @@
 //! Provides scheduling primitives for different platforms.
 
 use crate::platform::PlatformError;
+use crate::timer::{DesktopTimer, Timer};
+use serde::Serialize;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+mod cancellation;
+mod cooperative;
+mod thread_pool;
+
+pub use cancellation::CancellationToken;
+pub use cooperative::CooperativeScheduler;
+pub use thread_pool::ThreadPoolScheduler;
+
+/// Longest `DefaultScheduler::run_for` sleeps before checking its token
+pub const CANCEL_POLL: Duration = Duration::from_millis(10);
+
+/// Whether a task keeps running or runs only once
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum TaskKind {
+    /// Runs on every pass (subject to its period)
+    #[default]
+    Periodic,
+    /// Runs once, e.g. for initialisation, then is removed from the table
+    OneShot,
+}
 
 /// Task definition
 #[derive(Debug, Clone, Copy)]
 pub struct Task {
     pub id: u32,
+    /// Higher values run first under `SchedulingPolicy::Priority`
     pub priority: u8,
+    /// Minimum time between runs; 0 runs on every pass
     pub period_ms: u32,
+    /// Deadline after each release, used by
+    /// `SchedulingPolicy::EarliestDeadlineFirst`
+    pub deadline_ms: Option<u32>,
+    pub kind: TaskKind,
+}
+
+impl Task {
+    pub fn new(id: u32, priority: u8, period_ms: u32) -> Self {
+        Task {
+            id,
+            priority,
+            period_ms,
+            deadline_ms: None,
+            kind: TaskKind::Periodic,
+        }
+    }
+
+    /// Task that runs once on the next pass and is then removed
+    pub fn one_shot(id: u32, priority: u8) -> Self {
+        Task::new(id, priority, 0).with_kind(TaskKind::OneShot)
+    }
+
+    /// Set the task's relative deadline
+    pub fn with_deadline(mut self, deadline_ms: u32) -> Self {
+        self.deadline_ms = Some(deadline_ms);
+        self
+    }
+
+    pub fn with_kind(mut self, kind: TaskKind) -> Self {
+        self.kind = kind;
+        self
+    }
+}
+
+/// Work executed when a task runs
+pub type TaskHandler = Box<dyn FnMut() + Send>;
+
+/// Called with the task id and the time by which a run exceeded `period_ms`
+pub type OverrunHandler = Box<dyn FnMut(u32, Duration) + Send>;
+
+/// Order in which `DefaultScheduler::run` executes ready tasks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum SchedulingPolicy {
+    /// Highest `priority` first
+    #[default]
+    Priority,
+    /// Nearest absolute deadline first; tasks without a deadline run last
+    ///
+    /// A task is released when it is registered and again each time its
+    /// period elapses after a run; its absolute deadline is the latest
+    /// release plus `deadline_ms`.
+    EarliestDeadlineFirst,
+    /// Shortest `period_ms` first
+    RateMonotonic,
+}
+
+/// Priority aging
+///
+/// Under `SchedulingPolicy::Priority` the boosted priority decides the
+/// order; under the other policies it only breaks ties.
+///
+/// A task passed over for more than `threshold` consecutive passes gains
+/// `step` priority for every further pass it waits, so a steady stream of
+/// higher-priority work cannot starve it. The boost ends once the task runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Aging {
+    /// Passes a task may be passed over before it starts gaining priority
+    pub threshold: u32,
+    /// Priority gained per pass waited beyond `threshold`
+    pub step: u8,
+}
+
+impl Aging {
+    pub fn new(threshold: u32, step: u8) -> Self {
+        Aging { threshold, step }
+    }
 }
 
 /// Scheduler trait
@@ -19,15 +121,558 @@ pub trait Scheduler {
     fn run(&mut self) -> Result<(), PlatformError>;
 }
 
+/// Registered task and its work
+struct TaskEntry {
+    task: Task,
+    handler: Option<TaskHandler>,
+    enabled: bool,
+    run_count: u64,
+    overrun_count: u64,
+    last_run: Option<SystemTime>,
+    /// Monotonic start of the last run; `period_ms` is measured from it
+    last_started: Option<Instant>,
+    /// When the task was registered, which is its first release
+    added: Instant,
+    /// First pass the task has been waiting for; used for aging
+    waiting_since: u64,
+    /// Tasks that run before this one in every pass
+    depends_on: Vec<u32>,
+}
+
+impl TaskEntry {
+    fn new(task: Task, handler: Option<TaskHandler>, pass: u64) -> Self {
+        TaskEntry {
+            task,
+            handler,
+            enabled: true,
+            run_count: 0,
+            overrun_count: 0,
+            last_run: None,
+            last_started: None,
+            added: Instant::now(),
+            waiting_since: pass,
+            depends_on: Vec::new(),
+        }
+    }
+
+    /// Whether `period_ms` has elapsed since the last run started
+    ///
+    /// Tasks with a zero period, and tasks that have not run yet, are
+    /// always due.
+    fn is_due(&self, now: Instant) -> bool {
+        match self.last_started {
+            Some(started) if self.task.period_ms > 0 => now >= self.next_due(started),
+            _ => true,
+        }
+    }
+
+    fn next_due(&self, started: Instant) -> Instant {
+        started + Duration::from_millis(u64::from(self.task.period_ms))
+    }
+
+    /// Latest release: registration, then one period after each run
+    fn released(&self) -> Instant {
+        self.last_started
+            .map_or(self.added, |started| self.next_due(started))
+    }
+
+    fn snapshot(&self) -> TaskSnapshot {
+        TaskSnapshot {
+            id: self.task.id,
+            priority: self.task.priority,
+            period_ms: self.task.period_ms,
+            deadline_ms: self.task.deadline_ms,
+            kind: self.task.kind,
+            enabled: self.enabled,
+            run_count: self.run_count,
+            overrun_count: self.overrun_count,
+            last_run_ms: self
+                .last_run
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64),
+        }
+    }
+}
+
+/// Point-in-time view of one registered task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TaskSnapshot {
+    pub id: u32,
+    pub priority: u8,
+    pub period_ms: u32,
+    pub deadline_ms: Option<u32>,
+    pub kind: TaskKind,
+    pub enabled: bool,
+    /// Times the task's handler has been executed
+    pub run_count: u64,
+    /// Runs that took longer than `period_ms`
+    pub overrun_count: u64,
+    /// Wall-clock time of the last execution, in ms since the Unix epoch
+    pub last_run_ms: Option<u64>,
+}
+
+/// Point-in-time view of a scheduler's task table
+///
+/// Tasks are listed in the order the current policy would run them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SchedulerSnapshot {
+    pub policy: SchedulingPolicy,
+    pub tasks: Vec<TaskSnapshot>,
+}
+
 /// Default scheduler implementation
 pub struct DefaultScheduler {
-    tasks: Vec<Task>,
+    tasks: Vec<TaskEntry>,
+    policy: SchedulingPolicy,
+    on_overrun: Option<OverrunHandler>,
+    aging: Option<Aging>,
+    run_budget: Option<usize>,
+    /// Passes completed so far
+    passes: u64,
+    /// Handlers executed in the current pass
+    ran_this_pass: usize,
+    /// Origin for absolute deadlines, which are compared in whole
+    /// milliseconds like `deadline_ms`
+    epoch: Instant,
 }
 
 impl DefaultScheduler {
     pub fn new() -> Self {
-        DefaultScheduler { tasks: Vec::new() }
+        Self::with_policy(SchedulingPolicy::default())
+    }
+
+    pub fn with_policy(policy: SchedulingPolicy) -> Self {
+        DefaultScheduler {
+            tasks: Vec::new(),
+            policy,
+            on_overrun: None,
+            aging: None,
+            run_budget: None,
+            passes: 0,
+            ran_this_pass: 0,
+            epoch: Instant::now(),
+        }
+    }
+
+    pub fn policy(&self) -> SchedulingPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: SchedulingPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn aging(&self) -> Option<Aging> {
+        self.aging
+    }
+
+    /// Enable or disable priority aging (off by default)
+    ///
+    /// See `Aging` for how it combines with each policy.
+    pub fn set_aging(&mut self, aging: Option<Aging>) {
+        self.aging = aging;
+    }
+
+    pub fn run_budget(&self) -> Option<usize> {
+        self.run_budget
+    }
+
+    /// Execute at most `budget` handlers per `run` (unlimited by default)
+    ///
+    /// Tasks beyond the budget wait for a later pass, in policy order.
+    pub fn set_run_budget(&mut self, budget: Option<usize>) {
+        self.run_budget = budget;
+    }
+
+    /// Call `handler` whenever a task's run takes longer than its period
+    ///
+    /// Tasks with a zero period are never reported.
+    pub fn on_overrun<F>(&mut self, handler: F)
+    where
+        F: FnMut(u32, Duration) + Send + 'static,
+    {
+        self.on_overrun = Some(Box::new(handler));
+    }
+
+    /// Register a task together with the work it performs
+    pub fn add_task_with_handler<F>(&mut self, task: Task, handler: F) -> Result<(), PlatformError>
+    where
+        F: FnMut() + Send + 'static,
+    {
+        self.tasks
+            .push(TaskEntry::new(task, Some(Box::new(handler)), self.passes));
+        Ok(())
+    }
+
+    /// Run passes until `duration` has elapsed or `token` is cancelled
+    ///
+    /// Between passes the thread sleeps until the next task is due, waking
+    /// at least every `CANCEL_POLL` to check the token. The token is also
+    /// checked before every task, so cancellation takes effect once the
+    /// running handler returns; the call then returns `Ok(())`. A duration
+    /// too large to represent (e.g. `Duration::MAX`) runs until cancelled.
+    pub fn run_for(
+        &mut self,
+        duration: Duration,
+        token: &CancellationToken,
+    ) -> Result<(), PlatformError> {
+        let deadline = Instant::now().checked_add(duration);
+        let expired = |now: Instant| deadline.is_some_and(|deadline| now >= deadline);
+        while !token.is_cancelled() && !expired(Instant::now()) {
+            for index in self.execution_order() {
+                if token.is_cancelled() {
+                    break;
+                }
+                self.run_entry(index);
+            }
+            self.end_pass();
+            self.wait_until_due(deadline, token);
+        }
+        Ok(())
+    }
+
+    /// Sleep until a task is due, `deadline` passes or `token` is cancelled
+    fn wait_until_due(&self, deadline: Option<Instant>, token: &CancellationToken) {
+        while !token.is_cancelled() {
+            let now = Instant::now();
+            let wake = match (self.next_due(now), deadline) {
+                (Some(due), Some(deadline)) => Some(due.min(deadline)),
+                (due, deadline) => due.or(deadline),
+            };
+            let remaining = wake.map_or(CANCEL_POLL, |wake| wake.saturating_duration_since(now));
+            if remaining.is_zero() {
+                std::thread::yield_now();
+                return;
+            }
+            std::thread::sleep(remaining.min(CANCEL_POLL));
+        }
+    }
+
+    /// Earliest time any runnable task is due, or `None` if none can run
+    fn next_due(&self, now: Instant) -> Option<Instant> {
+        self.tasks
+            .iter()
+            .filter(|e| e.enabled && e.handler.is_some())
+            .map(|e| match e.last_started {
+                Some(started) if e.task.period_ms > 0 => e.next_due(started).max(now),
+                _ => now,
+            })
+            .min()
+    }
+
+    /// Run `task_id` after `dependency` in every pass
+    ///
+    /// Under `SchedulingPolicy::Priority` the dependency also runs with at
+    /// least the priority of its highest-priority dependent, so a
+    /// low-priority dependency cannot hold back urgent work; under the other
+    /// policies that priority only breaks ties. Ordering only:
+    /// a dependent still runs if its dependency is disabled or out of
+    /// budget. Fails with `OperationFailed` if the dependency would close a
+    /// cycle.
+    pub fn add_dependency(&mut self, task_id: u32, dependency: u32) -> Result<(), PlatformError> {
+        if self.entry(dependency).is_none() {
+            return Err(unknown_task(dependency));
+        }
+        if dependency == task_id || self.depends_on(dependency, task_id) {
+            return Err(PlatformError::OperationFailed(format!(
+                "task {} depending on task {} would create a cycle",
+                task_id, dependency
+            )));
+        }
+        let entry = self.entry_mut(task_id)?;
+        if !entry.depends_on.contains(&dependency) {
+            entry.depends_on.push(dependency);
+        }
+        Ok(())
+    }
+
+    pub fn remove_dependency(
+        &mut self,
+        task_id: u32,
+        dependency: u32,
+    ) -> Result<(), PlatformError> {
+        self.entry_mut(task_id)?
+            .depends_on
+            .retain(|&id| id != dependency);
+        Ok(())
+    }
+
+    /// Tasks `task_id` runs after, or `None` if it is not registered
+    pub fn dependencies(&self, task_id: u32) -> Option<&[u32]> {
+        self.entry(task_id).map(|e| e.depends_on.as_slice())
+    }
+
+    /// Resume a task previously paused with `disable_task`
+    pub fn enable_task(&mut self, task_id: u32) -> Result<(), PlatformError> {
+        self.set_enabled(task_id, true)
     }
+
+    /// Pause a task without removing it; `run` skips it until re-enabled
+    pub fn disable_task(&mut self, task_id: u32) -> Result<(), PlatformError> {
+        self.set_enabled(task_id, false)
+    }
+
+    /// Whether a task is enabled, or `None` if it is not registered
+    pub fn is_enabled(&self, task_id: u32) -> Option<bool> {
+        self.entry(task_id).map(|e| e.enabled)
+    }
+
+    /// Copy of the registered tasks in registration order
+    pub fn tasks(&self) -> Vec<Task> {
+        self.tasks.iter().map(|e| e.task).collect()
+    }
+
+    /// Number of passes completed
+    pub fn passes(&self) -> u64 {
+        self.passes
+    }
+
+    /// Task table with run statistics, in execution order
+    pub fn snapshot(&self) -> SchedulerSnapshot {
+        SchedulerSnapshot {
+            policy: self.policy,
+            tasks: self
+                .execution_order()
+                .into_iter()
+                .map(|i| self.tasks[i].snapshot())
+                .collect(),
+        }
+    }
+
+    /// Modify a registered task in place, keeping its handler and run statistics
+    ///
+    /// The change applies from the next `run`.
+    pub fn update_task(
+        &mut self,
+        task_id: u32,
+        f: impl FnOnce(&mut Task),
+    ) -> Result<(), PlatformError> {
+        f(&mut self.entry_mut(task_id)?.task);
+        Ok(())
+    }
+
+    /// Change a task's priority
+    pub fn set_priority(&mut self, task_id: u32, priority: u8) -> Result<(), PlatformError> {
+        self.update_task(task_id, |task| task.priority = priority)
+    }
+
+    /// Change a task's period
+    ///
+    /// Takes effect from the task's last run, so shortening the period can
+    /// make it due immediately.
+    pub fn set_period(&mut self, task_id: u32, period_ms: u32) -> Result<(), PlatformError> {
+        self.update_task(task_id, |task| task.period_ms = period_ms)
+    }
+
+    fn set_enabled(&mut self, task_id: u32, enabled: bool) -> Result<(), PlatformError> {
+        self.entry_mut(task_id)?.enabled = enabled;
+        Ok(())
+    }
+
+    fn entry_mut(&mut self, task_id: u32) -> Result<&mut TaskEntry, PlatformError> {
+        self.tasks
+            .iter_mut()
+            .find(|e| e.task.id == task_id)
+            .ok_or_else(|| unknown_task(task_id))
+    }
+
+    /// Whether `run_entry(index)` would execute a handler
+    fn will_run(&self, index: usize) -> bool {
+        let entry = &self.tasks[index];
+        entry.enabled
+            && entry.handler.is_some()
+            && entry.is_due(Instant::now())
+            && !self.budget_spent()
+    }
+
+    fn budget_spent(&self) -> bool {
+        self.run_budget
+            .is_some_and(|budget| self.ran_this_pass >= budget)
+    }
+
+    /// Execute the task at `index` if it is enabled, has a handler, is due
+    /// and the pass's run budget allows it
+    ///
+    /// Returns whether the handler ran.
+    fn run_entry(&mut self, index: usize) -> bool {
+        if self.budget_spent() {
+            return false;
+        }
+        let started = Instant::now();
+        let entry = &mut self.tasks[index];
+        if !entry.enabled || !entry.is_due(started) {
+            return false;
+        }
+        let Some(handler) = entry.handler.as_mut() else {
+            return false;
+        };
+        let mut timer = DesktopTimer::new();
+        // Starting a desktop timer cannot fail
+        let _ = timer.start();
+        handler();
+        let elapsed = timer.elapsed();
+        entry.run_count += 1;
+        entry.last_run = Some(SystemTime::now());
+        entry.last_started = Some(started);
+        entry.waiting_since = self.passes + 1;
+        self.ran_this_pass += 1;
+
+        let period = Duration::from_millis(u64::from(entry.task.period_ms));
+        if entry.task.period_ms > 0 && elapsed > period {
+            entry.overrun_count += 1;
+            if let Some(on_overrun) = self.on_overrun.as_mut() {
+                on_overrun(entry.task.id, elapsed - period);
+            }
+        }
+        true
+    }
+
+    /// Finish a pass: drop one-shot tasks that have run and advance the
+    /// pass counter used for aging
+    ///
+    /// Tasks still waiting for their period are not passed over, so they
+    /// do not age.
+    fn end_pass(&mut self) {
+        self.tasks
+            .retain(|e| e.task.kind != TaskKind::OneShot || e.run_count == 0);
+        let now = Instant::now();
+        for entry in self.tasks.iter_mut().filter(|e| !e.is_due(now)) {
+            entry.waiting_since = self.passes + 1;
+        }
+        self.passes += 1;
+        self.ran_this_pass = 0;
+    }
+
+    /// Priority including any aging boost
+    fn effective_priority(&self, entry: &TaskEntry) -> u8 {
+        let Some(aging) = self.aging else {
+            return entry.task.priority;
+        };
+        let waited = self.passes.saturating_sub(entry.waiting_since);
+        let boost = waited
+            .saturating_sub(u64::from(aging.threshold))
+            .saturating_mul(u64::from(aging.step))
+            .min(u64::from(u8::MAX)) as u8;
+        entry.task.priority.saturating_add(boost)
+    }
+
+    fn entry(&self, task_id: u32) -> Option<&TaskEntry> {
+        self.tasks.iter().find(|e| e.task.id == task_id)
+    }
+
+    fn index_of(&self, task_id: u32) -> Option<usize> {
+        self.tasks.iter().position(|e| e.task.id == task_id)
+    }
+
+    /// Whether `task_id` depends on `dependency`, directly or transitively
+    fn depends_on(&self, task_id: u32, dependency: u32) -> bool {
+        let mut stack = vec![task_id];
+        let mut seen = Vec::new();
+        while let Some(id) = stack.pop() {
+            if seen.contains(&id) {
+                continue;
+            }
+            seen.push(id);
+            if let Some(entry) = self.entry(id) {
+                if entry.depends_on.contains(&dependency) {
+                    return true;
+                }
+                stack.extend(&entry.depends_on);
+            }
+        }
+        false
+    }
+
+    /// Effective priority of every task, raised to that of its
+    /// highest-priority dependent
+    fn inherited_priorities(&self) -> Vec<u8> {
+        let mut priorities: Vec<u8> = self
+            .tasks
+            .iter()
+            .map(|e| self.effective_priority(e))
+            .collect();
+        // Dependencies are acyclic, so this settles within one sweep per level
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (i, entry) in self.tasks.iter().enumerate() {
+                for dep in entry.depends_on.iter().filter_map(|&id| self.index_of(id)) {
+                    if priorities[dep] < priorities[i] {
+                        priorities[dep] = priorities[i];
+                        changed = true;
+                    }
+                }
+            }
+        }
+        priorities
+    }
+
+    /// Reorder `pending` so every task follows its dependencies, otherwise
+    /// keeping its order
+    fn respect_dependencies(&self, mut pending: Vec<usize>) -> Vec<usize> {
+        if self.tasks.iter().all(|e| e.depends_on.is_empty()) {
+            return pending;
+        }
+        let mut placed = vec![false; self.tasks.len()];
+        let mut order = Vec::with_capacity(pending.len());
+        while !pending.is_empty() {
+            // Dependencies are acyclic, so some pending task is always ready
+            let next = pending
+                .iter()
+                .position(|&i| {
+                    self.tasks[i]
+                        .depends_on
+                        .iter()
+                        .all(|&id| self.index_of(id).map_or(true, |dep| placed[dep]))
+                })
+                .unwrap_or(0);
+            let index = pending.remove(next);
+            placed[index] = true;
+            order.push(index);
+        }
+        order
+    }
+
+    /// Indices into `tasks` in the order the current policy runs them
+    ///
+    /// Ties go to the higher inherited priority, then registration order;
+    /// dependencies always come first
+    fn execution_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.tasks.len()).collect();
+        let priorities = self.inherited_priorities();
+        match self.policy {
+            SchedulingPolicy::Priority => {
+                order.sort_by_key(|&i| std::cmp::Reverse(priorities[i]));
+            }
+            SchedulingPolicy::EarliestDeadlineFirst => {
+                order.sort_by_key(|&i| {
+                    let entry = &self.tasks[i];
+                    let deadline = entry.task.deadline_ms.map(|deadline_ms| {
+                        let released = entry.released().saturating_duration_since(self.epoch);
+                        released.as_millis() + u128::from(deadline_ms)
+                    });
+                    (
+                        deadline.is_none(),
+                        deadline,
+                        std::cmp::Reverse(priorities[i]),
+                    )
+                });
+            }
+            SchedulingPolicy::RateMonotonic => {
+                order.sort_by_key(|&i| {
+                    (
+                        self.tasks[i].task.period_ms,
+                        std::cmp::Reverse(priorities[i]),
+                    )
+                });
+            }
+        }
+        self.respect_dependencies(order)
+    }
+}
+
+fn unknown_task(task_id: u32) -> PlatformError {
+    PlatformError::NotSupported(format!("unknown task id {}", task_id))
 }
 
 impl Default for DefaultScheduler {
@@ -38,17 +683,23 @@ impl Default for DefaultScheduler {
 
 impl Scheduler for DefaultScheduler {
     fn add_task(&mut self, task: Task) -> Result<(), PlatformError> {
-        self.tasks.push(task);
+        self.tasks.push(TaskEntry::new(task, None, self.passes));
         Ok(())
     }
 
     fn remove_task(&mut self, task_id: u32) -> Result<(), PlatformError> {
-        self.tasks.retain(|t| t.id != task_id);
+        self.tasks.retain(|t| t.task.id != task_id);
+        for entry in &mut self.tasks {
+            entry.depends_on.retain(|&id| id != task_id);
+        }
         Ok(())
     }
 
     fn run(&mut self) -> Result<(), PlatformError> {
-        // TODO: Implement scheduling logic
+        for index in self.execution_order() {
+            self.run_entry(index);
+        }
+        self.end_pass();
         Ok(())
     }
 }
@@ -0,0 +1,295 @@
+//! Thread pool scheduler
+//!
+//! Dispatches due tasks to a fixed set of worker threads so CPU-bound
+//! periodic work runs in parallel instead of serially.
+
+use super::{unknown_task, Scheduler, Task, TaskHandler, TaskKind};
+use crate::platform::PlatformError;
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::BinaryHeap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+type SharedHandler = Arc<Mutex<TaskHandler>>;
+
+/// Registered task and its dispatch bookkeeping
+struct PoolEntry {
+    task: Task,
+    handler: Option<SharedHandler>,
+    /// Set from dispatch until the handler returns
+    running: Arc<AtomicBool>,
+    last_dispatch: Option<Instant>,
+}
+
+impl PoolEntry {
+    fn is_due(&self, now: Instant) -> bool {
+        match self.last_dispatch {
+            None => true,
+            Some(at) => {
+                now.duration_since(at) >= Duration::from_millis(u64::from(self.task.period_ms))
+            }
+        }
+    }
+}
+
+/// Dispatched firing waiting for a worker
+struct Job {
+    task_id: u32,
+    priority: u8,
+    /// Dispatch sequence; earlier jobs win priority ties
+    seq: u64,
+    handler: SharedHandler,
+    running: Arc<AtomicBool>,
+}
+
+impl PartialEq for Job {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == CmpOrdering::Equal
+    }
+}
+
+impl Eq for Job {}
+
+impl PartialOrd for Job {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Job {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+struct JobQueue {
+    jobs: BinaryHeap<Job>,
+    shutdown: bool,
+}
+
+type Shared = Arc<(Mutex<JobQueue>, Condvar)>;
+
+/// Scheduler that runs task handlers on a fixed-size worker pool
+///
+/// `run` is non-blocking: it queues every task with a handler whose period has
+/// elapsed since its last dispatch and returns. Workers pick the highest
+/// priority queued job first. A task whose previous run is still in flight
+/// is skipped for that firing, so overruns never pile up. One-shot tasks are
+/// removed from the table once dispatched.
+pub struct ThreadPoolScheduler {
+    tasks: Vec<PoolEntry>,
+    shared: Shared,
+    workers: Vec<JoinHandle<()>>,
+    next_seq: u64,
+    skipped: u64,
+}
+
+impl ThreadPoolScheduler {
+    /// Spawn `num_workers` worker threads (at least one)
+    pub fn new(num_workers: usize) -> Self {
+        let shared: Shared = Arc::new((
+            Mutex::new(JobQueue {
+                jobs: BinaryHeap::new(),
+                shutdown: false,
+            }),
+            Condvar::new(),
+        ));
+        let workers = (0..num_workers.max(1))
+            .map(|_| {
+                let shared = Arc::clone(&shared);
+                std::thread::spawn(move || worker_loop(shared))
+            })
+            .collect();
+        ThreadPoolScheduler {
+            tasks: Vec::new(),
+            shared,
+            workers,
+            next_seq: 0,
+            skipped: 0,
+        }
+    }
+
+    /// Number of worker threads still attached
+    pub fn num_workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Register a task together with the work it performs
+    pub fn add_task_with_handler<F>(&mut self, task: Task, handler: F) -> Result<(), PlatformError>
+    where
+        F: FnMut() + Send + 'static,
+    {
+        let handler: TaskHandler = Box::new(handler);
+        self.tasks.push(PoolEntry {
+            task,
+            handler: Some(Arc::new(Mutex::new(handler))),
+            running: Arc::new(AtomicBool::new(false)),
+            last_dispatch: None,
+        });
+        Ok(())
+    }
+
+    /// Modify a registered task in place
+    ///
+    /// The change applies from the next `run`. The time of the last dispatch
+    /// is kept, so a new period counts from the previous firing.
+    pub fn update_task(
+        &mut self,
+        task_id: u32,
+        f: impl FnOnce(&mut Task),
+    ) -> Result<(), PlatformError> {
+        let entry = self
+            .tasks
+            .iter_mut()
+            .find(|e| e.task.id == task_id)
+            .ok_or_else(|| unknown_task(task_id))?;
+        f(&mut entry.task);
+        Ok(())
+    }
+
+    /// Change a task's priority
+    pub fn set_priority(&mut self, task_id: u32, priority: u8) -> Result<(), PlatformError> {
+        self.update_task(task_id, |task| task.priority = priority)
+    }
+
+    /// Change a task's period
+    pub fn set_period(&mut self, task_id: u32, period_ms: u32) -> Result<(), PlatformError> {
+        self.update_task(task_id, |task| task.period_ms = period_ms)
+    }
+
+    /// Firings skipped because the previous run had not finished
+    pub fn skipped_runs(&self) -> u64 {
+        self.skipped
+    }
+
+    /// Stop accepting work, let workers finish queued and in-flight jobs,
+    /// and join them
+    pub fn shutdown(&mut self) -> Result<(), PlatformError> {
+        {
+            let (lock, cvar) = &*self.shared;
+            let mut queue = lock
+                .lock()
+                .map_err(|_| PlatformError::OperationFailed("job queue lock poisoned".into()))?;
+            queue.shutdown = true;
+            cvar.notify_all();
+        }
+        let mut result = Ok(());
+        for worker in self.workers.drain(..) {
+            if worker.join().is_err() {
+                result = Err(PlatformError::OperationFailed(
+                    "worker thread panicked".into(),
+                ));
+            }
+        }
+        result
+    }
+}
+
+fn worker_loop(shared: Shared) {
+    let (lock, cvar) = &*shared;
+    loop {
+        let job = {
+            let Ok(mut queue) = lock.lock() else {
+                return;
+            };
+            loop {
+                if let Some(job) = queue.jobs.pop() {
+                    break job;
+                }
+                if queue.shutdown {
+                    return;
+                }
+                queue = match cvar.wait(queue) {
+                    Ok(queue) => queue,
+                    Err(_) => return,
+                };
+            }
+        };
+        let _running = RunningGuard(Arc::clone(&job.running));
+        // A handler that panicked earlier may have poisoned its lock; the
+        // task still gets its next run
+        let mut handler = job
+            .handler
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // Keep the worker alive so one faulty task cannot shrink the pool
+        if panic::catch_unwind(AssertUnwindSafe(&mut *handler)).is_err() {
+            tracing::error!(task_id = job.task_id, "task handler panicked");
+        }
+    }
+}
+
+/// Clears a task's in-flight flag even if its handler panics
+struct RunningGuard(Arc<AtomicBool>);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+impl Scheduler for ThreadPoolScheduler {
+    fn add_task(&mut self, task: Task) -> Result<(), PlatformError> {
+        self.tasks.push(PoolEntry {
+            task,
+            handler: None,
+            running: Arc::new(AtomicBool::new(false)),
+            last_dispatch: None,
+        });
+        Ok(())
+    }
+
+    fn remove_task(&mut self, task_id: u32) -> Result<(), PlatformError> {
+        self.tasks.retain(|t| t.task.id != task_id);
+        Ok(())
+    }
+
+    fn run(&mut self) -> Result<(), PlatformError> {
+        let (lock, cvar) = &*self.shared;
+        let mut queue = lock
+            .lock()
+            .map_err(|_| PlatformError::OperationFailed("job queue lock poisoned".into()))?;
+        if queue.shutdown {
+            return Err(PlatformError::OperationFailed(
+                "scheduler is shut down".into(),
+            ));
+        }
+        let now = Instant::now();
+        for entry in self.tasks.iter_mut() {
+            let Some(handler) = entry.handler.as_ref() else {
+                continue;
+            };
+            if !entry.is_due(now) {
+                continue;
+            }
+            if entry.running.swap(true, Ordering::AcqRel) {
+                self.skipped += 1;
+                continue;
+            }
+            entry.last_dispatch = Some(now);
+            queue.jobs.push(Job {
+                task_id: entry.task.id,
+                priority: entry.task.priority,
+                seq: self.next_seq,
+                handler: Arc::clone(handler),
+                running: Arc::clone(&entry.running),
+            });
+            self.next_seq += 1;
+        }
+        self.tasks
+            .retain(|e| e.task.kind != TaskKind::OneShot || e.last_dispatch.is_none());
+        cvar.notify_all();
+        Ok(())
+    }
+}
+
+impl Drop for ThreadPoolScheduler {
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
@@ -51,3 +51,208 @@ impl Timer for DesktopTimer {
         self.start_time.is_some()
     }
 }
+
+/// Countdown timer with a fixed time budget
+///
+/// Counts down from `budget` once started. Useful for watchdog-style
+/// deadline checks where the remaining time matters more than elapsed time.
+pub struct CountdownTimer {
+    budget: Duration,
+    inner: DesktopTimer,
+}
+
+impl CountdownTimer {
+    pub fn new(budget: Duration) -> Self {
+        CountdownTimer {
+            budget,
+            inner: DesktopTimer::new(),
+        }
+    }
+
+    /// Total time budget this countdown was created with
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    /// Time left before expiry, saturating to zero
+    ///
+    /// A countdown that has not been started reports the full budget.
+    pub fn remaining(&self) -> Duration {
+        self.budget.saturating_sub(self.inner.elapsed())
+    }
+
+    /// Whether the budget has been used up since the last start
+    pub fn is_expired(&self) -> bool {
+        self.inner.is_running() && self.remaining() == Duration::ZERO
+    }
+
+    /// Restore the full budget
+    ///
+    /// A running countdown restarts from now; a stopped one stays stopped.
+    pub fn reset(&mut self) {
+        if self.inner.is_running() {
+            self.inner.start_time = Some(Instant::now());
+        }
+    }
+}
+
+impl Timer for CountdownTimer {
+    fn start(&mut self) -> Result<(), PlatformError> {
+        self.inner.start()
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.inner.elapsed()
+    }
+
+    fn stop(&mut self) -> Result<(), PlatformError> {
+        self.inner.stop()
+    }
+
+    fn is_running(&self) -> bool {
+        self.inner.is_running()
+    }
+}
+
+/// Stopwatch recording lap (split) times from a single running timer
+///
+/// Useful for profiling the stages of a pipeline: call `lap` after each
+/// stage and inspect `laps` at the end.
+pub struct LapTimer {
+    inner: DesktopTimer,
+    laps: Vec<Duration>,
+    /// Elapsed time at the most recent lap
+    last_lap: Duration,
+}
+
+impl LapTimer {
+    pub fn new() -> Self {
+        LapTimer {
+            inner: DesktopTimer::new(),
+            laps: Vec::new(),
+            last_lap: Duration::ZERO,
+        }
+    }
+
+    /// Record a split and return the time since the previous lap, or since
+    /// start for the first one
+    ///
+    /// A timer that is not running records nothing and returns zero.
+    pub fn lap(&mut self) -> Duration {
+        if !self.inner.is_running() {
+            return Duration::ZERO;
+        }
+        let elapsed = self.inner.elapsed();
+        let split = elapsed.saturating_sub(self.last_lap);
+        self.last_lap = elapsed;
+        self.laps.push(split);
+        split
+    }
+
+    /// Splits recorded since the last start
+    pub fn laps(&self) -> &[Duration] {
+        &self.laps
+    }
+
+    /// Cumulative time elapsed since start
+    pub fn total(&self) -> Duration {
+        self.inner.elapsed()
+    }
+}
+
+impl Default for LapTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timer for LapTimer {
+    /// Start timing and discard the laps of any previous run
+    fn start(&mut self) -> Result<(), PlatformError> {
+        self.laps.clear();
+        self.last_lap = Duration::ZERO;
+        self.inner.start()
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.inner.elapsed()
+    }
+
+    /// Stop timing; recorded laps remain available
+    fn stop(&mut self) -> Result<(), PlatformError> {
+        self.inner.stop()
+    }
+
+    fn is_running(&self) -> bool {
+        self.inner.is_running()
+    }
+}
+
+/// How an `AsyncIntervalTimer` catches up after ticks were missed
+#[cfg(feature = "async")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissedTicks {
+    /// Fire missed ticks back-to-back until caught up
+    #[default]
+    Burst,
+    /// Restart the schedule from the late tick
+    Delay,
+    /// Drop missed ticks and resume on the original schedule
+    Skip,
+}
+
+#[cfg(feature = "async")]
+impl From<MissedTicks> for tokio::time::MissedTickBehavior {
+    fn from(value: MissedTicks) -> Self {
+        match value {
+            MissedTicks::Burst => tokio::time::MissedTickBehavior::Burst,
+            MissedTicks::Delay => tokio::time::MissedTickBehavior::Delay,
+            MissedTicks::Skip => tokio::time::MissedTickBehavior::Skip,
+        }
+    }
+}
+
+/// Periodic timer for the async runtime, backed by `tokio::time::interval`
+///
+/// Kept separate from the blocking `Timer` trait so periodic work can await
+/// ticks without tying up a thread. The first tick completes immediately.
+#[cfg(feature = "async")]
+pub struct AsyncIntervalTimer {
+    interval: tokio::time::Interval,
+}
+
+#[cfg(feature = "async")]
+impl AsyncIntervalTimer {
+    /// Must be called from within a tokio runtime
+    pub fn new(period: Duration) -> Self {
+        AsyncIntervalTimer {
+            interval: tokio::time::interval(period),
+        }
+    }
+
+    /// Wait for the next tick
+    pub async fn tick(&mut self) {
+        self.interval.tick().await;
+    }
+
+    /// Restart the schedule so the next tick is one full period from now
+    pub fn reset(&mut self) {
+        self.interval.reset();
+    }
+
+    pub fn period(&self) -> Duration {
+        self.interval.period()
+    }
+
+    pub fn missed_tick_behavior(&self) -> MissedTicks {
+        match self.interval.missed_tick_behavior() {
+            tokio::time::MissedTickBehavior::Delay => MissedTicks::Delay,
+            tokio::time::MissedTickBehavior::Skip => MissedTicks::Skip,
+            _ => MissedTicks::Burst,
+        }
+    }
+
+    pub fn set_missed_tick_behavior(&mut self, behavior: MissedTicks) {
+        self.interval.set_missed_tick_behavior(behavior.into());
+    }
+}
@@ -0,0 +1,150 @@
+//! Watchdog implementation
+//!
+//! Proves liveness of a loop: if `kick` is not called within the timeout, a
+//! monitor thread invokes the expiry callback (e.g. to log or reset).
+
+use crate::platform::PlatformError;
+use crate::timer::{CountdownTimer, Timer};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Callback invoked when the watchdog expires
+pub type ExpiryCallback = Box<dyn FnMut() + Send>;
+
+struct WatchdogState {
+    countdown: CountdownTimer,
+    /// Set once the callback has fired for the current deadline
+    fired: bool,
+    stopped: bool,
+}
+
+type Shared = Arc<(Mutex<WatchdogState>, Condvar)>;
+
+/// Watchdog timer with a background monitor thread
+///
+/// The callback fires at most once per missed deadline; a later `kick`
+/// re-arms the watchdog.
+pub struct Watchdog {
+    shared: Option<Shared>,
+    monitor: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    pub fn new() -> Self {
+        Watchdog {
+            shared: None,
+            monitor: None,
+        }
+    }
+
+    /// Arm the watchdog and spawn its monitor thread
+    pub fn start(
+        &mut self,
+        timeout: Duration,
+        mut on_expire: ExpiryCallback,
+    ) -> Result<(), PlatformError> {
+        if self.is_running() {
+            return Err(PlatformError::OperationFailed(
+                "watchdog already running".into(),
+            ));
+        }
+        let mut countdown = CountdownTimer::new(timeout);
+        countdown.start()?;
+        let shared: Shared = Arc::new((
+            Mutex::new(WatchdogState {
+                countdown,
+                fired: false,
+                stopped: false,
+            }),
+            Condvar::new(),
+        ));
+
+        let monitor_shared = Arc::clone(&shared);
+        let monitor = std::thread::Builder::new()
+            .name("watchdog".into())
+            .spawn(move || {
+                let (lock, cvar) = &*monitor_shared;
+                let Ok(mut state) = lock.lock() else {
+                    return;
+                };
+                loop {
+                    if state.stopped {
+                        return;
+                    }
+                    if !state.fired && state.countdown.is_expired() {
+                        state.fired = true;
+                        drop(state);
+                        on_expire();
+                        state = match lock.lock() {
+                            Ok(state) => state,
+                            Err(_) => return,
+                        };
+                        continue;
+                    }
+                    // Sleep until the deadline, or until kicked/stopped if already
+                    // fired; a timed wait then would spin once the budget is zero
+                    let woken = if state.fired {
+                        cvar.wait(state).ok()
+                    } else {
+                        let remaining = state.countdown.remaining();
+                        cvar.wait_timeout(state, remaining)
+                            .ok()
+                            .map(|(state, _)| state)
+                    };
+                    let Some(woken) = woken else {
+                        return;
+                    };
+                    state = woken;
+                }
+            })
+            .map_err(|e| PlatformError::InitializationFailed(format!("watchdog thread: {}", e)))?;
+
+        self.shared = Some(shared);
+        self.monitor = Some(monitor);
+        Ok(())
+    }
+
+    /// Push the deadline back by a full timeout
+    pub fn kick(&self) {
+        if let Some((lock, cvar)) = self.shared.as_deref() {
+            if let Ok(mut state) = lock.lock() {
+                state.countdown.reset();
+                state.fired = false;
+                cvar.notify_one();
+            }
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.monitor.is_some()
+    }
+
+    /// Cancel the watchdog and join its monitor thread
+    pub fn stop(&mut self) -> Result<(), PlatformError> {
+        if let Some((lock, cvar)) = self.shared.take().as_deref() {
+            if let Ok(mut state) = lock.lock() {
+                state.stopped = true;
+                cvar.notify_one();
+            }
+        }
+        if let Some(monitor) = self.monitor.take() {
+            monitor
+                .join()
+                .map_err(|_| PlatformError::OperationFailed("watchdog callback panicked".into()))?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
@@ -1,15 +1,79 @@
 #[cfg(test)]
 mod tests {
-    use room619_core::platform::PlatformAbstraction;
-    use room619_core::scheduler::{Scheduler, Task};
+    use room619_core::platform::{
+        PlatformAbstraction, PlatformState, SchedulerBackend, TimerBackend,
+    };
+    use room619_core::scheduler::{
+        Aging, CancellationToken, CooperativeScheduler, DefaultScheduler, Scheduler,
+        SchedulingPolicy, Task, TaskKind, ThreadPoolScheduler,
+    };
     use room619_core::timer::Timer;
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn test_desktop_platform() {
-        let mut platform = room619_core::platform::DesktopPlatform;
+        let mut platform = room619_core::platform::DesktopPlatform::new();
+        assert_eq!(platform.state(), PlatformState::Stopped);
         assert!(platform.start().is_ok());
+        assert_eq!(platform.state(), PlatformState::Running);
         assert_eq!(platform.platform_name(), "Desktop (Tokio-based)");
         assert!(platform.stop().is_ok());
+        assert_eq!(platform.state(), PlatformState::Stopped);
+        assert!(platform.start().is_ok());
+    }
+
+    #[test]
+    fn test_desktop_platform_capabilities() {
+        let platform = room619_core::platform::DesktopPlatform::new();
+        let caps = platform.capabilities();
+        assert!(caps.num_cpus > 0);
+        assert!(caps.supports_async);
+        assert!(caps.high_res_timer);
+        assert!(caps.preemptive_scheduling);
+    }
+
+    #[test]
+    fn test_platform_default_capabilities_are_conservative() {
+        struct BarePlatform;
+
+        impl PlatformAbstraction for BarePlatform {
+            fn platform_name(&self) -> &'static str {
+                "bare"
+            }
+            fn start(&mut self) -> Result<(), room619_core::platform::PlatformError> {
+                Ok(())
+            }
+            fn stop(&mut self) -> Result<(), room619_core::platform::PlatformError> {
+                Ok(())
+            }
+        }
+
+        let caps = BarePlatform.capabilities();
+        assert_eq!(caps.num_cpus, 1);
+        assert!(!caps.supports_async);
+        assert!(!caps.high_res_timer);
+        assert!(!caps.preemptive_scheduling);
+    }
+
+    #[test]
+    fn test_desktop_platform_double_start_fails() {
+        let mut platform = room619_core::platform::DesktopPlatform::new();
+        assert!(platform.start().is_ok());
+        assert!(matches!(
+            platform.start(),
+            Err(room619_core::platform::PlatformError::OperationFailed(_))
+        ));
+        assert_eq!(platform.state(), PlatformState::Running);
+    }
+
+    #[test]
+    fn test_desktop_platform_stop_before_start_fails() {
+        let mut platform = room619_core::platform::DesktopPlatform::new();
+        assert!(matches!(
+            platform.stop(),
+            Err(room619_core::platform::PlatformError::OperationFailed(_))
+        ));
+        assert_eq!(platform.state(), PlatformState::Stopped);
     }
 
     #[test]
@@ -37,10 +101,885 @@ mod tests {
             id: 1,
             priority: 10,
             period_ms: 100,
+            deadline_ms: None,
+            kind: TaskKind::Periodic,
         };
 
         assert!(scheduler.add_task(task).is_ok());
         assert!(scheduler.run().is_ok());
         assert!(scheduler.remove_task(1).is_ok());
     }
+
+    #[test]
+    fn test_countdown_timer() {
+        let budget = std::time::Duration::from_millis(50);
+        let mut countdown = room619_core::timer::CountdownTimer::new(budget);
+
+        assert!(countdown.start().is_ok());
+        assert!(!countdown.is_expired());
+        assert!(countdown.remaining() <= budget);
+
+        std::thread::sleep(std::time::Duration::from_millis(60));
+
+        assert!(countdown.is_expired());
+        assert_eq!(countdown.remaining(), std::time::Duration::ZERO);
+
+        countdown.reset();
+        assert!(!countdown.is_expired());
+        assert!(countdown.remaining() > std::time::Duration::from_millis(40));
+    }
+
+    #[test]
+    fn test_lap_timer_records_splits() {
+        let mut timer = room619_core::timer::LapTimer::new();
+        assert_eq!(timer.lap(), std::time::Duration::ZERO);
+        assert!(timer.laps().is_empty());
+
+        assert!(timer.start().is_ok());
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let first = timer.lap();
+        std::thread::sleep(std::time::Duration::from_millis(30));
+        let second = timer.lap();
+        let total = timer.total();
+
+        assert_eq!(timer.laps(), &[first, second]);
+        assert!(first >= std::time::Duration::from_millis(20));
+        assert!(second >= std::time::Duration::from_millis(30));
+        assert!(first + second <= total);
+        assert!(total - (first + second) < std::time::Duration::from_millis(10));
+
+        assert!(timer.stop().is_ok());
+        assert_eq!(timer.laps().len(), 2);
+        assert!(timer.start().is_ok());
+        assert!(timer.laps().is_empty());
+    }
+
+    #[test]
+    fn test_desktop_timer_backend() {
+        let platform = room619_core::platform::DesktopPlatform::new();
+        let mut timer = platform.timer_backend();
+
+        assert_eq!(timer.elapsed(), std::time::Duration::ZERO);
+        assert!(timer.start(std::time::Duration::from_millis(100)).is_ok());
+        assert_eq!(timer.duration(), std::time::Duration::from_millis(100));
+
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        assert!(timer.elapsed().as_millis() >= 10);
+
+        assert!(timer.stop().is_ok());
+        assert_eq!(timer.elapsed(), std::time::Duration::ZERO);
+    }
+
+    #[test]
+    fn test_desktop_scheduler_backend() {
+        let platform = room619_core::platform::DesktopPlatform::new();
+        let mut scheduler = platform.scheduler_backend();
+
+        assert_eq!(scheduler.current_task_id(), 0);
+        assert!(scheduler.schedule_task(7).is_ok());
+        assert_eq!(scheduler.current_task_id(), 7);
+
+        scheduler.yield_cpu();
+        assert!(scheduler.schedule_task(3).is_ok());
+        assert_eq!(scheduler.current_task_id(), 3);
+    }
+
+    /// Register tasks that record their id into a shared log when run
+    fn recording_scheduler(
+        policy: SchedulingPolicy,
+        tasks: &[Task],
+    ) -> (DefaultScheduler, Arc<Mutex<Vec<u32>>>) {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut scheduler = DefaultScheduler::with_policy(policy);
+        for task in tasks {
+            let log = Arc::clone(&log);
+            let id = task.id;
+            scheduler
+                .add_task_with_handler(*task, move || log.lock().unwrap().push(id))
+                .unwrap();
+        }
+        (scheduler, log)
+    }
+
+    #[test]
+    fn test_scheduler_priority_policy() {
+        let tasks = [
+            Task::new(1, 1, 10),
+            Task::new(2, 9, 50),
+            Task::new(3, 5, 20),
+        ];
+        let (mut scheduler, log) = recording_scheduler(SchedulingPolicy::Priority, &tasks);
+        assert_eq!(scheduler.policy(), SchedulingPolicy::Priority);
+
+        assert!(scheduler.run().is_ok());
+        assert_eq!(*log.lock().unwrap(), vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn test_scheduler_edf_policy() {
+        let tasks = [
+            Task::new(1, 9, 100).with_deadline(30),
+            Task::new(2, 1, 100).with_deadline(5),
+            Task::new(3, 5, 100),
+            Task::new(4, 1, 100).with_deadline(15),
+        ];
+        let (mut scheduler, log) =
+            recording_scheduler(SchedulingPolicy::EarliestDeadlineFirst, &tasks);
+
+        assert!(scheduler.run().is_ok());
+        assert_eq!(*log.lock().unwrap(), vec![2, 4, 1, 3]);
+    }
+
+    #[test]
+    fn test_scheduler_edf_uses_absolute_deadlines() {
+        let (mut scheduler, log) = recording_scheduler(
+            SchedulingPolicy::EarliestDeadlineFirst,
+            &[Task::new(1, 1, 0).with_deadline(100)],
+        );
+        std::thread::sleep(std::time::Duration::from_millis(80));
+        let log_late = Arc::clone(&log);
+        scheduler
+            .add_task_with_handler(Task::new(2, 9, 0).with_deadline(30), move || {
+                log_late.lock().unwrap().push(2)
+            })
+            .unwrap();
+
+        // Task 1 is due 100ms after registering, task 2 only 110ms after
+        // task 1 registered, despite its shorter relative deadline
+        assert!(scheduler.run().is_ok());
+        assert_eq!(*log.lock().unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_scheduler_rate_monotonic_policy() {
+        let tasks = [Task::new(1, 9, 3), Task::new(2, 1, 1), Task::new(3, 5, 2)];
+        let (mut scheduler, log) = recording_scheduler(SchedulingPolicy::RateMonotonic, &tasks);
+
+        assert!(scheduler.run().is_ok());
+        assert_eq!(*log.lock().unwrap(), vec![2, 3, 1]);
+
+        scheduler.set_policy(SchedulingPolicy::Priority);
+        log.lock().unwrap().clear();
+        // Let every period elapse so all three are due again
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert!(scheduler.run().is_ok());
+        assert_eq!(*log.lock().unwrap(), vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn test_scheduler_disable_and_enable_task() {
+        let tasks = [Task::new(1, 5, 0), Task::new(2, 1, 0)];
+        let (mut scheduler, log) = recording_scheduler(SchedulingPolicy::Priority, &tasks);
+        assert_eq!(scheduler.is_enabled(1), Some(true));
+
+        assert!(scheduler.disable_task(1).is_ok());
+        assert_eq!(scheduler.is_enabled(1), Some(false));
+        assert!(scheduler.run().is_ok());
+        assert_eq!(*log.lock().unwrap(), vec![2]);
+
+        assert!(scheduler.enable_task(1).is_ok());
+        assert!(scheduler.run().is_ok());
+        assert_eq!(*log.lock().unwrap(), vec![2, 1, 2]);
+    }
+
+    #[test]
+    fn test_scheduler_one_shot_task_runs_once() {
+        let tasks = [Task::new(1, 1, 0), Task::one_shot(2, 9)];
+        let (mut scheduler, log) = recording_scheduler(SchedulingPolicy::Priority, &tasks);
+        assert_eq!(scheduler.tasks()[1].kind, TaskKind::OneShot);
+        assert_eq!(Task::new(3, 1, 10).kind, TaskKind::Periodic);
+
+        for _ in 0..3 {
+            assert!(scheduler.run().is_ok());
+        }
+
+        assert_eq!(*log.lock().unwrap(), vec![2, 1, 1, 1]);
+        let ids: Vec<u32> = scheduler.tasks().iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![1]);
+    }
+
+    #[test]
+    fn test_scheduler_run_budget_starves_low_priority_without_aging() {
+        let tasks = [Task::new(1, 200, 0), Task::new(2, 10, 0)];
+        let (mut scheduler, log) = recording_scheduler(SchedulingPolicy::Priority, &tasks);
+        scheduler.set_run_budget(Some(1));
+        assert_eq!(scheduler.aging(), None);
+
+        for _ in 0..50 {
+            assert!(scheduler.run().is_ok());
+        }
+        assert_eq!(*log.lock().unwrap(), vec![1; 50]);
+    }
+
+    #[test]
+    fn test_scheduler_aging_prevents_starvation() {
+        let tasks = [Task::new(1, 200, 0), Task::new(2, 10, 0)];
+        let (mut scheduler, log) = recording_scheduler(SchedulingPolicy::Priority, &tasks);
+        scheduler.set_run_budget(Some(1));
+        scheduler.set_aging(Some(Aging::new(3, 50)));
+
+        let mut low_runs = Vec::new();
+        for pass in 0..40 {
+            log.lock().unwrap().clear();
+            assert!(scheduler.run().is_ok());
+            if *log.lock().unwrap() == vec![2] {
+                low_runs.push(pass);
+            }
+        }
+
+        // Passed over 7 times, task 2 reaches 10 + 4 * 50 > 200 and runs,
+        // then its boost resets
+        assert_eq!(low_runs, vec![7, 15, 23, 31, 39]);
+    }
+
+    #[test]
+    fn test_scheduler_dependencies_inherit_priority() {
+        let tasks = [Task::new(1, 1, 0), Task::new(2, 9, 0), Task::new(3, 5, 0)];
+        let (mut scheduler, log) = recording_scheduler(SchedulingPolicy::Priority, &tasks);
+        assert!(scheduler.add_dependency(2, 1).is_ok());
+        assert_eq!(scheduler.dependencies(2), Some(&[1][..]));
+
+        // Task 1 is boosted to 9, so it and task 2 run ahead of task 3
+        assert!(scheduler.run().is_ok());
+        assert_eq!(*log.lock().unwrap(), vec![1, 2, 3]);
+
+        scheduler.set_policy(SchedulingPolicy::RateMonotonic);
+        assert!(scheduler.set_period(1, 1).is_ok());
+        log.lock().unwrap().clear();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        assert!(scheduler.run().is_ok());
+        assert_eq!(*log.lock().unwrap(), vec![3, 1, 2]);
+
+        assert!(scheduler.remove_dependency(2, 1).is_ok());
+        log.lock().unwrap().clear();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        assert!(scheduler.run().is_ok());
+        assert_eq!(*log.lock().unwrap(), vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn test_scheduler_rejects_dependency_cycle() {
+        let tasks = [
+            Task::new(1, 1, 10),
+            Task::new(2, 1, 10),
+            Task::new(3, 1, 10),
+        ];
+        let (mut scheduler, log) = recording_scheduler(SchedulingPolicy::Priority, &tasks);
+        assert!(scheduler.add_dependency(1, 2).is_ok());
+        assert!(scheduler.add_dependency(2, 3).is_ok());
+
+        assert!(matches!(
+            scheduler.add_dependency(3, 1),
+            Err(room619_core::platform::PlatformError::OperationFailed(_))
+        ));
+        assert!(matches!(
+            scheduler.add_dependency(3, 3),
+            Err(room619_core::platform::PlatformError::OperationFailed(_))
+        ));
+        assert!(matches!(
+            scheduler.add_dependency(3, 99),
+            Err(room619_core::platform::PlatformError::NotSupported(_))
+        ));
+        assert_eq!(scheduler.dependencies(3), Some(&[][..]));
+
+        assert!(scheduler.run().is_ok());
+        assert_eq!(*log.lock().unwrap(), vec![3, 2, 1]);
+
+        assert!(scheduler.remove_task(2).is_ok());
+        assert_eq!(scheduler.dependencies(1), Some(&[][..]));
+    }
+
+    #[test]
+    fn test_scheduler_run_for_stops_when_cancelled() {
+        let runs = Arc::new(Mutex::new(0u32));
+        let mut scheduler = DefaultScheduler::new();
+        let counter = Arc::clone(&runs);
+        scheduler
+            .add_task_with_handler(Task::new(1, 1, 0), move || {
+                *counter.lock().unwrap() += 1;
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            })
+            .unwrap();
+
+        let token = CancellationToken::new();
+        let loop_token = token.clone();
+        let started = std::time::Instant::now();
+        let handle = std::thread::spawn(move || {
+            // Too far out to represent as an `Instant`: runs until cancelled
+            scheduler.run_for(std::time::Duration::MAX, &loop_token)
+        });
+
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        token.cancel();
+        assert!(handle.join().unwrap().is_ok());
+
+        assert!(token.is_cancelled());
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        assert!(*runs.lock().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_scheduler_run_for_returns_after_duration() {
+        let tasks = [Task::new(1, 1, 0)];
+        let (mut scheduler, log) = recording_scheduler(SchedulingPolicy::Priority, &tasks);
+        let started = std::time::Instant::now();
+
+        assert!(scheduler
+            .run_for(
+                std::time::Duration::from_millis(20),
+                &CancellationToken::new()
+            )
+            .is_ok());
+
+        assert!(started.elapsed() >= std::time::Duration::from_millis(20));
+        assert!(!log.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_scheduler_run_for_sleeps_until_task_is_due() {
+        let tasks = [Task::new(1, 1, 20)];
+        let (mut scheduler, log) = recording_scheduler(SchedulingPolicy::Priority, &tasks);
+        let started = std::time::Instant::now();
+
+        assert!(scheduler
+            .run_for(
+                std::time::Duration::from_millis(110),
+                &CancellationToken::new()
+            )
+            .is_ok());
+
+        // Due at 0, 20, ..., 100ms; allow for a slow CI scheduler
+        let runs = log.lock().unwrap().len();
+        assert!((3..=6).contains(&runs), "{} runs", runs);
+        assert!(started.elapsed() >= std::time::Duration::from_millis(110));
+        // Sleeping between runs instead of spinning through empty passes
+        assert!(scheduler.passes() < 50, "{} passes", scheduler.passes());
+    }
+
+    #[test]
+    fn test_thread_pool_one_shot_task_runs_once() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut scheduler = ThreadPoolScheduler::new(1);
+        for task in [Task::new(1, 1, 0), Task::one_shot(2, 9)] {
+            let log = Arc::clone(&log);
+            scheduler
+                .add_task_with_handler(task, move || log.lock().unwrap().push(task.id))
+                .unwrap();
+        }
+
+        for _ in 0..3 {
+            assert!(scheduler.run().is_ok());
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert!(scheduler.shutdown().is_ok());
+
+        let log = log.lock().unwrap();
+        assert_eq!(log.iter().filter(|&&id| id == 2).count(), 1);
+        assert_eq!(log.iter().filter(|&&id| id == 1).count(), 3);
+    }
+
+    #[test]
+    fn test_thread_pool_survives_panicking_task() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut scheduler = ThreadPoolScheduler::new(1);
+        for task in [Task::new(1, 9, 0), Task::new(2, 1, 0)] {
+            let log = Arc::clone(&log);
+            scheduler
+                .add_task_with_handler(task, move || {
+                    log.lock().unwrap().push(task.id);
+                    if task.id == 1 {
+                        panic!("task 1 failed");
+                    }
+                })
+                .unwrap();
+        }
+
+        for _ in 0..3 {
+            assert!(scheduler.run().is_ok());
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        // The only worker is still alive and shuts down cleanly
+        assert!(scheduler.shutdown().is_ok());
+
+        // A run can be skipped while the panic is still being reported
+        let log = log.lock().unwrap();
+        assert!(log.iter().filter(|&&id| id == 1).count() >= 2);
+        assert!(log.iter().filter(|&&id| id == 2).count() >= 2);
+    }
+
+    #[test]
+    fn test_scheduler_disable_unknown_task() {
+        let mut scheduler = DefaultScheduler::new();
+        assert_eq!(scheduler.is_enabled(42), None);
+        assert!(matches!(
+            scheduler.disable_task(42),
+            Err(room619_core::platform::PlatformError::NotSupported(_))
+        ));
+        assert!(scheduler.enable_task(42).is_err());
+    }
+
+    #[test]
+    fn test_scheduler_update_task_priority_at_runtime() {
+        let tasks = [Task::new(1, 9, 0), Task::new(2, 5, 0), Task::new(3, 1, 0)];
+        let (mut scheduler, log) = recording_scheduler(SchedulingPolicy::Priority, &tasks);
+        assert!(scheduler.run().is_ok());
+        assert_eq!(*log.lock().unwrap(), vec![1, 2, 3]);
+
+        assert!(scheduler.set_priority(3, 10).is_ok());
+        assert!(scheduler.update_task(1, |task| task.priority = 0).is_ok());
+        log.lock().unwrap().clear();
+        assert!(scheduler.run().is_ok());
+        assert_eq!(*log.lock().unwrap(), vec![3, 2, 1]);
+
+        let snapshot = scheduler.snapshot();
+        assert!(snapshot.tasks.iter().all(|t| t.run_count == 2));
+        assert!(matches!(
+            scheduler.set_period(42, 10),
+            Err(room619_core::platform::PlatformError::NotSupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_scheduler_set_period_changes_firing_rate() {
+        let tasks = [Task::new(1, 1, 60_000)];
+        let (mut scheduler, log) = recording_scheduler(SchedulingPolicy::Priority, &tasks);
+
+        // One firing per minute: only the first run executes the task
+        for _ in 0..3 {
+            assert!(scheduler.run().is_ok());
+        }
+        assert_eq!(log.lock().unwrap().len(), 1);
+
+        // A zero period is due on every run
+        assert!(scheduler.set_period(1, 0).is_ok());
+        for _ in 0..3 {
+            assert!(scheduler.run().is_ok());
+        }
+        assert_eq!(log.lock().unwrap().len(), 4);
+
+        // The new period is measured from the last run, which is kept
+        assert!(scheduler.set_period(1, 20).is_ok());
+        assert!(scheduler.run().is_ok());
+        assert_eq!(log.lock().unwrap().len(), 4);
+        std::thread::sleep(std::time::Duration::from_millis(25));
+        assert!(scheduler.run().is_ok());
+        assert!(scheduler.run().is_ok());
+        assert_eq!(log.lock().unwrap().len(), 5);
+        assert_eq!(scheduler.snapshot().tasks[0].run_count, 5);
+    }
+
+    #[test]
+    fn test_thread_pool_set_period_changes_firing_rate() {
+        use std::sync::mpsc;
+
+        let (tx, rx) = mpsc::channel();
+        let mut scheduler = ThreadPoolScheduler::new(1);
+        scheduler
+            .add_task_with_handler(Task::new(1, 1, 60_000), move || tx.send(()).unwrap())
+            .unwrap();
+        // Run once and report whether the task fired within `wait_ms`,
+        // retrying runs skipped because the previous firing is still in flight
+        let fire = |scheduler: &mut ThreadPoolScheduler, wait_ms: u64| {
+            loop {
+                let skipped = scheduler.skipped_runs();
+                assert!(scheduler.run().is_ok());
+                if scheduler.skipped_runs() == skipped {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+            rx.recv_timeout(std::time::Duration::from_millis(wait_ms))
+                .is_ok()
+        };
+
+        // One firing per minute: only the first run dispatches
+        assert!(fire(&mut scheduler, 1_000));
+        assert!(!fire(&mut scheduler, 100));
+
+        // A zero period is due on every run
+        assert!(scheduler.set_period(1, 0).is_ok());
+        assert!(fire(&mut scheduler, 1_000));
+        assert!(fire(&mut scheduler, 1_000));
+
+        assert!(scheduler.set_period(1, 60_000).is_ok());
+        assert!(!fire(&mut scheduler, 100));
+        assert!(scheduler.set_priority(2, 1).is_err());
+        assert!(scheduler.shutdown().is_ok());
+    }
+
+    #[test]
+    fn test_scheduler_snapshot_reports_runs_in_order() {
+        let tasks = [
+            Task::new(1, 1, 0),
+            Task::new(2, 9, 0).with_deadline(20),
+            Task::new(3, 5, 0),
+        ];
+        let (mut scheduler, _log) = recording_scheduler(SchedulingPolicy::Priority, &tasks);
+        let ids: Vec<u32> = scheduler.tasks().iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+
+        let snapshot = scheduler.snapshot();
+        assert!(snapshot.tasks.iter().all(|t| t.run_count == 0));
+        assert!(snapshot.tasks.iter().all(|t| t.last_run_ms.is_none()));
+
+        assert!(scheduler.disable_task(3).is_ok());
+        for _ in 0..3 {
+            assert!(scheduler.run().is_ok());
+        }
+
+        let snapshot = scheduler.snapshot();
+        assert_eq!(snapshot.policy, SchedulingPolicy::Priority);
+        let ids: Vec<u32> = snapshot.tasks.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![2, 3, 1]);
+        let counts: Vec<u64> = snapshot.tasks.iter().map(|t| t.run_count).collect();
+        assert_eq!(counts, vec![3, 0, 3]);
+        assert!(snapshot.tasks[0].last_run_ms.is_some());
+        assert!(!snapshot.tasks[1].enabled);
+        assert_eq!(snapshot.tasks[0].deadline_ms, Some(20));
+
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(json["policy"], "Priority");
+        assert_eq!(json["tasks"][0]["id"], 2);
+        assert_eq!(json["tasks"][0]["run_count"], 3);
+    }
+
+    #[test]
+    fn test_scheduler_reports_overrunning_task() {
+        let mut scheduler = DefaultScheduler::new();
+        let overruns = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&overruns);
+        scheduler.on_overrun(move |id, overrun| sink.lock().unwrap().push((id, overrun)));
+        scheduler
+            .add_task_with_handler(Task::new(1, 5, 10), || {
+                std::thread::sleep(std::time::Duration::from_millis(40))
+            })
+            .unwrap();
+        scheduler
+            .add_task_with_handler(Task::new(2, 1, 1_000), || {})
+            .unwrap();
+
+        assert!(scheduler.run().is_ok());
+        assert!(scheduler.run().is_ok());
+
+        let overruns = overruns.lock().unwrap();
+        assert_eq!(overruns.len(), 2);
+        for &(id, overrun) in overruns.iter() {
+            assert_eq!(id, 1);
+            assert!(overrun >= std::time::Duration::from_millis(30));
+            assert!(overrun < std::time::Duration::from_millis(500));
+        }
+        let counts: Vec<u64> = scheduler
+            .snapshot()
+            .tasks
+            .iter()
+            .map(|t| t.overrun_count)
+            .collect();
+        assert_eq!(counts, vec![2, 0]);
+    }
+
+    /// Backend that only counts `yield_cpu` calls
+    struct CountingBackend {
+        yields: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl SchedulerBackend for CountingBackend {
+        fn schedule_task(
+            &mut self,
+            _task_id: u32,
+        ) -> Result<(), room619_core::platform::PlatformError> {
+            Ok(())
+        }
+
+        fn yield_cpu(&self) {
+            self.yields
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+
+        fn current_task_id(&self) -> u32 {
+            0
+        }
+    }
+
+    #[test]
+    fn test_cooperative_scheduler_yields_every_n_tasks() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let tasks: Vec<Task> = (1..=6).map(|id| Task::new(id, 0, 0)).collect();
+        let (mut inner, log) = recording_scheduler(SchedulingPolicy::Priority, &tasks);
+        assert!(inner.disable_task(6).is_ok());
+        let yields = Arc::new(AtomicUsize::new(0));
+        let backend = CountingBackend {
+            yields: Arc::clone(&yields),
+        };
+        let mut scheduler = CooperativeScheduler::new(inner, backend).with_yield_every(2);
+
+        // Five runnable tasks: yields before the 3rd and 5th, none at the end
+        assert!(scheduler.run().is_ok());
+        assert_eq!(yields.load(Ordering::SeqCst), 2);
+        assert_eq!(*log.lock().unwrap(), vec![1, 2, 3, 4, 5]);
+
+        let mut scheduler = CooperativeScheduler::new(
+            scheduler.into_inner(),
+            CountingBackend {
+                yields: Arc::clone(&yields),
+            },
+        );
+        yields.store(0, Ordering::SeqCst);
+        assert!(scheduler.run().is_ok());
+        assert_eq!(yields.load(Ordering::SeqCst), 4);
+        assert_eq!(scheduler.inner().snapshot().tasks[0].run_count, 2);
+    }
+
+    #[test]
+    fn test_watchdog_kicks_prevent_expiry() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let fired = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&fired);
+        let mut watchdog = room619_core::watchdog::Watchdog::new();
+        assert!(watchdog
+            .start(
+                std::time::Duration::from_millis(50),
+                Box::new(move || {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                }),
+            )
+            .is_ok());
+
+        for _ in 0..10 {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            watchdog.kick();
+        }
+        assert_eq!(fired.load(Ordering::SeqCst), 0);
+        assert!(watchdog.stop().is_ok());
+        assert!(!watchdog.is_running());
+    }
+
+    #[test]
+    fn test_watchdog_fires_once_without_kicks() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let fired = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&fired);
+        let mut watchdog = room619_core::watchdog::Watchdog::new();
+        assert!(watchdog
+            .start(
+                std::time::Duration::from_millis(20),
+                Box::new(move || {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                }),
+            )
+            .is_ok());
+
+        std::thread::sleep(std::time::Duration::from_millis(120));
+        assert_eq!(fired.load(Ordering::SeqCst), 1);
+        assert!(watchdog.stop().is_ok());
+    }
+
+    #[test]
+    fn test_watchdog_zero_timeout_fires_once_per_kick() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let fired = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&fired);
+        let mut watchdog = room619_core::watchdog::Watchdog::new();
+        assert!(watchdog
+            .start(
+                std::time::Duration::ZERO,
+                Box::new(move || {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                }),
+            )
+            .is_ok());
+
+        std::thread::sleep(std::time::Duration::from_millis(30));
+        assert_eq!(fired.load(Ordering::SeqCst), 1);
+        watchdog.kick();
+        std::thread::sleep(std::time::Duration::from_millis(30));
+        assert_eq!(fired.load(Ordering::SeqCst), 2);
+
+        // The fired monitor is parked, not spinning, and still wakes to stop
+        let started = std::time::Instant::now();
+        assert!(watchdog.stop().is_ok());
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_thread_pool_runs_tasks_concurrently() {
+        let mut scheduler = ThreadPoolScheduler::new(4);
+        assert_eq!(scheduler.num_workers(), 4);
+        for id in 0..4 {
+            scheduler
+                .add_task_with_handler(Task::new(id, 1, 1_000), || {
+                    std::thread::sleep(std::time::Duration::from_millis(150))
+                })
+                .unwrap();
+        }
+
+        let start = std::time::Instant::now();
+        assert!(scheduler.run().is_ok());
+        assert!(scheduler.shutdown().is_ok());
+
+        // Serial execution would take 600ms
+        assert!(start.elapsed() < std::time::Duration::from_millis(400));
+        assert_eq!(scheduler.num_workers(), 0);
+    }
+
+    #[test]
+    fn test_thread_pool_dispatches_by_priority() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut scheduler = ThreadPoolScheduler::new(1);
+        for task in [Task::new(1, 1, 0), Task::new(2, 9, 0), Task::new(3, 5, 0)] {
+            let log = Arc::clone(&log);
+            scheduler
+                .add_task_with_handler(task, move || log.lock().unwrap().push(task.id))
+                .unwrap();
+        }
+
+        assert!(scheduler.run().is_ok());
+        assert!(scheduler.shutdown().is_ok());
+        assert_eq!(*log.lock().unwrap(), vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn test_thread_pool_skips_overrunning_task() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&runs);
+        let mut scheduler = ThreadPoolScheduler::new(2);
+        scheduler
+            .add_task_with_handler(Task::new(1, 1, 0), move || {
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .unwrap();
+
+        assert!(scheduler.run().is_ok());
+        assert!(scheduler.run().is_ok());
+        assert!(scheduler.run().is_ok());
+        assert_eq!(scheduler.skipped_runs(), 2);
+
+        assert!(scheduler.shutdown().is_ok());
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert!(scheduler.run().is_err());
+    }
+
+    #[test]
+    fn test_thread_pool_shutdown_waits_for_in_flight_tasks() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let done = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&done);
+        let mut scheduler = ThreadPoolScheduler::new(2);
+        scheduler
+            .add_task_with_handler(Task::new(1, 1, 100), move || {
+                std::thread::sleep(std::time::Duration::from_millis(80));
+                flag.store(true, Ordering::SeqCst);
+            })
+            .unwrap();
+
+        assert!(scheduler.run().is_ok());
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        assert!(!done.load(Ordering::SeqCst));
+        assert!(scheduler.shutdown().is_ok());
+        assert!(done.load(Ordering::SeqCst));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_cooperative_scheduler_run_async() {
+        use room619_core::platform::AsyncSchedulerBackend;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        // Single-threaded runtime: the spawned task only runs if a yield
+        // happens between the two scheduler tasks
+        let other_ran = Arc::new(AtomicBool::new(false));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut inner = DefaultScheduler::new();
+        for id in 1..=2 {
+            let other_ran = Arc::clone(&other_ran);
+            let seen = Arc::clone(&seen);
+            inner
+                .add_task_with_handler(Task::new(id, 0, 10), move || {
+                    seen.lock().unwrap().push(other_ran.load(Ordering::SeqCst))
+                })
+                .unwrap();
+        }
+        let mut scheduler = CooperativeScheduler::new(inner, AsyncSchedulerBackend::new());
+
+        let flag = Arc::clone(&other_ran);
+        let other = tokio::spawn(async move { flag.store(true, Ordering::SeqCst) });
+        assert!(scheduler.run_async().await.is_ok());
+        assert_eq!(*seen.lock().unwrap(), vec![false, true]);
+        assert!(other.await.is_ok());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_interval_timer() {
+        use room619_core::timer::{AsyncIntervalTimer, MissedTicks};
+
+        let period = std::time::Duration::from_millis(20);
+        let mut timer = AsyncIntervalTimer::new(period);
+        timer.set_missed_tick_behavior(MissedTicks::Skip);
+        assert_eq!(timer.period(), period);
+        assert_eq!(timer.missed_tick_behavior(), MissedTicks::Skip);
+
+        let start = std::time::Instant::now();
+        for _ in 0..3 {
+            timer.tick().await;
+        }
+        assert!(start.elapsed() >= std::time::Duration::from_millis(40));
+    }
+
+    #[cfg(feature = "embedded")]
+    #[test]
+    fn test_embedded_platform_and_timer() {
+        use room619_core::platform::EmbeddedPlatform;
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        let ticks = Arc::new(AtomicU64::new(1_000));
+        let source = Arc::clone(&ticks);
+        let mut platform =
+            EmbeddedPlatform::new(Arc::new(move || source.load(Ordering::SeqCst)), 1_000);
+        assert_eq!(platform.platform_name(), "Embedded");
+        assert!(platform.start().is_ok());
+
+        let mut timer = platform.timer();
+        assert!(!timer.is_running());
+        assert_eq!(timer.elapsed(), std::time::Duration::ZERO);
+
+        assert!(timer.start().is_ok());
+        ticks.fetch_add(250, Ordering::SeqCst);
+        assert_eq!(timer.elapsed(), std::time::Duration::from_millis(250));
+
+        ticks.fetch_add(1_750, Ordering::SeqCst);
+        assert_eq!(timer.elapsed(), std::time::Duration::from_secs(2));
+
+        assert!(timer.stop().is_ok());
+        assert!(!timer.is_running());
+        assert!(platform.stop().is_ok());
+
+        let caps = platform.capabilities();
+        assert_eq!(caps.num_cpus, 1);
+        assert!(!caps.high_res_timer);
+        assert!(!caps.preemptive_scheduling);
+        assert!(!caps.supports_async);
+    }
+
+    #[cfg(feature = "embedded")]
+    #[test]
+    fn test_embedded_timer_handles_tick_rollover() {
+        use room619_core::platform::EmbeddedTimer;
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        let ticks = Arc::new(AtomicU64::new(u64::MAX - 5));
+        let source = Arc::clone(&ticks);
+        let mut timer = EmbeddedTimer::new(Arc::new(move || source.load(Ordering::SeqCst)), 10);
+
+        assert!(timer.start().is_ok());
+        ticks.store(4, Ordering::SeqCst);
+        assert_eq!(timer.elapsed(), std::time::Duration::from_secs(1));
+    }
 }
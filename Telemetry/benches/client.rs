@@ -0,0 +1,97 @@
+//! Client send-path and serialization benchmarks.
+//!
+//! Run from the `Telemetry` directory with
+//!
+//! ```text
+//! cargo bench --bench client
+//! cargo bench --bench client --features msgpack   # include MessagePack
+//! cargo bench --bench client -- send_message/null # filter by name
+//! ```
+//!
+//! Criterion keeps the previous run under `target/criterion` and reports
+//! the change against it, so run once on the base commit and again on the
+//! change to spot regressions. `NullSink` isolates client overhead;
+//! `InMemorySink` and `BufferingSink` add a typical terminal sink and
+//! decorator on top.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use telemetry::{
+    BufferingSink, InMemorySink, NullSink, RecordOverflow, TelemetryClient, TelemetryMessage,
+    TelemetrySink,
+};
+
+/// Payloads of increasing size: one reading, a sensor frame and a batch.
+fn payloads() -> Vec<(&'static str, Value)> {
+    let reading =
+        |i: usize| json!({"sensor": format!("s{}", i), "value": i as f64 * 0.5, "ok": true});
+    vec![
+        ("small", json!({"value": 21.5, "unit": "C"})),
+        (
+            "medium",
+            json!({"readings": (0..16).map(reading).collect::<Vec<_>>()}),
+        ),
+        (
+            "large",
+            json!({"readings": (0..256).map(reading).collect::<Vec<_>>()}),
+        ),
+    ]
+}
+
+fn message(payload: &Value) -> TelemetryMessage {
+    TelemetryMessage::builder()
+        .topic("bench/sensors")
+        .payload(payload.clone())
+        .timestamp(1_700_000_000_000)
+        .header("service", "bench")
+        .build()
+        .expect("valid message")
+}
+
+type MakeSink = fn() -> Arc<dyn TelemetrySink>;
+
+fn send_message(c: &mut Criterion) {
+    let sinks: Vec<(&str, MakeSink)> = vec![
+        ("null", || Arc::new(NullSink)),
+        // Capped so long runs do not grow memory without bound
+        ("in_memory", || {
+            Arc::new(InMemorySink::with_capacity(
+                1024,
+                RecordOverflow::DropOldest,
+            ))
+        }),
+        ("buffering", || Arc::new(BufferingSink::new(NullSink, 64))),
+    ];
+    let mut group = c.benchmark_group("send_message");
+    group.throughput(Throughput::Elements(1));
+    for (size, payload) in payloads() {
+        let msg = message(&payload);
+        for (name, make_sink) in &sinks {
+            let client = TelemetryClient::new(make_sink());
+            group.bench_with_input(BenchmarkId::new(*name, size), &msg, |b, msg| {
+                b.iter(|| client.send_message(black_box(msg)).expect("send"))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn serialization(c: &mut Criterion) {
+    let mut group = c.benchmark_group("serialize");
+    for (size, payload) in payloads() {
+        let msg = message(&payload);
+        group.throughput(Throughput::Bytes(msg.to_json().len() as u64));
+        group.bench_with_input(BenchmarkId::new("json", size), &msg, |b, msg| {
+            b.iter(|| black_box(msg).to_json())
+        });
+        #[cfg(feature = "msgpack")]
+        group.bench_with_input(BenchmarkId::new("msgpack", size), &msg, |b, msg| {
+            b.iter(|| black_box(msg).to_msgpack().expect("encode"))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, send_message, serialization);
+criterion_main!(benches);
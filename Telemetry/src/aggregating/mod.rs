@@ -0,0 +1,368 @@
+//! Aggregating sink decorator.
+//!
+//! Replaces a stream of numeric readings with one summary per topic and
+//! window, cutting traffic for high-rate sensors whose consumers only need
+//! count/min/max/mean.
+
+use crate::auto_flush::AutoFlusher;
+use crate::clock::{elapsed_since, ClockCell};
+use crate::{layered_sink_name, Clock, TelemetryError, TelemetryResult, TelemetrySink};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+/// Suffix appended to a topic to form its summary topic.
+pub const DEFAULT_SUMMARY_SUFFIX: &str = "/agg";
+
+/// Statistics for one topic over one window, emitted as JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AggregateSummary {
+    pub count: u64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// Window bounds in milliseconds since the Unix epoch.
+    pub window_start: i64,
+    pub window_end: i64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Stats {
+    count: u64,
+    min: f64,
+    max: f64,
+    sum: f64,
+}
+
+impl Stats {
+    fn new(value: f64) -> Self {
+        Stats {
+            count: 1,
+            min: value,
+            max: value,
+            sum: value,
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += value;
+    }
+}
+
+#[derive(Default)]
+struct Window {
+    start: Option<i64>,
+    stats: BTreeMap<String, Stats>,
+}
+
+impl Window {
+    /// Close the window at `end` and return its summaries, oldest topic first.
+    fn drain(&mut self, end: i64) -> Vec<(String, AggregateSummary)> {
+        let start = self.start.take().unwrap_or(end);
+        std::mem::take(&mut self.stats)
+            .into_iter()
+            .map(|(topic, s)| {
+                let summary = AggregateSummary {
+                    count: s.count,
+                    min: s.min,
+                    max: s.max,
+                    mean: s.sum / s.count as f64,
+                    window_start: start,
+                    window_end: end,
+                };
+                (topic, summary)
+            })
+            .collect()
+    }
+}
+
+/// State shared with the poll thread.
+struct Core<S: TelemetrySink> {
+    inner: S,
+    window: Duration,
+    /// Behind a lock so `with_summary_suffix` works after polling started.
+    suffix: RwLock<String>,
+    clock: ClockCell,
+    state: Mutex<Window>,
+}
+
+impl<S: TelemetrySink> Core<S> {
+    fn poll(&self) -> TelemetryResult<()> {
+        let now = self.clock.now_millis();
+        let due = {
+            let mut window = self.lock()?;
+            match window.start {
+                Some(start) if elapsed_since(now, start) >= self.window => window.drain(now),
+                _ => Vec::new(),
+            }
+        };
+        self.emit(due)
+    }
+
+    fn lock(&self) -> TelemetryResult<std::sync::MutexGuard<'_, Window>> {
+        self.state
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))
+    }
+
+    fn emit(&self, summaries: Vec<(String, AggregateSummary)>) -> TelemetryResult<()> {
+        if summaries.is_empty() {
+            return Ok(());
+        }
+        let suffix = self
+            .suffix
+            .read()
+            .map_or_else(|poisoned| poisoned.into_inner().clone(), |s| s.clone());
+        for (topic, summary) in summaries {
+            let payload = serde_json::to_vec(&summary)
+                .map_err(|e| TelemetryError::Serialization(format!("aggregate summary: {}", e)))?;
+            self.inner.send(&format!("{}{}", topic, suffix), &payload)?;
+        }
+        Ok(())
+    }
+}
+
+/// A sink that folds `{"value": <number>}` payloads (with no other fields)
+/// into periodic summaries.
+///
+/// Numeric readings are absorbed; once the current window has elapsed, the
+/// next reading or `poll` (or the poll thread started by
+/// `with_poll_interval`) sends a summary to `{topic}/agg` for every topic
+/// seen in that window, and the next reading starts a new window. `flush`
+/// emits the partial window. Any other payload is forwarded unchanged.
+pub struct AggregatingSink<S: TelemetrySink> {
+    poller: Option<AutoFlusher>,
+    core: Arc<Core<S>>,
+}
+
+impl<S: TelemetrySink> AggregatingSink<S> {
+    /// Summarise readings over consecutive windows of `window`.
+    pub fn new(inner: S, window: Duration) -> Self {
+        Self {
+            poller: None,
+            core: Arc::new(Core {
+                inner,
+                window,
+                suffix: RwLock::new(DEFAULT_SUMMARY_SUFFIX.to_string()),
+                clock: ClockCell::default(),
+                state: Mutex::new(Window::default()),
+            }),
+        }
+    }
+
+    /// Measure windows with `clock` instead of the system clock.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        self.core.clock.set(clock);
+        self
+    }
+
+    /// Use `suffix` instead of `/agg` for summary topics.
+    pub fn with_summary_suffix(self, suffix: impl Into<String>) -> Self {
+        let suffix = suffix.into();
+        match self.core.suffix.write() {
+            Ok(mut current) => *current = suffix,
+            Err(poisoned) => *poisoned.into_inner() = suffix,
+        }
+        self
+    }
+
+    /// Interval of the poll thread, if one is running.
+    pub fn poll_interval(&self) -> Option<Duration> {
+        self.poller.as_ref().map(AutoFlusher::interval)
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.core.inner
+    }
+
+    /// Emit the current window's summaries if the window has elapsed.
+    pub fn poll(&self) -> TelemetryResult<()> {
+        self.core.poll()
+    }
+}
+
+impl<S: TelemetrySink + 'static> AggregatingSink<S> {
+    /// Also `poll` every `interval` from a background thread, so the last
+    /// window's summaries are sent on time when readings stop (see the
+    /// crate docs).
+    ///
+    /// `None` stops a running thread; it is also stopped and joined when
+    /// the sink is dropped.
+    pub fn with_poll_interval(mut self, interval: Option<Duration>) -> Self {
+        self.poller = None;
+        self.poller = interval.map(|interval| {
+            let core = Arc::clone(&self.core);
+            AutoFlusher::spawn("aggregating", interval, move || core.poll())
+        });
+        self
+    }
+}
+
+/// Extract `n` from a `{"value": n}` payload.
+fn numeric_value(payload: &[u8]) -> Option<f64> {
+    let value: serde_json::Value = serde_json::from_slice(payload).ok()?;
+    let object = value.as_object()?;
+    if object.len() != 1 {
+        return None;
+    }
+    object.get("value")?.as_f64()
+}
+
+impl<S: TelemetrySink> TelemetrySink for AggregatingSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("aggregating", &[self.core.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let core = &self.core;
+        let Some(value) = numeric_value(payload) else {
+            return core.inner.send(topic, payload);
+        };
+        let now = core.clock.now_millis();
+        let due = {
+            let mut window = core.lock()?;
+            let due = match window.start {
+                Some(start) if elapsed_since(now, start) >= core.window => window.drain(now),
+                _ => Vec::new(),
+            };
+            window.start.get_or_insert(now);
+            window
+                .stats
+                .entry(topic.to_string())
+                .and_modify(|s| s.add(value))
+                .or_insert_with(|| Stats::new(value));
+            due
+        };
+        core.emit(due)
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        let now = self.core.clock.now_millis();
+        let partial = self.core.lock()?.drain(now);
+        self.core.emit(partial)?;
+        self.core.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.core.inner.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemorySink, MockClock};
+
+    fn summaries(sink: &AggregatingSink<InMemorySink>) -> Vec<(String, AggregateSummary)> {
+        sink.inner()
+            .records
+            .lock()
+            .expect("lock")
+            .iter()
+            .filter(|(topic, _)| topic.ends_with("/agg"))
+            .map(|(topic, payload)| {
+                (
+                    topic.clone(),
+                    serde_json::from_slice(payload).expect("summary"),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn emits_summary_when_window_elapses() {
+        let clock = MockClock::new(10_000);
+        let sink = AggregatingSink::new(InMemorySink::new(), Duration::from_secs(1))
+            .with_clock(Arc::new(clock.clone()));
+
+        for v in [3.0, 1.0, 5.0, 3.0] {
+            sink.send("sensors/temp", format!("{{\"value\":{}}}", v).as_bytes())
+                .expect("send");
+            clock.advance(Duration::from_millis(100));
+        }
+        sink.send("sensors/hum", br#"{"value": 40}"#).expect("send");
+        assert!(summaries(&sink).is_empty());
+
+        clock.set(11_000);
+        sink.send("sensors/temp", br#"{"value": 100}"#)
+            .expect("send");
+
+        let emitted = summaries(&sink);
+        assert_eq!(emitted.len(), 2);
+        assert_eq!(emitted[0].0, "sensors/hum/agg");
+        let (topic, temp) = &emitted[1];
+        assert_eq!(topic, "sensors/temp/agg");
+        assert_eq!(temp.count, 4);
+        assert_eq!(temp.min, 1.0);
+        assert_eq!(temp.max, 5.0);
+        assert_eq!(temp.mean, 3.0);
+        assert_eq!((temp.window_start, temp.window_end), (10_000, 11_000));
+
+        sink.flush().expect("flush");
+        let emitted = summaries(&sink);
+        assert_eq!(emitted.len(), 3);
+        assert_eq!(emitted[2].1.count, 1);
+        assert_eq!(emitted[2].1.mean, 100.0);
+        assert_eq!(emitted[2].1.window_start, 11_000);
+    }
+
+    #[test]
+    fn poll_closes_an_elapsed_window_without_new_readings() {
+        let clock = MockClock::new(0);
+        let sink = AggregatingSink::new(InMemorySink::new(), Duration::from_secs(1))
+            .with_clock(Arc::new(clock.clone()));
+        sink.send("t", br#"{"value": 2}"#).expect("send");
+
+        clock.advance(Duration::from_millis(999));
+        sink.poll().expect("poll");
+        assert!(summaries(&sink).is_empty());
+
+        clock.advance(Duration::from_millis(1));
+        sink.poll().expect("poll");
+        let emitted = summaries(&sink);
+        assert_eq!(emitted.len(), 1);
+        assert_eq!(
+            (emitted[0].1.window_start, emitted[0].1.window_end),
+            (0, 1_000)
+        );
+
+        sink.poll().expect("idle poll");
+        assert_eq!(summaries(&sink).len(), 1);
+    }
+
+    #[test]
+    fn non_numeric_payloads_pass_through() {
+        let sink = AggregatingSink::new(InMemorySink::new(), Duration::from_secs(1));
+        sink.send("status", b"online").expect("send");
+        sink.send("event", br#"{"value": "high"}"#).expect("send");
+        sink.send("reading", br#"{"value": 1, "unit": "C"}"#)
+            .expect("send");
+
+        let records = sink.inner().records.lock().expect("lock").clone();
+        let topics: Vec<&str> = records.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(topics, vec!["status", "event", "reading"]);
+    }
+
+    #[test]
+    fn poll_thread_closes_window_without_new_readings() {
+        let clock = MockClock::new(0);
+        let sink = AggregatingSink::new(InMemorySink::new(), Duration::from_secs(1))
+            .with_clock(Arc::new(clock.clone()))
+            .with_poll_interval(Some(Duration::from_millis(5)));
+        assert_eq!(sink.poll_interval(), Some(Duration::from_millis(5)));
+        sink.send("t", br#"{"value": 2}"#).expect("send");
+        clock.advance(Duration::from_secs(1));
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while summaries(&sink).is_empty() {
+            assert!(std::time::Instant::now() < deadline, "window never closed");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(summaries(&sink)[0].1.count, 1);
+    }
+}
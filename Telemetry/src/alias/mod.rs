@@ -0,0 +1,256 @@
+//! Topic aliasing sink decorator.
+//!
+//! Rewrites topics on their way to the inner sink, so a naming convention
+//! can change without touching every producer: old topics are mapped to new
+//! ones in one place while the producers migrate.
+
+use crate::topic::TopicFilter;
+use crate::{layered_sink_name, TelemetryError, TelemetryResult, TelemetrySink};
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    /// 1-based index of a wildcard capture.
+    Capture(usize),
+}
+
+/// One `pattern → replacement` rewrite rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AliasRule {
+    pattern: TopicFilter,
+    replacement: String,
+    parts: Vec<Part>,
+}
+
+impl AliasRule {
+    /// Rewrite topics matching `pattern` to `replacement`.
+    ///
+    /// In the replacement, `$1`, `$2`, ... stand for the levels matched by
+    /// the pattern's wildcards, in order, and `$$` for a literal `$`. Fails
+    /// if the pattern is malformed or the replacement refers to a wildcard
+    /// the pattern does not have.
+    pub fn new(
+        pattern: impl Into<String>,
+        replacement: impl Into<String>,
+    ) -> TelemetryResult<Self> {
+        let pattern = TopicFilter::new(pattern)?;
+        let replacement = replacement.into();
+        let wildcards = pattern
+            .as_str()
+            .split('/')
+            .filter(|level| matches!(*level, "+" | "#"))
+            .count();
+        let parts = parse_replacement(&replacement, wildcards)?;
+        Ok(Self {
+            pattern,
+            replacement,
+            parts,
+        })
+    }
+
+    pub fn pattern(&self) -> &TopicFilter {
+        &self.pattern
+    }
+
+    pub fn replacement(&self) -> &str {
+        &self.replacement
+    }
+
+    /// `topic` rewritten by this rule, or `None` if it does not match.
+    pub fn apply(&self, topic: &str) -> Option<String> {
+        let captures = self.pattern.captures(topic)?;
+        let mut out = String::with_capacity(self.replacement.len() + topic.len());
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => out.push_str(text),
+                Part::Capture(n) => out.push_str(captures[n - 1]),
+            }
+        }
+        Some(out)
+    }
+}
+
+fn parse_replacement(replacement: &str, wildcards: usize) -> TelemetryResult<Vec<Part>> {
+    let invalid = |reason: String| {
+        TelemetryError::new(format!(
+            "invalid topic replacement '{}': {}",
+            replacement, reason
+        ))
+    };
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = replacement.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            literal.push(c);
+            continue;
+        }
+        if chars.peek() == Some(&'$') {
+            chars.next();
+            literal.push('$');
+            continue;
+        }
+        let mut digits = String::new();
+        while let Some(d) = chars.next_if(char::is_ascii_digit) {
+            digits.push(d);
+        }
+        let index: usize = digits
+            .parse()
+            .map_err(|_| invalid("'$' must be followed by a capture number or '$'".to_string()))?;
+        if index == 0 || index > wildcards {
+            return Err(invalid(format!(
+                "${} does not name one of the pattern's {} wildcard(s)",
+                index, wildcards
+            )));
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(std::mem::take(&mut literal)));
+        }
+        parts.push(Part::Capture(index));
+    }
+    if !literal.is_empty() {
+        parts.push(Part::Literal(literal));
+    }
+    Ok(parts)
+}
+
+/// A sink that renames topics by the first matching `AliasRule`.
+///
+/// Rules are tried in order; topics matching none are forwarded unchanged.
+pub struct AliasSink<S: TelemetrySink> {
+    inner: S,
+    rules: Vec<AliasRule>,
+}
+
+impl<S: TelemetrySink> AliasSink<S> {
+    /// Rewrite with `(pattern, replacement)` rules, e.g.
+    /// `("sensors/+/temp", "v2/sensors/$1/temperature")`.
+    ///
+    /// Fails if any rule is invalid (see `AliasRule::new`).
+    pub fn new<I, P, R>(inner: S, rules: I) -> TelemetryResult<Self>
+    where
+        I: IntoIterator<Item = (P, R)>,
+        P: Into<String>,
+        R: Into<String>,
+    {
+        let rules = rules
+            .into_iter()
+            .map(|(pattern, replacement)| AliasRule::new(pattern, replacement))
+            .collect::<TelemetryResult<Vec<_>>>()?;
+        Ok(Self { inner, rules })
+    }
+
+    /// Configured rules, in the order they are tried.
+    pub fn rules(&self) -> &[AliasRule] {
+        &self.rules
+    }
+
+    /// The topic `topic` is forwarded to.
+    pub fn rewrite<'a>(&self, topic: &'a str) -> Cow<'a, str> {
+        self.rules
+            .iter()
+            .find_map(|rule| rule.apply(topic))
+            .map_or(Cow::Borrowed(topic), Cow::Owned)
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for AliasSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("alias", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        self.inner.send(&self.rewrite(topic), payload)
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemorySink;
+
+    fn sink() -> AliasSink<InMemorySink> {
+        AliasSink::new(
+            InMemorySink::new(),
+            [
+                ("sensors/+/temp", "v2/sensors/$1/temperature"),
+                ("sensors/+/+", "v2/sensors/$1/$2"),
+                ("legacy/#", "archive/$1"),
+                ("price/+", "cost/$$/$1"),
+            ],
+        )
+        .expect("rules")
+    }
+
+    fn topics(sink: &AliasSink<InMemorySink>) -> Vec<String> {
+        sink.inner()
+            .records
+            .lock()
+            .expect("lock")
+            .iter()
+            .map(|(topic, _)| topic.clone())
+            .collect()
+    }
+
+    #[test]
+    fn wildcard_captures_are_substituted() {
+        let sink = sink();
+        sink.send("sensors/lab/temp", b"21").expect("send");
+        sink.send("sensors/lab/humidity", b"40").expect("send");
+        sink.send("legacy/a/b", b"x").expect("send");
+        sink.send("price/eur", b"x").expect("send");
+
+        assert_eq!(
+            topics(&sink),
+            [
+                "v2/sensors/lab/temperature",
+                "v2/sensors/lab/humidity",
+                "archive/a/b",
+                "cost/$/eur",
+            ]
+        );
+        assert_eq!(sink.inner().records.lock().expect("lock")[0].1, b"21");
+    }
+
+    #[test]
+    fn unmatched_topic_is_forwarded_verbatim() {
+        let sink = sink();
+        sink.send("alerts/fire", b"x").expect("send");
+        assert_eq!(topics(&sink), ["alerts/fire"]);
+        assert!(matches!(sink.rewrite("alerts/fire"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        for (pattern, replacement) in [
+            ("sensors/#/temp", "x"),
+            ("sensors/+", "x/$2"),
+            ("sensors/+", "x/$0"),
+            ("sensors/+", "x/$"),
+        ] {
+            assert!(
+                AliasRule::new(pattern, replacement).is_err(),
+                "{} -> {}",
+                pattern,
+                replacement
+            );
+        }
+        let rule = AliasRule::new("a/+", "b/$1").expect("rule");
+        assert_eq!(rule.pattern().as_str(), "a/+");
+        assert_eq!(rule.replacement(), "b/$1");
+    }
+}
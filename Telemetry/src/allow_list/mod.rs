@@ -0,0 +1,118 @@
+//! Topic allow-list sink decorator.
+//!
+//! Confines a module to its own namespace in a multi-tenant service: only
+//! topics matching one of the configured MQTT-style patterns (`+` for one
+//! level, `#` for the remaining levels) reach the inner sink.
+
+use crate::topic::TopicFilter;
+use crate::{layered_sink_name, TelemetryError, TelemetryResult, TelemetrySink};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A sink that rejects sends to topics outside its allowed patterns.
+///
+/// Rejected sends return an error without touching the inner sink and are
+/// counted in `rejected_count`.
+pub struct AllowListSink<S: TelemetrySink> {
+    inner: S,
+    patterns: Vec<TopicFilter>,
+    rejected: AtomicU64,
+}
+
+impl<S: TelemetrySink> AllowListSink<S> {
+    /// Allow only topics matching one of `patterns`, e.g. `tenantA/#`.
+    ///
+    /// Fails if a pattern is malformed (`#` not last, `+` sharing a level).
+    pub fn new<I, P>(inner: S, patterns: I) -> TelemetryResult<Self>
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<String>,
+    {
+        let patterns = patterns
+            .into_iter()
+            .map(TopicFilter::new)
+            .collect::<TelemetryResult<Vec<_>>>()?;
+        Ok(Self {
+            inner,
+            patterns,
+            rejected: AtomicU64::new(0),
+        })
+    }
+
+    /// Configured patterns.
+    pub fn patterns(&self) -> &[TopicFilter] {
+        &self.patterns
+    }
+
+    /// Whether `topic` may be published.
+    pub fn is_allowed(&self, topic: &str) -> bool {
+        self.patterns.iter().any(|p| p.matches(topic))
+    }
+
+    /// Sends rejected so far.
+    pub fn rejected_count(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for AllowListSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("allow_list", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        if !self.is_allowed(topic) {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(TelemetryError::new(format!(
+                "topic '{}' is not in the allow-list",
+                topic
+            )));
+        }
+        self.inner.send(topic, payload)
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemorySink;
+
+    #[test]
+    fn tenant_namespace_passes_and_others_are_rejected() {
+        let sink = AllowListSink::new(InMemorySink::new(), ["tenantA/#"]).expect("patterns");
+
+        sink.send("tenantA/sensors/temp", b"1").expect("allowed");
+        let err = sink
+            .send("tenantB/sensors/temp", b"2")
+            .expect_err("rejected");
+        assert_eq!(
+            err.message(),
+            "topic 'tenantB/sensors/temp' is not in the allow-list"
+        );
+        assert!(sink.send("tenantAB/x", b"3").is_err());
+
+        assert_eq!(sink.rejected_count(), 2);
+        assert_eq!(
+            *sink.inner().records.lock().expect("lock"),
+            vec![("tenantA/sensors/temp".to_string(), b"1".to_vec())]
+        );
+    }
+
+    #[test]
+    fn malformed_pattern_is_refused() {
+        assert!(AllowListSink::new(InMemorySink::new(), ["tenantA/#/x"]).is_err());
+        assert!(AllowListSink::new(InMemorySink::new(), ["tenant+/x"]).is_err());
+    }
+}
@@ -0,0 +1,122 @@
+//! Fire-and-forget client over a `QueueSink`.
+//!
+//! `AsyncTelemetryClient` returns as soon as a message is queued; delivery
+//! happens on the queue's worker. Callers that still need the outcome use
+//! `send_with_ack`, which reports it through a callback, so submission
+//! latency stays independent of delivery confirmation.
+
+use crate::{QueueSink, TelemetryMessage, TelemetryResult, TelemetrySink};
+use std::sync::Arc;
+
+/// A client that enqueues JSON-encoded `TelemetryMessage`s on a
+/// `QueueSink`.
+///
+/// Cloning is cheap; clones share the queue.
+#[derive(Clone)]
+pub struct AsyncTelemetryClient {
+    queue: Arc<QueueSink>,
+}
+
+impl AsyncTelemetryClient {
+    pub fn new(queue: Arc<QueueSink>) -> Self {
+        Self { queue }
+    }
+
+    /// The queue messages are submitted to.
+    pub fn queue(&self) -> &Arc<QueueSink> {
+        &self.queue
+    }
+
+    /// Enqueue `msg`; errors only report that it could not be queued.
+    pub fn send_message(&self, msg: &TelemetryMessage) -> TelemetryResult<()> {
+        let payload = msg.try_to_json()?;
+        self.queue.send(&msg.topic, payload.as_bytes())
+    }
+
+    /// Enqueue `msg` and call `on_complete` once with its delivery outcome.
+    ///
+    /// `on_complete` receives `Ok` once the inner sink accepts the message,
+    /// or the final error: the inner sink's (after the retries of any retry
+    /// layer inside the queue), or the queue's if the message was rejected,
+    /// evicted or expired. It runs on the queue's worker, or on the calling
+    /// thread if the message is rejected, so it should not block.
+    pub fn send_with_ack<F>(&self, msg: &TelemetryMessage, on_complete: F)
+    where
+        F: FnOnce(TelemetryResult<()>) + Send + 'static,
+    {
+        match msg.try_to_json() {
+            Ok(payload) => self
+                .queue
+                .send_with_ack(&msg.topic, payload.as_bytes(), on_complete),
+            Err(e) => on_complete(Err(e)),
+        }
+    }
+
+    /// Deliver everything queued and stop the queue's worker.
+    pub async fn shutdown(&self) -> TelemetryResult<()> {
+        self.queue.shutdown().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BlockingSink, OverflowPolicy, RetrySink, TelemetryError};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+    use tokio::sync::oneshot;
+
+    /// Sink that fails its first `failures` sends.
+    struct FlakySink {
+        failures: u32,
+        attempts: AtomicU32,
+    }
+
+    impl TelemetrySink for FlakySink {
+        fn send(&self, _topic: &str, _payload: &[u8]) -> TelemetryResult<()> {
+            if self.attempts.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(TelemetryError::Connection("broker down".into()));
+            }
+            Ok(())
+        }
+    }
+
+    fn client(failures: u32) -> AsyncTelemetryClient {
+        let flaky = FlakySink {
+            failures,
+            attempts: AtomicU32::new(0),
+        };
+        let inner = BlockingSink::new(RetrySink::new(flaky, 3, Duration::ZERO));
+        AsyncTelemetryClient::new(Arc::new(QueueSink::new(inner, 8, OverflowPolicy::Reject)))
+    }
+
+    async fn outcome(client: &AsyncTelemetryClient) -> TelemetryResult<()> {
+        let (tx, rx) = oneshot::channel();
+        client.send_with_ack(&TelemetryMessage::new("t", json!(1)), move |result| {
+            let _ = tx.send(result);
+        });
+        rx.await.expect("ack called")
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn ack_reports_success_after_retries() {
+        let client = client(2);
+        assert_eq!(outcome(&client).await, Ok(()));
+        client.shutdown().await.expect("shutdown");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn ack_reports_final_failure() {
+        let client = client(u32::MAX);
+        let err = outcome(&client).await.expect_err("never delivered");
+        assert_eq!(
+            std::error::Error::source(&err).and_then(|e| e.downcast_ref()),
+            Some(&TelemetryError::Connection("broker down".into()))
+        );
+
+        client.shutdown().await.expect("shutdown");
+        let err = outcome(&client).await.expect_err("queue closed");
+        assert!(matches!(err, TelemetryError::Connection(_)));
+    }
+}
@@ -0,0 +1,111 @@
+//! In-memory `AsyncTelemetrySink` for tests.
+//!
+//! The async counterpart of `InMemorySink`: code written against
+//! `ArcAsyncSink` can be exercised without a transport and its output
+//! inspected afterwards.
+
+use crate::{AsyncTelemetrySink, SinkFuture, TelemetryRecord};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// An async sink that records every payload it is sent.
+#[derive(Default)]
+pub struct AsyncInMemorySink {
+    pub records: Arc<Mutex<Vec<TelemetryRecord>>>,
+    flushes: AtomicUsize,
+}
+
+impl AsyncInMemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of records held.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Remove and return every record held, oldest first.
+    pub fn drain(&self) -> Vec<TelemetryRecord> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    /// Times `flush` has been called.
+    pub fn flush_count(&self) -> usize {
+        self.flushes.load(Ordering::Relaxed)
+    }
+
+    /// Get a cloneable `Arc` to the internal storage.
+    pub fn records_arc(&self) -> Arc<Mutex<Vec<TelemetryRecord>>> {
+        Arc::clone(&self.records)
+    }
+
+    // Pushing a record cannot be interrupted halfway, so a poisoned lock is
+    // safe to keep using.
+    fn lock(&self) -> MutexGuard<'_, Vec<TelemetryRecord>> {
+        self.records
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl AsyncTelemetrySink for AsyncInMemorySink {
+    fn send<'a>(&'a self, topic: &'a str, payload: &'a [u8]) -> SinkFuture<'a> {
+        Box::pin(async move {
+            self.lock().push((topic.to_string(), payload.to_vec()));
+            Ok(())
+        })
+    }
+
+    fn flush(&self) -> SinkFuture<'_> {
+        Box::pin(async move {
+            self.flushes.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ArcAsyncSink, ConcurrencyLimitSink};
+
+    #[tokio::test]
+    async fn records_sends_through_a_trait_object() {
+        let memory = Arc::new(AsyncInMemorySink::new());
+        let sinks: Vec<ArcAsyncSink> = vec![memory.clone(), Arc::new(AsyncInMemorySink::new())];
+
+        for sink in &sinks {
+            sink.send("sensors/temp", b"21.5").await.expect("send");
+            sink.flush().await.expect("flush");
+        }
+
+        assert_eq!(
+            memory.drain(),
+            [("sensors/temp".to_string(), b"21.5".to_vec())]
+        );
+        assert_eq!(memory.flush_count(), 1);
+        assert!(memory.is_empty());
+    }
+
+    #[tokio::test]
+    async fn decorators_accept_a_shared_trait_object() {
+        let memory = Arc::new(AsyncInMemorySink::new());
+        let shared: ArcAsyncSink = memory.clone();
+        let limited = ConcurrencyLimitSink::new(Arc::clone(&shared), 2);
+        let boxed: Box<dyn AsyncTelemetrySink> = Box::new(limited);
+
+        for i in 0..3 {
+            boxed.send("t", &[i]).await.expect("send");
+        }
+        boxed.flush().await.expect("flush");
+
+        assert_eq!(memory.len(), 3);
+        assert_eq!(memory.records.lock().expect("lock")[2].1, [2]);
+        assert_eq!(memory.flush_count(), 1);
+    }
+}
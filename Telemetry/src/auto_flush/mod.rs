@@ -0,0 +1,74 @@
+//! Background thread driving a sink's timers.
+//!
+//! **Why?** Sinks that hold data until a window or timeout has passed only
+//! check the deadline when they are called, so without further traffic the
+//! last window stays open. An `AutoFlusher` calls them on an interval
+//! instead; the sinks offer it as an opt-in builder so nothing spawns a
+//! thread unless asked to.
+
+use crate::TelemetryResult;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Background thread running a tick every `interval`.
+///
+/// Dropping it wakes the thread and joins it.
+pub(crate) struct AutoFlusher {
+    interval: Duration,
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl AutoFlusher {
+    /// Run `tick` every `interval` until dropped, logging its failures
+    /// under `name`.
+    pub(crate) fn spawn<F>(name: &'static str, interval: Duration, tick: F) -> Self
+    where
+        F: Fn() -> TelemetryResult<()> + Send + 'static,
+    {
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+        let signal = Arc::clone(&stop);
+        let thread = std::thread::spawn(move || {
+            let (flag, wake) = &*signal;
+            let Ok(mut stopped) = flag.lock() else {
+                return;
+            };
+            loop {
+                stopped = match wake.wait_timeout_while(stopped, interval, |stop| !*stop) {
+                    Ok((guard, _)) => guard,
+                    Err(_) => return,
+                };
+                if *stopped {
+                    return;
+                }
+                if let Err(e) = tick() {
+                    log::warn!("{} auto-flush failed: {}", name, e);
+                }
+            }
+        });
+        Self {
+            interval,
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    /// Time between ticks.
+    pub(crate) fn interval(&self) -> Duration {
+        self.interval
+    }
+}
+
+impl Drop for AutoFlusher {
+    fn drop(&mut self) {
+        let (flag, wake) = &*self.stop;
+        if let Ok(mut stopped) = flag.lock() {
+            *stopped = true;
+            wake.notify_all();
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
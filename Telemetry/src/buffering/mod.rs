@@ -0,0 +1,366 @@
+//! Buffering sink decorator.
+//!
+//! Collects payloads in memory and forwards them to an inner sink once the
+//! buffer reaches its capacity, when `flush` is called explicitly, or, with
+//! an auto-flush interval, periodically from a background thread.
+//!
+//! Payloads that are `TelemetryMessage` envelopes are forwarded by descending
+//! `priority`, and envelopes whose `ttl_ms` ran out while buffered are dropped.
+
+use crate::auto_flush::AutoFlusher;
+use crate::clock::ClockCell;
+use crate::delivery::{insert_position, QueuedRecord};
+use crate::{
+    layered_sink_name, Clock, ShutdownSink, TelemetryError, TelemetryResult, TelemetrySink,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// State shared with the auto-flush thread.
+struct Core<S: TelemetrySink> {
+    inner: S,
+    capacity: usize,
+    /// Ordered by descending priority, FIFO within a priority.
+    buffer: Mutex<Vec<QueuedRecord>>,
+    /// Held for a whole drain so concurrent drains keep their order, while
+    /// `send` only needs `buffer`.
+    draining: Mutex<()>,
+    expired: AtomicU64,
+    clock: ClockCell,
+}
+
+impl<S: TelemetrySink> Core<S> {
+    /// Forward every buffered message to the inner sink in priority order,
+    /// skipping expired ones.
+    ///
+    /// The buffer is not locked while sending. If the inner sink fails, the
+    /// failed message and everything after it go back to the front of the
+    /// buffer so a later flush can retry them.
+    fn drain_buffer(&self) -> TelemetryResult<()> {
+        let _draining = self
+            .draining
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+        let pending = std::mem::take(&mut *self.lock_buffer()?);
+        let now = self.clock.now_millis();
+        let mut iter = pending.into_iter();
+        while let Some(record) = iter.next() {
+            if record.is_expired_at(now) {
+                self.expired.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            if let Err(e) = self.inner.send(&record.topic, &record.payload) {
+                let mut buffer = self.lock_buffer()?;
+                let unsent: Vec<QueuedRecord> = std::iter::once(record).chain(iter).collect();
+                buffer.splice(0..0, unsent);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    fn lock_buffer(&self) -> TelemetryResult<std::sync::MutexGuard<'_, Vec<QueuedRecord>>> {
+        self.buffer
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.drain_buffer()?;
+        self.inner.flush()
+    }
+}
+
+/// A sink that buffers payloads before forwarding them to an inner sink.
+///
+/// **Why buffer?** Transports with a high per-send cost (connection setup,
+/// framing) benefit from receiving messages in bursts instead of one by one.
+pub struct BufferingSink<S: TelemetrySink> {
+    core: Arc<Core<S>>,
+    flusher: Option<AutoFlusher>,
+}
+
+impl<S: TelemetrySink> BufferingSink<S> {
+    /// Create a buffering sink that flushes after `capacity` messages.
+    ///
+    /// A capacity of zero behaves like a capacity of one (no buffering).
+    pub fn new(inner: S, capacity: usize) -> Self {
+        Self {
+            core: Arc::new(Core {
+                inner,
+                capacity: capacity.max(1),
+                buffer: Mutex::new(Vec::new()),
+                draining: Mutex::new(()),
+                expired: AtomicU64::new(0),
+                clock: ClockCell::default(),
+            }),
+            flusher: None,
+        }
+    }
+
+    /// Judge TTL expiry against `clock`, e.g. a `MockClock` in tests.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        self.core.clock.set(clock);
+        self
+    }
+
+    /// Interval of the background flusher, if one is running.
+    pub fn auto_flush_interval(&self) -> Option<Duration> {
+        self.flusher.as_ref().map(AutoFlusher::interval)
+    }
+
+    /// Messages discarded at flush time because their TTL had run out.
+    pub fn expired_count(&self) -> u64 {
+        self.core.expired.load(Ordering::Relaxed)
+    }
+
+    /// Number of messages waiting to be flushed.
+    pub fn pending(&self) -> usize {
+        self.core.buffer.lock().map(|b| b.len()).unwrap_or(0)
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.core.inner
+    }
+}
+
+impl<S: TelemetrySink + 'static> BufferingSink<S> {
+    /// Also flush every `interval` from a background thread, so a trickle of
+    /// messages does not sit in the buffer until it fills up.
+    ///
+    /// `None` stops a running flusher. The thread is stopped and joined when
+    /// the sink is dropped.
+    pub fn with_auto_flush_interval(mut self, interval: Option<Duration>) -> Self {
+        // Join any previous flusher before starting its replacement
+        self.flusher = None;
+        self.flusher = interval.map(|interval| {
+            let core = Arc::clone(&self.core);
+            AutoFlusher::spawn("buffering", interval, move || core.flush())
+        });
+        self
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for BufferingSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("buffering", &[self.core.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let full = {
+            let mut buffer = self.core.lock_buffer()?;
+            let record = QueuedRecord::new(topic, payload, self.core.clock.now_millis());
+            let at = insert_position(buffer.iter(), record.priority);
+            buffer.insert(at, record);
+            buffer.len() >= self.core.capacity
+        };
+        if full {
+            self.core.drain_buffer()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.core.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.core.inner.health_check()
+    }
+}
+
+impl<S: TelemetrySink> ShutdownSink for BufferingSink<S> {
+    fn close(self) -> TelemetryResult<()> {
+        self.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Fault, FaultInjectionSink, InMemorySink, MockClock, TelemetryMessage};
+
+    #[test]
+    fn buffers_until_capacity() {
+        let inner = InMemorySink::new();
+        let records = inner.records_arc();
+        let sink = BufferingSink::new(inner, 3);
+
+        sink.send("a", b"1").expect("send");
+        sink.send("a", b"2").expect("send");
+        assert_eq!(records.lock().expect("lock").len(), 0);
+        assert_eq!(sink.pending(), 2);
+
+        sink.send("a", b"3").expect("send");
+        assert_eq!(records.lock().expect("lock").len(), 3);
+        assert_eq!(sink.pending(), 0);
+    }
+
+    #[test]
+    fn flush_delivers_pending_messages() {
+        let inner = InMemorySink::new();
+        let records = inner.records_arc();
+        let sink = BufferingSink::new(inner, 10);
+
+        sink.send("t/1", b"one").expect("send");
+        sink.send("t/2", b"two").expect("send");
+        assert!(records.lock().expect("lock").is_empty());
+
+        sink.flush().expect("flush");
+
+        let records = records.lock().expect("lock");
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0], ("t/1".to_string(), b"one".to_vec()));
+        assert_eq!(records[1], ("t/2".to_string(), b"two".to_vec()));
+        assert_eq!(sink.pending(), 0);
+    }
+
+    #[test]
+    fn high_priority_is_flushed_first() {
+        let inner = InMemorySink::new();
+        let records = inner.records_arc();
+        let sink = BufferingSink::new(inner, 10);
+
+        for (topic, priority) in [("low", 0), ("high", 9), ("low2", 0)] {
+            let msg = TelemetryMessage::new(topic, serde_json::json!(null)).with_priority(priority);
+            sink.send(topic, msg.to_json().as_bytes()).expect("send");
+        }
+        sink.flush().expect("flush");
+
+        let topics: Vec<_> = records
+            .lock()
+            .expect("lock")
+            .iter()
+            .map(|(t, _)| t.clone())
+            .collect();
+        assert_eq!(topics, vec!["high", "low", "low2"]);
+    }
+
+    #[test]
+    fn expired_message_is_dropped_at_flush() {
+        let clock = MockClock::new(1_000);
+        let inner = InMemorySink::new();
+        let records = inner.records_arc();
+        let sink = BufferingSink::new(inner, 10).with_clock(Arc::new(clock.clone()));
+
+        let short = TelemetryMessage::builder()
+            .topic("short")
+            .payload(serde_json::json!(null))
+            .ttl(100)
+            .created_at(1_000)
+            .build()
+            .expect("valid message");
+        sink.send("short", short.to_json().as_bytes())
+            .expect("send");
+        sink.send("plain", b"no ttl").expect("send");
+        clock.advance(std::time::Duration::from_millis(101));
+        sink.flush().expect("flush");
+
+        let records = records.lock().expect("lock");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].0, "plain");
+        assert_eq!(sink.expired_count(), 1);
+    }
+
+    #[test]
+    fn close_flushes_before_shutdown() {
+        let inner = InMemorySink::new();
+        let records = inner.records_arc();
+        let sink = BufferingSink::new(inner, 10);
+
+        sink.send("t", b"last words").expect("send");
+        sink.close().expect("close");
+
+        assert_eq!(records.lock().expect("lock").len(), 1);
+    }
+
+    #[test]
+    fn failed_flush_requeues_unsent_tail_ahead_of_new_messages() {
+        let inner = FaultInjectionSink::scripted([
+            Fault::Ok,
+            Fault::Fail(TelemetryError::Transport("down".into())),
+        ]);
+        let sink = BufferingSink::new(inner, 10);
+        for topic in ["a", "b", "c"] {
+            sink.send(topic, b"x").expect("send");
+        }
+
+        sink.flush().expect_err("second send fails");
+        assert_eq!(sink.pending(), 2);
+        sink.send("d", b"x").expect("send");
+        sink.flush().expect("flush");
+
+        let topics: Vec<String> = sink.inner().calls().into_iter().map(|c| c.topic).collect();
+        assert_eq!(topics, vec!["a", "b", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn send_does_not_wait_for_a_drain_in_progress() {
+        let inner = FaultInjectionSink::from_fn(|_, topic, _| match topic {
+            "slow" => Fault::Delay(Duration::from_millis(300)),
+            _ => Fault::Ok,
+        });
+        let sink = BufferingSink::new(inner, 10);
+        sink.send("slow", b"x").expect("send");
+
+        std::thread::scope(|scope| {
+            let drain = scope.spawn(|| sink.flush());
+            std::thread::sleep(Duration::from_millis(50));
+            let started = std::time::Instant::now();
+            sink.send("fast", b"x").expect("send");
+            assert!(started.elapsed() < Duration::from_millis(200));
+            drain.join().expect("join").expect("flush");
+        });
+        assert_eq!(sink.pending(), 1);
+    }
+
+    #[test]
+    fn auto_flush_delivers_below_capacity() {
+        let inner = InMemorySink::new();
+        let records = inner.records_arc();
+        let sink = BufferingSink::new(inner, 100)
+            .with_auto_flush_interval(Some(Duration::from_millis(10)));
+        assert_eq!(sink.auto_flush_interval(), Some(Duration::from_millis(10)));
+
+        sink.send("t", b"trickle").expect("send");
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while records.lock().expect("lock").is_empty() {
+            assert!(std::time::Instant::now() < deadline, "auto-flush never ran");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(sink.pending(), 0);
+    }
+
+    /// Sink that records when it is dropped.
+    struct DropFlag(Arc<std::sync::atomic::AtomicBool>);
+
+    impl TelemetrySink for DropFlag {
+        fn send(&self, _topic: &str, _payload: &[u8]) -> TelemetryResult<()> {
+            Ok(())
+        }
+    }
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn dropping_sink_stops_flusher_promptly() {
+        let dropped = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let sink = BufferingSink::new(DropFlag(dropped.clone()), 10)
+            .with_auto_flush_interval(Some(Duration::from_secs(60)));
+
+        let started = std::time::Instant::now();
+        drop(sink);
+
+        // The thread released its handle on the inner sink without waiting
+        // out the interval
+        assert!(dropped.load(Ordering::SeqCst));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,109 @@
+//! Panic-isolating sink decorator.
+//!
+//! **Why?** A custom sink that panics on one bad message would otherwise
+//! unwind through the caller and can poison locks shared with the rest of
+//! the pipeline. This decorator turns the panic into a `TelemetryError` so
+//! the next message goes through normally.
+
+use crate::{layered_sink_name, TelemetryError, TelemetryResult, TelemetrySink};
+use std::any::Any;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A sink that converts panics in the inner sink into errors.
+pub struct CatchPanicSink<S: TelemetrySink> {
+    inner: S,
+    panics: AtomicU64,
+}
+
+impl<S: TelemetrySink> CatchPanicSink<S> {
+    /// Wrap `inner`, catching any panic from `send` or `flush`.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            panics: AtomicU64::new(0),
+        }
+    }
+
+    /// Number of panics caught so far.
+    pub fn panics_caught(&self) -> u64 {
+        self.panics.load(Ordering::Relaxed)
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn guard(&self, what: &str, op: impl FnOnce() -> TelemetryResult<()>) -> TelemetryResult<()> {
+        // The inner sink is only observed through `&self`; if it left its own
+        // state inconsistent, that is its lock's poison to report.
+        catch_unwind(AssertUnwindSafe(op)).unwrap_or_else(|panic| {
+            self.panics.fetch_add(1, Ordering::Relaxed);
+            Err(TelemetryError::new(format!(
+                "sink panicked during {}: {}",
+                what,
+                panic_message(panic.as_ref())
+            )))
+        })
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    if let Some(s) = panic.downcast_ref::<&str>() {
+        s
+    } else if let Some(s) = panic.downcast_ref::<String>() {
+        s
+    } else {
+        "non-string panic payload"
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for CatchPanicSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("catch_panic", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        self.guard("send", || self.inner.send(topic, payload))
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.guard("flush", || self.inner.flush())
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemorySink;
+
+    /// Panics on payloads equal to `b"boom"`, records everything else.
+    struct PanickySink(InMemorySink);
+
+    impl TelemetrySink for PanickySink {
+        fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+            if payload == b"boom" {
+                panic!("cannot handle {}", topic);
+            }
+            self.0.send(topic, payload)
+        }
+    }
+
+    #[test]
+    fn panic_becomes_error_and_pipeline_keeps_working() {
+        let sink = CatchPanicSink::new(PanickySink(InMemorySink::new()));
+
+        sink.send("t", b"ok").expect("send");
+        let err = sink.send("bad/topic", b"boom").expect_err("panic caught");
+        assert!(err.message().contains("cannot handle bad/topic"));
+        sink.send("t", b"still ok").expect("send after panic");
+
+        assert_eq!(sink.panics_caught(), 1);
+        assert_eq!(sink.inner().0.records.lock().expect("lock").len(), 2);
+    }
+}
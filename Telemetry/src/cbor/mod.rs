@@ -0,0 +1,90 @@
+//! CBOR encoding for `TelemetryMessage`.
+//!
+//! **Why CBOR?** Embedded consumers that already ship a CBOR parser can
+//! decode it with far less code and memory than JSON, and the encoding is
+//! more compact.
+//!
+//! The whole message (topic, payload and metadata) is encoded as one CBOR
+//! map with the same field names as the JSON encoding. The transport topic
+//! stays a plain string, so brokers can still route on it; only the bytes on
+//! the wire are CBOR.
+
+use crate::{TelemetryError, TelemetryMessage, TelemetryResult};
+
+impl TelemetryMessage {
+    /// Encode the message as CBOR bytes.
+    pub fn to_cbor(&self) -> TelemetryResult<Vec<u8>> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(self, &mut bytes)
+            .map_err(|e| TelemetryError::Serialization(format!("CBOR encode: {}", e)))?;
+        Ok(bytes)
+    }
+
+    /// Decode a message from CBOR bytes produced by `to_cbor`.
+    ///
+    /// CBOR values without a JSON counterpart in the payload (byte strings,
+    /// non-string map keys, tags) are rejected with
+    /// `TelemetryError::Serialization`.
+    pub fn from_cbor(bytes: &[u8]) -> TelemetryResult<Self> {
+        ciborium::from_reader(bytes)
+            .map_err(|e| TelemetryError::Serialization(format!("CBOR decode: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemorySink, TelemetryClient};
+    use ciborium::value::Value as Cbor;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn round_trips_message_with_metadata() {
+        let msg = TelemetryMessage::builder()
+            .topic("sensors/temp")
+            .payload(json!({"value": 21.5, "tags": ["a", "b"], "ok": true, "n": null}))
+            .timestamp(1_700_000_000_000)
+            .header("service", "hvac")
+            .priority(3)
+            .build()
+            .expect("build");
+
+        let bytes = msg.to_cbor().expect("encode");
+
+        assert!(bytes.len() < msg.to_json().len());
+        assert_eq!(TelemetryMessage::from_cbor(&bytes).expect("decode"), msg);
+    }
+
+    #[test]
+    fn client_sends_cbor_on_plain_topic() {
+        let sink = Arc::new(InMemorySink::new());
+        let client = TelemetryClient::new(sink.clone()).with_topic_prefix("site1/");
+
+        client
+            .send_message_cbor(&TelemetryMessage::new("temp", json!(1)))
+            .expect("send");
+
+        let records = sink.records.lock().expect("lock");
+        assert_eq!(records[0].0, "site1/temp");
+        let sent = TelemetryMessage::from_cbor(&records[0].1).expect("decode");
+        assert_eq!(sent.topic, "site1/temp");
+        assert_eq!(sent.payload, json!(1));
+    }
+
+    #[test]
+    fn payload_without_json_counterpart_is_a_serialization_error() {
+        let cbor = Cbor::Map(vec![
+            (Cbor::Text("topic".into()), Cbor::Text("t".into())),
+            (Cbor::Text("payload".into()), Cbor::Bytes(vec![0xde, 0xad])),
+        ]);
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&cbor, &mut bytes).expect("encode");
+
+        let err = TelemetryMessage::from_cbor(&bytes).expect_err("bytes payload");
+        assert!(matches!(err, TelemetryError::Serialization(_)));
+
+        let err = TelemetryMessage::from_cbor(&[0xa2, 0x65]).expect_err("truncated");
+        assert!(matches!(err, TelemetryError::Serialization(_)));
+    }
+}
@@ -0,0 +1,263 @@
+//! Circuit breaker sink decorator.
+//!
+//! **Why?** When a broker is down, every send blocks on a transport timeout
+//! and stalls the caller. After repeated failures the breaker "opens" and
+//! fails fast for a cooldown period instead of touching the dead endpoint.
+
+use crate::clock::elapsed_since;
+use crate::{
+    layered_sink_name, Clock, SystemClock, TelemetryError, TelemetryResult, TelemetrySink,
+};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Current state of a `CircuitBreakerSink`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Sends go to the inner sink; failures are being counted.
+    Closed,
+    /// Sends fail fast without touching the inner sink.
+    Open,
+    /// Cooldown elapsed; the next send is a trial that decides the state.
+    HalfOpen,
+}
+
+struct BreakerInner {
+    state: BreakerState,
+    consecutive_failures: u32,
+    opened_at: Option<i64>,
+    trial_in_flight: bool,
+}
+
+/// A sink that stops calling a failing inner sink for a cooldown period.
+pub struct CircuitBreakerSink<S: TelemetrySink> {
+    inner: S,
+    failure_threshold: u32,
+    cooldown: Duration,
+    breaker: Mutex<BreakerInner>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<S: TelemetrySink> CircuitBreakerSink<S> {
+    /// Open the circuit after `failure_threshold` consecutive failures and
+    /// keep it open for `cooldown`.
+    ///
+    /// A threshold of zero is treated as one.
+    pub fn new(inner: S, failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            inner,
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            breaker: Mutex::new(BreakerInner {
+                state: BreakerState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                trial_in_flight: false,
+            }),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Measure the cooldown with `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Current breaker state.
+    ///
+    /// An open circuit whose cooldown has elapsed reports `HalfOpen`.
+    pub fn state(&self) -> BreakerState {
+        match self.breaker.lock() {
+            Ok(breaker) => self.effective_state(&breaker),
+            Err(_) => BreakerState::Open,
+        }
+    }
+
+    fn effective_state(&self, breaker: &BreakerInner) -> BreakerState {
+        match (breaker.state, breaker.opened_at) {
+            (BreakerState::Open, Some(at))
+                if elapsed_since(self.clock.now_millis(), at) >= self.cooldown =>
+            {
+                BreakerState::HalfOpen
+            }
+            (state, _) => state,
+        }
+    }
+
+    /// Decide whether a send may reach the inner sink.
+    fn admit(&self) -> TelemetryResult<bool> {
+        let mut breaker = self
+            .breaker
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+        match self.effective_state(&breaker) {
+            BreakerState::Closed => Ok(true),
+            BreakerState::HalfOpen if !breaker.trial_in_flight => {
+                breaker.state = BreakerState::HalfOpen;
+                breaker.trial_in_flight = true;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Let another send act as the half-open trial.
+    fn release_trial(&self) {
+        if let Ok(mut breaker) = self.breaker.lock() {
+            breaker.trial_in_flight = false;
+        }
+    }
+
+    fn record(&self, success: bool) {
+        let Ok(mut breaker) = self.breaker.lock() else {
+            return;
+        };
+        breaker.trial_in_flight = false;
+        if success {
+            breaker.state = BreakerState::Closed;
+            breaker.consecutive_failures = 0;
+            breaker.opened_at = None;
+            return;
+        }
+        breaker.consecutive_failures = breaker.consecutive_failures.saturating_add(1);
+        if breaker.state == BreakerState::HalfOpen
+            || breaker.consecutive_failures >= self.failure_threshold
+        {
+            breaker.state = BreakerState::Open;
+            breaker.opened_at = Some(self.clock.now_millis());
+        }
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for CircuitBreakerSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("circuit_breaker", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        if !self.admit()? {
+            return Err(TelemetryError::Transport("circuit open".into()));
+        }
+        let result = self.inner.send(topic, payload);
+        self.record(result.is_ok());
+        result
+    }
+
+    /// Returns `Ok(false)` while the circuit is open.
+    fn try_send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<bool> {
+        if !self.admit()? {
+            return Ok(false);
+        }
+        let result = self.inner.try_send(topic, payload);
+        match result {
+            Ok(true) => self.record(true),
+            // Pushback from the inner sink says nothing about its health
+            Ok(false) => self.release_trial(),
+            Err(_) => self.record(false),
+        }
+        result
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+    /// Fails while `healthy` is false and counts every call.
+    struct SwitchableSink {
+        healthy: AtomicBool,
+        calls: AtomicU32,
+    }
+
+    impl SwitchableSink {
+        fn failing() -> Self {
+            Self {
+                healthy: AtomicBool::new(false),
+                calls: AtomicU32::new(0),
+            }
+        }
+    }
+
+    impl TelemetrySink for SwitchableSink {
+        fn send(&self, _topic: &str, _payload: &[u8]) -> TelemetryResult<()> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.healthy.load(Ordering::SeqCst) {
+                Ok(())
+            } else {
+                Err(TelemetryError::Connection("broker down".into()))
+            }
+        }
+    }
+
+    #[test]
+    fn trips_after_threshold_and_fails_fast() {
+        let sink = CircuitBreakerSink::new(SwitchableSink::failing(), 3, Duration::from_secs(60));
+
+        for _ in 0..3 {
+            let err = sink.send("t", b"x").expect_err("inner fails");
+            assert!(matches!(err, TelemetryError::Connection(_)));
+        }
+        assert_eq!(sink.state(), BreakerState::Open);
+
+        let err = sink.send("t", b"x").expect_err("circuit open");
+        assert_eq!(err, TelemetryError::Transport("circuit open".into()));
+        assert_eq!(sink.inner().calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn try_send_returns_false_while_open() {
+        let sink = CircuitBreakerSink::new(SwitchableSink::failing(), 1, Duration::from_secs(60));
+        assert!(sink.try_send("t", b"x").is_err());
+        assert_eq!(sink.state(), BreakerState::Open);
+
+        assert!(!sink.try_send("t", b"x").expect("no error while open"));
+        assert_eq!(sink.inner().calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn recovers_after_cooldown_when_inner_succeeds() {
+        let sink = CircuitBreakerSink::new(SwitchableSink::failing(), 2, Duration::from_millis(30));
+        for _ in 0..2 {
+            assert!(sink.send("t", b"x").is_err());
+        }
+        assert_eq!(sink.state(), BreakerState::Open);
+
+        sink.inner().healthy.store(true, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(40));
+        assert_eq!(sink.state(), BreakerState::HalfOpen);
+
+        sink.send("t", b"x").expect("trial send succeeds");
+        assert_eq!(sink.state(), BreakerState::Closed);
+        assert_eq!(sink.inner().calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn failed_trial_reopens_circuit() {
+        let sink = CircuitBreakerSink::new(SwitchableSink::failing(), 1, Duration::from_millis(20));
+        assert!(sink.send("t", b"x").is_err());
+        std::thread::sleep(Duration::from_millis(30));
+
+        let err = sink.send("t", b"x").expect_err("trial fails");
+        assert!(matches!(err, TelemetryError::Connection(_)));
+        assert_eq!(sink.state(), BreakerState::Open);
+        assert_eq!(
+            sink.send("t", b"x").expect_err("open again"),
+            TelemetryError::Transport("circuit open".into())
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! Coalescing sink decorator.
+//!
+//! Rate-limits each topic to its freshest value, e.g. a 1 kHz sensor feeding
+//! a UI gauge that redraws at 4 Hz.
+//!
+//! **Why not sampling?** Sampling keeps an arbitrary fraction of readings,
+//! so the value shown may already be stale when the next one is dropped.
+//! Coalescing always forwards the latest value seen in each interval.
+
+use crate::auto_flush::AutoFlusher;
+use crate::clock::{elapsed_since, ClockCell};
+use crate::{layered_sink_name, Clock, TelemetryError, TelemetryResult, TelemetrySink};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Latest payload held for one topic.
+struct Slot {
+    /// When the first held update arrived.
+    opened: i64,
+    payload: Vec<u8>,
+}
+
+/// State shared with the poll thread.
+struct Core<S: TelemetrySink> {
+    inner: S,
+    min_interval: Duration,
+    clock: ClockCell,
+    slots: Mutex<BTreeMap<String, Slot>>,
+    coalesced: AtomicU64,
+}
+
+impl<S: TelemetrySink> Core<S> {
+    fn poll(&self) -> TelemetryResult<()> {
+        let now = self.clock.now_millis();
+        let due = self.take(|slot| elapsed_since(now, slot.opened) >= self.min_interval)?;
+        self.forward(due)
+    }
+
+    fn take(&self, due: impl Fn(&Slot) -> bool) -> TelemetryResult<Vec<(String, Vec<u8>)>> {
+        let mut slots = self
+            .slots
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+        let topics: Vec<String> = slots
+            .iter()
+            .filter(|(_, slot)| due(slot))
+            .map(|(topic, _)| topic.clone())
+            .collect();
+        Ok(topics
+            .into_iter()
+            .filter_map(|topic| slots.remove(&topic).map(|slot| (topic, slot.payload)))
+            .collect())
+    }
+
+    /// Send `records`, attempting all and returning the first error.
+    fn forward(&self, records: Vec<(String, Vec<u8>)>) -> TelemetryResult<()> {
+        let mut result = Ok(());
+        for (topic, payload) in records {
+            if let Err(e) = self.inner.send(&topic, &payload) {
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+}
+
+/// A sink that forwards at most one payload per topic per `min_interval`,
+/// always the most recent one.
+///
+/// The first update to an idle topic opens an interval; later updates
+/// replace the held payload. Once the interval has elapsed the held payload
+/// is forwarded by the next `send` (to any topic), `poll` or `flush`, or by
+/// the poll thread started by `with_poll_interval`.
+pub struct CoalescingSink<S: TelemetrySink> {
+    poller: Option<AutoFlusher>,
+    core: Arc<Core<S>>,
+}
+
+impl<S: TelemetrySink> CoalescingSink<S> {
+    /// Forward each topic at most once per `min_interval`.
+    pub fn new(inner: S, min_interval: Duration) -> Self {
+        Self {
+            poller: None,
+            core: Arc::new(Core {
+                inner,
+                min_interval,
+                clock: ClockCell::default(),
+                slots: Mutex::new(BTreeMap::new()),
+                coalesced: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Measure intervals with `clock` instead of the system clock.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        self.core.clock.set(clock);
+        self
+    }
+
+    /// Minimum time between forwards on one topic.
+    pub fn min_interval(&self) -> Duration {
+        self.core.min_interval
+    }
+
+    /// Interval of the poll thread, if one is running.
+    pub fn poll_interval(&self) -> Option<Duration> {
+        self.poller.as_ref().map(AutoFlusher::interval)
+    }
+
+    /// Number of topics holding a payload that has not been forwarded yet.
+    pub fn pending_count(&self) -> usize {
+        self.core.slots.lock().map_or(0, |slots| slots.len())
+    }
+
+    /// Payloads replaced by a newer one before they were forwarded.
+    pub fn coalesced_count(&self) -> u64 {
+        self.core.coalesced.load(Ordering::Relaxed)
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.core.inner
+    }
+
+    /// Forward every held payload whose interval has elapsed.
+    pub fn poll(&self) -> TelemetryResult<()> {
+        self.core.poll()
+    }
+}
+
+impl<S: TelemetrySink + 'static> CoalescingSink<S> {
+    /// Also `poll` every `interval` from a background thread, so the latest
+    /// value is forwarded on time when updates stop (see the crate docs).
+    ///
+    /// `None` stops a running thread; it is also stopped and joined when
+    /// the sink is dropped.
+    pub fn with_poll_interval(mut self, interval: Option<Duration>) -> Self {
+        self.poller = None;
+        self.poller = interval.map(|interval| {
+            let core = Arc::clone(&self.core);
+            AutoFlusher::spawn("coalescing", interval, move || core.poll())
+        });
+        self
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for CoalescingSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("coalescing", &[self.core.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let now = self.core.clock.now_millis();
+        {
+            let mut slots = self
+                .core
+                .slots
+                .lock()
+                .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+            match slots.get_mut(topic) {
+                Some(slot) => {
+                    slot.payload = payload.to_vec();
+                    self.core.coalesced.fetch_add(1, Ordering::Relaxed);
+                }
+                None => {
+                    slots.insert(
+                        topic.to_string(),
+                        Slot {
+                            opened: now,
+                            payload: payload.to_vec(),
+                        },
+                    );
+                }
+            }
+        }
+        self.poll()
+    }
+
+    /// Forward every held payload regardless of its interval, then flush
+    /// the inner sink.
+    fn flush(&self) -> TelemetryResult<()> {
+        let pending = self.core.take(|_| true)?;
+        self.core.forward(pending)?;
+        self.core.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.core.inner.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemorySink, MockClock, TelemetryRecord};
+
+    fn records(sink: &CoalescingSink<InMemorySink>) -> Vec<TelemetryRecord> {
+        sink.inner().records.lock().expect("lock").clone()
+    }
+
+    fn coalescing(clock: &MockClock) -> CoalescingSink<InMemorySink> {
+        CoalescingSink::new(InMemorySink::new(), Duration::from_millis(250))
+            .with_clock(Arc::new(clock.clone()))
+    }
+
+    #[test]
+    fn forwards_only_latest_value_once_interval_elapses() {
+        let clock = MockClock::new(0);
+        let sink = coalescing(&clock);
+
+        for i in 0..100 {
+            sink.send("gauge", format!("{}", i).as_bytes())
+                .expect("send");
+            clock.advance(Duration::from_millis(1));
+        }
+        assert!(records(&sink).is_empty());
+        assert_eq!(sink.coalesced_count(), 99);
+
+        clock.advance(Duration::from_millis(150));
+        sink.poll().expect("poll");
+
+        assert_eq!(records(&sink), vec![("gauge".to_string(), b"99".to_vec())]);
+        assert_eq!(sink.pending_count(), 0);
+    }
+
+    #[test]
+    fn topics_are_coalesced_independently() {
+        let clock = MockClock::new(0);
+        let sink = coalescing(&clock);
+
+        sink.send("a", b"a1").expect("send");
+        clock.advance(Duration::from_millis(200));
+        sink.send("b", b"b1").expect("send");
+        sink.send("a", b"a2").expect("send");
+        clock.advance(Duration::from_millis(50));
+        sink.send("b", b"b2").expect("send");
+
+        assert_eq!(records(&sink), vec![("a".to_string(), b"a2".to_vec())]);
+
+        sink.flush().expect("flush");
+        assert_eq!(records(&sink)[1], ("b".to_string(), b"b2".to_vec()));
+    }
+
+    #[test]
+    fn poll_thread_forwards_latest_value_without_further_updates() {
+        let clock = MockClock::new(0);
+        let sink = coalescing(&clock).with_poll_interval(Some(Duration::from_millis(5)));
+        assert_eq!(sink.poll_interval(), Some(Duration::from_millis(5)));
+        sink.send("gauge", b"1").expect("send");
+        sink.send("gauge", b"2").expect("send");
+        clock.advance(Duration::from_millis(250));
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while records(&sink).is_empty() {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "value never forwarded"
+            );
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(records(&sink), vec![("gauge".to_string(), b"2".to_vec())]);
+    }
+}
@@ -0,0 +1,152 @@
+//! Pluggable message encodings.
+//!
+//! **Why a trait?** `SerializationFormat` covers the encodings this crate
+//! ships; a `PayloadCodec` lets a deployment plug in its own (a proprietary
+//! framing, an obfuscation layer) without forking the client.
+
+use crate::{TelemetryError, TelemetryMessage, TelemetryResult};
+
+/// Converts messages to and from wire bytes.
+///
+/// Install one on a client with `TelemetryClient::with_codec`.
+pub trait PayloadCodec: Send + Sync {
+    /// Encode `msg` for transmission.
+    fn encode(&self, msg: &TelemetryMessage) -> TelemetryResult<Vec<u8>>;
+
+    /// Decode bytes produced by `encode`.
+    fn decode(&self, bytes: &[u8]) -> TelemetryResult<TelemetryMessage>;
+}
+
+/// JSON encoding, the client's default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonCodec;
+
+impl PayloadCodec for JsonCodec {
+    fn encode(&self, msg: &TelemetryMessage) -> TelemetryResult<Vec<u8>> {
+        msg.try_to_json().map(String::into_bytes)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> TelemetryResult<TelemetryMessage> {
+        serde_json::from_slice(bytes)
+            .map_err(|e| TelemetryError::Serialization(format!("message: {}", e)))
+    }
+}
+
+/// MessagePack encoding; see `TelemetryMessage::to_msgpack`.
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MsgPackCodec;
+
+#[cfg(feature = "msgpack")]
+impl PayloadCodec for MsgPackCodec {
+    fn encode(&self, msg: &TelemetryMessage) -> TelemetryResult<Vec<u8>> {
+        msg.to_msgpack()
+    }
+
+    fn decode(&self, bytes: &[u8]) -> TelemetryResult<TelemetryMessage> {
+        TelemetryMessage::from_msgpack(bytes)
+    }
+}
+
+/// CBOR encoding; see `TelemetryMessage::to_cbor`.
+#[cfg(feature = "cbor")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CborCodec;
+
+#[cfg(feature = "cbor")]
+impl PayloadCodec for CborCodec {
+    fn encode(&self, msg: &TelemetryMessage) -> TelemetryResult<Vec<u8>> {
+        msg.to_cbor()
+    }
+
+    fn decode(&self, bytes: &[u8]) -> TelemetryResult<TelemetryMessage> {
+        TelemetryMessage::from_cbor(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemorySink, SerializationFormat, TelemetryClient};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    /// JSON with every byte XOR-ed with a fixed key.
+    struct XorCodec(u8);
+
+    impl PayloadCodec for XorCodec {
+        fn encode(&self, msg: &TelemetryMessage) -> TelemetryResult<Vec<u8>> {
+            let mut bytes = JsonCodec.encode(msg)?;
+            bytes.iter_mut().for_each(|b| *b ^= self.0);
+            Ok(bytes)
+        }
+
+        fn decode(&self, bytes: &[u8]) -> TelemetryResult<TelemetryMessage> {
+            let plain: Vec<u8> = bytes.iter().map(|b| b ^ self.0).collect();
+            JsonCodec.decode(&plain)
+        }
+    }
+
+    fn sample() -> TelemetryMessage {
+        TelemetryMessage::builder()
+            .topic("sensors/temp")
+            .payload(json!({"value": 21.5}))
+            .timestamp(1_700_000_000_000)
+            .header("service", "hvac")
+            .build()
+            .expect("build")
+    }
+
+    #[test]
+    fn client_uses_custom_codec() {
+        let codec = Arc::new(XorCodec(0x5a));
+        let sink = Arc::new(InMemorySink::new());
+        let client = TelemetryClient::new(sink.clone()).with_codec(codec.clone());
+        assert!(client.codec().is_some());
+
+        let msg = sample();
+        client.send_message(&msg).expect("send");
+
+        let (topic, payload) = sink.records.lock().expect("lock")[0].clone();
+        assert_eq!(topic, "sensors/temp");
+        assert!(serde_json::from_slice::<serde_json::Value>(&payload).is_err());
+        assert_eq!(codec.decode(&payload).expect("decode"), msg);
+    }
+
+    #[test]
+    fn with_format_replaces_codec() {
+        let sink = Arc::new(InMemorySink::new());
+        let client = TelemetryClient::new(sink.clone())
+            .with_codec(Arc::new(XorCodec(0x5a)))
+            .with_format(SerializationFormat::Json)
+            .expect("json");
+        assert!(client.codec().is_none());
+
+        let msg = sample();
+        client.send_message(&msg).expect("send");
+        let payload = sink.records.lock().expect("lock")[0].1.clone();
+        assert_eq!(JsonCodec.decode(&payload).expect("decode"), msg);
+    }
+
+    #[test]
+    fn json_codec_rejects_garbage() {
+        let err = JsonCodec.decode(b"{not json").expect_err("garbage");
+        assert!(matches!(err, TelemetryError::Serialization(_)));
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack_codec_round_trips() {
+        let msg = sample();
+        let bytes = MsgPackCodec.encode(&msg).expect("encode");
+        assert_eq!(MsgPackCodec.decode(&bytes).expect("decode"), msg);
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_codec_round_trips() {
+        let msg = sample();
+        let bytes = CborCodec.encode(&msg).expect("encode");
+        assert_eq!(CborCodec.decode(&bytes).expect("decode"), msg);
+    }
+}
@@ -0,0 +1,399 @@
+//! Compressing sink decorator.
+//!
+//! **Why compress?** JSON telemetry is highly repetitive; compressing before
+//! the transport cuts bandwidth substantially on metered links (e.g. MQTT
+//! over cellular).
+//!
+//! Every payload produced by `CompressingSink` starts with a one-byte marker
+//! identifying the algorithm, so `decompress` can inflate it without any
+//! out-of-band configuration. That includes auto mode, where the algorithm
+//! varies from one payload to the next.
+
+use crate::{layered_sink_name, TelemetryError, TelemetryResult, TelemetrySink};
+use std::io::{Read, Write};
+
+/// Compression algorithm applied by `CompressingSink`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Forward the payload unchanged (still prefixed with a marker byte).
+    None,
+    /// DEFLATE with gzip framing.
+    Gzip,
+    /// Zstandard.
+    Zstd,
+    /// LZ4 block format with the uncompressed length prepended.
+    Lz4,
+}
+
+impl Compression {
+    /// Marker byte written in front of the payload.
+    pub fn magic(self) -> u8 {
+        match self {
+            Compression::None => 0x00,
+            Compression::Gzip => 0x01,
+            Compression::Zstd => 0x02,
+            Compression::Lz4 => 0x03,
+        }
+    }
+
+    /// Look up the algorithm for a marker byte.
+    pub fn from_magic(byte: u8) -> Option<Self> {
+        match byte {
+            0x00 => Some(Compression::None),
+            0x01 => Some(Compression::Gzip),
+            0x02 => Some(Compression::Zstd),
+            0x03 => Some(Compression::Lz4),
+            _ => None,
+        }
+    }
+}
+
+/// Default `AutoCompression::large_size`.
+pub const DEFAULT_LARGE_PAYLOAD: usize = 16 * 1024;
+
+/// Size thresholds for `CompressingSink::auto`.
+///
+/// **Why by size?** Below a few hundred bytes the framing overhead eats
+/// most of the gain, so compressing only costs CPU. LZ4 is cheap enough
+/// for everything in between, and zstd's better ratio pays off on large
+/// payloads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoCompression {
+    /// Payloads shorter than this are forwarded uncompressed.
+    pub min_size: usize,
+    /// Payloads at least this long use zstd; shorter ones use LZ4.
+    pub large_size: usize,
+}
+
+impl AutoCompression {
+    /// Compress payloads of `min_size` bytes or more, switching to zstd at
+    /// `DEFAULT_LARGE_PAYLOAD`.
+    pub fn new(min_size: usize) -> Self {
+        Self {
+            min_size,
+            large_size: DEFAULT_LARGE_PAYLOAD,
+        }
+    }
+
+    pub fn with_large_size(mut self, large_size: usize) -> Self {
+        self.large_size = large_size;
+        self
+    }
+
+    /// Algorithm for a payload of `len` bytes.
+    pub fn select(&self, len: usize) -> Compression {
+        if len < self.min_size {
+            Compression::None
+        } else if len < self.large_size {
+            Compression::Lz4
+        } else {
+            Compression::Zstd
+        }
+    }
+}
+
+/// How a `CompressingSink` picks the algorithm for each payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionMode {
+    /// Use the same algorithm for every payload.
+    Fixed(Compression),
+    /// Choose by payload size.
+    Auto(AutoCompression),
+}
+
+impl CompressionMode {
+    /// Algorithm for a payload of `len` bytes.
+    pub fn select(&self, len: usize) -> Compression {
+        match self {
+            CompressionMode::Fixed(compression) => *compression,
+            CompressionMode::Auto(auto) => auto.select(len),
+        }
+    }
+}
+
+/// A sink that compresses payloads before forwarding them to an inner sink.
+pub struct CompressingSink<S: TelemetrySink> {
+    inner: S,
+    mode: CompressionMode,
+}
+
+impl<S: TelemetrySink> CompressingSink<S> {
+    /// Create a compressing sink using the given algorithm.
+    pub fn new(inner: S, compression: Compression) -> Self {
+        Self {
+            inner,
+            mode: CompressionMode::Fixed(compression),
+        }
+    }
+
+    /// Create a compressing sink choosing the algorithm per payload by size.
+    pub fn auto(inner: S, auto: AutoCompression) -> Self {
+        Self {
+            inner,
+            mode: CompressionMode::Auto(auto),
+        }
+    }
+
+    pub fn mode(&self) -> CompressionMode {
+        self.mode
+    }
+
+    /// Algorithm applied to a payload of `len` bytes.
+    pub fn compression_for(&self, len: usize) -> Compression {
+        self.mode.select(len)
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for CompressingSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("compression", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let framed = compress(self.compression_for(payload.len()), payload)?;
+        self.inner.send(topic, &framed)
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+/// Compress `data` and prefix it with the algorithm's marker byte.
+pub fn compress(compression: Compression, data: &[u8]) -> TelemetryResult<Vec<u8>> {
+    let mut out = vec![compression.magic()];
+    match compression {
+        Compression::None => out.extend_from_slice(data),
+        Compression::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(out, flate2::Compression::default());
+            encoder.write_all(data).map_err(|e| {
+                TelemetryError::Serialization(format!("gzip compression failed: {}", e))
+            })?;
+            out = encoder.finish().map_err(|e| {
+                TelemetryError::Serialization(format!("gzip compression failed: {}", e))
+            })?;
+        }
+        Compression::Zstd => {
+            let compressed = zstd::stream::encode_all(data, 0).map_err(|e| {
+                TelemetryError::Serialization(format!("zstd compression failed: {}", e))
+            })?;
+            out.extend_from_slice(&compressed);
+        }
+        Compression::Lz4 => out.extend_from_slice(&lz4_flex::compress_prepend_size(data)),
+    }
+    Ok(out)
+}
+
+/// Default `decompress` limit on the inflated size.
+///
+/// **Why a limit?** A few kilobytes of crafted input can inflate to
+/// gigabytes; payloads from untrusted peers must not be able to exhaust
+/// memory.
+pub const DEFAULT_MAX_DECOMPRESSED: usize = 16 * 1024 * 1024;
+
+/// Inflate a payload produced by `CompressingSink`, up to
+/// `DEFAULT_MAX_DECOMPRESSED` bytes.
+///
+/// Returns an error if the payload is empty, carries an unknown marker byte,
+/// is not valid data for the marked algorithm, or inflates past the limit.
+pub fn decompress(bytes: &[u8]) -> TelemetryResult<Vec<u8>> {
+    decompress_with_limit(bytes, DEFAULT_MAX_DECOMPRESSED)
+}
+
+/// Inflate a payload produced by `CompressingSink`, failing if the result
+/// would exceed `max_len` bytes.
+///
+/// Decoding stops once the limit is passed, so an oversized payload costs
+/// at most `max_len` bytes of memory.
+pub fn decompress_with_limit(bytes: &[u8], max_len: usize) -> TelemetryResult<Vec<u8>> {
+    let (&magic, body) = bytes
+        .split_first()
+        .ok_or_else(|| TelemetryError::Serialization("cannot decompress empty payload".into()))?;
+    let compression = Compression::from_magic(magic).ok_or_else(|| {
+        TelemetryError::Serialization(format!("unknown compression marker 0x{:02x}", magic))
+    })?;
+    let out = match compression {
+        Compression::None => body.to_vec(),
+        Compression::Gzip => read_limited(flate2::read::GzDecoder::new(body), max_len, "gzip")?,
+        Compression::Zstd => {
+            let decoder = zstd::stream::read::Decoder::new(body).map_err(|e| {
+                TelemetryError::Serialization(format!("zstd decompression failed: {}", e))
+            })?;
+            read_limited(decoder, max_len, "zstd")?
+        }
+        Compression::Lz4 => {
+            let declared = body
+                .get(..4)
+                .and_then(|header| header.try_into().ok())
+                .map(u32::from_le_bytes)
+                .ok_or_else(|| {
+                    TelemetryError::Serialization("lz4 payload has no size header".into())
+                })?;
+            if usize::try_from(declared).map_or(true, |len| len > max_len) {
+                return Err(too_large(max_len));
+            }
+            lz4_flex::decompress_size_prepended(body).map_err(|e| {
+                TelemetryError::Serialization(format!("lz4 decompression failed: {}", e))
+            })?
+        }
+    };
+    if out.len() > max_len {
+        return Err(too_large(max_len));
+    }
+    Ok(out)
+}
+
+/// Read at most one byte past `max_len`, enough to detect the overflow.
+fn read_limited(reader: impl Read, max_len: usize, algorithm: &str) -> TelemetryResult<Vec<u8>> {
+    let mut out = Vec::new();
+    let cap = u64::try_from(max_len).unwrap_or(u64::MAX).saturating_add(1);
+    reader.take(cap).read_to_end(&mut out).map_err(|e| {
+        TelemetryError::Serialization(format!("{} decompression failed: {}", algorithm, e))
+    })?;
+    Ok(out)
+}
+
+fn too_large(max_len: usize) -> TelemetryError {
+    TelemetryError::Serialization(format!(
+        "decompressed payload exceeds limit of {} bytes",
+        max_len
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemorySink;
+
+    fn round_trip(compression: Compression) {
+        let inner = InMemorySink::new();
+        let records = inner.records_arc();
+        let sink = CompressingSink::new(inner, compression);
+
+        let payload = br#"{"temp":23.5,"unit":"C"}"#;
+        sink.send("sensors/temp", payload).expect("send");
+
+        let records = records.lock().expect("lock");
+        let (topic, bytes) = &records[0];
+        assert_eq!(topic, "sensors/temp");
+        assert_eq!(bytes[0], compression.magic());
+        assert_eq!(decompress(bytes).expect("decompress"), payload.to_vec());
+    }
+
+    #[test]
+    fn gzip_round_trip() {
+        round_trip(Compression::Gzip);
+    }
+
+    #[test]
+    fn zstd_round_trip() {
+        round_trip(Compression::Zstd);
+    }
+
+    #[test]
+    fn lz4_round_trip() {
+        round_trip(Compression::Lz4);
+    }
+
+    #[test]
+    fn none_round_trip() {
+        round_trip(Compression::None);
+    }
+
+    #[test]
+    fn repetitive_payload_shrinks() {
+        let payload = r#"{"sensor":"temp_01","value":24.5,"unit":"celsius"}"#.repeat(50);
+        for compression in [Compression::Gzip, Compression::Zstd, Compression::Lz4] {
+            let compressed = compress(compression, payload.as_bytes()).expect("compress");
+            assert!(
+                compressed.len() < payload.len() / 2,
+                "{:?} produced {} bytes from {}",
+                compression,
+                compressed.len(),
+                payload.len()
+            );
+        }
+    }
+
+    #[test]
+    fn auto_mode_picks_algorithm_by_size() {
+        let inner = InMemorySink::new();
+        let records = inner.records_arc();
+        let sink = CompressingSink::auto(inner, AutoCompression::new(256).with_large_size(4096));
+
+        let tiny = br#"{"temp":23.5}"#.to_vec();
+        let medium = r#"{"sensor":"temp_01","value":24.5}"#.repeat(20).into_bytes();
+        let large = r#"{"sensor":"temp_01","value":24.5}"#.repeat(500).into_bytes();
+        for payload in [&tiny, &medium, &large] {
+            sink.send("sensors/temp", payload).expect("send");
+        }
+
+        let records = records.lock().expect("lock");
+        let markers: Vec<u8> = records.iter().map(|(_, bytes)| bytes[0]).collect();
+        assert_eq!(
+            markers,
+            [
+                Compression::None.magic(),
+                Compression::Lz4.magic(),
+                Compression::Zstd.magic()
+            ]
+        );
+        assert_eq!(records[0].1.len(), tiny.len() + 1);
+        assert!(records[2].1.len() < large.len() / 10);
+        for ((_, bytes), payload) in records.iter().zip([tiny, medium, large]) {
+            assert_eq!(decompress(bytes).expect("decompress"), payload);
+        }
+        assert_eq!(sink.compression_for(0), Compression::None);
+        assert!(matches!(sink.mode(), CompressionMode::Auto(_)));
+    }
+
+    #[test]
+    fn decompress_stops_at_the_limit() {
+        let payload = vec![b'x'; 64 * 1024];
+        for compression in [
+            Compression::None,
+            Compression::Gzip,
+            Compression::Zstd,
+            Compression::Lz4,
+        ] {
+            let framed = compress(compression, &payload).expect("compress");
+            let err = decompress_with_limit(&framed, 1024).expect_err("over limit");
+            assert!(
+                err.to_string().contains("exceeds limit"),
+                "{:?}: {}",
+                compression,
+                err
+            );
+            assert_eq!(
+                decompress_with_limit(&framed, payload.len()).expect("at limit"),
+                payload
+            );
+        }
+    }
+
+    #[test]
+    fn lz4_size_header_is_checked_before_allocating() {
+        let mut forged = vec![Compression::Lz4.magic()];
+        forged.extend_from_slice(&u32::MAX.to_le_bytes());
+        forged.extend_from_slice(&[0; 8]);
+        let err = decompress(&forged).expect_err("forged size");
+        assert!(err.to_string().contains("exceeds limit"));
+        assert!(decompress(&[Compression::Lz4.magic(), 1]).is_err());
+    }
+
+    #[test]
+    fn decompress_rejects_unknown_marker() {
+        let err = decompress(&[0xff, 1, 2, 3]).expect_err("unknown marker");
+        assert!(err.to_string().contains("0xff"));
+        assert!(decompress(&[]).is_err());
+    }
+}
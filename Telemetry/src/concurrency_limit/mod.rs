@@ -0,0 +1,121 @@
+//! In-flight limit for async sinks.
+//!
+//! Caps how many sends may be outstanding on an `AsyncTelemetrySink` at
+//! once, so a burst cannot open hundreds of concurrent requests against a
+//! broker. Additional sends wait for a slot.
+//!
+//! **Why not rate limiting?** A rate limit counts sends per unit of time; it
+//! does nothing when a slow broker lets requests pile up. This limit counts
+//! sends that have started but not finished.
+
+use crate::{AsyncTelemetrySink, SinkFuture, TelemetryError};
+use tokio::sync::Semaphore;
+
+/// An async sink that allows at most `max_in_flight` concurrent sends to the
+/// inner sink.
+pub struct ConcurrencyLimitSink<S: AsyncTelemetrySink> {
+    inner: S,
+    max_in_flight: usize,
+    permits: Semaphore,
+}
+
+impl<S: AsyncTelemetrySink> ConcurrencyLimitSink<S> {
+    /// Allow up to `max_in_flight` (at least one) concurrent sends.
+    pub fn new(inner: S, max_in_flight: usize) -> Self {
+        let max_in_flight = max_in_flight.clamp(1, Semaphore::MAX_PERMITS);
+        Self {
+            inner,
+            max_in_flight,
+            permits: Semaphore::new(max_in_flight),
+        }
+    }
+
+    /// Configured limit.
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight
+    }
+
+    /// Sends that could start right now without waiting.
+    pub fn available_permits(&self) -> usize {
+        self.permits.available_permits()
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+fn closed(e: tokio::sync::AcquireError) -> TelemetryError {
+    TelemetryError::new(format!("concurrency limit closed: {}", e))
+}
+
+impl<S: AsyncTelemetrySink> AsyncTelemetrySink for ConcurrencyLimitSink<S> {
+    fn send<'a>(&'a self, topic: &'a str, payload: &'a [u8]) -> SinkFuture<'a> {
+        Box::pin(async move {
+            let _permit = self.permits.acquire().await.map_err(closed)?;
+            self.inner.send(topic, payload).await
+        })
+    }
+
+    /// Wait for every in-flight send to finish, then flush the inner sink.
+    fn flush(&self) -> SinkFuture<'_> {
+        Box::pin(async move {
+            // The limit is clamped to MAX_PERMITS, which fits in u32
+            let all = self.max_in_flight as u32;
+            let _permits = self.permits.acquire_many(all).await.map_err(closed)?;
+            self.inner.flush().await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Async sink that takes a while per send and tracks peak concurrency.
+    #[derive(Default)]
+    struct SlowSink {
+        current: AtomicUsize,
+        peak: AtomicUsize,
+        completed: AtomicUsize,
+    }
+
+    impl AsyncTelemetrySink for SlowSink {
+        fn send<'a>(&'a self, _topic: &'a str, _payload: &'a [u8]) -> SinkFuture<'a> {
+            Box::pin(async move {
+                let now = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                self.current.fetch_sub(1, Ordering::SeqCst);
+                self.completed.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrency_never_exceeds_limit() {
+        let sink = Arc::new(ConcurrencyLimitSink::new(SlowSink::default(), 3));
+        assert_eq!(sink.available_permits(), 3);
+
+        let sends: Vec<_> = (0..20)
+            .map(|_| {
+                let sink = Arc::clone(&sink);
+                tokio::spawn(async move { sink.send("t", b"x").await })
+            })
+            .collect();
+        for send in sends {
+            send.await.expect("join").expect("send");
+        }
+        sink.flush().await.expect("flush");
+
+        assert_eq!(sink.inner().completed.load(Ordering::SeqCst), 20);
+        let peak = sink.inner().peak.load(Ordering::SeqCst);
+        assert!((1..=3).contains(&peak), "peak concurrency {}", peak);
+        assert_eq!(sink.available_permits(), 3);
+    }
+}
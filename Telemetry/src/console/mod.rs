@@ -0,0 +1,231 @@
+//! Console sink with machine- or human-readable output.
+//!
+//! Unlike `MockSink`, which prints a debug representation, `ConsoleSink`
+//! writes one of several stable formats to stdout, stderr, or any injected
+//! writer.
+
+use crate::{TelemetryError, TelemetryResult, TelemetrySink};
+use base64::Engine;
+use std::any::Any;
+use std::io::Write;
+use std::sync::Mutex;
+
+/// Output format of `ConsoleSink`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleFormat {
+    /// One JSON object per line: `{"topic": ..., "payload": ...}`.
+    ///
+    /// Non-UTF-8 payloads are written as `"payload_base64"` instead.
+    Json,
+    /// `topic: payload`, with non-UTF-8 bytes escaped.
+    HumanReadable,
+    /// Offset / hex / ASCII dump for binary debugging.
+    HexDump,
+}
+
+/// Standard stream used by `ConsoleSink::new`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleTarget {
+    Stdout,
+    Stderr,
+}
+
+/// A sink that writes payloads to a console stream or writer.
+pub struct ConsoleSink {
+    format: ConsoleFormat,
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl ConsoleSink {
+    /// Create a sink writing to stdout or stderr.
+    pub fn new(format: ConsoleFormat, target: ConsoleTarget) -> Self {
+        let writer: Box<dyn Write + Send> = match target {
+            ConsoleTarget::Stdout => Box::new(std::io::stdout()),
+            ConsoleTarget::Stderr => Box::new(std::io::stderr()),
+        };
+        Self {
+            format,
+            writer: Mutex::new(writer),
+        }
+    }
+
+    /// Create a human-readable sink writing to an arbitrary writer.
+    ///
+    /// Useful for capturing output in tests or writing to a log file.
+    pub fn with_writer(writer: Box<dyn Write + Send>) -> Self {
+        Self {
+            format: ConsoleFormat::HumanReadable,
+            writer: Mutex::new(writer),
+        }
+    }
+
+    /// Change the output format.
+    pub fn with_format(mut self, format: ConsoleFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Active output format.
+    pub fn format(&self) -> ConsoleFormat {
+        self.format
+    }
+
+    fn render(&self, topic: &str, payload: &[u8]) -> TelemetryResult<String> {
+        match self.format {
+            ConsoleFormat::Json => {
+                let line = match std::str::from_utf8(payload) {
+                    Ok(text) => serde_json::json!({ "topic": topic, "payload": text }),
+                    Err(_) => serde_json::json!({
+                        "topic": topic,
+                        "payload_base64": base64::engine::general_purpose::STANDARD.encode(payload),
+                    }),
+                };
+                serde_json::to_string(&line)
+                    .map(|s| s + "\n")
+                    .map_err(|e| TelemetryError::Serialization(format!("console JSON: {}", e)))
+            }
+            ConsoleFormat::HumanReadable => {
+                let text = match std::str::from_utf8(payload) {
+                    Ok(text) => text.to_string(),
+                    Err(_) => payload.escape_ascii().to_string(),
+                };
+                Ok(format!("{}: {}\n", topic, text))
+            }
+            ConsoleFormat::HexDump => Ok(hex_dump(topic, payload)),
+        }
+    }
+}
+
+/// Render `payload` as 16-byte rows of offset, hex bytes and printable ASCII.
+fn hex_dump(topic: &str, payload: &[u8]) -> String {
+    let mut out = format!("{} ({} bytes)\n", topic, payload.len());
+    for (row, chunk) in payload.chunks(16).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        out.push_str(&format!(
+            "{:08x}  {:<47}  |{}|\n",
+            row * 16,
+            hex.join(" "),
+            ascii
+        ));
+    }
+    out
+}
+
+impl TelemetrySink for ConsoleSink {
+    fn sink_name(&self) -> &'static str {
+        "console"
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let text = self.render(topic, payload)?;
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+        writer
+            .write_all(text.as_bytes())
+            .map_err(|e| TelemetryError::Transport(format!("console write failed: {}", e)))
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+        writer
+            .flush()
+            .map_err(|e| TelemetryError::Transport(format!("console flush failed: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Writer appending into a shared buffer the test can inspect.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().expect("lock").extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.lock().expect("lock").clone()).expect("utf8")
+        }
+    }
+
+    fn sink(format: ConsoleFormat) -> (ConsoleSink, SharedBuffer) {
+        let buffer = SharedBuffer::default();
+        let sink = ConsoleSink::with_writer(Box::new(buffer.clone())).with_format(format);
+        (sink, buffer)
+    }
+
+    #[test]
+    fn json_format_writes_topic_and_payload() {
+        let (sink, out) = sink(ConsoleFormat::Json);
+        sink.send("sensors/temp", br#"{"t":1}"#).expect("send");
+        sink.send("blob", &[0xff, 0x00]).expect("send");
+
+        let text = out.text();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|l| serde_json::from_str(l).expect("json line"))
+            .collect();
+        assert_eq!(
+            lines[0],
+            serde_json::json!({ "topic": "sensors/temp", "payload": "{\"t\":1}" })
+        );
+        assert_eq!(
+            lines[1],
+            serde_json::json!({ "topic": "blob", "payload_base64": "/wA=" })
+        );
+    }
+
+    #[test]
+    fn human_readable_format_escapes_binary() {
+        let (sink, out) = sink(ConsoleFormat::HumanReadable);
+        sink.send("svc/status", b"ok").expect("send");
+        sink.send("blob", &[b'a', 0xff]).expect("send");
+
+        assert_eq!(out.text(), "svc/status: ok\nblob: a\\xff\n");
+    }
+
+    #[test]
+    fn hex_dump_format_shows_offsets_hex_and_ascii() {
+        let (sink, out) = sink(ConsoleFormat::HexDump);
+        let payload: Vec<u8> = b"Hello, telemetry!\x00\x01".to_vec();
+        sink.send("dump", &payload).expect("send");
+
+        let text = out.text();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "dump (19 bytes)");
+        assert!(lines[1].starts_with("00000000  48 65 6c 6c 6f 2c 20 74"));
+        assert!(lines[1].ends_with("|Hello, telemetry|"));
+        assert!(lines[2].starts_with("00000010  21 00 01"));
+        assert!(lines[2].ends_with("|!..|"));
+    }
+}
@@ -0,0 +1,155 @@
+//! Content-based routing sink.
+//!
+//! Chooses a destination by inspecting the JSON payload rather than the
+//! topic, e.g. sending `{"severity": "critical", ..}` to a pager sink while
+//! everything else goes to the regular pipeline.
+
+use crate::{layered_sink_name, TelemetryResult, TelemetrySink};
+use serde_json::Value;
+use std::any::Any;
+use std::sync::Arc;
+
+/// Test applied to a parsed payload.
+pub type ContentPredicate = Box<dyn Fn(&Value) -> bool + Send + Sync>;
+
+/// A sink that forwards each payload to the first route whose predicate
+/// matches it.
+///
+/// Routes are tried in the order they were added. Payloads that match no
+/// route, or that are not JSON, go to the default sink.
+pub struct ContentRoutingSink {
+    routes: Vec<(ContentPredicate, Arc<dyn TelemetrySink>)>,
+    default: Arc<dyn TelemetrySink>,
+}
+
+impl ContentRoutingSink {
+    /// Create a router that sends everything to `default` until routes are
+    /// added.
+    pub fn new(default: Arc<dyn TelemetrySink>) -> Self {
+        Self {
+            routes: Vec::new(),
+            default,
+        }
+    }
+
+    /// Append a route sending payloads matching `predicate` to `sink`.
+    pub fn route<F>(mut self, predicate: F, sink: Arc<dyn TelemetrySink>) -> Self
+    where
+        F: Fn(&Value) -> bool + Send + Sync + 'static,
+    {
+        self.routes.push((Box::new(predicate), sink));
+        self
+    }
+
+    /// Number of configured routes, excluding the default.
+    pub fn route_count(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Sink a payload would be delivered to.
+    pub fn target_for(&self, payload: &[u8]) -> &Arc<dyn TelemetrySink> {
+        let Ok(value) = serde_json::from_slice::<Value>(payload) else {
+            return &self.default;
+        };
+        self.routes
+            .iter()
+            .find(|(predicate, _)| predicate(&value))
+            .map_or(&self.default, |(_, sink)| sink)
+    }
+}
+
+impl TelemetrySink for ContentRoutingSink {
+    fn sink_name(&self) -> &'static str {
+        let names: Vec<&str> = self
+            .routes
+            .iter()
+            .map(|(_, sink)| sink)
+            .chain(std::iter::once(&self.default))
+            .map(|sink| sink.sink_name())
+            .collect();
+        layered_sink_name("content_routing", &names)
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        self.target_for(payload).send(topic, payload)
+    }
+
+    /// Flush every route and the default, returning the first error.
+    fn flush(&self) -> TelemetryResult<()> {
+        let mut result = Ok(());
+        for sink in self
+            .routes
+            .iter()
+            .map(|(_, sink)| sink)
+            .chain(std::iter::once(&self.default))
+        {
+            if let Err(e) = sink.flush() {
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+
+    /// Check every route and the default, returning the first error.
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.routes
+            .iter()
+            .map(|(_, sink)| sink)
+            .chain(std::iter::once(&self.default))
+            .try_for_each(|sink| sink.health_check())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemorySink;
+
+    fn topics(sink: &InMemorySink) -> Vec<String> {
+        sink.records
+            .lock()
+            .expect("lock")
+            .iter()
+            .map(|(topic, _)| topic.clone())
+            .collect()
+    }
+
+    #[test]
+    fn routes_critical_severity_to_pager() {
+        let pager = Arc::new(InMemorySink::new());
+        let regular = Arc::new(InMemorySink::new());
+        let sink = ContentRoutingSink::new(regular.clone())
+            .route(|v| v["severity"] == "critical", pager.clone());
+
+        sink.send("alarms/fire", br#"{"severity":"critical"}"#)
+            .expect("send");
+        sink.send("alarms/door", br#"{"severity":"info"}"#)
+            .expect("send");
+        sink.send("sensors/temp", br#"{"value":21}"#).expect("send");
+        sink.send("raw", b"\xff\x00").expect("send");
+
+        assert_eq!(topics(&pager), vec!["alarms/fire"]);
+        assert_eq!(topics(&regular), vec!["alarms/door", "sensors/temp", "raw"]);
+    }
+
+    #[test]
+    fn first_matching_route_wins() {
+        let first = Arc::new(InMemorySink::new());
+        let second = Arc::new(InMemorySink::new());
+        let sink = ContentRoutingSink::new(Arc::new(InMemorySink::new()))
+            .route(|v| v.get("value").is_some(), first.clone())
+            .route(|v| v["value"].as_i64() > Some(10), second.clone());
+
+        sink.send("t", br#"{"value":42}"#).expect("send");
+
+        assert_eq!(sink.route_count(), 2);
+        assert_eq!(topics(&first), vec!["t"]);
+        assert!(topics(&second).is_empty());
+    }
+}
@@ -0,0 +1,210 @@
+//! Host context enrichment sink decorator.
+//!
+//! Adds a `_meta` object describing the sending process (hostname, pid,
+//! OS, architecture, CPU count) to every JSON object payload, so collectors
+//! can tell instances apart without each producer adding it by hand.
+
+use crate::{layered_sink_name, TelemetryError, TelemetryResult, TelemetrySink};
+use serde_json::{Map, Value};
+
+/// Key of the object added to JSON payloads.
+pub const META_KEY: &str = "_meta";
+
+/// A field `ContextEnrichingSink` can add to `_meta`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MetaField {
+    /// From `HOSTNAME`/`COMPUTERNAME` or `/etc/hostname`; omitted if none is set.
+    Hostname,
+    Pid,
+    /// `std::env::consts::OS`
+    Os,
+    /// `std::env::consts::ARCH`
+    Arch,
+    NumCpus,
+}
+
+impl MetaField {
+    /// Every field, in the order they appear in `_meta`.
+    pub const ALL: [MetaField; 5] = [
+        MetaField::Hostname,
+        MetaField::Pid,
+        MetaField::Os,
+        MetaField::Arch,
+        MetaField::NumCpus,
+    ];
+
+    /// Key of this field inside `_meta`.
+    pub fn key(self) -> &'static str {
+        match self {
+            MetaField::Hostname => "hostname",
+            MetaField::Pid => "pid",
+            MetaField::Os => "os",
+            MetaField::Arch => "arch",
+            MetaField::NumCpus => "num_cpus",
+        }
+    }
+
+    fn collect(self) -> Option<Value> {
+        match self {
+            MetaField::Hostname => hostname().map(Value::from),
+            MetaField::Pid => Some(std::process::id().into()),
+            MetaField::Os => Some(std::env::consts::OS.into()),
+            MetaField::Arch => Some(std::env::consts::ARCH.into()),
+            MetaField::NumCpus => Some(num_cpus::get().into()),
+        }
+    }
+}
+
+fn hostname() -> Option<String> {
+    ["HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok())
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// A sink that adds host metadata to JSON object payloads.
+///
+/// The values are collected once, when the sink is built. Payloads that are
+/// not a JSON object (binary data, bare JSON values) are forwarded
+/// unchanged. Keys the payload's `_meta` already has are left alone.
+pub struct ContextEnrichingSink<S: TelemetrySink> {
+    inner: S,
+    meta: Map<String, Value>,
+}
+
+impl<S: TelemetrySink> ContextEnrichingSink<S> {
+    /// Add every `MetaField`.
+    pub fn new(inner: S) -> Self {
+        Self::with_fields(inner, &MetaField::ALL)
+    }
+
+    /// Add only `fields`.
+    pub fn with_fields(inner: S, fields: &[MetaField]) -> Self {
+        let meta = MetaField::ALL
+            .iter()
+            .filter(|field| fields.contains(field))
+            .filter_map(|field| Some((field.key().to_string(), field.collect()?)))
+            .collect();
+        Self { inner, meta }
+    }
+
+    /// Stop adding `field`.
+    pub fn without(mut self, field: MetaField) -> Self {
+        self.meta.remove(field.key());
+        self
+    }
+
+    /// The `_meta` entries added to each payload.
+    pub fn meta(&self) -> &Map<String, Value> {
+        &self.meta
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// `payload` with `_meta` added, or `None` if it is not a JSON object.
+    fn enrich(&self, payload: &[u8]) -> TelemetryResult<Option<Vec<u8>>> {
+        let Ok(Value::Object(mut fields)) = serde_json::from_slice(payload) else {
+            return Ok(None);
+        };
+        let meta = fields
+            .entry(META_KEY)
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Value::Object(meta) = meta {
+            for (key, value) in &self.meta {
+                meta.entry(key.as_str()).or_insert_with(|| value.clone());
+            }
+        }
+        serde_json::to_vec(&fields)
+            .map(Some)
+            .map_err(|e| TelemetryError::Serialization(format!("enriched payload: {}", e)))
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for ContextEnrichingSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("context_enriching", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        match self.enrich(payload)? {
+            Some(enriched) => self.inner.send(topic, &enriched),
+            None => self.inner.send(topic, payload),
+        }
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemorySink, TelemetryMessage};
+    use serde_json::json;
+
+    fn sent(sink: &ContextEnrichingSink<InMemorySink>) -> Vec<Vec<u8>> {
+        let records = sink.inner().records.lock().expect("lock");
+        records.iter().map(|(_, payload)| payload.clone()).collect()
+    }
+
+    #[test]
+    fn json_payload_gets_meta_block() {
+        let sink = ContextEnrichingSink::new(InMemorySink::new());
+        let msg = TelemetryMessage::new("svc/status", json!({"ok": true}));
+        sink.send(&msg.topic, msg.to_json().as_bytes())
+            .expect("send");
+
+        let value: Value = serde_json::from_slice(&sent(&sink)[0]).expect("json");
+        let meta = value[META_KEY].as_object().expect("_meta object");
+        for key in ["pid", "os", "arch", "num_cpus"] {
+            assert!(meta.contains_key(key), "missing {}", key);
+        }
+        assert_eq!(meta["pid"], json!(std::process::id()));
+        assert_eq!(meta["os"], json!(std::env::consts::OS));
+        assert_eq!(value["payload"], json!({"ok": true}));
+
+        // The envelope still decodes as a message
+        let decoded: TelemetryMessage = serde_json::from_value(value).expect("message");
+        assert_eq!(decoded.payload, json!({"ok": true}));
+    }
+
+    #[test]
+    fn fields_can_be_opted_out() {
+        let sink = ContextEnrichingSink::with_fields(
+            InMemorySink::new(),
+            &[MetaField::Os, MetaField::Arch, MetaField::Pid],
+        )
+        .without(MetaField::Pid);
+        assert_eq!(sink.meta().keys().collect::<Vec<_>>(), vec!["arch", "os"]);
+
+        sink.send("t", br#"{"_meta": {"os": "custom"}}"#)
+            .expect("send");
+        let value: Value = serde_json::from_slice(&sent(&sink)[0]).expect("json");
+        assert_eq!(
+            value[META_KEY],
+            json!({"os": "custom", "arch": std::env::consts::ARCH})
+        );
+    }
+
+    #[test]
+    fn non_object_payloads_pass_through_unchanged() {
+        let sink = ContextEnrichingSink::new(InMemorySink::new());
+        sink.send("raw", &[0xde, 0xad, 0xbe, 0xef]).expect("send");
+        sink.send("raw", b"21.5").expect("send");
+
+        assert_eq!(
+            sent(&sink),
+            vec![vec![0xde, 0xad, 0xbe, 0xef], b"21.5".to_vec()]
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! Conversion of CSV rows into structured telemetry.
+//!
+//! For legacy devices that emit one CSV line per reading: the row is zipped
+//! with a known header into a JSON object, so it can be published like any
+//! other message instead of being parsed at every call site.
+//!
+//! Fields are split on the delimiter and trimmed; quoting is not supported,
+//! so values must not contain the delimiter.
+
+use crate::{TelemetryError, TelemetryMessage, TelemetryResult};
+use serde_json::{Map, Number, Value};
+
+impl TelemetryMessage {
+    /// Build a message whose payload maps each `header` column to the
+    /// matching field of the comma-separated `row`.
+    ///
+    /// Fields that parse as integers or finite floats become JSON numbers;
+    /// everything else stays a string. A row with a different number of
+    /// fields than `header` is a `Serialization` error.
+    pub fn from_csv_row(
+        topic: impl Into<String>,
+        header: &[&str],
+        row: &str,
+    ) -> TelemetryResult<Self> {
+        Self::from_csv_row_with_delimiter(topic, header, row, ',')
+    }
+
+    /// Like `from_csv_row`, splitting on `delimiter` instead of a comma.
+    pub fn from_csv_row_with_delimiter(
+        topic: impl Into<String>,
+        header: &[&str],
+        row: &str,
+        delimiter: char,
+    ) -> TelemetryResult<Self> {
+        let fields: Vec<&str> = row
+            .trim_end_matches(['\r', '\n'])
+            .split(delimiter)
+            .collect();
+        if fields.len() != header.len() {
+            return Err(TelemetryError::Serialization(format!(
+                "CSV row has {} fields but header has {} columns: '{}'",
+                fields.len(),
+                header.len(),
+                row
+            )));
+        }
+        let payload: Map<String, Value> = header
+            .iter()
+            .zip(fields)
+            .map(|(column, field)| (column.trim().to_string(), csv_value(field.trim())))
+            .collect();
+        Self::try_new(topic, Value::Object(payload))
+    }
+}
+
+/// `field` as a JSON number if it looks numeric, otherwise as a string.
+fn csv_value(field: &str) -> Value {
+    if let Ok(int) = field.parse::<i64>() {
+        return int.into();
+    }
+    field
+        .parse::<f64>()
+        .ok()
+        .and_then(Number::from_f64)
+        // "inf" and "NaN" parse as floats but are not meant as numbers here
+        .filter(|_| field.bytes().any(|b| b.is_ascii_digit()))
+        .map_or_else(|| Value::String(field.to_string()), Value::Number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const HEADER: [&str; 4] = ["sensor", "temp", "count", "status"];
+
+    #[test]
+    fn row_becomes_json_object() {
+        let msg = TelemetryMessage::from_csv_row("legacy/th1", &HEADER, "th1, 21.5 ,3,OK\r\n")
+            .expect("parse");
+
+        assert_eq!(msg.topic, "legacy/th1");
+        assert_eq!(
+            msg.payload,
+            json!({"sensor": "th1", "temp": 21.5, "count": 3, "status": "OK"})
+        );
+    }
+
+    #[test]
+    fn numeric_looking_fields_are_coerced() {
+        let msg = TelemetryMessage::from_csv_row_with_delimiter(
+            "t",
+            &["a", "b", "c", "d", "e", "f"],
+            "-7;1e3;0x10;inf;;007",
+            ';',
+        )
+        .expect("parse");
+
+        assert_eq!(
+            msg.payload,
+            json!({"a": -7, "b": 1000.0, "c": "0x10", "d": "inf", "e": "", "f": 7})
+        );
+    }
+
+    #[test]
+    fn column_count_mismatch_is_an_error() {
+        let err = TelemetryMessage::from_csv_row("t", &HEADER, "th1,21.5").expect_err("short row");
+        match err {
+            TelemetryError::Serialization(msg) => {
+                assert!(
+                    msg.contains("2 fields") && msg.contains("4 columns"),
+                    "{}",
+                    msg
+                )
+            }
+            other => panic!("expected Serialization error, got {:?}", other),
+        }
+        assert!(TelemetryMessage::from_csv_row("t", &HEADER, "a,1,2,3,4").is_err());
+    }
+}
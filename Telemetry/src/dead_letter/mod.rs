@@ -0,0 +1,158 @@
+//! Dead-letter sink decorator.
+//!
+//! Captures messages the primary sink failed to deliver in a secondary
+//! "dead-letter" sink so they can be inspected or replayed later instead of
+//! being lost.
+
+use crate::{layered_sink_name, TelemetryResult, TelemetrySink};
+
+/// Separator between the error context and the original payload.
+const CONTEXT_SEPARATOR: u8 = b'\n';
+
+/// A sink that diverts failed sends to a dead-letter sink.
+///
+/// Dead-letter entries keep the original topic. Their payload is the primary
+/// sink's error message, a `\n` separator, then the original payload bytes;
+/// use `split_dead_letter` to take an entry apart.
+pub struct DeadLetterSink<P: TelemetrySink, D: TelemetrySink> {
+    primary: P,
+    dead_letter: D,
+    suppress_errors: bool,
+}
+
+impl<P: TelemetrySink, D: TelemetrySink> DeadLetterSink<P, D> {
+    /// Create a dead-letter sink that still reports primary failures.
+    pub fn new(primary: P, dead_letter: D) -> Self {
+        Self {
+            primary,
+            dead_letter,
+            suppress_errors: false,
+        }
+    }
+
+    /// Return `Ok(())` once a failed message has been captured.
+    ///
+    /// **Why optional?** Some callers treat "captured for later" as success;
+    /// others still need to know the primary transport is failing.
+    pub fn suppress_errors(mut self, suppress: bool) -> Self {
+        self.suppress_errors = suppress;
+        self
+    }
+
+    /// Access the primary sink.
+    pub fn primary(&self) -> &P {
+        &self.primary
+    }
+
+    /// Access the dead-letter sink.
+    pub fn dead_letter(&self) -> &D {
+        &self.dead_letter
+    }
+}
+
+impl<P: TelemetrySink, D: TelemetrySink> TelemetrySink for DeadLetterSink<P, D> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name(
+            "dead_letter",
+            &[self.primary.sink_name(), self.dead_letter.sink_name()],
+        )
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let err = match self.primary.send(topic, payload) {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+
+        let context = err.to_string().replace('\n', " ");
+        let mut entry = Vec::with_capacity(context.len() + 1 + payload.len());
+        entry.extend_from_slice(context.as_bytes());
+        entry.push(CONTEXT_SEPARATOR);
+        entry.extend_from_slice(payload);
+
+        if let Err(dlq_err) = self.dead_letter.send(topic, &entry) {
+            log::error!("dead-letter capture failed for {}: {}", topic, dlq_err);
+            return Err(err);
+        }
+        if self.suppress_errors {
+            Ok(())
+        } else {
+            Err(err)
+        }
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.primary.flush()?;
+        self.dead_letter.flush()
+    }
+
+    /// Health of the primary sink; the dead-letter sink is only a fallback.
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.primary.health_check()
+    }
+}
+
+/// Split a dead-letter payload into `(error context, original payload)`.
+///
+/// Returns `None` if the bytes were not produced by `DeadLetterSink`.
+pub fn split_dead_letter(entry: &[u8]) -> Option<(&str, &[u8])> {
+    let pos = entry.iter().position(|b| *b == CONTEXT_SEPARATOR)?;
+    let context = std::str::from_utf8(&entry[..pos]).ok()?;
+    Some((context, &entry[pos + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemorySink, TelemetryError};
+
+    struct FailingSink;
+
+    impl TelemetrySink for FailingSink {
+        fn send(&self, _topic: &str, _payload: &[u8]) -> TelemetryResult<()> {
+            Err(TelemetryError::Transport("broker rejected publish".into()))
+        }
+    }
+
+    #[test]
+    fn failed_message_lands_in_dead_letter_sink() {
+        let dlq = InMemorySink::new();
+        let records = dlq.records_arc();
+        let sink = DeadLetterSink::new(FailingSink, dlq);
+
+        let err = sink
+            .send("sensors/temp", b"{\"t\":1}")
+            .expect_err("primary fails");
+        assert!(matches!(err, TelemetryError::Transport(_)));
+
+        let records = records.lock().expect("lock");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].0, "sensors/temp");
+        let (context, payload) = split_dead_letter(&records[0].1).expect("framed entry");
+        assert!(context.contains("broker rejected publish"));
+        assert_eq!(payload, b"{\"t\":1}");
+    }
+
+    #[test]
+    fn suppressed_errors_return_ok() {
+        let dlq = InMemorySink::new();
+        let records = dlq.records_arc();
+        let sink = DeadLetterSink::new(FailingSink, dlq).suppress_errors(true);
+
+        assert!(sink.send("t", b"x").is_ok());
+        assert_eq!(records.lock().expect("lock").len(), 1);
+    }
+
+    #[test]
+    fn successful_sends_skip_dead_letter_sink() {
+        let primary = InMemorySink::new();
+        let primary_records = primary.records_arc();
+        let dlq = InMemorySink::new();
+        let dlq_records = dlq.records_arc();
+        let sink = DeadLetterSink::new(primary, dlq);
+
+        sink.send("t", b"ok").expect("send");
+        assert_eq!(primary_records.lock().expect("lock").len(), 1);
+        assert!(dlq_records.lock().expect("lock").is_empty());
+    }
+}
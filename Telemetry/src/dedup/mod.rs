@@ -0,0 +1,266 @@
+//! Deduplicating sink decorator.
+//!
+//! Upstream retry loops sometimes re-send identical messages; this decorator
+//! suppresses a `(topic, payload)` pair that was already forwarded within a
+//! configurable window so collectors do not double-count it.
+
+use crate::clock::elapsed_since;
+use crate::{
+    layered_sink_name, Clock, SystemClock, TelemetryError, TelemetryResult, TelemetrySink,
+};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Upper bound on remembered hashes for time-based windows.
+///
+/// **Why?** A time window alone does not bound memory under a high message
+/// rate; the oldest entries are evicted once this many are tracked.
+pub const DEFAULT_MAX_ENTRIES: usize = 10_000;
+
+/// How long a forwarded message suppresses identical copies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupWindow {
+    /// Suppress copies seen within this duration of the first one.
+    Time(Duration),
+    /// Suppress copies of any of the last `n` forwarded messages.
+    Count(usize),
+}
+
+#[derive(Default)]
+struct DedupState {
+    order: VecDeque<(u64, i64)>,
+    seen: HashMap<u64, usize>,
+}
+
+impl DedupState {
+    fn pop_oldest(&mut self) {
+        if let Some((hash, _)) = self.order.pop_front() {
+            if let Some(count) = self.seen.get_mut(&hash) {
+                *count -= 1;
+                if *count == 0 {
+                    self.seen.remove(&hash);
+                }
+            }
+        }
+    }
+}
+
+/// A sink that drops duplicate messages seen within a window.
+///
+/// Only delivered messages count: if the inner sink fails, the message is
+/// forgotten so a retry of it is forwarded.
+pub struct DedupSink<S: TelemetrySink> {
+    inner: S,
+    window: DedupWindow,
+    max_entries: usize,
+    state: Mutex<DedupState>,
+    suppressed: AtomicU64,
+    clock: Arc<dyn Clock>,
+}
+
+impl<S: TelemetrySink> DedupSink<S> {
+    /// Create a deduplicating sink with the given window.
+    pub fn new(inner: S, window: DedupWindow) -> Self {
+        let max_entries = match window {
+            DedupWindow::Time(_) => DEFAULT_MAX_ENTRIES,
+            DedupWindow::Count(n) => n.max(1),
+        };
+        Self {
+            inner,
+            window,
+            max_entries,
+            state: Mutex::new(DedupState::default()),
+            suppressed: AtomicU64::new(0),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Measure time windows with `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Cap the number of remembered hashes for time-based windows.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        if let DedupWindow::Time(_) = self.window {
+            self.max_entries = max_entries.max(1);
+        }
+        self
+    }
+
+    /// Number of messages dropped as duplicates.
+    pub fn duplicates_suppressed(&self) -> u64 {
+        self.suppressed.load(Ordering::Relaxed)
+    }
+
+    fn hash(topic: &str, payload: &[u8]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        topic.hash(&mut hasher);
+        payload.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Record the message if it is new, returning when it was recorded.
+    fn admit(&self, hash: u64) -> TelemetryResult<Option<i64>> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+        let now = self.clock.now_millis();
+        if let DedupWindow::Time(window) = self.window {
+            while matches!(state.order.front(), Some((_, at)) if elapsed_since(now, *at) > window) {
+                state.pop_oldest();
+            }
+        }
+        if state.seen.contains_key(&hash) {
+            return Ok(None);
+        }
+        state.order.push_back((hash, now));
+        *state.seen.entry(hash).or_insert(0) += 1;
+        while state.order.len() > self.max_entries {
+            state.pop_oldest();
+        }
+        Ok(Some(now))
+    }
+
+    /// Undo `admit` for a message the inner sink failed to deliver, so a
+    /// retry is forwarded instead of suppressed.
+    fn forget(&self, hash: u64, at: i64) {
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let Some(pos) = state.order.iter().rposition(|&entry| entry == (hash, at)) else {
+            return;
+        };
+        state.order.remove(pos);
+        if let Some(count) = state.seen.get_mut(&hash) {
+            *count -= 1;
+            if *count == 0 {
+                state.seen.remove(&hash);
+            }
+        }
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for DedupSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("dedup", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let hash = Self::hash(topic, payload);
+        let Some(at) = self.admit(hash)? else {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        };
+        let result = self.inner.send(topic, payload);
+        if result.is_err() {
+            self.forget(hash, at);
+        }
+        result
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Fault, FaultInjectionSink, InMemorySink, MockClock};
+
+    #[test]
+    fn duplicate_within_time_window_is_suppressed() {
+        let inner = InMemorySink::new();
+        let records = inner.records_arc();
+        let sink = DedupSink::new(inner, DedupWindow::Time(Duration::from_secs(60)));
+
+        sink.send("sensors/temp", b"21").expect("send");
+        sink.send("sensors/temp", b"21").expect("send");
+        sink.send("sensors/temp", b"22").expect("send");
+        sink.send("sensors/hum", b"21").expect("send");
+
+        assert_eq!(records.lock().expect("lock").len(), 3);
+        assert_eq!(sink.duplicates_suppressed(), 1);
+    }
+
+    #[test]
+    fn duplicate_after_time_window_passes() {
+        let inner = InMemorySink::new();
+        let records = inner.records_arc();
+        let clock = MockClock::new(0);
+        let sink = DedupSink::new(inner, DedupWindow::Time(Duration::from_millis(20)))
+            .with_clock(Arc::new(clock.clone()));
+
+        sink.send("t", b"same").expect("send");
+        clock.advance(Duration::from_millis(20));
+        sink.send("t", b"same").expect("window still open");
+        assert_eq!(sink.duplicates_suppressed(), 1);
+
+        clock.advance(Duration::from_millis(1));
+        sink.send("t", b"same").expect("window closed");
+
+        assert_eq!(records.lock().expect("lock").len(), 2);
+        assert_eq!(sink.duplicates_suppressed(), 1);
+    }
+
+    #[test]
+    fn count_window_forgets_older_messages() {
+        let inner = InMemorySink::new();
+        let records = inner.records_arc();
+        let sink = DedupSink::new(inner, DedupWindow::Count(2));
+
+        sink.send("t", b"a").expect("send");
+        sink.send("t", b"b").expect("send");
+        sink.send("t", b"a").expect("duplicate of recent message");
+        sink.send("t", b"c").expect("send");
+        sink.send("t", b"a").expect("'a' fell out of the window");
+
+        assert_eq!(records.lock().expect("lock").len(), 4);
+        assert_eq!(sink.duplicates_suppressed(), 1);
+    }
+
+    #[test]
+    fn failed_send_is_not_remembered() {
+        let sink = DedupSink::new(
+            FaultInjectionSink::scripted([Fault::Fail(TelemetryError::Transport(
+                "down".to_string(),
+            ))]),
+            DedupWindow::Time(Duration::from_secs(60)),
+        );
+
+        assert!(sink.send("t", b"same").is_err());
+        sink.send("t", b"same").expect("retry is forwarded");
+        sink.send("t", b"same")
+            .expect("duplicate of delivered message");
+
+        assert_eq!(sink.inner.call_count(), 2);
+        assert_eq!(sink.duplicates_suppressed(), 1);
+    }
+
+    #[test]
+    fn time_window_memory_is_bounded() {
+        let sink = DedupSink::new(
+            InMemorySink::new(),
+            DedupWindow::Time(Duration::from_secs(60)),
+        )
+        .with_max_entries(3);
+        for i in 0..10u8 {
+            sink.send("t", &[i]).expect("send");
+        }
+        let state = sink.state.lock().expect("lock");
+        assert_eq!(state.order.len(), 3);
+        assert_eq!(state.seen.len(), 3);
+    }
+}
@@ -0,0 +1,108 @@
+//! Delivery metadata shared by the queueing sinks.
+//!
+//! Sinks only see `(topic, payload)`, so `QueueSink` and `BufferingSink`
+//! recover a message's priority and TTL by peeking at its JSON envelope
+//! when it is enqueued.
+
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+
+/// The subset of a `TelemetryMessage` envelope relevant to queueing.
+///
+/// `topic` and `payload` are required so that arbitrary JSON payloads which
+/// merely happen to contain a `priority` key are not mistaken for envelopes.
+#[derive(Deserialize)]
+struct Envelope {
+    #[serde(rename = "topic")]
+    _topic: IgnoredAny,
+    #[serde(rename = "payload")]
+    _payload: IgnoredAny,
+    #[serde(default)]
+    priority: u8,
+    #[serde(default)]
+    ttl_ms: Option<u32>,
+    #[serde(default)]
+    created_at: Option<i64>,
+}
+
+/// A queued payload together with its ordering and expiry metadata.
+pub(crate) struct QueuedRecord {
+    pub(crate) topic: String,
+    pub(crate) payload: Vec<u8>,
+    pub(crate) priority: u8,
+    /// Milliseconds since the Unix epoch after which the record is dropped.
+    pub(crate) expires_at: Option<i64>,
+}
+
+impl QueuedRecord {
+    /// Capture `payload` for queueing at `now_millis`.
+    ///
+    /// Payloads that are not message envelopes get priority 0 and no TTL. An
+    /// envelope with a TTL but no `created_at` expires relative to `now_millis`.
+    pub(crate) fn new(topic: &str, payload: &[u8], now_millis: i64) -> Self {
+        let (priority, expires_at) = match serde_json::from_slice::<Envelope>(payload) {
+            Ok(env) => (
+                env.priority,
+                env.ttl_ms.map(|ttl| {
+                    env.created_at
+                        .unwrap_or(now_millis)
+                        .saturating_add(i64::from(ttl))
+                }),
+            ),
+            Err(_) => (0, None),
+        };
+        Self {
+            topic: topic.to_string(),
+            payload: payload.to_vec(),
+            priority,
+            expires_at,
+        }
+    }
+
+    pub(crate) fn is_expired_at(&self, now_millis: i64) -> bool {
+        self.expires_at.is_some_and(|at| now_millis > at)
+    }
+}
+
+/// Index at which a record of `priority` keeps the queue ordered by
+/// descending priority and FIFO within equal priorities.
+pub(crate) fn insert_position<'a, I>(mut queued: I, priority: u8) -> usize
+where
+    I: ExactSizeIterator<Item = &'a QueuedRecord>,
+{
+    let len = queued.len();
+    queued.position(|r| r.priority < priority).unwrap_or(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TelemetryMessage;
+
+    #[test]
+    fn reads_priority_and_ttl_from_envelope() {
+        let msg = TelemetryMessage::builder()
+            .topic("t")
+            .payload(serde_json::json!({ "priority": "not ours" }))
+            .priority(7)
+            .ttl(500)
+            .created_at(1_000)
+            .build()
+            .expect("valid message");
+        let record = QueuedRecord::new("t", msg.to_json().as_bytes(), 0);
+
+        assert_eq!(record.priority, 7);
+        assert_eq!(record.expires_at, Some(1_500));
+        assert!(!record.is_expired_at(1_500));
+        assert!(record.is_expired_at(1_501));
+    }
+
+    #[test]
+    fn plain_payloads_use_defaults() {
+        for payload in [&b"\xff\x00"[..], br#"{"priority": 9}"#] {
+            let record = QueuedRecord::new("t", payload, 0);
+            assert_eq!(record.priority, 0);
+            assert_eq!(record.expires_at, None);
+        }
+    }
+}
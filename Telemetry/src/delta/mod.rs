@@ -0,0 +1,280 @@
+//! Delta encoding for slowly-changing JSON telemetry.
+//!
+//! `DeltaSink` remembers the last object sent on each topic and forwards
+//! only the top-level fields that changed, with a full keyframe every
+//! `keyframe_interval` messages so consumers can resynchronise.
+//! `DeltaDecoder` rebuilds the full payloads on the receiving side.
+//!
+//! Wire format:
+//!
+//! - keyframe: `{"delta": false, "state": <full payload>}`
+//! - delta: `{"delta": true, "changed": {<field>: <new value>, ..}, "removed": [<field>, ..]}`
+//!   (`removed` is omitted when empty)
+//!
+//! Fields are compared as whole values, so a change deep inside a nested
+//! object resends that top-level field.
+
+use crate::{layered_sink_name, TelemetryError, TelemetryResult, TelemetrySink};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+struct TopicState {
+    last: Map<String, Value>,
+    /// Messages sent since the last keyframe, including it.
+    since_keyframe: u32,
+}
+
+/// A sink that forwards JSON object payloads as deltas against the previous
+/// payload on the same topic.
+///
+/// Payloads that are JSON but not objects are always sent as keyframes;
+/// payloads that are not JSON are rejected with
+/// `TelemetryError::Serialization`. If the inner sink fails, the next
+/// message on that topic is sent as a keyframe.
+pub struct DeltaSink<S: TelemetrySink> {
+    inner: S,
+    keyframe_interval: u32,
+    topics: Mutex<HashMap<String, TopicState>>,
+}
+
+impl<S: TelemetrySink> DeltaSink<S> {
+    /// Send a keyframe every `keyframe_interval` messages per topic (at
+    /// least every message).
+    pub fn new(inner: S, keyframe_interval: u32) -> Self {
+        Self {
+            inner,
+            keyframe_interval: keyframe_interval.max(1),
+            topics: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Messages per topic between keyframes.
+    pub fn keyframe_interval(&self) -> u32 {
+        self.keyframe_interval
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Encode `value` for `topic` and update the remembered state.
+    fn encode(&self, topic: &str, value: Value) -> TelemetryResult<Value> {
+        let mut topics = self
+            .topics
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+        let Value::Object(current) = value else {
+            topics.remove(topic);
+            return Ok(json!({"delta": false, "state": value}));
+        };
+        match topics.get_mut(topic) {
+            Some(state) if state.since_keyframe < self.keyframe_interval => {
+                let changed: Map<String, Value> = current
+                    .iter()
+                    .filter(|(k, v)| state.last.get(*k) != Some(*v))
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect();
+                let removed: Vec<&String> = state
+                    .last
+                    .keys()
+                    .filter(|k| !current.contains_key(*k))
+                    .collect();
+                let mut delta = json!({"delta": true, "changed": changed});
+                if !removed.is_empty() {
+                    delta["removed"] = json!(removed);
+                }
+                state.last = current;
+                state.since_keyframe += 1;
+                Ok(delta)
+            }
+            _ => {
+                let keyframe = json!({"delta": false, "state": current});
+                topics.insert(
+                    topic.to_string(),
+                    TopicState {
+                        last: current,
+                        since_keyframe: 1,
+                    },
+                );
+                Ok(keyframe)
+            }
+        }
+    }
+
+    fn forget(&self, topic: &str) {
+        if let Ok(mut topics) = self.topics.lock() {
+            topics.remove(topic);
+        }
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for DeltaSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("delta", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let value: Value = serde_json::from_slice(payload)
+            .map_err(|e| TelemetryError::Serialization(format!("payload is not JSON: {}", e)))?;
+        let encoded = self.encode(topic, value)?;
+        let result = self.inner.send(topic, encoded.to_string().as_bytes());
+        if result.is_err() {
+            // The consumer may not have seen this delta; resync next time
+            self.forget(topic);
+        }
+        result
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+/// Rebuilds full payloads from a `DeltaSink` stream.
+#[derive(Debug, Default)]
+pub struct DeltaDecoder {
+    state: HashMap<String, Value>,
+}
+
+impl DeltaDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply one encoded payload and return the full payload it stands for.
+    ///
+    /// A delta for a topic with no keyframe yet, or whose last keyframe was
+    /// not an object, is a `TelemetryError::Serialization`.
+    pub fn decode(&mut self, topic: &str, payload: &[u8]) -> TelemetryResult<Value> {
+        let malformed = |what: &str| TelemetryError::Serialization(format!("delta {}", what));
+        let encoded: Value = serde_json::from_slice(payload)
+            .map_err(|e| TelemetryError::Serialization(format!("delta payload: {}", e)))?;
+        match encoded.get("delta").and_then(Value::as_bool) {
+            Some(false) => {
+                let state = encoded
+                    .get("state")
+                    .cloned()
+                    .ok_or_else(|| malformed("keyframe without state"))?;
+                self.state.insert(topic.to_string(), state.clone());
+                Ok(state)
+            }
+            Some(true) => {
+                let Some(Value::Object(state)) = self.state.get_mut(topic) else {
+                    return Err(malformed(&format!("for '{}' before a keyframe", topic)));
+                };
+                if let Some(Value::Object(changed)) = encoded.get("changed") {
+                    state.extend(changed.clone());
+                }
+                if let Some(Value::Array(removed)) = encoded.get("removed") {
+                    for key in removed.iter().filter_map(Value::as_str) {
+                        state.remove(key);
+                    }
+                }
+                Ok(Value::Object(state.clone()))
+            }
+            None => Err(malformed("without a 'delta' flag")),
+        }
+    }
+
+    /// Last full payload rebuilt for `topic`.
+    pub fn state(&self, topic: &str) -> Option<&Value> {
+        self.state.get(topic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemorySink;
+
+    fn sent(sink: &DeltaSink<InMemorySink>) -> Vec<Value> {
+        sink.inner()
+            .records
+            .lock()
+            .expect("lock")
+            .iter()
+            .map(|(_, payload)| serde_json::from_slice(payload).expect("JSON"))
+            .collect()
+    }
+
+    #[test]
+    fn deltas_carry_only_changed_fields() {
+        let sink = DeltaSink::new(InMemorySink::new(), 100);
+        for temp in [20, 21, 21] {
+            let payload = json!({"device": "d1", "unit": "C", "temp": temp});
+            sink.send("sensors/d1", payload.to_string().as_bytes())
+                .expect("send");
+        }
+
+        let sent = sent(&sink);
+        assert_eq!(
+            sent[0],
+            json!({"delta": false, "state": {"device": "d1", "unit": "C", "temp": 20}})
+        );
+        assert_eq!(sent[1], json!({"delta": true, "changed": {"temp": 21}}));
+        assert_eq!(sent[2], json!({"delta": true, "changed": {}}));
+
+        let mut decoder = DeltaDecoder::new();
+        for (i, (topic, payload)) in sink
+            .inner()
+            .records
+            .lock()
+            .expect("lock")
+            .iter()
+            .enumerate()
+        {
+            let full = decoder.decode(topic, payload).expect("decode");
+            assert_eq!(full["temp"], json!(if i == 0 { 20 } else { 21 }));
+            assert_eq!(full["device"], "d1");
+        }
+    }
+
+    #[test]
+    fn sends_keyframe_every_interval() {
+        let sink = DeltaSink::new(InMemorySink::new(), 3);
+        for n in 0..7 {
+            sink.send("t", json!({"n": n}).to_string().as_bytes())
+                .expect("send");
+        }
+
+        let flags: Vec<bool> = sent(&sink)
+            .iter()
+            .map(|v| v["delta"].as_bool().expect("flag"))
+            .collect();
+        assert_eq!(flags, vec![false, true, true, false, true, true, false]);
+    }
+
+    #[test]
+    fn removed_fields_are_listed_and_applied() {
+        let sink = DeltaSink::new(InMemorySink::new(), 10);
+        sink.send("t", br#"{"a":1,"b":2}"#).expect("send");
+        sink.send("t", br#"{"a":1}"#).expect("send");
+        assert_eq!(
+            sent(&sink)[1],
+            json!({"delta": true, "changed": {}, "removed": ["b"]})
+        );
+
+        let mut decoder = DeltaDecoder::new();
+        let records = sink.inner().records.lock().expect("lock");
+        decoder.decode("t", &records[0].1).expect("keyframe");
+        assert_eq!(
+            decoder.decode("t", &records[1].1).expect("delta"),
+            json!({"a": 1})
+        );
+    }
+
+    #[test]
+    fn delta_before_keyframe_is_an_error() {
+        let mut decoder = DeltaDecoder::new();
+        let err = decoder
+            .decode("t", br#"{"delta":true,"changed":{"a":1}}"#)
+            .expect_err("no keyframe");
+        assert!(matches!(err, TelemetryError::Serialization(_)));
+    }
+}
@@ -0,0 +1,125 @@
+//! Eager validation of transport URLs.
+//!
+//! Used by the `try_new` constructors so a mistyped broker or collector
+//! address fails when the sink is built rather than on the first send.
+
+use crate::{TelemetryError, TelemetryResult};
+
+/// The parts of a `scheme://[user@]host[:port][/path]` URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EndpointUrl {
+    pub scheme: String,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+/// Parse `url`, requiring one of `schemes` (case-insensitive), a non-empty
+/// host and, if present, a port in `1..=65535`. `kind` names the transport
+/// in error messages.
+pub(crate) fn parse_endpoint(
+    kind: &str,
+    url: &str,
+    schemes: &[&str],
+) -> TelemetryResult<EndpointUrl> {
+    let invalid = |reason: String| {
+        TelemetryError::Connection(format!("invalid {} URL '{}': {}", kind, url, reason))
+    };
+    let (scheme, rest) = url
+        .split_once("://")
+        .ok_or_else(|| invalid("expected scheme://host".to_string()))?;
+    let scheme = scheme.to_ascii_lowercase();
+    if !schemes.contains(&scheme.as_str()) {
+        return Err(invalid(format!(
+            "scheme must be one of {}",
+            schemes.join(", ")
+        )));
+    }
+
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, hp)| hp);
+    let (host, port) = if let Some(v6) = host_port.strip_prefix('[') {
+        let (host, after) = v6
+            .split_once(']')
+            .ok_or_else(|| invalid("unterminated IPv6 address".to_string()))?;
+        match after {
+            "" => (host, None),
+            _ => (
+                host,
+                Some(
+                    after
+                        .strip_prefix(':')
+                        .ok_or_else(|| invalid(format!("unexpected '{}' after host", after)))?,
+                ),
+            ),
+        }
+    } else {
+        match host_port.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (host_port, None),
+        }
+    };
+
+    let valid_host = !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | ':'));
+    if !valid_host {
+        return Err(invalid(format!("invalid host '{}'", host)));
+    }
+    let port = port
+        .map(|p| match p.parse::<u16>() {
+            Ok(port) if port > 0 => Ok(port),
+            _ => Err(invalid(format!("invalid port '{}'", p))),
+        })
+        .transpose()?;
+
+    Ok(EndpointUrl {
+        scheme,
+        host: host.to_string(),
+        port,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMES: &[&str] = &["mqtt", "mqtts"];
+
+    #[test]
+    fn accepts_well_formed_urls() {
+        let url = parse_endpoint("MQTT", "mqtt://broker.local:1883", SCHEMES).expect("valid");
+        assert_eq!(
+            url,
+            EndpointUrl {
+                scheme: "mqtt".to_string(),
+                host: "broker.local".to_string(),
+                port: Some(1883),
+            }
+        );
+
+        let url = parse_endpoint("MQTT", "MQTTS://user:pw@[::1]/x", SCHEMES).expect("valid");
+        assert_eq!(url.scheme, "mqtts");
+        assert_eq!(url.host, "::1");
+        assert_eq!(url.port, None);
+    }
+
+    #[test]
+    fn rejects_malformed_urls() {
+        for url in [
+            "broker:1883",
+            "tcp://broker:1883",
+            "mqtt://",
+            "mqtt://:1883",
+            "mqtt://bro ker",
+            "mqtt://broker:port",
+            "mqtt://broker:0",
+            "mqtt://broker:70000",
+            "mqtt://[::1",
+        ] {
+            let err = parse_endpoint("MQTT", url, SCHEMES).expect_err(url);
+            assert!(matches!(err, TelemetryError::Connection(_)), "{}", url);
+            assert!(err.message().contains(url), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! URI-based sink construction.
+//!
+//! Lets deployments pick a transport from configuration, e.g.
+//! `TELEMETRY_SINK=grpc://collector:50051`, without touching call sites.
+//!
+//! Supported schemes:
+//!
+//! | URI | Sink | Feature |
+//! |-----|------|---------|
+//! | `memory://` | `InMemorySink` | — |
+//! | `file:///var/log/telem.jsonl` | `RecordingSink` | — |
+//! | `grpc://host:50051` | `grpc::GrpcSink` (plain-text HTTP/2) | `grpc` |
+//! | `http://host/ingest`, `https://...` | `http::HttpSink` | `http` |
+//! | `kafka://broker1:9092,broker2:9092` | `kafka::KafkaSink` | `kafka` |
+//! | `nats://host:4222` | `nats::NatsSink` | `nats` |
+//! | `udp://host:9000` | `udp::UdpSink` | `udp` |
+//! | `ws://host/path`, `wss://...` | `websocket::WebSocketSink` | `websocket` |
+//!
+//! `mqtt://` and `mqtts://` are refused: `mqtt::MqttSink` is still a stub
+//! that drops every message, which must not be picked up from configuration.
+
+use crate::{InMemorySink, RecordingSink, TelemetryError, TelemetryResult, TelemetrySink};
+use std::sync::Arc;
+
+/// Environment variable read by `sink_from_env`.
+pub const SINK_URI_ENV: &str = "TELEMETRY_SINK";
+
+/// Build the sink described by `uri`.
+///
+/// Unknown or unimplemented schemes, and schemes whose feature is not
+/// compiled in, return a `TelemetryError` naming the scheme (and the feature
+/// to enable).
+pub fn sink_from_uri(uri: &str) -> TelemetryResult<Arc<dyn TelemetrySink>> {
+    let (scheme, rest) = uri.split_once("://").ok_or_else(|| {
+        TelemetryError::new(format!("invalid sink URI '{}': expected scheme://...", uri))
+    })?;
+    match scheme.to_ascii_lowercase().as_str() {
+        "memory" => Ok(Arc::new(InMemorySink::new())),
+        "file" => {
+            if rest.is_empty() {
+                return Err(TelemetryError::new(format!(
+                    "invalid sink URI '{}': file path is empty",
+                    uri
+                )));
+            }
+            Ok(Arc::new(RecordingSink::create(rest)?))
+        }
+        "mqtt" | "mqtts" => Err(TelemetryError::new(format!(
+            "sink URI scheme '{}' is not implemented: the MQTT transport is a stub",
+            scheme
+        ))),
+        "grpc" => grpc(rest),
+        "http" | "https" => http(uri),
+        "kafka" => kafka(rest),
+        "nats" => nats(uri),
+        "udp" => udp(rest),
+        "ws" | "wss" => websocket(uri),
+        other => Err(TelemetryError::new(format!(
+            "unsupported sink URI scheme '{}' in '{}'",
+            other, uri
+        ))),
+    }
+}
+
+/// Build the sink named by the `TELEMETRY_SINK` environment variable,
+/// falling back to `memory://` when it is unset.
+pub fn sink_from_env() -> TelemetryResult<Arc<dyn TelemetrySink>> {
+    match std::env::var(SINK_URI_ENV) {
+        Ok(uri) => sink_from_uri(&uri),
+        Err(std::env::VarError::NotPresent) => sink_from_uri("memory://"),
+        Err(e) => Err(TelemetryError::new(format!("{}: {}", SINK_URI_ENV, e))),
+    }
+}
+
+// Unused when every transport feature is enabled
+#[allow(dead_code)]
+fn feature_disabled(scheme: &str, feature: &str) -> TelemetryError {
+    TelemetryError::new(format!(
+        "sink URI scheme '{}' requires the `{}` feature",
+        scheme, feature
+    ))
+}
+
+#[cfg(feature = "grpc")]
+fn grpc(authority: &str) -> TelemetryResult<Arc<dyn TelemetrySink>> {
+    Ok(Arc::new(crate::grpc::GrpcSink::try_new(format!(
+        "http://{}",
+        authority
+    ))?))
+}
+
+#[cfg(not(feature = "grpc"))]
+fn grpc(_authority: &str) -> TelemetryResult<Arc<dyn TelemetrySink>> {
+    Err(feature_disabled("grpc", "grpc"))
+}
+
+#[cfg(feature = "http")]
+fn http(uri: &str) -> TelemetryResult<Arc<dyn TelemetrySink>> {
+    Ok(Arc::new(crate::http::HttpSink::new(uri)?))
+}
+
+#[cfg(not(feature = "http"))]
+fn http(_uri: &str) -> TelemetryResult<Arc<dyn TelemetrySink>> {
+    Err(feature_disabled("http", "http"))
+}
+
+#[cfg(feature = "kafka")]
+fn kafka(brokers: &str) -> TelemetryResult<Arc<dyn TelemetrySink>> {
+    Ok(Arc::new(crate::kafka::KafkaSink::new(
+        brokers.trim_end_matches('/'),
+    )?))
+}
+
+#[cfg(not(feature = "kafka"))]
+fn kafka(_brokers: &str) -> TelemetryResult<Arc<dyn TelemetrySink>> {
+    Err(feature_disabled("kafka", "kafka"))
+}
+
+#[cfg(feature = "nats")]
+fn nats(uri: &str) -> TelemetryResult<Arc<dyn TelemetrySink>> {
+    Ok(Arc::new(crate::nats::NatsSink::new(uri)?))
+}
+
+#[cfg(not(feature = "nats"))]
+fn nats(_uri: &str) -> TelemetryResult<Arc<dyn TelemetrySink>> {
+    Err(feature_disabled("nats", "nats"))
+}
+
+#[cfg(feature = "udp")]
+fn udp(authority: &str) -> TelemetryResult<Arc<dyn TelemetrySink>> {
+    use std::net::ToSocketAddrs;
+    let target = authority
+        .trim_end_matches('/')
+        .to_socket_addrs()
+        .map_err(|e| TelemetryError::Connection(format!("cannot resolve {}: {}", authority, e)))?
+        .next()
+        .ok_or_else(|| TelemetryError::Connection(format!("no address for {}", authority)))?;
+    Ok(Arc::new(crate::udp::UdpSink::new(target)?))
+}
+
+#[cfg(not(feature = "udp"))]
+fn udp(_authority: &str) -> TelemetryResult<Arc<dyn TelemetrySink>> {
+    Err(feature_disabled("udp", "udp"))
+}
+
+#[cfg(feature = "websocket")]
+fn websocket(uri: &str) -> TelemetryResult<Arc<dyn TelemetrySink>> {
+    Ok(Arc::new(crate::websocket::WebSocketSink::new(uri)))
+}
+
+#[cfg(not(feature = "websocket"))]
+fn websocket(_uri: &str) -> TelemetryResult<Arc<dyn TelemetrySink>> {
+    Err(feature_disabled("ws", "websocket"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_scheme_always_works() {
+        let sink = sink_from_uri("memory://").expect("memory sink");
+        sink.send("t", b"x").expect("send");
+    }
+
+    #[test]
+    fn unsupported_scheme_is_descriptive() {
+        let err = sink_from_uri("carrier-pigeon://loft")
+            .err()
+            .expect("unsupported");
+        assert_eq!(
+            err.message(),
+            "unsupported sink URI scheme 'carrier-pigeon' in 'carrier-pigeon://loft'"
+        );
+
+        let err = sink_from_uri("localhost:1883").err().expect("no scheme");
+        assert!(err.message().contains("expected scheme://"));
+    }
+
+    #[test]
+    fn file_scheme_records_to_path() {
+        let path =
+            std::env::temp_dir().join(format!("telemetry-factory-{}.jsonl", std::process::id()));
+        let sink = sink_from_uri(&format!("file://{}", path.display())).expect("file sink");
+        sink.send("t", b"x").expect("send");
+        sink.flush().expect("flush");
+        drop(sink);
+
+        let contents = std::fs::read_to_string(&path).expect("read");
+        std::fs::remove_file(&path).ok();
+        assert_eq!(contents.lines().count(), 1);
+    }
+
+    #[test]
+    fn mqtt_scheme_is_refused_while_the_transport_is_a_stub() {
+        for uri in ["mqtt://broker.local:1883", "MQTTS://broker.local"] {
+            let err = sink_from_uri(uri).err().expect("stub transport");
+            assert!(err.message().contains("not implemented"), "{}", err);
+        }
+    }
+
+    #[cfg(feature = "mqtt")]
+    #[test]
+    fn mqtt_broker_url_is_validated_up_front() {
+        assert!(crate::mqtt::MqttSink::try_new("mqtts://broker.local").is_ok());
+        let err = crate::mqtt::MqttSink::try_new("mqtt://broker.local:18830000")
+            .err()
+            .expect("bad port");
+        assert!(matches!(err, TelemetryError::Connection(_)));
+        assert!(err.message().contains("invalid port"), "{}", err);
+        assert!(crate::mqtt::MqttSink::try_new("broker.local:1883").is_err());
+    }
+
+    #[cfg(not(feature = "grpc"))]
+    #[test]
+    fn disabled_feature_is_named() {
+        let err = sink_from_uri("grpc://host:50051").err().expect("disabled");
+        assert!(err.message().contains("requires the `grpc` feature"));
+    }
+}
@@ -0,0 +1,185 @@
+//! Fallback sink chain.
+//!
+//! Tries a primary transport and falls back to the next one only when it
+//! fails, e.g. MQTT first and a local file when the broker is unreachable.
+//! Unlike fan-out, each payload is delivered at most once.
+
+use crate::{layered_sink_name, TelemetryError, TelemetryResult, TelemetrySink};
+use std::any::Any;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// `last_used` value before any send has succeeded.
+const NONE_USED: usize = usize::MAX;
+
+/// A sink that sends to the first of an ordered list of sinks that accepts
+/// the payload.
+///
+/// If every sink fails, the returned `TelemetryError::Transport` lists each
+/// sink's error in order.
+pub struct FallbackSink {
+    sinks: Vec<Arc<dyn TelemetrySink>>,
+    last_used: AtomicUsize,
+}
+
+impl FallbackSink {
+    /// Try `sinks` in the given order.
+    pub fn new(sinks: Vec<Arc<dyn TelemetrySink>>) -> Self {
+        Self {
+            sinks,
+            last_used: AtomicUsize::new(NONE_USED),
+        }
+    }
+
+    /// Append `sink` as the last resort.
+    pub fn with_fallback(mut self, sink: Arc<dyn TelemetrySink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Number of sinks in the chain.
+    pub fn len(&self) -> usize {
+        self.sinks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+
+    /// Index of the sink that accepted the most recent successful send.
+    pub fn last_used_index(&self) -> Option<usize> {
+        match self.last_used.load(Ordering::Relaxed) {
+            NONE_USED => None,
+            index => Some(index),
+        }
+    }
+}
+
+impl TelemetrySink for FallbackSink {
+    fn sink_name(&self) -> &'static str {
+        let names: Vec<&str> = self.sinks.iter().map(|sink| sink.sink_name()).collect();
+        layered_sink_name("fallback", &names)
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let mut errors = Vec::new();
+        let mut last_error = None;
+        for (index, sink) in self.sinks.iter().enumerate() {
+            match sink.send(topic, payload) {
+                Ok(()) => {
+                    if !errors.is_empty() {
+                        log::warn!(
+                            "topic {}: fell back to sink {} after {} failure(s)",
+                            topic,
+                            index,
+                            errors.len()
+                        );
+                    }
+                    self.last_used.store(index, Ordering::Relaxed);
+                    return Ok(());
+                }
+                Err(e) => {
+                    errors.push(format!("[{}] {}", index, e));
+                    last_error = Some(e);
+                }
+            }
+        }
+        let err = TelemetryError::Transport(format!(
+            "all {} fallback sinks failed: {}",
+            self.sinks.len(),
+            errors.join("; ")
+        ));
+        Err(match last_error {
+            Some(last) => err.with_source(last),
+            None => err,
+        })
+    }
+
+    /// Flush every sink in the chain, returning the first error.
+    fn flush(&self) -> TelemetryResult<()> {
+        let mut result = Ok(());
+        for sink in &self.sinks {
+            if let Err(e) = sink.flush() {
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+
+    /// Healthy if any sink in the chain is; otherwise the last sink's error.
+    fn health_check(&self) -> TelemetryResult<()> {
+        let mut result = Ok(());
+        for sink in &self.sinks {
+            result = sink.health_check();
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemorySink;
+
+    struct FailingSink(TelemetryError);
+
+    impl TelemetrySink for FailingSink {
+        fn send(&self, _topic: &str, _payload: &[u8]) -> TelemetryResult<()> {
+            Err(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn failing_primary_falls_back_to_secondary() {
+        let tertiary = Arc::new(InMemorySink::new());
+        let secondary = Arc::new(InMemorySink::new());
+        let sink = FallbackSink::new(vec![
+            Arc::new(FailingSink(TelemetryError::Connection("down".into()))),
+            secondary.clone(),
+        ])
+        .with_fallback(tertiary.clone());
+        assert_eq!(sink.last_used_index(), None);
+
+        sink.send("t", b"x").expect("send");
+
+        assert_eq!(sink.last_used_index(), Some(1));
+        assert_eq!(
+            *secondary.records.lock().expect("lock"),
+            vec![("t".to_string(), b"x".to_vec())]
+        );
+        assert!(tertiary.records.lock().expect("lock").is_empty());
+    }
+
+    #[test]
+    fn all_failing_returns_combined_error() {
+        let sink = FallbackSink::new(vec![
+            Arc::new(FailingSink(TelemetryError::Connection(
+                "broker down".into(),
+            ))),
+            Arc::new(FailingSink(TelemetryError::Transport("disk full".into()))),
+        ]);
+
+        let err = sink.send("t", b"x").expect_err("all fail");
+
+        assert_eq!(
+            *err.head(),
+            TelemetryError::Transport(
+                "all 2 fallback sinks failed: [0] Connection error: broker down; \
+                 [1] Transport error: disk full"
+                    .into()
+            )
+        );
+        let source = std::error::Error::source(&err).expect("last sink's error");
+        assert_eq!(source.to_string(), "Transport error: disk full");
+        assert_eq!(sink.last_used_index(), None);
+    }
+}
@@ -0,0 +1,245 @@
+//! Scriptable fault-injection sink for tests.
+//!
+//! **Why not a failing mock?** Retry, fallback and circuit-breaker logic is
+//! about *sequences* of outcomes: fail twice then recover, slow down after
+//! the tenth call. Scripting each call's outcome makes those tests exact
+//! instead of relying on real transports or timing.
+
+use crate::{Clock, SystemClock, TelemetryError, TelemetryResult, TelemetrySink};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Outcome of one call to a `FaultInjectionSink`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fault {
+    /// Accept the payload.
+    Ok,
+    /// Return this error.
+    Fail(TelemetryError),
+    /// Wait this long through the sink's clock, then accept the payload.
+    Delay(Duration),
+}
+
+/// One call received by a `FaultInjectionSink`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FaultCall {
+    /// 0-based position among all calls to the sink.
+    pub index: usize,
+    pub topic: String,
+    pub payload: Vec<u8>,
+    /// What the sink did with the call.
+    pub fault: Fault,
+}
+
+type FaultFn = dyn Fn(usize, &str, &[u8]) -> Fault + Send + Sync;
+
+enum Script {
+    Sequence {
+        faults: VecDeque<Fault>,
+        then: Fault,
+    },
+    Predicate(Box<FaultFn>),
+}
+
+/// A terminal sink whose per-call outcome follows a script.
+///
+/// Every call is recorded with its outcome for later assertions. Delays go
+/// through the sink's clock, so with a `MockClock` they advance mock time
+/// instead of blocking.
+pub struct FaultInjectionSink {
+    script: Mutex<Script>,
+    calls: Mutex<Vec<FaultCall>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl FaultInjectionSink {
+    /// Apply `faults` to successive calls, one each; calls after the script
+    /// runs out succeed (see `then`).
+    pub fn scripted(faults: impl IntoIterator<Item = Fault>) -> Self {
+        Self::with_script(Script::Sequence {
+            faults: faults.into_iter().collect(),
+            then: Fault::Ok,
+        })
+    }
+
+    /// Decide each call's outcome with `decide(index, topic, payload)`.
+    pub fn from_fn<F>(decide: F) -> Self
+    where
+        F: Fn(usize, &str, &[u8]) -> Fault + Send + Sync + 'static,
+    {
+        Self::with_script(Script::Predicate(Box::new(decide)))
+    }
+
+    fn with_script(script: Script) -> Self {
+        Self {
+            script: Mutex::new(script),
+            calls: Mutex::new(Vec::new()),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Outcome for calls after a `scripted` sequence is exhausted.
+    ///
+    /// Has no effect on a sink built with `from_fn`.
+    pub fn then(self, fault: Fault) -> Self {
+        if let Script::Sequence { then, .. } = &mut *self
+            .script
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+        {
+            *then = fault;
+        }
+        self
+    }
+
+    /// Wait out `Fault::Delay` through `clock`, e.g. a `MockClock` in tests.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Every call received so far, in order.
+    pub fn calls(&self) -> Vec<FaultCall> {
+        self.calls
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Number of calls received so far.
+    pub fn call_count(&self) -> usize {
+        self.calls.lock().map_or(0, |calls| calls.len())
+    }
+
+    fn next_fault(&self, index: usize, topic: &str, payload: &[u8]) -> Fault {
+        match &mut *self
+            .script
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+        {
+            Script::Sequence { faults, then } => faults.pop_front().unwrap_or_else(|| then.clone()),
+            Script::Predicate(decide) => decide(index, topic, payload),
+        }
+    }
+}
+
+impl TelemetrySink for FaultInjectionSink {
+    fn sink_name(&self) -> &'static str {
+        "fault_injection"
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        // Hold the log while deciding so indices match the script order
+        let fault = {
+            let mut calls = self
+                .calls
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let index = calls.len();
+            let fault = self.next_fault(index, topic, payload);
+            calls.push(FaultCall {
+                index,
+                topic: topic.to_string(),
+                payload: payload.to_vec(),
+                fault: fault.clone(),
+            });
+            fault
+        };
+        match fault {
+            Fault::Ok => Ok(()),
+            Fault::Fail(e) => Err(e),
+            Fault::Delay(duration) => {
+                self.clock.sleep(duration);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BreakerState, CircuitBreakerSink, MockClock, RetrySink};
+
+    fn transport(msg: &str) -> Fault {
+        Fault::Fail(TelemetryError::Transport(msg.to_string()))
+    }
+
+    #[test]
+    fn retry_recovers_from_scripted_failures() {
+        let clock = MockClock::new(0);
+        let sink = RetrySink::new(
+            FaultInjectionSink::scripted([Fault::Ok, transport("a"), transport("b"), Fault::Ok]),
+            3,
+            Duration::from_millis(100),
+        )
+        .with_clock(Arc::new(clock.clone()));
+
+        sink.send("t", b"1").expect("first send");
+        sink.send("t", b"2").expect("retried send");
+
+        let calls = sink.inner().calls();
+        assert_eq!(sink.inner().call_count(), 4);
+        assert_eq!(calls[3].index, 3);
+        assert_eq!(calls[3].payload, b"2");
+        assert_eq!(
+            calls.iter().map(|c| &c.fault).collect::<Vec<_>>(),
+            [&Fault::Ok, &transport("a"), &transport("b"), &Fault::Ok]
+        );
+        assert_eq!(clock.now_millis(), 200);
+    }
+
+    #[test]
+    fn retry_gives_up_when_script_keeps_failing() {
+        let sink = RetrySink::new(
+            FaultInjectionSink::scripted([Fault::Ok, transport("a"), transport("b"), Fault::Ok]),
+            2,
+            Duration::ZERO,
+        );
+
+        sink.send("t", b"1").expect("first send");
+        let err = sink.send("t", b"2").expect_err("exhausted");
+
+        assert!(matches!(err.head(), TelemetryError::Transport(_)));
+        assert_eq!(sink.inner().call_count(), 3);
+        sink.send("t", b"3").expect("script recovered");
+        assert_eq!(sink.inner().call_count(), 4);
+    }
+
+    #[test]
+    fn delays_advance_the_clock_and_then_applies_after_script() {
+        let clock = MockClock::new(0);
+        let sink = FaultInjectionSink::scripted([Fault::Delay(Duration::from_secs(3))])
+            .then(Fault::Fail(TelemetryError::Connection("gone".to_string())))
+            .with_clock(Arc::new(clock.clone()));
+
+        sink.send("t", b"x").expect("delayed send");
+        assert_eq!(clock.now_millis(), 3_000);
+        assert!(matches!(
+            sink.send("t", b"x"),
+            Err(TelemetryError::Connection(_))
+        ));
+    }
+
+    #[test]
+    fn predicate_drives_circuit_breaker_open() {
+        let sink = CircuitBreakerSink::new(
+            FaultInjectionSink::from_fn(|_, topic, _| match topic {
+                "down" => transport("down"),
+                _ => Fault::Ok,
+            }),
+            2,
+            Duration::from_secs(60),
+        );
+
+        sink.send("up", b"x").expect("healthy topic");
+        for _ in 0..3 {
+            assert!(sink.send("down", b"x").is_err());
+        }
+
+        assert_eq!(sink.state(), BreakerState::Open);
+        // The third failure was rejected by the open breaker, not the sink
+        assert_eq!(sink.inner().call_count(), 3);
+    }
+}
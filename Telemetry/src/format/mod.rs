@@ -0,0 +1,134 @@
+//! Wire format used by `TelemetryClient::send_message`.
+//!
+//! **Why on the client?** Choosing the format once, where the client is
+//! built, keeps call sites identical whichever encoding a deployment uses,
+//! instead of scattering `send_message_*` variants through the code.
+
+use crate::{TelemetryError, TelemetryMessage, TelemetryResult};
+use std::fmt;
+
+/// Encoding applied to structured messages.
+///
+/// Every variant exists regardless of features; selecting one whose feature
+/// is disabled is an error rather than a compile failure, so configuration
+/// can name a format the current build lacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SerializationFormat {
+    #[default]
+    Json,
+    /// Requires the `msgpack` feature.
+    MsgPack,
+    /// Requires the `cbor` feature.
+    Cbor,
+}
+
+impl SerializationFormat {
+    /// Cargo feature that enables this format, if one is needed.
+    pub fn feature(self) -> Option<&'static str> {
+        match self {
+            Self::Json => None,
+            Self::MsgPack => Some("msgpack"),
+            Self::Cbor => Some("cbor"),
+        }
+    }
+
+    /// Whether this build can encode the format.
+    pub fn is_available(self) -> bool {
+        match self {
+            Self::Json => true,
+            Self::MsgPack => cfg!(feature = "msgpack"),
+            Self::Cbor => cfg!(feature = "cbor"),
+        }
+    }
+
+    /// Encode `msg` in this format.
+    pub fn encode(self, msg: &TelemetryMessage) -> TelemetryResult<Vec<u8>> {
+        match self {
+            Self::Json => msg.try_to_json().map(String::into_bytes),
+            #[cfg(feature = "msgpack")]
+            Self::MsgPack => msg.to_msgpack(),
+            #[cfg(feature = "cbor")]
+            Self::Cbor => msg.to_cbor(),
+            #[allow(unreachable_patterns)]
+            _ => Err(self.unavailable()),
+        }
+    }
+
+    pub(crate) fn unavailable(self) -> TelemetryError {
+        TelemetryError::new(format!(
+            "{} serialization requires the '{}' feature",
+            self,
+            self.feature().unwrap_or_default()
+        ))
+    }
+}
+
+impl fmt::Display for SerializationFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Json => "JSON",
+            Self::MsgPack => "MessagePack",
+            Self::Cbor => "CBOR",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemorySink, TelemetryClient};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    /// Send one message with a client using `format`; return the recorded bytes.
+    fn sent_with(format: SerializationFormat) -> (TelemetryMessage, Vec<u8>) {
+        let sink = Arc::new(InMemorySink::new());
+        let client = TelemetryClient::new(sink.clone())
+            .with_format(format)
+            .expect("format available");
+        assert_eq!(client.format(), format);
+        let msg = TelemetryMessage::new("sensors/temp", json!({"value": 21.5}));
+        client.send_message(&msg).expect("send");
+
+        let payload = sink.records.lock().expect("lock")[0].1.clone();
+        (msg, payload)
+    }
+
+    #[test]
+    fn json_is_the_default() {
+        let client = TelemetryClient::new(Arc::new(InMemorySink::new()));
+        assert_eq!(client.format(), SerializationFormat::Json);
+
+        let (msg, payload) = sent_with(SerializationFormat::Json);
+        let decoded: TelemetryMessage = serde_json::from_slice(&payload).expect("decode");
+        assert_eq!(decoded, msg);
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack_client_sends_msgpack() {
+        let (msg, payload) = sent_with(SerializationFormat::MsgPack);
+        assert_eq!(
+            TelemetryMessage::from_msgpack(&payload).expect("decode"),
+            msg
+        );
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_client_sends_cbor() {
+        let (msg, payload) = sent_with(SerializationFormat::Cbor);
+        assert_eq!(TelemetryMessage::from_cbor(&payload).expect("decode"), msg);
+    }
+
+    #[cfg(not(feature = "msgpack"))]
+    #[test]
+    fn disabled_format_is_rejected() {
+        assert!(!SerializationFormat::MsgPack.is_available());
+        let err = TelemetryClient::new(Arc::new(InMemorySink::new()))
+            .with_format(SerializationFormat::MsgPack)
+            .err()
+            .expect("msgpack disabled");
+        assert!(err.message().contains("'msgpack' feature"), "{}", err);
+    }
+}
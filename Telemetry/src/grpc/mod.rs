@@ -0,0 +1,445 @@
+//! gRPC transport for telemetry data.
+//!
+//! **Why feature-gated?** gRPC adds protobuf/networking complexity;
+//! only enable if your deployment uses gRPC for telemetry.
+//!
+//! `GrpcSink` publishes over the client-streaming `Publish` RPC defined in
+//! `proto/telemetry_service.proto`. One stream carries every send until it is
+//! closed by `flush`, at which point the server's acknowledgement confirms how
+//! many envelopes arrived.
+
+pub mod proto;
+
+use crate::{ShutdownSink, TelemetryError, TelemetryResult, TelemetrySink};
+use proto::telemetry_service_client::TelemetryServiceClient;
+use proto::{PublishAck, TelemetryEnvelope};
+use std::any::Any;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Status};
+use tonic_health::pb::health_check_response::ServingStatus;
+use tonic_health::pb::health_client::HealthClient;
+use tonic_health::pb::HealthCheckRequest;
+
+/// Envelopes buffered between `send` and the stream before `send` blocks.
+const STREAM_BUFFER: usize = 256;
+
+/// Map a gRPC status onto the matching `TelemetryError` category.
+pub fn status_to_error(status: &Status) -> TelemetryError {
+    let message = format!("gRPC {:?}: {}", status.code(), status.message());
+    match status.code() {
+        Code::Unavailable => TelemetryError::Connection(message),
+        Code::ResourceExhausted => TelemetryError::RateLimited(message),
+        Code::InvalidArgument => TelemetryError::Serialization(message),
+        _ => TelemetryError::Transport(message),
+    }
+}
+
+/// An open `Publish` call.
+struct PublishStream {
+    tx: mpsc::Sender<TelemetryEnvelope>,
+    call: JoinHandle<Result<tonic::Response<PublishAck>, Status>>,
+    sent: u64,
+}
+
+/// A sink that streams payloads to a gRPC telemetry service.
+///
+/// The stream is opened on first send and reused until `flush` closes it.
+/// A send only queues its envelope on the stream, so a failure of the call
+/// surfaces later: the first send after the stream broke returns the error
+/// (including any envelopes the server did not acknowledge) without sending
+/// its own payload, and the send after that opens a new stream.
+///
+/// The sink drives tonic on a private runtime, so it must not be used from
+/// inside an async context.
+pub struct GrpcSink {
+    endpoint: Endpoint,
+    runtime: Runtime,
+    stream: Mutex<Option<PublishStream>>,
+}
+
+impl GrpcSink {
+    /// Create a sink for a service at `endpoint`, e.g. `http://[::1]:50051`.
+    ///
+    /// The connection is made lazily by the first send.
+    pub fn new(endpoint: impl Into<String>) -> TelemetryResult<Self> {
+        let endpoint = Endpoint::from_shared(endpoint.into())
+            .map_err(|e| TelemetryError::Connection(format!("invalid gRPC endpoint: {}", e)))?
+            .connect_timeout(Duration::from_secs(5));
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .map_err(|e| TelemetryError::new(format!("gRPC runtime: {}", e)))?;
+        Ok(Self {
+            endpoint,
+            runtime,
+            stream: Mutex::new(None),
+        })
+    }
+
+    /// Like `new`, but first checks that `endpoint` is an `http://` or
+    /// `https://` URL with a host and a valid port, if any, which `new`
+    /// does not fully enforce.
+    pub fn try_new(endpoint: impl Into<String>) -> TelemetryResult<Self> {
+        let endpoint = endpoint.into();
+        crate::endpoint::parse_endpoint("gRPC endpoint", &endpoint, &["http", "https"])?;
+        Self::new(endpoint)
+    }
+
+    /// Set the connect timeout (default 5s).
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.endpoint = self.endpoint.connect_timeout(timeout);
+        self
+    }
+
+    /// Service address this sink publishes to.
+    pub fn endpoint(&self) -> String {
+        self.endpoint.uri().to_string()
+    }
+
+    fn connect(&self) -> TelemetryResult<Channel> {
+        self.runtime.block_on(self.endpoint.connect()).map_err(|e| {
+            TelemetryError::Connection(format!("gRPC connect to {}: {}", self.endpoint.uri(), e))
+        })
+    }
+
+    fn open_stream(&self) -> TelemetryResult<PublishStream> {
+        let channel = self.connect()?;
+        let mut client = TelemetryServiceClient::new(channel);
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+        let call = self
+            .runtime
+            .spawn(async move { client.publish(ReceiverStream::new(rx)).await });
+        Ok(PublishStream { tx, call, sent: 0 })
+    }
+
+    /// Wait for the server's verdict on a stream whose sender is gone.
+    fn finish(&self, stream: PublishStream) -> TelemetryResult<PublishAck> {
+        let PublishStream { tx, call, sent } = stream;
+        drop(tx);
+        let ack = self
+            .runtime
+            .block_on(call)
+            .map_err(|e| TelemetryError::new(format!("gRPC stream task failed: {}", e)))?
+            .map_err(|status| status_to_error(&status))?
+            .into_inner();
+        if ack.received < sent {
+            return Err(TelemetryError::Transport(format!(
+                "gRPC server acknowledged {} of {} envelopes",
+                ack.received, sent
+            )));
+        }
+        Ok(ack)
+    }
+}
+
+impl TelemetrySink for GrpcSink {
+    fn sink_name(&self) -> &'static str {
+        "grpc"
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let mut guard = self
+            .stream
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+        let mut envelope = TelemetryEnvelope {
+            topic: topic.to_string(),
+            payload: payload.to_vec(),
+        };
+        if let Some(stream) = guard.as_mut() {
+            match stream.tx.blocking_send(envelope) {
+                Ok(()) => {
+                    stream.sent += 1;
+                    return Ok(());
+                }
+                // The call ended and dropped its receiver. Envelopes queued
+                // on it may be lost, so report that rather than reconnect
+                // silently; a clean end just means starting a new stream.
+                Err(mpsc::error::SendError(rejected)) => {
+                    envelope = rejected;
+                    if let Some(broken) = guard.take() {
+                        self.finish(broken)?;
+                    }
+                }
+            }
+        }
+
+        let mut stream = self.open_stream()?;
+        if let Err(mpsc::error::SendError(_)) = stream.tx.blocking_send(envelope) {
+            return Err(self
+                .finish(stream)
+                .err()
+                .unwrap_or_else(|| TelemetryError::Transport("gRPC stream closed".into())));
+        }
+        stream.sent += 1;
+        *guard = Some(stream);
+        Ok(())
+    }
+
+    /// Close the current stream and confirm the server received every
+    /// envelope sent on it.
+    fn flush(&self) -> TelemetryResult<()> {
+        let stream = self
+            .stream
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?
+            .take();
+        match stream {
+            Some(stream) => self.finish(stream).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    /// Ask the server's standard `grpc.health.v1.Health` service whether
+    /// the telemetry service is `SERVING`.
+    fn health_check(&self) -> TelemetryResult<()> {
+        let mut client = HealthClient::new(self.connect()?);
+        let request = HealthCheckRequest {
+            service: proto::telemetry_service_server::SERVICE_NAME.to_string(),
+        };
+        let status = self
+            .runtime
+            .block_on(client.check(request))
+            .map_err(|status| status_to_error(&status))?
+            .into_inner()
+            .status();
+        if status != ServingStatus::Serving {
+            return Err(TelemetryError::Connection(format!(
+                "gRPC service at {} is {}",
+                self.endpoint.uri(),
+                status.as_str_name()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl ShutdownSink for GrpcSink {
+    fn close(self) -> TelemetryResult<()> {
+        self.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::proto::telemetry_service_server::{TelemetryService, TelemetryServiceServer};
+    use super::*;
+    use crate::TelemetryRecord;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tokio_stream::StreamExt;
+
+    /// Collects envelopes; the first `fail_streams` calls end with `status`,
+    /// as soon as `abort_after` envelopes have arrived if that is set.
+    #[derive(Clone, Default)]
+    struct Collector {
+        received: Arc<Mutex<Vec<TelemetryRecord>>>,
+        calls: Arc<AtomicUsize>,
+        fail_streams: usize,
+        status: Option<Code>,
+        abort_after: Option<usize>,
+    }
+
+    #[tonic::async_trait]
+    impl TelemetryService for Collector {
+        async fn publish(
+            &self,
+            request: tonic::Request<tonic::Streaming<TelemetryEnvelope>>,
+        ) -> Result<tonic::Response<PublishAck>, Status> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            let mut stream = request.into_inner();
+            let mut received = 0;
+            while let Some(envelope) = stream.next().await {
+                let envelope = envelope?;
+                if call < self.fail_streams {
+                    received += 1;
+                    if let (Some(code), Some(limit)) = (self.status, self.abort_after) {
+                        if received as usize >= limit {
+                            return Err(Status::new(code, "aborted"));
+                        }
+                    }
+                    continue;
+                }
+                self.received
+                    .lock()
+                    .expect("lock")
+                    .push((envelope.topic, envelope.payload));
+                received += 1;
+            }
+            match self.status {
+                Some(code) if call < self.fail_streams => Err(Status::new(code, "rejected")),
+                _ => Ok(tonic::Response::new(PublishAck { received })),
+            }
+        }
+    }
+
+    /// Serve `collector` on an ephemeral port; returns the endpoint URL.
+    fn serve(runtime: &Runtime, collector: Collector) -> String {
+        let listener = runtime
+            .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+            .expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let (mut reporter, health) = tonic_health::server::health_reporter();
+        runtime.block_on(reporter.set_serving::<TelemetryServiceServer<Collector>>());
+        runtime.spawn(
+            tonic::transport::Server::builder()
+                .add_service(health)
+                .add_service(TelemetryServiceServer::new(collector))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        format!("http://{}", addr)
+    }
+
+    fn server_runtime() -> Runtime {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .expect("runtime")
+    }
+
+    #[test]
+    fn streams_envelopes_to_server() {
+        let runtime = server_runtime();
+        let collector = Collector::default();
+        let url = serve(&runtime, collector.clone());
+
+        let sink = GrpcSink::new(url).expect("sink");
+        sink.send("sensors/temp", b"21.5").expect("send");
+        sink.send("sensors/hum", b"\x00\xff").expect("send");
+        sink.flush().expect("flush");
+
+        assert_eq!(
+            *collector.received.lock().expect("lock"),
+            vec![
+                ("sensors/temp".to_string(), b"21.5".to_vec()),
+                ("sensors/hum".to_string(), b"\x00\xff".to_vec()),
+            ]
+        );
+        assert_eq!(collector.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn status_is_mapped_and_next_send_reconnects() {
+        let runtime = server_runtime();
+        let collector = Collector {
+            fail_streams: 1,
+            status: Some(Code::ResourceExhausted),
+            ..Collector::default()
+        };
+        let url = serve(&runtime, collector.clone());
+
+        let sink = GrpcSink::new(url).expect("sink");
+        sink.send("t", b"lost").expect("send");
+        let err = sink.flush().expect_err("server rejected stream");
+        assert!(matches!(err, TelemetryError::RateLimited(_)));
+
+        sink.send("t", b"kept").expect("send");
+        sink.flush().expect("flush");
+        assert_eq!(
+            *collector.received.lock().expect("lock"),
+            vec![("t".to_string(), b"kept".to_vec())]
+        );
+        assert_eq!(collector.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn stream_broken_mid_way_is_reported_to_the_caller() {
+        let runtime = server_runtime();
+        let collector = Collector {
+            fail_streams: 1,
+            status: Some(Code::Unavailable),
+            abort_after: Some(2),
+            ..Collector::default()
+        };
+        let url = serve(&runtime, collector.clone());
+
+        let sink = GrpcSink::new(url).expect("sink");
+        let mut outcomes = Vec::new();
+        for i in 0..6u8 {
+            outcomes.push(sink.send("t", &[i]));
+            if i == 2 {
+                // Let the server abort the call and drop its receiver
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        }
+        outcomes.push(sink.flush());
+
+        let errors: Vec<&TelemetryError> =
+            outcomes.iter().filter_map(|r| r.as_ref().err()).collect();
+        let delivered = collector.received.lock().expect("lock").len();
+        assert!(
+            !errors.is_empty() || delivered == 6,
+            "{} of 6 delivered with no error",
+            delivered
+        );
+        if let Some(first) = errors.first() {
+            assert!(matches!(first, TelemetryError::Connection(_)));
+        }
+        assert_eq!(collector.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn health_check_queries_health_service() {
+        let runtime = server_runtime();
+        let url = serve(&runtime, Collector::default());
+
+        GrpcSink::new(url)
+            .expect("sink")
+            .health_check()
+            .expect("serving");
+    }
+
+    #[test]
+    fn unreachable_server_is_connection_error() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|l| l.local_addr())
+            .expect("free port")
+            .port();
+        let sink = GrpcSink::new(format!("http://127.0.0.1:{}", port)).expect("sink");
+        let err = sink.send("t", b"x").expect_err("nothing listening");
+        assert!(matches!(err, TelemetryError::Connection(_)));
+    }
+
+    #[test]
+    fn try_new_validates_endpoint() {
+        let sink = GrpcSink::try_new("http://collector.local:50051").expect("valid");
+        assert_eq!(sink.endpoint(), "http://collector.local:50051/");
+
+        for bad in [
+            "collector:50051",
+            "grpc://collector:50051",
+            "http://:50051",
+            "http://c:x",
+        ] {
+            let err = GrpcSink::try_new(bad).err().expect(bad);
+            assert!(matches!(err, TelemetryError::Connection(_)), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn maps_status_codes() {
+        let cases = [
+            (Code::Unavailable, "Connection"),
+            (Code::ResourceExhausted, "RateLimited"),
+            (Code::InvalidArgument, "Serialization"),
+            (Code::Internal, "Transport"),
+        ];
+        for (code, expected) in cases {
+            let err = status_to_error(&Status::new(code, "x"));
+            assert!(format!("{:?}", err).starts_with(expected), "{:?}", err);
+        }
+    }
+}
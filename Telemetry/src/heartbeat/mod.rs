@@ -0,0 +1,191 @@
+//! Periodic liveness messages.
+//!
+//! **Why?** Monitoring that alerts on silence cannot tell an idle service
+//! from a dead one. A heartbeat on a fixed interval keeps the topic alive
+//! whether or not there is real data to report.
+
+use crate::{TelemetryClient, TelemetryError, TelemetryMessage, TelemetryResult};
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+type PayloadFn = Box<dyn FnMut() -> Value + Send>;
+
+/// A heartbeat waiting to be started.
+pub struct Heartbeat {
+    client: TelemetryClient,
+    topic: String,
+    interval: Duration,
+    payload: PayloadFn,
+}
+
+impl Heartbeat {
+    /// Send `payload()` to `topic` through `client` every `interval`.
+    ///
+    /// Messages go through the client's normal send path (prefix, headers,
+    /// limits and counters). A zero interval is treated as 1ms.
+    pub fn new<F>(
+        client: TelemetryClient,
+        topic: impl Into<String>,
+        interval: Duration,
+        payload: F,
+    ) -> Self
+    where
+        F: FnMut() -> Value + Send + 'static,
+    {
+        Self {
+            client,
+            topic: topic.into(),
+            interval: interval.max(Duration::from_millis(1)),
+            payload: Box::new(payload),
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Send the first heartbeat now and the rest from a background thread.
+    ///
+    /// Ticks are scheduled at fixed offsets from the start, so a slow send
+    /// does not push every later heartbeat back. A failed send is logged and
+    /// the next tick tries again.
+    pub fn start(self) -> TelemetryResult<HeartbeatHandle> {
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+        let sent = Arc::new(AtomicU64::new(0));
+        let signal = Arc::clone(&stop);
+        let counter = Arc::clone(&sent);
+        let thread = std::thread::Builder::new()
+            .name(format!("heartbeat-{}", self.topic))
+            .spawn(move || self.run(&signal, &counter))
+            .map_err(|e| TelemetryError::new(format!("cannot spawn heartbeat thread: {}", e)))?;
+        Ok(HeartbeatHandle {
+            stop,
+            sent,
+            thread: Some(thread),
+        })
+    }
+
+    fn run(mut self, stop: &(Mutex<bool>, Condvar), sent: &AtomicU64) {
+        let (flag, wake) = stop;
+        let started = Instant::now();
+        let mut ticks: u32 = 0;
+        loop {
+            let msg = TelemetryMessage::new(self.topic.as_str(), (self.payload)());
+            match self.client.send_message(&msg) {
+                Ok(()) => {
+                    sent.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(e) => log::warn!("heartbeat on '{}' failed: {}", self.topic, e),
+            }
+
+            ticks = ticks.saturating_add(1);
+            let next = started + self.interval.saturating_mul(ticks);
+            let wait = next.saturating_duration_since(Instant::now());
+            let guard = flag.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let (guard, _) = wake
+                .wait_timeout_while(guard, wait, |stopped| !*stopped)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if *guard {
+                return;
+            }
+        }
+    }
+}
+
+/// Handle to a running heartbeat; dropping it stops the heartbeat too.
+pub struct HeartbeatHandle {
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    sent: Arc<AtomicU64>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl HeartbeatHandle {
+    /// Heartbeats delivered so far.
+    pub fn sent(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    /// Stop sending and wait for the background thread to exit.
+    ///
+    /// Returns once any heartbeat in progress has been sent; no further
+    /// heartbeats follow.
+    pub fn stop(mut self) {
+        self.shut_down();
+    }
+
+    fn shut_down(&mut self) {
+        let Some(thread) = self.thread.take() else {
+            return;
+        };
+        let (flag, wake) = &*self.stop;
+        *flag.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = true;
+        wake.notify_all();
+        if thread.join().is_err() {
+            log::warn!("heartbeat thread panicked");
+        }
+    }
+}
+
+impl Drop for HeartbeatHandle {
+    fn drop(&mut self) {
+        self.shut_down();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemorySink;
+    use serde_json::json;
+
+    fn delivered(sink: &InMemorySink) -> usize {
+        sink.records.lock().expect("lock").len()
+    }
+
+    #[test]
+    fn sends_on_every_tick_until_stopped() {
+        let sink = Arc::new(InMemorySink::new());
+        let client = TelemetryClient::new(sink.clone());
+        let mut seq = 0;
+        let handle = Heartbeat::new(client, "health", Duration::from_millis(20), move || {
+            seq += 1;
+            json!({ "seq": seq })
+        })
+        .start()
+        .expect("start");
+
+        std::thread::sleep(Duration::from_millis(110));
+        let sent = handle.sent();
+        handle.stop();
+
+        // Ticks at 0, 20, ..., 100ms; allow for a slow CI scheduler
+        let count = delivered(&sink);
+        assert!((3..=7).contains(&count), "{} heartbeats", count);
+        assert!(sent as usize <= count);
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(delivered(&sink), count);
+
+        let records = sink.records.lock().expect("lock");
+        assert_eq!(records[0].0, "health");
+        let last: Value = serde_json::from_slice(&records[count - 1].1).expect("json");
+        assert_eq!(last["payload"]["seq"], json!(count));
+    }
+
+    #[test]
+    fn dropping_the_handle_stops_the_heartbeat() {
+        let sink = Arc::new(InMemorySink::new());
+        let client = TelemetryClient::new(sink.clone());
+        let heartbeat = Heartbeat::new(client, "health", Duration::ZERO, || json!("ok"));
+        assert_eq!(heartbeat.interval(), Duration::from_millis(1));
+
+        drop(heartbeat.start().expect("start"));
+        let count = delivered(&sink);
+        assert!(count >= 1);
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(delivered(&sink), count);
+    }
+}
@@ -0,0 +1,250 @@
+//! HTTP transport for telemetry data.
+//!
+//! **Why feature-gated?** The HTTP client pulls in TLS and networking
+//! dependencies that embedded builds do not need.
+//! Enable with `features = ["http"]` in Cargo.toml.
+
+use crate::{TelemetryError, TelemetryResult, TelemetrySink};
+use std::any::Any;
+use std::time::Duration;
+
+/// Placeholder replaced by the topic in `HttpSinkConfig::url_template`.
+pub const TOPIC_PLACEHOLDER: &str = "{topic}";
+
+/// Configuration for `HttpSink`.
+#[derive(Debug, Clone)]
+pub struct HttpSinkConfig {
+    /// Target URL; every `{topic}` is replaced by the message topic.
+    pub url_template: String,
+    /// Value of the `Content-Type` header sent with each payload.
+    pub content_type: String,
+    /// Per-request timeout (connect + transfer).
+    pub timeout: Duration,
+    /// Extra headers sent with every request (e.g. an auth token).
+    pub headers: Vec<(String, String)>,
+    /// URL fetched by `health_check`; without one the check always passes.
+    pub health_url: Option<String>,
+}
+
+impl HttpSinkConfig {
+    /// Configuration posting to `{base_url}/{topic}` as JSON with a 5s timeout.
+    pub fn new(base_url: impl AsRef<str>) -> Self {
+        Self {
+            url_template: format!(
+                "{}/{}",
+                base_url.as_ref().trim_end_matches('/'),
+                TOPIC_PLACEHOLDER
+            ),
+            content_type: "application/json".to_string(),
+            timeout: Duration::from_secs(5),
+            headers: Vec::new(),
+            health_url: None,
+        }
+    }
+
+    /// Use a custom URL template instead of `{base_url}/{topic}`.
+    pub fn url_template(mut self, template: impl Into<String>) -> Self {
+        self.url_template = template.into();
+        self
+    }
+
+    /// Set the `Content-Type` header.
+    pub fn content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = content_type.into();
+        self
+    }
+
+    /// Set the per-request timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Add a header sent with every request.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// GET `url` in `health_check`, expecting a 2xx response.
+    pub fn health_url(mut self, url: impl Into<String>) -> Self {
+        self.health_url = Some(url.into());
+        self
+    }
+}
+
+/// A sink that POSTs each payload to an HTTP endpoint.
+pub struct HttpSink {
+    client: reqwest::blocking::Client,
+    config: HttpSinkConfig,
+}
+
+impl HttpSink {
+    /// Create a sink posting to `{base_url}/{topic}` with default settings.
+    pub fn new(base_url: impl AsRef<str>) -> TelemetryResult<Self> {
+        Self::with_config(HttpSinkConfig::new(base_url))
+    }
+
+    /// Create a sink from an explicit configuration.
+    ///
+    /// Returns an error if a header name or value is invalid or the HTTP
+    /// client cannot be initialised.
+    pub fn with_config(config: HttpSinkConfig) -> TelemetryResult<Self> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in &config.headers {
+            let name = reqwest::header::HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                TelemetryError::new(format!("invalid header name '{}': {}", name, e))
+            })?;
+            let value = reqwest::header::HeaderValue::from_str(value)
+                .map_err(|e| TelemetryError::new(format!("invalid header value: {}", e)))?;
+            headers.insert(name, value);
+        }
+        let client = reqwest::blocking::Client::builder()
+            .timeout(config.timeout)
+            .default_headers(headers)
+            .build()
+            .map_err(|e| TelemetryError::Connection(format!("HTTP client init failed: {}", e)))?;
+        Ok(Self { client, config })
+    }
+
+    /// Active configuration.
+    pub fn config(&self) -> &HttpSinkConfig {
+        &self.config
+    }
+
+    /// URL a payload for `topic` is posted to.
+    pub fn url_for(&self, topic: &str) -> String {
+        self.config.url_template.replace(TOPIC_PLACEHOLDER, topic)
+    }
+}
+
+impl TelemetrySink for HttpSink {
+    fn sink_name(&self) -> &'static str {
+        "http"
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let url = self.url_for(topic);
+        let response = self
+            .client
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, &self.config.content_type)
+            .body(payload.to_vec())
+            .send()
+            .map_err(|e| TelemetryError::Connection(format!("POST {} failed: {}", url, e)))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(TelemetryError::Transport(format!(
+                "POST {} returned HTTP {}",
+                url,
+                status.as_u16()
+            )));
+        }
+        Ok(())
+    }
+
+    /// GET the configured `health_url`, if any.
+    fn health_check(&self) -> TelemetryResult<()> {
+        let Some(url) = &self.config.health_url else {
+            return Ok(());
+        };
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .map_err(|e| TelemetryError::Connection(format!("GET {} failed: {}", url, e)))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(TelemetryError::Transport(format!(
+                "GET {} returned HTTP {}",
+                url,
+                status.as_u16()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::mock_server;
+    use std::net::TcpListener;
+
+    #[test]
+    fn posts_payload_to_topic_path() {
+        let (base, server) = mock_server(200);
+        let sink = HttpSink::with_config(
+            HttpSinkConfig::new(format!("{}/ingest", base)).header("Authorization", "Bearer t0k3n"),
+        )
+        .expect("sink");
+
+        sink.send("sensors/temp", br#"{"temp":21}"#).expect("send");
+
+        let (request_line, headers, body) = server.join().expect("server");
+        assert_eq!(request_line, "POST /ingest/sensors/temp HTTP/1.1");
+        assert_eq!(body, br#"{"temp":21}"#.to_vec());
+        let headers: Vec<String> = headers.iter().map(|h| h.to_ascii_lowercase()).collect();
+        assert!(headers.contains(&"content-type: application/json".to_string()));
+        assert!(headers.contains(&"authorization: bearer t0k3n".to_string()));
+    }
+
+    #[test]
+    fn non_success_status_maps_to_transport_error() {
+        let (base, server) = mock_server(503);
+        let sink = HttpSink::new(&base).expect("sink");
+
+        let err = sink.send("svc/status", b"{}").expect_err("503 should fail");
+        server.join().expect("server");
+        match err {
+            TelemetryError::Transport(msg) => assert!(msg.contains("503"), "{}", msg),
+            other => panic!("expected Transport error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unreachable_endpoint_maps_to_connection_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let base = format!("http://{}", listener.local_addr().expect("addr"));
+        drop(listener);
+
+        let sink =
+            HttpSink::with_config(HttpSinkConfig::new(&base).timeout(Duration::from_secs(1)))
+                .expect("sink");
+        let err = sink.send("t", b"x").expect_err("nothing listening");
+        assert!(matches!(err, TelemetryError::Connection(_)), "{:?}", err);
+    }
+
+    #[test]
+    fn health_check_gets_health_url() {
+        let (base, server) = mock_server(200);
+        let sink = HttpSink::with_config(
+            HttpSinkConfig::new(&base).health_url(format!("{}/healthz", base)),
+        )
+        .expect("sink");
+
+        sink.health_check().expect("healthy");
+        let (request_line, _, _) = server.join().expect("server");
+        assert_eq!(request_line, "GET /healthz HTTP/1.1");
+
+        let (base, server) = mock_server(503);
+        let sink =
+            HttpSink::with_config(HttpSinkConfig::new(&base).health_url(&base)).expect("sink");
+        let err = sink.health_check().expect_err("503 is unhealthy");
+        server.join().expect("server");
+        assert!(matches!(err, TelemetryError::Transport(_)), "{:?}", err);
+    }
+
+    #[test]
+    fn url_template_substitutes_topic() {
+        let sink = HttpSink::with_config(
+            HttpSinkConfig::new("http://unused").url_template("http://collector/v1?topic={topic}"),
+        )
+        .expect("sink");
+        assert_eq!(sink.url_for("a/b"), "http://collector/v1?topic=a/b");
+    }
+}
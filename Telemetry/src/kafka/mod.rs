@@ -0,0 +1,309 @@
+//! Kafka transport for telemetry data.
+//!
+//! **Why feature-gated?** `rdkafka` builds the librdkafka C library; only
+//! deployments that feed a Kafka-backed data lake should pay for it.
+//! Enable with `features = ["kafka"]` in Cargo.toml.
+//!
+//! Each payload is produced to a Kafka topic derived from the telemetry
+//! topic. An optional partition key keeps related readings (e.g. one
+//! device's) on one partition, and so in order.
+
+use crate::{ShutdownSink, TelemetryError, TelemetryResult, TelemetrySink};
+use rdkafka::config::ClientConfig;
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::producer::{BaseRecord, DeliveryResult, Producer, ProducerContext, ThreadedProducer};
+use rdkafka::ClientContext;
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Translates a telemetry topic into a Kafka topic.
+pub type TopicMapper = Box<dyn Fn(&str) -> String + Send + Sync>;
+
+/// Derives the partition key for a `(topic, payload)` pair; `None` lets the
+/// producer's partitioner choose.
+pub type PartitionKeyFn = Box<dyn Fn(&str, &[u8]) -> Option<Vec<u8>> + Send + Sync>;
+
+/// Default topic mapping: `sensors/temp` becomes `sensors.temp`, since `/` is
+/// not a legal character in Kafka topic names.
+pub fn default_topic(topic: &str) -> String {
+    topic.replace('/', ".")
+}
+
+/// Partition key taken from a top-level field of a JSON payload.
+///
+/// Strings are used as-is; other values use their JSON text. Payloads that
+/// are not JSON objects or lack the field get no key.
+pub fn key_from_json_field(
+    field: impl Into<String>,
+) -> impl Fn(&str, &[u8]) -> Option<Vec<u8>> + Send + Sync + 'static {
+    let field = field.into();
+    move |_topic, payload| {
+        let value: serde_json::Value = serde_json::from_slice(payload).ok()?;
+        match value.get(&field)? {
+            serde_json::Value::String(s) => Some(s.clone().into_bytes()),
+            other => Some(other.to_string().into_bytes()),
+        }
+    }
+}
+
+/// Configuration for `KafkaSink`.
+#[derive(Debug, Clone)]
+pub struct KafkaSinkConfig {
+    /// Comma-separated bootstrap servers, e.g. `localhost:9092`.
+    pub brokers: String,
+    /// Upper bound on delivery, used for `message.timeout.ms` and `flush`.
+    pub timeout: Duration,
+    /// Extra librdkafka properties, e.g. `acks` or `compression.type`.
+    pub properties: BTreeMap<String, String>,
+}
+
+impl KafkaSinkConfig {
+    /// Configuration with a 5s delivery timeout.
+    pub fn new(brokers: impl Into<String>) -> Self {
+        Self {
+            brokers: brokers.into(),
+            timeout: Duration::from_secs(5),
+            properties: BTreeMap::new(),
+        }
+    }
+
+    /// Set the delivery and flush timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set a librdkafka producer property.
+    pub fn property(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.properties.insert(key.into(), value.into());
+        self
+    }
+
+    fn client_config(&self) -> ClientConfig {
+        let mut config = ClientConfig::new();
+        config
+            .set("bootstrap.servers", &self.brokers)
+            .set("message.timeout.ms", self.timeout.as_millis().to_string());
+        for (key, value) in &self.properties {
+            config.set(key, value);
+        }
+        config
+    }
+}
+
+/// Records delivery-report failures until the next flush collects them.
+#[derive(Default)]
+struct DeliveryTracker {
+    failures: Mutex<Vec<String>>,
+}
+
+impl ClientContext for DeliveryTracker {}
+
+impl ProducerContext for DeliveryTracker {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, result: &DeliveryResult<'_>, _: ()) {
+        if let Err((e, _)) = result {
+            log::warn!("Kafka delivery failed: {}", e);
+            if let Ok(mut failures) = self.failures.lock() {
+                failures.push(e.to_string());
+            }
+        }
+    }
+}
+
+/// Map a producer error onto the matching `TelemetryError` category.
+fn kafka_error(context: &str, e: KafkaError) -> TelemetryError {
+    let message = format!("Kafka {}: {}", context, e);
+    match e.rdkafka_error_code() {
+        Some(RDKafkaErrorCode::QueueFull) => TelemetryError::RateLimited(message),
+        Some(RDKafkaErrorCode::MessageSizeTooLarge) => TelemetryError::Serialization(message),
+        Some(RDKafkaErrorCode::AllBrokersDown | RDKafkaErrorCode::BrokerTransportFailure) => {
+            TelemetryError::Connection(message)
+        }
+        _ => TelemetryError::Transport(message),
+    }
+}
+
+/// A sink that produces each payload to a Kafka topic.
+///
+/// `send` only enqueues the record with librdkafka; delivery happens in the
+/// background. Failed deliveries are reported by the next `flush`.
+pub struct KafkaSink {
+    config: KafkaSinkConfig,
+    producer: ThreadedProducer<DeliveryTracker>,
+    topic_mapper: TopicMapper,
+    partition_key: Option<PartitionKeyFn>,
+}
+
+impl KafkaSink {
+    /// Create a producer for `brokers` with default settings.
+    pub fn new(brokers: impl Into<String>) -> TelemetryResult<Self> {
+        Self::with_config(KafkaSinkConfig::new(brokers))
+    }
+
+    /// Create a producer from an explicit configuration.
+    pub fn with_config(config: KafkaSinkConfig) -> TelemetryResult<Self> {
+        let producer = config
+            .client_config()
+            .create_with_context(DeliveryTracker::default())
+            .map_err(|e| TelemetryError::Connection(format!("Kafka producer: {}", e)))?;
+        Ok(Self {
+            config,
+            producer,
+            topic_mapper: Box::new(default_topic),
+            partition_key: None,
+        })
+    }
+
+    /// Replace the default `/`→`.` topic translation.
+    pub fn with_topic_mapper<F>(mut self, mapper: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.topic_mapper = Box::new(mapper);
+        self
+    }
+
+    /// Key each record with `key_fn`, e.g. `key_from_json_field("device_id")`.
+    pub fn with_partition_key<F>(mut self, key_fn: F) -> Self
+    where
+        F: Fn(&str, &[u8]) -> Option<Vec<u8>> + Send + Sync + 'static,
+    {
+        self.partition_key = Some(Box::new(key_fn));
+        self
+    }
+
+    /// Active configuration.
+    pub fn config(&self) -> &KafkaSinkConfig {
+        &self.config
+    }
+
+    /// Kafka topic a payload for `topic` is produced to.
+    pub fn topic_for(&self, topic: &str) -> String {
+        (self.topic_mapper)(topic)
+    }
+
+    /// Partition key a payload would be produced with.
+    pub fn key_for(&self, topic: &str, payload: &[u8]) -> Option<Vec<u8>> {
+        self.partition_key.as_ref()?(topic, payload)
+    }
+
+    /// Wait up to `timeout` for every queued record to be delivered.
+    ///
+    /// Fails if records are still in flight when the timeout expires, or if
+    /// any delivery since the last flush was rejected.
+    pub fn flush_timeout(&self, timeout: Duration) -> TelemetryResult<()> {
+        self.producer
+            .flush(timeout)
+            .map_err(|e| kafka_error("flush", e))?;
+        let failures = std::mem::take(
+            &mut *self
+                .producer
+                .context()
+                .failures
+                .lock()
+                .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?,
+        );
+        match failures.last() {
+            None => Ok(()),
+            Some(last) => Err(TelemetryError::Transport(format!(
+                "{} Kafka deliveries failed, last: {}",
+                failures.len(),
+                last
+            ))),
+        }
+    }
+}
+
+impl TelemetrySink for KafkaSink {
+    fn sink_name(&self) -> &'static str {
+        "kafka"
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let kafka_topic = self.topic_for(topic);
+        let key = self.key_for(topic, payload);
+        let mut record = BaseRecord::<[u8], [u8]>::to(&kafka_topic).payload(payload);
+        if let Some(key) = key.as_deref() {
+            record = record.key(key);
+        }
+        self.producer
+            .send(record)
+            .map_err(|(e, _)| kafka_error("produce", e))
+    }
+
+    /// Flush with the configured timeout.
+    fn flush(&self) -> TelemetryResult<()> {
+        self.flush_timeout(self.config.timeout)
+    }
+}
+
+impl ShutdownSink for KafkaSink {
+    fn close(self) -> TelemetryResult<()> {
+        self.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_from_json_field_extracts_device_id() {
+        let key = key_from_json_field("device_id");
+        assert_eq!(
+            key("t", br#"{"device_id":"pump-7","value":1}"#),
+            Some(b"pump-7".to_vec())
+        );
+        assert_eq!(key("t", br#"{"device_id":42}"#), Some(b"42".to_vec()));
+        assert_eq!(key("t", br#"{"value":1}"#), None);
+        assert_eq!(key("t", b"\xff"), None);
+    }
+
+    #[test]
+    fn maps_topics_and_keys() {
+        let sink = KafkaSink::new("127.0.0.1:1")
+            .expect("producer")
+            .with_partition_key(key_from_json_field("device_id"));
+
+        assert_eq!(sink.topic_for("sensors/temp"), "sensors.temp");
+        assert_eq!(
+            sink.key_for("sensors/temp", br#"{"device_id":"d1"}"#),
+            Some(b"d1".to_vec())
+        );
+
+        let sink = sink.with_topic_mapper(|t| format!("telemetry-{}", t.replace('/', "-")));
+        assert_eq!(sink.topic_for("sensors/temp"), "telemetry-sensors-temp");
+    }
+
+    #[test]
+    fn flush_fails_when_broker_is_unreachable() {
+        let config = KafkaSinkConfig::new("127.0.0.1:1").timeout(Duration::from_millis(200));
+        let sink = KafkaSink::with_config(config).expect("producer");
+
+        sink.send("t", b"x").expect("enqueue");
+        assert!(sink.flush().is_err());
+    }
+
+    /// Runs against a real cluster when `ROOM619_KAFKA_BROKERS` is set,
+    /// e.g. `ROOM619_KAFKA_BROKERS=localhost:9092 cargo test --features kafka`.
+    #[test]
+    fn produces_to_live_cluster() {
+        let Ok(brokers) = std::env::var("ROOM619_KAFKA_BROKERS") else {
+            return;
+        };
+        let sink = KafkaSink::new(brokers)
+            .expect("producer")
+            .with_partition_key(key_from_json_field("device_id"));
+        sink.send("room619/test", br#"{"device_id":"d1","value":1}"#)
+            .expect("produce");
+        sink.flush().expect("flush");
+    }
+}
@@ -8,52 +8,317 @@
 //! **Why traits?** A trait-based design lets each protocol (MQTT, gRPC, etc.)
 //! provide its own `TelemetrySink` implementation, and allows tests to inject
 //! mock or in-memory sinks without external dependencies.
+//!
+//! **Timed sinks.** `TimeWindowSink`, `MergeSink`, `AggregatingSink` and
+//! `CoalescingSink` hold data until a window or timeout has passed, but only
+//! check for that when they are called (`send`, `poll` or `flush`). If
+//! traffic can stop, the last window waits for the next call unless
+//! something polls the sink. Each has an opt-in `with_poll_interval` that
+//! starts a background thread doing so, joined when the sink is dropped;
+//! without it nothing runs in the background.
 
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
+use std::any::Any;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+pub mod aggregating;
+pub mod alias;
+pub mod allow_list;
+#[cfg(feature = "async")]
+pub mod async_client;
+#[cfg(feature = "async")]
+pub mod async_in_memory;
+mod auto_flush;
+pub mod buffering;
+pub mod catch_panic;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod circuit_breaker;
+pub mod clock;
+pub mod coalescing;
+pub mod codec;
+#[cfg(feature = "compression")]
+pub mod compression;
+#[cfg(feature = "async")]
+pub mod concurrency_limit;
+pub mod console;
+pub mod content_routing;
+pub mod context;
+pub mod csv;
+pub mod dead_letter;
+pub mod dedup;
+mod delivery;
+pub mod delta;
+#[cfg(any(feature = "mqtt", feature = "grpc"))]
+mod endpoint;
+pub mod factory;
+pub mod fallback;
+pub mod fault_injection;
+pub mod format;
+pub mod heartbeat;
+pub mod merge;
+pub mod metrics;
+pub mod monotonic;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+pub mod ordered;
+pub mod outbox;
+pub mod pooled;
+pub mod prometheus;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
+#[cfg(feature = "async")]
+pub mod queue;
+pub mod rate_stats;
+pub mod replay;
+pub mod restful;
+pub mod retry;
+pub mod sampling;
+pub mod scheduled;
+pub mod sequencing;
+pub mod shutdown;
+#[cfg(feature = "signing")]
+pub mod signing;
+pub mod source;
+pub mod time_window;
+pub mod timing;
+pub mod topic;
+pub mod transform;
+pub mod typed;
+#[cfg(feature = "jsonschema")]
+pub mod validating;
+pub mod writer;
+
+pub use aggregating::{AggregateSummary, AggregatingSink};
+pub use alias::{AliasRule, AliasSink};
+pub use allow_list::AllowListSink;
+#[cfg(feature = "async")]
+pub use async_client::AsyncTelemetryClient;
+#[cfg(feature = "async")]
+pub use async_in_memory::AsyncInMemorySink;
+pub use buffering::BufferingSink;
+pub use catch_panic::CatchPanicSink;
+pub use circuit_breaker::{BreakerState, CircuitBreakerSink};
+pub use clock::{Clock, MockClock, SystemClock};
+pub use coalescing::CoalescingSink;
+#[cfg(feature = "cbor")]
+pub use codec::CborCodec;
+#[cfg(feature = "msgpack")]
+pub use codec::MsgPackCodec;
+pub use codec::{JsonCodec, PayloadCodec};
+#[cfg(feature = "compression")]
+pub use compression::{
+    decompress, decompress_with_limit, AutoCompression, CompressingSink, Compression,
+    CompressionMode,
+};
+#[cfg(feature = "async")]
+pub use concurrency_limit::ConcurrencyLimitSink;
+pub use console::{ConsoleFormat, ConsoleSink, ConsoleTarget};
+pub use content_routing::{ContentPredicate, ContentRoutingSink};
+pub use context::{ContextEnrichingSink, MetaField};
+pub use dead_letter::{split_dead_letter, DeadLetterSink};
+pub use dedup::{DedupSink, DedupWindow};
+pub use delta::{DeltaDecoder, DeltaSink};
+pub use factory::{sink_from_env, sink_from_uri};
+pub use fallback::FallbackSink;
+pub use fault_injection::{Fault, FaultCall, FaultInjectionSink};
+pub use format::SerializationFormat;
+pub use heartbeat::{Heartbeat, HeartbeatHandle};
+pub use merge::MergeSink;
+pub use metrics::{Counter, Gauge, Histogram, HistogramSnapshot, MetricsRegistry};
+pub use monotonic::{MonotonicStampSink, StampMode, StampScope};
+pub use ordered::OrderedSink;
+pub use outbox::{OutboxEntry, OutboxSink};
+pub use pooled::PooledSink;
+pub use prometheus::{PrometheusKind, PrometheusTextSink};
+#[cfg(feature = "async")]
+pub use queue::{BlockingSink, DeliveryAck, OverflowPolicy, QueueSink};
+pub use rate_stats::{RateStats, RateStatsSink};
+pub use replay::{RecordingSink, ReplayRecord, ReplaySpeed, Replayer};
+pub use restful::{RestfulMode, RestfulSink};
+pub use retry::RetrySink;
+pub use sampling::{AdaptiveSamplingSink, SamplingSink, SamplingStrategy};
+pub use scheduled::ScheduledSend;
+pub use sequencing::{SequenceCheck, SequenceTracker, SequencingSink};
+pub use shutdown::{ShutdownCoordinator, ShutdownReport};
+#[cfg(feature = "signing")]
+pub use signing::{verify_signed, SigningSink};
+pub use source::{InMemorySource, TelemetrySource};
+pub use time_window::TimeWindowSink;
+pub use timing::{LatencySnapshot, TimingSink};
+pub use topic::{TopicFilter, TopicPolicy};
+pub use transform::{AddCorrelationId, RedactFields, Transform, TransformSink};
+pub use typed::{parse_envelope, Envelope, TypedMessage};
+#[cfg(feature = "jsonschema")]
+pub use validating::ValidatingSink;
+pub use writer::TopicWriter;
 
 // ============================================================================
 // Error type
 // ============================================================================
 
 /// Errors that can occur when sending telemetry data.
-#[derive(Debug, Clone)]
-pub struct TelemetryError {
-    /// Human-readable error message.
-    pub message: String,
+///
+/// **Why variants?** Callers such as retry or circuit-breaker layers need to
+/// tell transient transport problems apart from permanent encoding errors.
+///
+/// Decorators that replace an inner error keep it as the new error's source
+/// (`with_source`, `context`). `{}` shows only the outermost error; `{:#}`
+/// walks the whole chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TelemetryError {
+    /// The transport was reached but rejected or failed the send.
+    Transport(String),
+    /// The transport could not be reached.
+    Connection(String),
+    /// A payload could not be encoded or decoded.
+    Serialization(String),
+    /// The send was refused to protect the transport (queue full, rate limit).
+    RateLimited(String),
+    /// Any other failure.
+    Other(String),
+    /// `error`, caused by `source`. Built by `with_source` and `context`.
+    Chained {
+        error: Box<TelemetryError>,
+        source: Box<TelemetryError>,
+    },
 }
 
 impl TelemetryError {
+    /// Create an uncategorised error (`TelemetryError::Other`).
     pub fn new(message: impl Into<String>) -> Self {
-        Self {
-            message: message.into(),
+        TelemetryError::Other(message.into())
+    }
+
+    /// Human-readable error message without the category prefix.
+    pub fn message(&self) -> &str {
+        match self {
+            TelemetryError::Transport(msg)
+            | TelemetryError::Connection(msg)
+            | TelemetryError::Serialization(msg)
+            | TelemetryError::RateLimited(msg)
+            | TelemetryError::Other(msg) => msg,
+            TelemetryError::Chained { error, .. } => error.message(),
+        }
+    }
+
+    /// This error without its source chain; match on it to check the
+    /// category of a chained error.
+    pub fn head(&self) -> &TelemetryError {
+        match self {
+            TelemetryError::Chained { error, .. } => error,
+            other => other,
+        }
+    }
+
+    /// Record `source` as the cause of this error, at the end of any
+    /// existing chain.
+    pub fn with_source(self, source: TelemetryError) -> Self {
+        match self {
+            TelemetryError::Chained {
+                error,
+                source: cause,
+            } => TelemetryError::Chained {
+                error,
+                source: Box::new(cause.with_source(source)),
+            },
+            error => TelemetryError::Chained {
+                error: Box::new(error),
+                source: Box::new(source),
+            },
+        }
+    }
+
+    /// Wrap this error in a new one of the same category with `message`,
+    /// keeping this one as its source.
+    pub fn context(self, message: impl Into<String>) -> Self {
+        let message = message.into();
+        let error = match self.head() {
+            TelemetryError::Transport(_) => TelemetryError::Transport(message),
+            TelemetryError::Connection(_) => TelemetryError::Connection(message),
+            TelemetryError::Serialization(_) => TelemetryError::Serialization(message),
+            TelemetryError::RateLimited(_) => TelemetryError::RateLimited(message),
+            _ => TelemetryError::Other(message),
+        };
+        TelemetryError::Chained {
+            error: Box::new(error),
+            source: Box::new(self),
         }
     }
 }
 
 impl std::fmt::Display for TelemetryError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "TelemetryError: {}", self.message)
+        match self {
+            TelemetryError::Transport(msg) => write!(f, "Transport error: {}", msg),
+            TelemetryError::Connection(msg) => write!(f, "Connection error: {}", msg),
+            TelemetryError::Serialization(msg) => write!(f, "Serialization error: {}", msg),
+            TelemetryError::RateLimited(msg) => write!(f, "Rate limited: {}", msg),
+            TelemetryError::Other(msg) => write!(f, "TelemetryError: {}", msg),
+            TelemetryError::Chained { error, source } if f.alternate() => {
+                write!(f, "{}: caused by: {:#}", error, source)
+            }
+            TelemetryError::Chained { error, .. } => error.fmt(f),
+        }
     }
 }
 
-impl std::error::Error for TelemetryError {}
+impl std::error::Error for TelemetryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TelemetryError::Chained { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 /// Convenience type alias for telemetry operations.
 pub type TelemetryResult<T> = Result<T, TelemetryError>;
 
 /// Type alias for in-memory records: (topic, payload bytes)
-type TelemetryRecord = (String, Vec<u8>);
+pub type TelemetryRecord = (String, Vec<u8>);
 
 // ============================================================================
 // Message type
 // ============================================================================
 
 /// Basic telemetry message structure used for examples and tests.
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+///
+/// `timestamp`, `headers`, `priority`, `ttl_ms` and `created_at` are optional
+/// metadata; they are omitted from the JSON encoding when unset so plain
+/// messages keep their original wire format.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TelemetryMessage {
     pub topic: String,
     pub payload: serde_json::Value,
+    /// Milliseconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    /// Free-form string metadata (e.g. `service`, `correlation_id`).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Delivery priority; queueing sinks dequeue higher values first.
+    #[serde(default, skip_serializing_if = "is_default_priority")]
+    pub priority: u8,
+    /// Time-to-live in milliseconds, counted from `created_at`.
+    ///
+    /// Queueing sinks drop the message if it is still queued after that.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_ms: Option<u32>,
+    /// Creation time in milliseconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<i64>,
+}
+
+fn is_default_priority(priority: &u8) -> bool {
+    *priority == 0
 }
 
 impl TelemetryMessage {
@@ -61,20 +326,295 @@ impl TelemetryMessage {
     ///
     /// The topic should follow a hierarchical path convention (e.g., `sensors/temp`).
     /// The payload is a JSON value allowing flexible data structures.
+    ///
+    /// The topic is **not** validated; use `try_new` to reject malformed topics
+    /// before they reach a broker.
     pub fn new(topic: impl Into<String>, payload: serde_json::Value) -> Self {
         TelemetryMessage {
             topic: topic.into(),
             payload,
+            timestamp: None,
+            headers: BTreeMap::new(),
+            priority: 0,
+            ttl_ms: None,
+            created_at: None,
+        }
+    }
+
+    /// Start building a message with the fluent `TelemetryMessageBuilder`.
+    pub fn builder() -> TelemetryMessageBuilder {
+        TelemetryMessageBuilder::default()
+    }
+
+    /// Create a new telemetry message after validating its topic.
+    ///
+    /// See `validate_topic` for the rules applied.
+    pub fn try_new(topic: impl Into<String>, payload: serde_json::Value) -> TelemetryResult<Self> {
+        let topic = topic.into();
+        validate_topic(&topic)?;
+        Ok(Self::new(topic, payload))
+    }
+
+    /// Set the timestamp to the current system time.
+    pub fn stamp_now(&mut self) {
+        self.timestamp = Some(now_millis());
+    }
+
+    /// Set the timestamp to the current time of `clock`.
+    pub fn stamp_with(&mut self, clock: &dyn Clock) {
+        self.timestamp = Some(clock.now_millis());
+    }
+
+    /// Set the delivery priority.
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Expire the message `ttl_ms` after creation.
+    ///
+    /// `created_at` is set to the current system time if it is not set yet.
+    pub fn with_ttl(mut self, ttl_ms: u32) -> Self {
+        self.ttl_ms = Some(ttl_ms);
+        self.created_at.get_or_insert_with(now_millis);
+        self
+    }
+
+    /// Whether the TTL has run out at `now_millis`.
+    ///
+    /// Messages without a TTL or without `created_at` never expire.
+    pub fn is_expired_at(&self, now_millis: i64) -> bool {
+        match (self.ttl_ms, self.created_at) {
+            (Some(ttl), Some(created)) => now_millis > created.saturating_add(i64::from(ttl)),
+            _ => false,
+        }
+    }
+
+    /// Store `bytes` under `key` in the payload as a base64 string.
+    ///
+    /// A `null` payload becomes an object; any other non-object payload is
+    /// rejected with `TelemetryError::Serialization`.
+    pub fn with_binary_field(
+        mut self,
+        key: impl Into<String>,
+        bytes: &[u8],
+    ) -> TelemetryResult<Self> {
+        if self.payload.is_null() {
+            self.payload = serde_json::Value::Object(serde_json::Map::new());
         }
+        let object = self.payload.as_object_mut().ok_or_else(|| {
+            TelemetryError::Serialization("binary field needs an object payload".into())
+        })?;
+        object.insert(key.into(), BASE64.encode(bytes).into());
+        Ok(self)
+    }
+
+    /// Decode a base64 field written by `with_binary_field`.
+    pub fn get_binary_field(&self, key: &str) -> TelemetryResult<Vec<u8>> {
+        let encoded = self
+            .payload
+            .get(key)
+            .ok_or_else(|| TelemetryError::Serialization(format!("no field '{}' in payload", key)))?
+            .as_str()
+            .ok_or_else(|| {
+                TelemetryError::Serialization(format!("field '{}' is not a string", key))
+            })?;
+        BASE64.decode(encoded).map_err(|e| {
+            TelemetryError::Serialization(format!("field '{}' is not base64: {}", key, e))
+        })
     }
 
     /// Serialize message to a JSON string.
     ///
     /// This is a convenience method for protocol implementations that want JSON
     /// transmission; other implementations may use custom encoding.
+    ///
+    /// # Panics
+    ///
+    /// Only on a bug: every field, including the `Value` payload, is plain
+    /// JSON by construction. Code that must not panic, like the client's
+    /// send path, uses `try_to_json` instead.
     pub fn to_json(&self) -> String {
-        serde_json::to_string(self).expect("serialization should succeed")
+        self.try_to_json()
+            .expect("TelemetryMessage is always representable as JSON")
+    }
+
+    /// Serialize message to a JSON string, returning
+    /// `TelemetryError::Serialization` instead of panicking on failure.
+    pub fn try_to_json(&self) -> TelemetryResult<String> {
+        serde_json::to_string(self)
+            .map_err(|e| TelemetryError::Serialization(format!("message: {}", e)))
+    }
+}
+
+/// Current system time in milliseconds since the Unix epoch.
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Fluent builder for `TelemetryMessage`.
+///
+/// **Why a builder?** Messages with metadata need several optional pieces;
+/// the builder keeps construction readable and validates the topic once, in
+/// `build`.
+#[derive(Debug, Default)]
+pub struct TelemetryMessageBuilder {
+    topic: Option<String>,
+    payload: Option<serde_json::Value>,
+    timestamp: Option<i64>,
+    headers: BTreeMap<String, String>,
+    priority: u8,
+    ttl_ms: Option<u32>,
+    created_at: Option<i64>,
+}
+
+impl TelemetryMessageBuilder {
+    /// Set the topic (required).
+    pub fn topic(mut self, topic: impl Into<String>) -> Self {
+        self.topic = Some(topic.into());
+        self
+    }
+
+    /// Set the payload (required).
+    pub fn payload(mut self, payload: serde_json::Value) -> Self {
+        self.payload = Some(payload);
+        self
+    }
+
+    /// Set an explicit timestamp in milliseconds since the Unix epoch.
+    pub fn timestamp(mut self, millis: i64) -> Self {
+        self.timestamp = Some(millis);
+        self
+    }
+
+    /// Stamp the message with the current system time.
+    pub fn stamp_now(mut self) -> Self {
+        self.timestamp = Some(now_millis());
+        self
+    }
+
+    /// Add a header; a later call with the same key replaces the value.
+    pub fn header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set the delivery priority (default 0).
+    pub fn priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Set a time-to-live; `created_at` defaults to build time.
+    pub fn ttl(mut self, ttl_ms: u32) -> Self {
+        self.ttl_ms = Some(ttl_ms);
+        self
+    }
+
+    /// Set an explicit creation time in milliseconds since the Unix epoch.
+    pub fn created_at(mut self, millis: i64) -> Self {
+        self.created_at = Some(millis);
+        self
+    }
+
+    /// Validate and build the message.
+    ///
+    /// Returns an error if the topic or payload is missing, or if the topic
+    /// fails `validate_topic`.
+    pub fn build(self) -> TelemetryResult<TelemetryMessage> {
+        let topic = self
+            .topic
+            .ok_or_else(|| TelemetryError::new("cannot build message: missing topic"))?;
+        let payload = self
+            .payload
+            .ok_or_else(|| TelemetryError::new("cannot build message: missing payload"))?;
+        let mut msg = TelemetryMessage::try_new(topic, payload)?;
+        msg.timestamp = self.timestamp;
+        msg.headers = self.headers;
+        msg.priority = self.priority;
+        msg.ttl_ms = self.ttl_ms;
+        msg.created_at = match self.ttl_ms {
+            Some(_) => Some(self.created_at.unwrap_or_else(now_millis)),
+            None => self.created_at,
+        };
+        Ok(msg)
+    }
+}
+
+/// Validate a publish topic.
+///
+/// A valid topic is non-empty, has no leading or trailing `/`, contains no
+/// empty path segments (`a//b`), and contains no MQTT wildcard characters
+/// (`+`, `#`), which are only meaningful in subscription filters.
+///
+/// **Why here?** Brokers often accept malformed topics silently and then fail
+/// to route them; catching the problem at the producer makes it visible.
+pub fn validate_topic(topic: &str) -> TelemetryResult<()> {
+    if topic.is_empty() {
+        return Err(TelemetryError::new("invalid topic: topic is empty"));
+    }
+    if topic.starts_with('/') {
+        return Err(TelemetryError::new(format!(
+            "invalid topic '{}': leading '/' is not allowed",
+            topic
+        )));
     }
+    if topic.ends_with('/') {
+        return Err(TelemetryError::new(format!(
+            "invalid topic '{}': trailing '/' is not allowed",
+            topic
+        )));
+    }
+    if topic.split('/').any(str::is_empty) {
+        return Err(TelemetryError::new(format!(
+            "invalid topic '{}': empty path segment",
+            topic
+        )));
+    }
+    if let Some(c) = topic.chars().find(|c| *c == '+' || *c == '#') {
+        return Err(TelemetryError::new(format!(
+            "invalid topic '{}': wildcard '{}' is not allowed in a publish topic",
+            topic, c
+        )));
+    }
+    Ok(())
+}
+
+/// Encode a binary payload as lowercase hex, e.g. for `send_binary` to a
+/// text-only transport.
+pub fn to_hex_payload(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut hex, b| {
+            let _ = write!(hex, "{:02x}", b);
+            hex
+        })
+}
+
+/// Decode a payload produced by `to_hex_payload` (either case is accepted).
+pub fn from_hex_payload(hex: &str) -> TelemetryResult<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return Err(TelemetryError::Serialization(format!(
+            "hex payload has odd length {}",
+            hex.len()
+        )));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            // `from_str_radix` alone would accept a sign, e.g. "+f"
+            hex.get(i..i + 2)
+                .filter(|pair| pair.bytes().all(|b| b.is_ascii_hexdigit()))
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| {
+                    TelemetryError::Serialization(format!("invalid hex digit at offset {}", i))
+                })
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -99,6 +639,143 @@ mod message_tests {
         let msg = TelemetryMessage::new("a/topic", payload);
         assert_eq!(msg.topic, "a/topic");
     }
+
+    #[test]
+    fn validate_topic_accepts_valid_topics() {
+        for topic in ["sensors", "sensors/temp", "a/b/c/d", "svc-1/status_ok"] {
+            assert!(validate_topic(topic).is_ok(), "{} should be valid", topic);
+        }
+    }
+
+    #[test]
+    fn validate_topic_rejects_malformed_topics() {
+        let cases = [
+            ("", "empty"),
+            ("/sensors/temp", "leading '/'"),
+            ("sensors/temp/", "trailing '/'"),
+            ("sensors//temp", "empty path segment"),
+            ("sensors/+/temp", "wildcard '+'"),
+            ("sensors/#", "wildcard '#'"),
+        ];
+        for (topic, reason) in cases {
+            let err = validate_topic(topic).expect_err(topic);
+            assert!(
+                err.to_string().contains(reason),
+                "error for {:?} should mention {:?}, got {}",
+                topic,
+                reason,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn builder_builds_full_message() {
+        let msg = TelemetryMessage::builder()
+            .topic("sensors/temp")
+            .payload(serde_json::json!({ "temp": 21.5 }))
+            .timestamp(1_700_000_000_000)
+            .header("service", "hvac")
+            .header("unit", "C")
+            .build()
+            .expect("valid message");
+
+        assert_eq!(msg.topic, "sensors/temp");
+        assert_eq!(msg.payload, serde_json::json!({ "temp": 21.5 }));
+        assert_eq!(msg.timestamp, Some(1_700_000_000_000));
+        assert_eq!(msg.headers.get("service").map(String::as_str), Some("hvac"));
+        assert_eq!(msg.headers.len(), 2);
+
+        let parsed: TelemetryMessage = serde_json::from_str(&msg.to_json()).expect("parse");
+        assert_eq!(parsed, msg);
+    }
+
+    #[test]
+    fn builder_requires_topic_and_payload() {
+        let err = TelemetryMessage::builder()
+            .payload(serde_json::json!(1))
+            .build()
+            .expect_err("missing topic");
+        assert!(err.to_string().contains("missing topic"));
+
+        let err = TelemetryMessage::builder()
+            .topic("a/b")
+            .build()
+            .expect_err("missing payload");
+        assert!(err.to_string().contains("missing payload"));
+
+        assert!(TelemetryMessage::builder()
+            .topic("a/+")
+            .payload(serde_json::json!(1))
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn builder_stamp_now_sets_current_time() {
+        let before = now_millis();
+        let msg = TelemetryMessage::builder()
+            .topic("heartbeat")
+            .payload(serde_json::json!(null))
+            .stamp_now()
+            .build()
+            .expect("valid message");
+        let after = now_millis();
+
+        let ts = msg.timestamp.expect("timestamp set");
+        assert!(before <= ts && ts <= after);
+    }
+
+    #[test]
+    fn plain_message_omits_optional_fields() {
+        let msg = TelemetryMessage::new("a/b", serde_json::json!(1));
+        assert_eq!(msg.to_json(), r#"{"topic":"a/b","payload":1}"#);
+    }
+
+    #[test]
+    fn try_new_validates_topic() {
+        assert!(TelemetryMessage::try_new("sensors/temp", serde_json::json!(1)).is_ok());
+        assert!(TelemetryMessage::try_new("sensors//temp", serde_json::json!(1)).is_err());
+    }
+
+    #[test]
+    fn binary_field_round_trips_through_json() {
+        let blob: Vec<u8> = (0..=255).collect();
+        let msg = TelemetryMessage::new("camera/frame", serde_json::json!({ "id": 7 }))
+            .with_binary_field("thumbnail", &blob)
+            .expect("object payload");
+
+        let parsed: TelemetryMessage = serde_json::from_str(&msg.to_json()).expect("parse");
+        assert_eq!(parsed.payload["id"], 7);
+        assert_eq!(parsed.get_binary_field("thumbnail").expect("decode"), blob);
+    }
+
+    #[test]
+    fn binary_field_errors_are_descriptive() {
+        let msg = TelemetryMessage::new("t", serde_json::Value::Null)
+            .with_binary_field("raw", b"\x00\x01")
+            .expect("null becomes an object");
+        assert_eq!(msg.get_binary_field("raw").expect("decode"), b"\x00\x01");
+
+        let err = msg.get_binary_field("missing").expect_err("absent");
+        assert!(err.to_string().contains("no field 'missing'"));
+
+        let scalar = TelemetryMessage::new("t", serde_json::json!(1));
+        assert!(scalar.with_binary_field("raw", b"x").is_err());
+    }
+
+    #[test]
+    fn hex_payload_round_trips() {
+        let bytes = [0x00, 0x7f, 0xab, 0xff];
+        let hex = to_hex_payload(&bytes);
+        assert_eq!(hex, "007fabff");
+        assert_eq!(from_hex_payload(&hex).expect("decode"), bytes);
+        assert_eq!(from_hex_payload("ABFF").expect("upper case"), [0xab, 0xff]);
+        assert!(from_hex_payload("abc").is_err());
+        assert!(from_hex_payload("zz").is_err());
+        assert!(from_hex_payload("+f").is_err());
+        assert!(from_hex_payload("00-1").is_err());
+    }
 }
 pub trait TelemetrySink: Send + Sync {
     /// Send a telemetry payload to a named topic/channel.
@@ -109,6 +786,139 @@ pub trait TelemetrySink: Send + Sync {
     ///
     /// Returns `Ok(())` on success or `TelemetryError` on transport failure.
     fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()>;
+
+    /// Send without blocking or dropping anything to make room.
+    ///
+    /// Returns `Ok(false)` if the sink cannot accept the payload right now
+    /// (queue full, circuit open), leaving the caller to buffer or shed it.
+    /// Simple sinks never push back, so the default is a plain `send`.
+    fn try_send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<bool> {
+        self.send(topic, payload).map(|()| true)
+    }
+
+    /// Send several payloads as one batch.
+    ///
+    /// Transports with a native batch operation (one request, one
+    /// produce call) override this; the default sends each record in order
+    /// and stops at the first error.
+    fn send_batch(&self, records: &[TelemetryRecord]) -> TelemetryResult<()> {
+        records
+            .iter()
+            .try_for_each(|(topic, payload)| self.send(topic, payload))
+    }
+
+    /// Deliver any buffered or in-flight payloads.
+    ///
+    /// **Why a default?** Most sinks send synchronously and have nothing to
+    /// flush; only buffering/queueing sinks need to override this.
+    fn flush(&self) -> TelemetryResult<()> {
+        Ok(())
+    }
+
+    /// Check that the transport is reachable, e.g. before sending critical
+    /// telemetry or from a readiness probe.
+    ///
+    /// Network sinks override this with a cheap round trip; sinks with
+    /// nothing to reach, such as `InMemorySink`, keep the default `Ok`, and
+    /// decorators ask the sink they wrap.
+    fn health_check(&self) -> TelemetryResult<()> {
+        Ok(())
+    }
+
+    /// Short name identifying the sink in logs and error messages.
+    ///
+    /// Decorators describe the whole stack beneath them, e.g.
+    /// `"retry(circuit_breaker(mqtt))"`, using `layered_sink_name`. The
+    /// default is the full type name.
+    fn sink_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// The sink as `Any`, for downcasting a `dyn TelemetrySink` back to its
+    /// concrete type (see `<dyn TelemetrySink>::downcast_ref`).
+    ///
+    /// **Why optional?** Only `'static` sinks can be `Any`, and a default
+    /// body cannot require that, so sinks opt in by returning `Some(self)`.
+    fn as_any(&self) -> Option<&dyn Any> {
+        None
+    }
+}
+
+impl dyn TelemetrySink {
+    /// The concrete sink behind this trait object, if it is a `T` and
+    /// implements `as_any`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.as_any()?.downcast_ref()
+    }
+}
+
+/// Compose a decorator's `sink_name` from its own layer name and the names
+/// of the sinks it wraps: `layered_sink_name("retry", &["mqtt"])` is
+/// `"retry(mqtt)"`.
+///
+/// Names are interned, so each distinct stack is allocated once.
+pub fn layered_sink_name(layer: &str, inner: &[&str]) -> &'static str {
+    static NAMES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let name = format!("{}({})", layer, inner.join(", "));
+    let mut names = NAMES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if let Some(interned) = names.get(name.as_str()) {
+        return interned;
+    }
+    let interned: &'static str = Box::leak(name.into_boxed_str());
+    names.insert(interned);
+    interned
+}
+
+/// Shutdown hook for sinks that own background threads or connections.
+///
+/// **Why a separate trait?** `close` consumes the sink, which would make
+/// `TelemetrySink` unusable as a trait object. Keeping it apart lets
+/// `Arc<dyn TelemetrySink>` work while owners of concrete sinks can still
+/// shut them down explicitly.
+pub trait ShutdownSink: TelemetrySink + Sized {
+    /// Flush pending data and release the sink's resources.
+    fn close(self) -> TelemetryResult<()>;
+}
+
+/// Boxed future returned by `AsyncTelemetrySink` methods.
+#[cfg(feature = "async")]
+pub type SinkFuture<'a> =
+    std::pin::Pin<Box<dyn std::future::Future<Output = TelemetryResult<()>> + Send + 'a>>;
+
+/// Asynchronous counterpart of `TelemetrySink` for non-blocking transports.
+///
+/// **Why boxed futures?** Returning `SinkFuture` keeps the trait object-safe,
+/// so background workers can hold an `Arc<dyn AsyncTelemetrySink>` just like
+/// the synchronous client holds `Arc<dyn TelemetrySink>`.
+#[cfg(feature = "async")]
+pub trait AsyncTelemetrySink: Send + Sync {
+    /// Send a telemetry payload to a named topic/channel.
+    fn send<'a>(&'a self, topic: &'a str, payload: &'a [u8]) -> SinkFuture<'a>;
+
+    /// Deliver any buffered or in-flight payloads.
+    fn flush(&self) -> SinkFuture<'_> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Shared, type-erased async sink.
+#[cfg(feature = "async")]
+pub type ArcAsyncSink = Arc<dyn AsyncTelemetrySink>;
+
+/// Lets decorators generic over `S: AsyncTelemetrySink` wrap a shared
+/// `ArcAsyncSink` (or any `Arc` of a sink) directly.
+#[cfg(feature = "async")]
+impl<T: AsyncTelemetrySink + ?Sized> AsyncTelemetrySink for Arc<T> {
+    fn send<'a>(&'a self, topic: &'a str, payload: &'a [u8]) -> SinkFuture<'a> {
+        (**self).send(topic, payload)
+    }
+
+    fn flush(&self) -> SinkFuture<'_> {
+        (**self).flush()
+    }
 }
 
 /// A small mock sink used for local testing and CI.
@@ -120,12 +930,100 @@ impl TelemetrySink for MockSink {
         println!("MockSink sending to '{}': {:?}", topic, _payload);
         Ok(())
     }
+
+    fn sink_name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+}
+
+/// A sink that discards everything without doing any work.
+///
+/// Unlike `MockSink` it prints nothing, so benchmarks through it measure
+/// only the client and decorator overhead.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NullSink;
+
+impl TelemetrySink for NullSink {
+    #[inline]
+    fn send(&self, _topic: &str, _payload: &[u8]) -> TelemetryResult<()> {
+        Ok(())
+    }
+
+    fn sink_name(&self) -> &'static str {
+        "null"
+    }
+}
+
+/// Snapshot of a `TelemetryClient`'s send counters.
+///
+/// **Why Serialize?** The snapshot can itself be emitted as telemetry, e.g.
+/// on a `telemetry/client/metrics` topic.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientMetrics {
+    /// Messages (structured or binary) successfully handed to the sink.
+    pub messages_sent: u64,
+    /// Payload bytes successfully handed to the sink.
+    pub bytes_sent: u64,
+    /// Sends the sink rejected with an error.
+    pub send_errors: u64,
+}
+
+/// Live counters backing `ClientMetrics`.
+///
+/// **Why atomics?** The client is shared across threads via `Arc`; atomics let
+/// every send update the counters without taking a lock.
+#[derive(Default)]
+struct ClientCounters {
+    messages_sent: AtomicU64,
+    bytes_sent: AtomicU64,
+    send_errors: AtomicU64,
+}
+
+impl ClientCounters {
+    fn record(&self, result: &TelemetryResult<()>, len: usize) {
+        match result {
+            Ok(()) => {
+                self.messages_sent.fetch_add(1, Ordering::Relaxed);
+                self.bytes_sent.fetch_add(len as u64, Ordering::Relaxed);
+            }
+            Err(_) => {
+                self.send_errors.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn snapshot(&self) -> ClientMetrics {
+        ClientMetrics {
+            messages_sent: self.messages_sent.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            send_errors: self.send_errors.load(Ordering::Relaxed),
+        }
+    }
 }
 
 /// A client that sends structured `TelemetryMessage` instances through a
 /// `TelemetrySink`. This separates message construction from the transport.
+///
+/// Cloning is cheap: clones share the sink, clock and send counters, so a
+/// client can be cloned into each thread or task instead of wrapped in `Arc`.
+#[derive(Clone)]
 pub struct TelemetryClient {
     sink: Arc<dyn TelemetrySink>,
+    /// Shared by all clones so `metrics` reports the combined totals.
+    counters: Arc<ClientCounters>,
+    max_payload_bytes: Option<usize>,
+    clock: Arc<dyn Clock>,
+    topic_prefix: String,
+    default_headers: BTreeMap<String, String>,
+    format: SerializationFormat,
+    /// Overrides `format` when set.
+    codec: Option<Arc<dyn PayloadCodec>>,
+    topic_policy: TopicPolicy,
+    shutdown: Option<Arc<ShutdownCoordinator>>,
 }
 
 impl TelemetryClient {
@@ -134,98 +1032,621 @@ impl TelemetryClient {
     /// **Why Arc?** Multiple threads/tasks may need to send telemetry concurrently.
     /// An Arc allows safe, cheap cloning of the client or direct sharing.
     pub fn new(sink: Arc<dyn TelemetrySink>) -> Self {
-        Self { sink }
+        Self::with_limits(sink, None)
     }
 
-    /// Send a structured telemetry message. The default serialization is JSON.
+    /// Create a client that rejects payloads larger than `max_payload_bytes`.
     ///
-    /// This is the primary API for most use cases: create a `TelemetryMessage`,
-    /// then call this to serialize and transmit it.
-    pub fn send_message(&self, msg: &TelemetryMessage) -> TelemetryResult<()> {
-        let payload = msg.to_json();
-        self.sink.send(&msg.topic, payload.as_bytes())
+    /// **Why client-side?** Brokers reject oversized messages with cryptic
+    /// errors (or drop the connection); failing fast here gives a clear error
+    /// and never touches the sink. `None` means no limit.
+    pub fn with_limits(sink: Arc<dyn TelemetrySink>, max_payload_bytes: Option<usize>) -> Self {
+        Self {
+            sink,
+            counters: Arc::default(),
+            max_payload_bytes,
+            clock: Arc::new(SystemClock),
+            topic_prefix: String::new(),
+            default_headers: BTreeMap::new(),
+            format: SerializationFormat::default(),
+            codec: None,
+            topic_policy: TopicPolicy::default(),
+            shutdown: None,
+        }
     }
 
-    /// Send arbitrary binary payload to a topic.
+    /// Prepend `prefix` to the topic of every message sent by this client.
     ///
-    /// Use this when you have pre-encoded data (msgpack, protobuf, custom binary)
-    /// that should not be re-encoded by `TelemetryMessage`.
-    pub fn send_binary(&self, topic: &str, data: &[u8]) -> TelemetryResult<()> {
-        self.sink.send(topic, data)
+    /// The prefix is joined verbatim, so include the separator
+    /// (e.g. `room619/`).
+    pub fn with_topic_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.topic_prefix = prefix.into();
+        self
     }
-}
 
-/// An in-memory sink useful for testing and local inspection.
-pub struct InMemorySink {
-    pub records: Arc<Mutex<Vec<TelemetryRecord>>>,
-}
+    /// Add `headers` to every structured message sent by this client.
+    ///
+    /// Headers already set on a message take precedence.
+    pub fn with_default_headers(mut self, headers: BTreeMap<String, String>) -> Self {
+        self.default_headers = headers;
+        self
+    }
 
-impl InMemorySink {
-    /// Create a new in-memory sink with no records.
-    pub fn new() -> Self {
-        Self {
-            records: Arc::new(Mutex::new(Vec::new())),
+    /// Encode `send_message` payloads as `format` instead of JSON.
+    ///
+    /// Returns an error if the format's feature is not enabled in this
+    /// build. Replaces any codec set with `with_codec`.
+    pub fn with_format(mut self, format: SerializationFormat) -> TelemetryResult<Self> {
+        if !format.is_available() {
+            return Err(format.unavailable());
         }
+        self.format = format;
+        self.codec = None;
+        Ok(self)
     }
 
-    /// Get a cloneable `Arc` to the internal storage.
-    ///
-    /// Useful in tests to inspect recorded messages without ownership issues.
-    pub fn records_arc(&self) -> Arc<Mutex<Vec<TelemetryRecord>>> {
-        Arc::clone(&self.records)
+    /// Format used by `send_message` when no codec is set.
+    pub fn format(&self) -> SerializationFormat {
+        self.format
     }
-}
 
-impl Default for InMemorySink {
-    fn default() -> Self {
-        Self::new()
+    /// Encode `send_message` payloads with `codec`, taking precedence over
+    /// the serialization format.
+    pub fn with_codec(mut self, codec: Arc<dyn PayloadCodec>) -> Self {
+        self.codec = Some(codec);
+        self
     }
-}
 
-impl TelemetrySink for InMemorySink {
-    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
-        let mut lock = self
-            .records
-            .lock()
-            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
-        lock.push((topic.to_string(), payload.to_vec()));
-        Ok(())
+    /// Custom codec used by `send_message`, if one is set.
+    pub fn codec(&self) -> Option<&Arc<dyn PayloadCodec>> {
+        self.codec.as_ref()
     }
-}
 
-#[cfg(test)]
-mod sink_tests {
-    use super::*;
+    /// Reject sends whose topic, after the prefix is applied, breaks
+    /// `policy`, before they reach the sink.
+    pub fn with_topic_policy(mut self, policy: TopicPolicy) -> Self {
+        self.topic_policy = policy;
+        self
+    }
 
-    #[test]
-    fn mock_sink_send_ok() {
-        let sink = MockSink;
-        let res = sink.send("telemetry/health", b"alive");
-        assert!(res.is_ok());
+    /// Topic limits enforced on every send.
+    pub fn topic_policy(&self) -> TopicPolicy {
+        self.topic_policy
     }
 
-    #[test]
-    fn in_memory_sink_records_messages() {
-        let sink = InMemorySink::new();
-        let records_arc = sink.records_arc();
-        let client = TelemetryClient::new(Arc::new(sink));
+    /// Topic prefix applied to every send.
+    pub fn topic_prefix(&self) -> &str {
+        &self.topic_prefix
+    }
 
-        let payload = serde_json::json!({ "status": "ok" });
-        let msg = TelemetryMessage::new("svc/status", payload.clone());
+    /// Use `clock` instead of the system clock for timestamps.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
 
-        // send the message
-        client.send_message(&msg).expect("send should succeed");
+    /// Clock used for timestamps.
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
 
-        // inspect recorded messages
-        let records = records_arc.lock().expect("lock");
-        assert_eq!(records.len(), 1);
-        let (topic, bytes) = &records[0];
-        assert_eq!(topic, "svc/status");
+    /// Set `msg`'s timestamp from the client's clock.
+    pub fn stamp(&self, msg: &mut TelemetryMessage) {
+        msg.stamp_with(self.clock.as_ref());
+    }
+
+    /// Configured payload size limit, if any.
+    pub fn max_payload_bytes(&self) -> Option<usize> {
+        self.max_payload_bytes
+    }
+
+    /// Send a structured telemetry message, serialized with the client's
+    /// codec or format (JSON unless changed with `with_codec` or
+    /// `with_format`).
+    ///
+    /// This is the primary API for most use cases: create a `TelemetryMessage`,
+    /// then call this to serialize and transmit it.
+    pub fn send_message(&self, msg: &TelemetryMessage) -> TelemetryResult<()> {
+        let msg = self.prepare(msg);
+        let payload = match &self.codec {
+            Some(codec) => codec.encode(&msg)?,
+            None => self.format.encode(&msg)?,
+        };
+        self.send_raw("send_message", &msg.topic, &payload)
+    }
+
+    /// Send a structured telemetry message encoded as protobuf.
+    ///
+    /// See `TelemetryMessage::to_protobuf` for the wire schema.
+    #[cfg(feature = "protobuf")]
+    pub fn send_message_protobuf(&self, msg: &TelemetryMessage) -> TelemetryResult<()> {
+        let msg = self.prepare(msg);
+        let payload = msg.to_protobuf()?;
+        self.send_raw("send_message_protobuf", &msg.topic, &payload)
+    }
+
+    /// Send a structured telemetry message encoded as CBOR.
+    ///
+    /// The topic is passed to the sink as usual; only the payload bytes are
+    /// CBOR. See `TelemetryMessage::to_cbor`.
+    #[cfg(feature = "cbor")]
+    pub fn send_message_cbor(&self, msg: &TelemetryMessage) -> TelemetryResult<()> {
+        let msg = self.prepare(msg);
+        let payload = msg.to_cbor()?;
+        self.send_raw("send_message_cbor", &msg.topic, &payload)
+    }
+
+    /// Send arbitrary binary payload to a topic.
+    ///
+    /// Use this when you have pre-encoded data (msgpack, protobuf, custom binary)
+    /// that should not be re-encoded by `TelemetryMessage`.
+    pub fn send_binary(&self, topic: &str, data: &[u8]) -> TelemetryResult<()> {
+        let topic = self.prefixed(topic);
+        self.send_raw("send_binary", &topic, data)
+    }
+
+    /// A `std::io::Write` that sends what is written to it as one payload
+    /// on `topic` when flushed or dropped.
+    ///
+    /// Handy for `write!`-style output or copying from a reader; the bytes
+    /// are sent exactly as `send_binary` would send them.
+    pub fn payload_writer(&self, topic: &str) -> TopicWriter<'_> {
+        TopicWriter::new(self, self.prefixed(topic).into_owned())
+    }
+
+    /// Serialize `value` straight to JSON and send it to `topic`.
+    ///
+    /// Unlike `send_message` there is no envelope: the payload bytes are the
+    /// JSON encoding of `value` itself.
+    pub fn send_typed<T: Serialize>(&self, topic: &str, value: &T) -> TelemetryResult<()> {
+        let payload = serde_json::to_vec(value)
+            .map_err(|e| TelemetryError::Serialization(format!("typed payload: {}", e)))?;
+        let topic = self.prefixed(topic);
+        self.send_raw("send_typed", &topic, &payload)
+    }
+
+    /// Send `value` wrapped in an `Envelope` tagged with `kind`, so
+    /// consumers can dispatch on the kind (see `parse_envelope`).
+    pub fn send_tagged<T: Serialize>(
+        &self,
+        topic: &str,
+        kind: &str,
+        value: &T,
+    ) -> TelemetryResult<()> {
+        let payload = serde_json::to_vec(&Envelope::new(kind, value))
+            .map_err(|e| TelemetryError::Serialization(format!("tagged payload: {}", e)))?;
+        let topic = self.prefixed(topic);
+        self.send_raw("send_tagged", &topic, &payload)
+    }
+
+    /// Send a `TypedMessage`, encoded exactly like `send_message` would
+    /// encode the equivalent `TelemetryMessage`.
+    pub fn send_typed_message<T: Serialize>(&self, msg: &TypedMessage<T>) -> TelemetryResult<()> {
+        let prepared = TypedMessage {
+            topic: self.prefixed(&msg.topic).into_owned(),
+            payload: &msg.payload,
+            timestamp: msg.timestamp,
+            headers: self.merged_headers(&msg.headers).into_owned(),
+            priority: msg.priority,
+            ttl_ms: msg.ttl_ms,
+            created_at: msg.created_at,
+        };
+        let payload = serde_json::to_vec(&prepared)
+            .map_err(|e| TelemetryError::Serialization(format!("typed message: {}", e)))?;
+        self.send_raw("send_typed_message", &prepared.topic, &payload)
+    }
+
+    /// Whether the sink's `health_check` passes; failures are logged.
+    pub fn is_healthy(&self) -> bool {
+        match self.sink.health_check() {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("{} health check failed: {}", self.sink.sink_name(), e);
+                false
+            }
+        }
+    }
+
+    /// Snapshot of the messages, bytes and errors counted so far.
+    pub fn metrics(&self) -> ClientMetrics {
+        self.counters.snapshot()
+    }
+
+    fn prefixed<'a>(&self, topic: &'a str) -> Cow<'a, str> {
+        if self.topic_prefix.is_empty() {
+            Cow::Borrowed(topic)
+        } else {
+            Cow::Owned(format!("{}{}", self.topic_prefix, topic))
+        }
+    }
+
+    /// Apply the topic prefix and default headers, copying only if needed.
+    fn prepare<'a>(&self, msg: &'a TelemetryMessage) -> Cow<'a, TelemetryMessage> {
+        if self.topic_prefix.is_empty() && self.default_headers.is_empty() {
+            return Cow::Borrowed(msg);
+        }
+        Cow::Owned(TelemetryMessage {
+            topic: self.prefixed(&msg.topic).into_owned(),
+            headers: self.merged_headers(&msg.headers).into_owned(),
+            ..msg.clone()
+        })
+    }
+
+    /// `headers` plus any default headers they do not already set.
+    fn merged_headers<'a>(
+        &self,
+        headers: &'a BTreeMap<String, String>,
+    ) -> Cow<'a, BTreeMap<String, String>> {
+        if self.default_headers.is_empty() {
+            return Cow::Borrowed(headers);
+        }
+        let mut merged = headers.clone();
+        for (key, value) in &self.default_headers {
+            merged.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+        Cow::Owned(merged)
+    }
+
+    /// Hand an encoded payload to the sink and update the counters.
+    ///
+    /// `method` names the public entry point for tracing.
+    fn send_raw(&self, method: &'static str, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::debug_span!("telemetry_send", method, topic, payload_len = payload.len())
+                .entered();
+        #[cfg(not(feature = "tracing"))]
+        let _ = method;
+
+        let result = self
+            .topic_policy
+            .check(topic)
+            .and_then(|()| self.check_payload_size(payload.len()))
+            .and_then(|()| self.sink.send(topic, payload));
+        self.counters.record(&result, payload.len());
+
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(()) => tracing::debug!("telemetry sent"),
+            Err(e) => tracing::error!(error = %e, "telemetry send failed"),
+        }
+        result
+    }
+
+    fn check_payload_size(&self, len: usize) -> TelemetryResult<()> {
+        match self.max_payload_bytes {
+            Some(max) if len > max => Err(TelemetryError::new(format!(
+                "payload of {} bytes exceeds limit of {} bytes",
+                len, max
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Flush the underlying sink.
+    ///
+    /// Call this during graceful shutdown (e.g. on SIGTERM) so buffered
+    /// telemetry is delivered before the process exits.
+    pub fn flush(&self) -> TelemetryResult<()> {
+        self.sink.flush()
+    }
+
+    /// Run `coordinator`'s steps as part of `shutdown`.
+    pub fn with_shutdown_coordinator(mut self, coordinator: Arc<ShutdownCoordinator>) -> Self {
+        self.shutdown = Some(coordinator);
+        self
+    }
+
+    /// Flush the sink stack, then run the shutdown coordinator's steps, if
+    /// one is set, all within `timeout`.
+    ///
+    /// Never blocks much past `timeout`: a step still running then is
+    /// listed in `ShutdownReport::timed_out` and left to finish on its own.
+    /// A timeout too large to represent (e.g. `Duration::MAX`) waits for
+    /// every step instead.
+    pub fn shutdown(&self, timeout: Duration) -> ShutdownReport {
+        let deadline = Instant::now().checked_add(timeout);
+        let sink = Arc::clone(&self.sink);
+        let mut steps: Vec<(String, shutdown::ShutdownHook)> =
+            vec![(sink.sink_name().to_string(), Box::new(move || sink.flush()))];
+        if let Some(coordinator) = &self.shutdown {
+            steps.extend(coordinator.take_steps());
+        }
+        shutdown::run_steps(steps, deadline)
+    }
+}
+
+/// What a capped `InMemorySink` does with a send once it is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordOverflow {
+    /// Discard the oldest record to make room, keeping the most recent ones.
+    #[default]
+    DropOldest,
+    /// Reject the send with `TelemetryError::RateLimited`.
+    Reject,
+}
+
+/// An in-memory sink useful for testing and local inspection.
+///
+/// Unbounded by default; use `with_capacity` to cap memory in long-running
+/// tests.
+pub struct InMemorySink {
+    pub records: Arc<Mutex<Vec<TelemetryRecord>>>,
+    source: Option<InMemorySource>,
+    limit: Option<(usize, RecordOverflow)>,
+}
+
+impl InMemorySink {
+    /// Create a new in-memory sink with no records.
+    pub fn new() -> Self {
+        Self {
+            records: Arc::new(Mutex::new(Vec::new())),
+            source: None,
+            limit: None,
+        }
+    }
+
+    /// Create a sink holding at most `max_records` (at least 1), applying
+    /// `policy` to sends once full.
+    pub fn with_capacity(max_records: usize, policy: RecordOverflow) -> Self {
+        let max_records = max_records.max(1);
+        Self {
+            records: Arc::new(Mutex::new(Vec::with_capacity(max_records))),
+            source: None,
+            limit: Some((max_records, policy)),
+        }
+    }
+
+    /// Maximum number of records held, if capped.
+    pub fn max_records(&self) -> Option<usize> {
+        self.limit.map(|(max, _)| max)
+    }
+
+    /// Number of records held.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Whether a capped sink has reached its limit; never true when unbounded.
+    pub fn is_full(&self) -> bool {
+        self.max_records().is_some_and(|max| self.len() >= max)
+    }
+
+    /// Discard every record held.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Remove and return every record held, oldest first.
+    pub fn drain(&self) -> Vec<TelemetryRecord> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    /// Remove and return the most recent record.
+    pub fn take_last(&self) -> Option<TelemetryRecord> {
+        self.lock().pop()
+    }
+
+    /// Like `drain`, decoding each JSON payload as `T`.
+    ///
+    /// If any payload fails to decode, a `Serialization` error is returned
+    /// and the records are left in place.
+    pub fn drain_messages<T: DeserializeOwned>(&self) -> TelemetryResult<Vec<(String, T)>> {
+        let mut records = self.lock();
+        let decoded = records
+            .iter()
+            .map(|(topic, payload)| {
+                serde_json::from_slice(payload)
+                    .map(|value| (topic.clone(), value))
+                    .map_err(|e| {
+                        TelemetryError::Serialization(format!("record on '{}': {}", topic, e))
+                    })
+            })
+            .collect::<TelemetryResult<Vec<_>>>()?;
+        records.clear();
+        Ok(decoded)
+    }
+
+    // A panic elsewhere while holding the lock cannot leave a half-pushed
+    // record behind, so a poisoned lock is safe to keep using.
+    fn lock(&self) -> MutexGuard<'_, Vec<TelemetryRecord>> {
+        self.records
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Also publish every recorded payload to `source`.
+    ///
+    /// This loops sends back to subscribers, so send/receive flows can be
+    /// tested in-process without a broker.
+    pub fn with_source(mut self, source: InMemorySource) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Get a cloneable `Arc` to the internal storage.
+    ///
+    /// Useful in tests to inspect recorded messages without ownership issues.
+    pub fn records_arc(&self) -> Arc<Mutex<Vec<TelemetryRecord>>> {
+        Arc::clone(&self.records)
+    }
+}
+
+impl Default for InMemorySink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TelemetrySink for InMemorySink {
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let mut lock = self.lock();
+        if let Some((max, policy)) = self.limit {
+            if lock.len() >= max {
+                match policy {
+                    RecordOverflow::DropOldest => {
+                        let excess = lock.len() + 1 - max;
+                        lock.drain(..excess);
+                    }
+                    RecordOverflow::Reject => {
+                        return Err(TelemetryError::RateLimited(format!(
+                            "in-memory sink full ({} records)",
+                            max
+                        )))
+                    }
+                }
+            }
+        }
+        lock.push((topic.to_string(), payload.to_vec()));
+        drop(lock);
+        if let Some(source) = &self.source {
+            source.publish(topic, payload)?;
+        }
+        Ok(())
+    }
+
+    fn sink_name(&self) -> &'static str {
+        "in_memory"
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod sink_tests {
+    use super::*;
+
+    #[test]
+    fn mock_sink_send_ok() {
+        let sink = MockSink;
+        let res = sink.send("telemetry/health", b"alive");
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn null_sink_accepts_everything() {
+        let client = TelemetryClient::new(Arc::new(NullSink));
+        let msg = TelemetryMessage::new("bench/t", serde_json::json!({"v": 1}));
+        client.send_message(&msg).expect("send");
+        client.flush().expect("flush");
+        assert_eq!(client.metrics().messages_sent, 1);
+        assert_eq!(NullSink.sink_name(), "null");
+    }
+
+    #[test]
+    fn in_memory_sink_records_messages() {
+        let sink = InMemorySink::new();
+        let records_arc = sink.records_arc();
+        let client = TelemetryClient::new(Arc::new(sink));
+
+        let payload = serde_json::json!({ "status": "ok" });
+        let msg = TelemetryMessage::new("svc/status", payload.clone());
+
+        // send the message
+        client.send_message(&msg).expect("send should succeed");
+
+        // inspect recorded messages
+        let records = records_arc.lock().expect("lock");
+        assert_eq!(records.len(), 1);
+        let (topic, bytes) = &records[0];
+        assert_eq!(topic, "svc/status");
         let parsed: TelemetryMessage = serde_json::from_slice(bytes).expect("valid json");
         assert_eq!(parsed.topic, "svc/status");
         assert_eq!(parsed.payload, payload);
     }
 
+    #[test]
+    fn capped_sink_drops_oldest_when_full() {
+        let sink = InMemorySink::with_capacity(2, RecordOverflow::DropOldest);
+        assert_eq!(sink.max_records(), Some(2));
+        for payload in [b"1", b"2", b"3"] {
+            sink.send("t", payload).expect("send");
+        }
+
+        assert!(sink.is_full());
+        let payloads: Vec<Vec<u8>> = sink.lock().iter().map(|(_, p)| p.clone()).collect();
+        assert_eq!(payloads, vec![b"2".to_vec(), b"3".to_vec()]);
+    }
+
+    #[test]
+    fn capped_sink_rejects_when_full() {
+        let sink = InMemorySink::with_capacity(2, RecordOverflow::Reject);
+        sink.send("t", b"1").expect("send");
+        sink.send("t", b"2").expect("send");
+
+        let err = sink.send("t", b"3").expect_err("full");
+        assert!(matches!(err, TelemetryError::RateLimited(_)));
+        assert_eq!(sink.len(), 2);
+        assert_eq!(sink.lock()[1].1, b"2".to_vec());
+    }
+
+    #[test]
+    fn clear_empties_store_and_frees_capacity() {
+        let sink = InMemorySink::with_capacity(1, RecordOverflow::Reject);
+        sink.send("t", b"1").expect("send");
+        assert!(sink.is_full());
+
+        sink.clear();
+        assert!(sink.is_empty());
+        assert!(!sink.is_full());
+        sink.send("t", b"2").expect("send after clear");
+        assert!(!InMemorySink::new().is_full());
+    }
+
+    #[test]
+    fn drain_and_take_last_remove_records() {
+        let sink = InMemorySink::new();
+        for i in 0..3 {
+            sink.send("t", &[i]).expect("send");
+        }
+
+        assert_eq!(sink.take_last(), Some(("t".to_string(), vec![2])));
+        assert_eq!(
+            sink.drain(),
+            vec![("t".to_string(), vec![0]), ("t".to_string(), vec![1])]
+        );
+        assert!(sink.is_empty());
+        assert!(sink.drain().is_empty());
+        assert_eq!(sink.take_last(), None);
+    }
+
+    #[test]
+    fn drain_messages_decodes_typed_payloads() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Reading {
+            temp: f64,
+        }
+
+        let sink = Arc::new(InMemorySink::new());
+        let client = TelemetryClient::new(sink.clone());
+        client
+            .send_typed("a", &serde_json::json!({"temp": 20.0}))
+            .expect("send");
+        client
+            .send_typed("b", &serde_json::json!({"temp": 21.5}))
+            .expect("send");
+
+        let readings: Vec<(String, Reading)> = sink.drain_messages().expect("decode");
+        assert_eq!(
+            readings,
+            vec![
+                ("a".to_string(), Reading { temp: 20.0 }),
+                ("b".to_string(), Reading { temp: 21.5 }),
+            ]
+        );
+        assert!(sink.is_empty());
+
+        sink.send("c", b"not json").expect("send");
+        assert!(sink.drain_messages::<Reading>().is_err());
+        assert_eq!(sink.len(), 1);
+    }
+
     #[test]
     fn send_binary_via_client() {
         let sink = InMemorySink::new();
@@ -251,6 +1672,60 @@ mod sink_tests {
         assert!(msg.contains("transport failed"));
     }
 
+    #[test]
+    fn telemetry_error_variants_keep_message() {
+        let err = TelemetryError::Connection("refused".into());
+        assert_eq!(err.message(), "refused");
+        assert_eq!(err.to_string(), "Connection error: refused");
+        assert_eq!(TelemetryError::new("x"), TelemetryError::Other("x".into()));
+    }
+
+    #[test]
+    fn wrapped_error_keeps_its_source() {
+        use std::error::Error;
+
+        let cause = TelemetryError::Connection("refused".into());
+        let err = cause.clone().context("gave up after 3 attempts");
+
+        assert_eq!(
+            err.head(),
+            &TelemetryError::Connection("gave up after 3 attempts".into())
+        );
+        assert_eq!(err.message(), "gave up after 3 attempts");
+        let source = err.source().expect("source");
+        assert_eq!(source.downcast_ref::<TelemetryError>(), Some(&cause));
+        assert!(source.source().is_none());
+
+        assert_eq!(
+            err.to_string(),
+            "Connection error: gave up after 3 attempts"
+        );
+        assert_eq!(
+            format!("{:#}", err),
+            "Connection error: gave up after 3 attempts: caused by: Connection error: refused"
+        );
+    }
+
+    #[test]
+    fn with_source_appends_to_chain() {
+        let err = TelemetryError::new("outer")
+            .with_source(TelemetryError::Transport("middle".into()))
+            .with_source(TelemetryError::Connection("root".into()));
+
+        let chain: Vec<String> =
+            std::iter::successors(Some(&err as &dyn std::error::Error), |e| e.source())
+                .map(|e| e.to_string())
+                .collect();
+        assert_eq!(
+            chain,
+            [
+                "TelemetryError: outer",
+                "Transport error: middle",
+                "Connection error: root"
+            ]
+        );
+    }
+
     #[test]
     fn client_propagates_sink_errors() {
         // MockSink always returns Ok, but this documents the error path.
@@ -263,6 +1738,191 @@ mod sink_tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn client_flush_delivers_buffered_messages() {
+        let inner = InMemorySink::new();
+        let records_arc = inner.records_arc();
+        let client = TelemetryClient::new(Arc::new(BufferingSink::new(inner, 100)));
+
+        let msg = TelemetryMessage::new("svc/status", serde_json::json!({ "up": true }));
+        client.send_message(&msg).expect("send");
+        assert!(records_arc.lock().expect("lock").is_empty());
+
+        client.flush().expect("flush");
+        let records = records_arc.lock().expect("lock");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].0, "svc/status");
+    }
+
+    #[test]
+    fn default_flush_is_ok() {
+        assert!(MockSink.flush().is_ok());
+    }
+
+    /// A sink that rejects every payload and fails its health check.
+    struct FailingSink;
+
+    impl TelemetrySink for FailingSink {
+        fn send(&self, _topic: &str, _payload: &[u8]) -> TelemetryResult<()> {
+            Err(TelemetryError::new("broker unreachable"))
+        }
+
+        fn health_check(&self) -> TelemetryResult<()> {
+            Err(TelemetryError::Connection("broker unreachable".into()))
+        }
+    }
+
+    #[test]
+    fn default_health_check_is_ok() {
+        assert!(MockSink.health_check().is_ok());
+        assert!(InMemorySink::new().health_check().is_ok());
+        assert!(TelemetryClient::new(Arc::new(InMemorySink::new())).is_healthy());
+    }
+
+    #[test]
+    fn failing_health_check_makes_client_unhealthy() {
+        assert!(!TelemetryClient::new(Arc::new(FailingSink)).is_healthy());
+
+        // Decorators report the health of the sink they wrap
+        let retry = RetrySink::new(FailingSink, 3, std::time::Duration::ZERO);
+        assert!(matches!(
+            retry.health_check(),
+            Err(TelemetryError::Connection(_))
+        ));
+        let fallback = FallbackSink::new(vec![Arc::new(FailingSink), Arc::new(MockSink)]);
+        assert!(fallback.health_check().is_ok());
+    }
+
+    #[test]
+    fn client_metrics_count_messages_and_bytes() {
+        let client = TelemetryClient::new(Arc::new(InMemorySink::new()));
+        assert_eq!(client.metrics(), ClientMetrics::default());
+
+        let msg = TelemetryMessage::new("svc/status", serde_json::json!({ "ok": true }));
+        let json_len = msg.to_json().len() as u64;
+        client.send_message(&msg).expect("send");
+        client.send_message(&msg).expect("send");
+        client.send_binary("raw", &[0u8; 16]).expect("send");
+
+        let metrics = client.metrics();
+        assert_eq!(metrics.messages_sent, 3);
+        assert_eq!(metrics.bytes_sent, 2 * json_len + 16);
+        assert_eq!(metrics.send_errors, 0);
+    }
+
+    #[test]
+    fn cloned_clients_share_sink_and_counters() {
+        let sink = Arc::new(InMemorySink::new());
+        let client = TelemetryClient::new(sink.clone());
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let client = client.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..25 {
+                        client
+                            .send_binary(&format!("worker/{}", i), b"x")
+                            .expect("send");
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().expect("join");
+        }
+
+        assert_eq!(sink.records.lock().expect("lock").len(), 100);
+        assert_eq!(client.metrics().messages_sent, 100);
+        assert_eq!(client.clone().metrics().bytes_sent, 100);
+    }
+
+    #[test]
+    fn client_metrics_count_errors() {
+        let client = TelemetryClient::new(Arc::new(FailingSink));
+        let msg = TelemetryMessage::new("svc/status", serde_json::json!(1));
+
+        assert!(client.send_message(&msg).is_err());
+        assert!(client.send_binary("raw", b"abc").is_err());
+
+        let metrics = client.metrics();
+        assert_eq!(metrics.messages_sent, 0);
+        assert_eq!(metrics.bytes_sent, 0);
+        assert_eq!(metrics.send_errors, 2);
+
+        let json = serde_json::to_value(metrics).expect("serialize");
+        assert_eq!(json["send_errors"], 2);
+    }
+
+    #[test]
+    fn topic_policy_limits_depth_and_length() {
+        let sink = InMemorySink::new();
+        let records_arc = sink.records_arc();
+        let client = TelemetryClient::new(Arc::new(sink)).with_topic_policy(TopicPolicy {
+            max_segments: Some(3),
+            max_topic_len: Some(16),
+        });
+
+        client.send_binary("a/b/c", b"x").expect("three levels");
+        let err = client
+            .send_binary("a/b/c/d", b"x")
+            .expect_err("four levels");
+        assert!(err.to_string().contains("4 levels"), "{}", err);
+        let err = client
+            .send_binary("sensors/temperature", b"x")
+            .expect_err("too long");
+        assert!(err.to_string().contains("19 bytes"), "{}", err);
+
+        assert_eq!(records_arc.lock().expect("lock").len(), 1);
+        assert_eq!(client.metrics().send_errors, 2);
+        assert_eq!(TopicPolicy::default().check(&"a/".repeat(500)), Ok(()));
+    }
+
+    #[test]
+    fn topic_policy_applies_to_prefixed_topic() {
+        let client = TelemetryClient::new(Arc::new(InMemorySink::new()))
+            .with_topic_prefix("site1/")
+            .with_topic_policy(TopicPolicy {
+                max_segments: Some(2),
+                ..TopicPolicy::default()
+            });
+
+        client.send_binary("temp", b"x").expect("site1/temp");
+        assert!(client.send_binary("hvac/temp", b"x").is_err());
+    }
+
+    #[test]
+    fn payload_limit_allows_payload_at_limit() {
+        let sink = InMemorySink::new();
+        let records_arc = sink.records_arc();
+        let client = TelemetryClient::with_limits(Arc::new(sink), Some(8));
+        assert_eq!(client.max_payload_bytes(), Some(8));
+
+        client.send_binary("t", &[0u8; 7]).expect("under limit");
+        client.send_binary("t", &[0u8; 8]).expect("at limit");
+        assert_eq!(records_arc.lock().expect("lock").len(), 2);
+    }
+
+    #[test]
+    fn payload_limit_rejects_oversized_payload_before_sink() {
+        let sink = InMemorySink::new();
+        let records_arc = sink.records_arc();
+        let client = TelemetryClient::with_limits(Arc::new(sink), Some(8));
+
+        let err = client.send_binary("t", &[0u8; 9]).expect_err("over limit");
+        let text = err.to_string();
+        assert!(
+            text.contains("9 bytes") && text.contains("limit of 8"),
+            "{}",
+            text
+        );
+
+        let msg = TelemetryMessage::new("t", serde_json::json!({ "big": "payload" }));
+        assert!(client.send_message(&msg).is_err());
+
+        assert!(records_arc.lock().expect("lock").is_empty());
+        assert_eq!(client.metrics().send_errors, 2);
+    }
+
     #[test]
     fn in_memory_sink_default() {
         let sink = InMemorySink::default();
@@ -275,6 +1935,317 @@ mod sink_tests {
         let records = records_arc.lock().expect("lock");
         assert_eq!(records.len(), 1);
     }
+
+    #[test]
+    fn in_memory_sink_recovers_from_poisoned_lock() {
+        let sink = InMemorySink::new();
+        let records_arc = sink.records_arc();
+        let poisoner = Arc::clone(&records_arc);
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.lock().expect("lock");
+            panic!("poison the records lock");
+        })
+        .join();
+        assert!(records_arc.is_poisoned());
+
+        sink.send("t", b"after").expect("send despite poison");
+        let records = records_arc.lock().unwrap_or_else(|e| e.into_inner());
+        assert_eq!(records.len(), 1);
+    }
+
+    #[test]
+    fn default_try_send_delivers_and_accepts() {
+        let sink = InMemorySink::new();
+        assert!(sink.try_send("t", b"x").expect("try_send"));
+        assert_eq!(sink.records.lock().expect("lock").len(), 1);
+    }
+
+    #[test]
+    fn client_applies_topic_prefix() {
+        let sink = InMemorySink::new();
+        let records_arc = sink.records_arc();
+        let client = TelemetryClient::new(Arc::new(sink)).with_topic_prefix("room619/");
+
+        let msg = TelemetryMessage::new("sensors/temp", serde_json::json!(21));
+        client.send_message(&msg).expect("send");
+        client.send_binary("raw", b"x").expect("send");
+
+        let records = records_arc.lock().expect("lock");
+        assert_eq!(records[0].0, "room619/sensors/temp");
+        assert_eq!(records[1].0, "room619/raw");
+        let sent: TelemetryMessage = serde_json::from_slice(&records[0].1).expect("decode");
+        assert_eq!(sent.topic, "room619/sensors/temp");
+        assert_eq!(msg.topic, "sensors/temp");
+    }
+
+    #[test]
+    fn client_merges_default_headers_without_overwriting() {
+        let sink = InMemorySink::new();
+        let records_arc = sink.records_arc();
+        let defaults = BTreeMap::from([
+            ("service".to_string(), "foo".to_string()),
+            ("region".to_string(), "eu".to_string()),
+        ]);
+        let client = TelemetryClient::new(Arc::new(sink)).with_default_headers(defaults);
+
+        let msg = TelemetryMessage::builder()
+            .topic("t")
+            .payload(serde_json::json!(1))
+            .header("region", "us")
+            .build()
+            .expect("build");
+        client.send_message(&msg).expect("send");
+
+        let records = records_arc.lock().expect("lock");
+        let sent: TelemetryMessage = serde_json::from_slice(&records[0].1).expect("decode");
+        assert_eq!(sent.headers["service"], "foo");
+        assert_eq!(sent.headers["region"], "us");
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Reading {
+        temp: f64,
+    }
+
+    #[test]
+    fn try_to_json_matches_to_json() {
+        let msg = TelemetryMessage::new("svc/status", serde_json::json!({ "ok": true }));
+        assert_eq!(msg.try_to_json().expect("json"), msg.to_json());
+    }
+
+    #[test]
+    fn unserializable_payload_is_an_error_not_a_panic() {
+        // JSON object keys must be strings; tuple keys cannot be encoded
+        let grid: BTreeMap<(u8, u8), f64> = [((0, 0), 21.5)].into_iter().collect();
+        let sink = InMemorySink::new();
+        let records_arc = sink.records_arc();
+        let client = TelemetryClient::new(Arc::new(sink));
+
+        let err = client.send_typed("grid", &grid).expect_err("typed");
+        assert!(matches!(err, TelemetryError::Serialization(_)), "{:?}", err);
+        let err = client
+            .send_typed_message(&TypedMessage::new("grid", &grid))
+            .expect_err("typed message");
+        assert!(matches!(err, TelemetryError::Serialization(_)), "{:?}", err);
+        assert!(TypedMessage::new("grid", &grid).to_message().is_err());
+        assert!(records_arc.lock().expect("lock").is_empty());
+    }
+
+    #[test]
+    fn send_typed_encodes_value_directly() {
+        let sink = InMemorySink::new();
+        let records_arc = sink.records_arc();
+        let client = TelemetryClient::new(Arc::new(sink));
+
+        client
+            .send_typed("sensors/temp", &Reading { temp: 21.5 })
+            .expect("send");
+
+        let records = records_arc.lock().expect("lock");
+        assert_eq!(records[0].0, "sensors/temp");
+        let decoded: Reading = serde_json::from_slice(&records[0].1).expect("decode");
+        assert_eq!(decoded, Reading { temp: 21.5 });
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct DoorEvent {
+        open: bool,
+    }
+
+    #[test]
+    fn send_tagged_wraps_value_with_kind() {
+        let sink = InMemorySink::new();
+        let records_arc = sink.records_arc();
+        let client = TelemetryClient::new(Arc::new(sink));
+
+        client
+            .send_tagged("site1", "reading", &Reading { temp: 21.5 })
+            .expect("send");
+        client
+            .send_tagged("site1", "door", &DoorEvent { open: true })
+            .expect("send");
+
+        let records = records_arc.lock().expect("lock");
+        let reading = parse_envelope(&records[0].1).expect("envelope");
+        assert_eq!(reading.kind, "reading");
+        assert_eq!(
+            reading.data_as::<Reading>().expect("reading"),
+            Reading { temp: 21.5 }
+        );
+        let door = parse_envelope(&records[1].1).expect("envelope");
+        assert_eq!(door.kind, "door");
+        assert_eq!(door.data, serde_json::json!({ "open": true }));
+        assert_eq!(
+            door.data_as::<DoorEvent>().expect("door"),
+            DoorEvent { open: true }
+        );
+    }
+
+    #[test]
+    fn send_typed_message_matches_send_message_bytes() {
+        let sink = InMemorySink::new();
+        let records_arc = sink.records_arc();
+        let client = TelemetryClient::new(Arc::new(sink)).with_topic_prefix("room619/");
+
+        let typed = TypedMessage::new("sensors/temp", Reading { temp: 21.5 });
+        client.send_typed_message(&typed).expect("send typed");
+        let untyped = typed.to_message().expect("convert");
+        client.send_message(&untyped).expect("send");
+
+        let records = records_arc.lock().expect("lock");
+        assert_eq!(records[0], records[1]);
+        let decoded: TypedMessage<Reading> = serde_json::from_slice(&records[0].1).expect("decode");
+        assert_eq!(decoded.topic, "room619/sensors/temp");
+        assert_eq!(decoded.payload, Reading { temp: 21.5 });
+    }
+
+    #[test]
+    fn client_stamps_with_injected_clock() {
+        let clock = MockClock::new(1_700_000_000_000);
+        let client =
+            TelemetryClient::new(Arc::new(InMemorySink::new())).with_clock(Arc::new(clock.clone()));
+        let mut msg = TelemetryMessage::new("t", serde_json::json!(1));
+
+        client.stamp(&mut msg);
+        assert_eq!(msg.timestamp, Some(1_700_000_000_000));
+
+        clock.advance(std::time::Duration::from_millis(5));
+        client.stamp(&mut msg);
+        assert_eq!(msg.timestamp, Some(1_700_000_000_005));
+    }
+
+    #[test]
+    fn decorator_stack_reports_composite_name() {
+        use std::time::Duration;
+
+        let stack = RetrySink::new(
+            CircuitBreakerSink::new(InMemorySink::new(), 3, Duration::from_secs(1)),
+            3,
+            Duration::ZERO,
+        );
+        assert_eq!(stack.sink_name(), "retry(circuit_breaker(in_memory))");
+
+        let fallback = FallbackSink::new(vec![Arc::new(stack), Arc::new(MockSink)]);
+        assert_eq!(
+            fallback.sink_name(),
+            "fallback(retry(circuit_breaker(in_memory)), mock)"
+        );
+    }
+
+    #[test]
+    fn boxed_sink_downcasts_to_concrete_type() {
+        let sink: Box<dyn TelemetrySink> = Box::new(InMemorySink::new());
+        sink.send("t", b"x").expect("send");
+
+        let concrete = sink.downcast_ref::<InMemorySink>().expect("in-memory sink");
+        assert_eq!(concrete.records.lock().expect("lock").len(), 1);
+        assert!(sink.downcast_ref::<MockSink>().is_none());
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tracing_tests {
+    use super::*;
+    use tracing::field::{Field, Visit};
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::Layer;
+
+    /// Flattened record of a span or event: (kind, level, fields as "name=value").
+    type Captured = (&'static str, tracing::Level, Vec<String>);
+
+    struct FieldCollector(Vec<String>);
+
+    impl Visit for FieldCollector {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.push(format!("{}={:?}", field.name(), value));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.push(format!("{}={}", field.name(), value));
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct CaptureLayer(Arc<Mutex<Vec<Captured>>>);
+
+    impl<S: tracing::Subscriber> Layer<S> for CaptureLayer {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            _id: &tracing::span::Id,
+            _ctx: Context<'_, S>,
+        ) {
+            let mut fields = FieldCollector(Vec::new());
+            attrs.record(&mut fields);
+            self.0
+                .lock()
+                .expect("lock")
+                .push(("span", *attrs.metadata().level(), fields.0));
+        }
+
+        fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+            let mut fields = FieldCollector(Vec::new());
+            event.record(&mut fields);
+            self.0
+                .lock()
+                .expect("lock")
+                .push(("event", *event.metadata().level(), fields.0));
+        }
+    }
+
+    struct FailingSink;
+
+    impl TelemetrySink for FailingSink {
+        fn send(&self, _topic: &str, _payload: &[u8]) -> TelemetryResult<()> {
+            Err(TelemetryError::Transport("nack".into()))
+        }
+    }
+
+    fn capture(f: impl FnOnce()) -> Vec<Captured> {
+        let layer = CaptureLayer::default();
+        let captured = Arc::clone(&layer.0);
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, f);
+        let records = captured.lock().expect("lock").clone();
+        records
+    }
+
+    #[test]
+    fn send_emits_span_with_topic_and_length() {
+        let client = TelemetryClient::new(Arc::new(InMemorySink::new()));
+        let records = capture(|| {
+            client.send_binary("sensors/temp", b"12345").expect("send");
+        });
+
+        let (_, level, fields) = records.iter().find(|r| r.0 == "span").expect("span");
+        assert_eq!(*level, tracing::Level::DEBUG);
+        assert!(fields.contains(&"method=send_binary".to_string()));
+        assert!(fields.contains(&"topic=sensors/temp".to_string()));
+        assert!(fields.contains(&"payload_len=5".to_string()));
+        assert!(records
+            .iter()
+            .any(|r| r.0 == "event" && r.1 == tracing::Level::DEBUG));
+    }
+
+    #[test]
+    fn send_error_emits_error_event() {
+        let client = TelemetryClient::new(Arc::new(FailingSink));
+        let msg = TelemetryMessage::new("svc/status", serde_json::json!(1));
+        let records = capture(|| {
+            assert!(client.send_message(&msg).is_err());
+        });
+
+        let (_, _, fields) = records
+            .iter()
+            .find(|r| r.0 == "event" && r.1 == tracing::Level::ERROR)
+            .expect("error event");
+        assert!(fields
+            .iter()
+            .any(|f| f.starts_with("error=") && f.contains("nack")));
+        assert!(records
+            .iter()
+            .any(|r| r.0 == "span" && r.2.contains(&"method=send_message".to_string())));
+    }
 }
 
 // ============================================================================
@@ -289,7 +2260,7 @@ pub mod mqtt {
     //! binary size and avoids pulling in heavy dependencies.
     //! Enable with `features = ["mqtt"]` in Cargo.toml.
 
-    use super::{TelemetryResult, TelemetrySink};
+    use super::{ShutdownSink, TelemetryError, TelemetryResult, TelemetrySink};
 
     /// MQTT sink stub. A real implementation would:
     /// - Connect to an MQTT broker (mosquitto, AWS IoT, etc.)
@@ -302,56 +2273,102 @@ pub mod mqtt {
 
     impl MqttSink {
         /// Create a new MQTT sink pointing to a broker.
+        ///
+        /// The URL is not checked; use `try_new` to catch a malformed one
+        /// up front.
         pub fn new(broker_url: impl Into<String>) -> Self {
             Self {
                 broker_url: broker_url.into(),
             }
         }
+
+        /// Create a sink after checking that `broker_url` is an
+        /// `mqtt://` or `mqtts://` URL with a host and a valid port, if any.
+        pub fn try_new(broker_url: impl Into<String>) -> TelemetryResult<Self> {
+            let broker_url = broker_url.into();
+            crate::endpoint::parse_endpoint("MQTT broker", &broker_url, &["mqtt", "mqtts"])?;
+            Ok(Self::new(broker_url))
+        }
     }
 
     impl TelemetrySink for MqttSink {
+        fn sink_name(&self) -> &'static str {
+            "mqtt"
+        }
+
+        fn as_any(&self) -> Option<&dyn std::any::Any> {
+            Some(self)
+        }
+
         fn send(&self, topic: &str, _payload: &[u8]) -> TelemetryResult<()> {
             // TODO: Implement MQTT publish
             // For now, this is a stub that logs intent.
             log::debug!("MQTT: would publish to {} @ {}", topic, self.broker_url);
             Ok(())
         }
-    }
-}
-
-#[cfg(feature = "grpc")]
-pub mod grpc {
-    //! gRPC transport for telemetry data.
-    //!
-    //! **Why feature-gated?** gRPC adds protobuf/networking complexity;
-    //! only enable if your deployment uses gRPC for telemetry.
 
-    use super::{TelemetryResult, TelemetrySink};
+        /// Always fails: with no transport behind it the sink cannot reach
+        /// the broker, and reporting it healthy would hide that.
+        fn health_check(&self) -> TelemetryResult<()> {
+            // TODO: Send PINGREQ and wait for PINGRESP
+            Err(TelemetryError::Connection(
+                "MQTT transport not implemented".to_string(),
+            ))
+        }
 
-    /// gRPC sink stub. A real implementation would:
-    /// - Connect to a gRPC service
-    /// - Send telemetry as gRPC messages
-    /// - Handle service availability and retries
-    pub struct GrpcSink {
-        /// Placeholder for gRPC client endpoint
-        pub endpoint: String,
+        fn flush(&self) -> TelemetryResult<()> {
+            // TODO: Wait for outstanding QoS 1/2 acknowledgements
+            log::debug!(
+                "MQTT: would flush in-flight publishes @ {}",
+                self.broker_url
+            );
+            Ok(())
+        }
     }
 
-    impl GrpcSink {
-        /// Create a new gRPC sink pointing to a service endpoint.
-        pub fn new(endpoint: impl Into<String>) -> Self {
-            Self {
-                endpoint: endpoint.into(),
-            }
+    impl ShutdownSink for MqttSink {
+        fn close(self) -> TelemetryResult<()> {
+            self.flush()?;
+            // TODO: Send DISCONNECT to the broker
+            log::debug!("MQTT: would disconnect from {}", self.broker_url);
+            Ok(())
         }
     }
 
-    impl TelemetrySink for GrpcSink {
-        fn send(&self, topic: &str, _payload: &[u8]) -> TelemetryResult<()> {
-            // TODO: Implement gRPC send
-            // For now, this is a stub that logs intent.
-            log::debug!("gRPC: would send to {} @ {}", topic, self.endpoint);
-            Ok(())
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn stub_reports_itself_unhealthy() {
+            let sink = MqttSink::try_new("mqtt://broker.local:1883").expect("valid url");
+            let err = sink.health_check().expect_err("no transport");
+            assert!(matches!(err, TelemetryError::Connection(_)));
+            assert!(err.to_string().contains("not implemented"));
         }
     }
 }
+
+#[cfg(feature = "http")]
+pub mod http;
+
+#[cfg(feature = "kafka")]
+pub mod kafka;
+
+#[cfg(feature = "nats")]
+pub mod nats;
+
+#[cfg(feature = "otlp")]
+pub mod otlp;
+
+#[cfg(feature = "udp")]
+pub mod udp;
+
+#[cfg(all(test, any(feature = "http", feature = "otlp")))]
+mod test_support;
+
+#[cfg(feature = "websocket")]
+pub mod websocket;
+
+#[cfg(feature = "grpc")]
+pub mod grpc;
@@ -0,0 +1,337 @@
+//! Merging sink decorator.
+//!
+//! Combines consecutive JSON payloads on a topic into one JSON array, so an
+//! expensive transport sends one document instead of many small ones.
+//!
+//! **Why not `TimeWindowSink`?** That hands the inner sink a batch of
+//! separate records; here the inner sink receives a single payload and
+//! needs no batch support.
+
+use crate::auto_flush::AutoFlusher;
+use crate::clock::{elapsed_since, ClockCell};
+use crate::{layered_sink_name, Clock, TelemetryError, TelemetryResult, TelemetrySink};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+struct Pending {
+    /// When the first payload of the open merge arrived.
+    start: i64,
+    payloads: Vec<Vec<u8>>,
+}
+
+type PendingMap = BTreeMap<String, Pending>;
+
+/// State shared with the poll thread.
+struct Core<S: TelemetrySink> {
+    inner: S,
+    merge_count: usize,
+    merge_timeout: Duration,
+    clock: ClockCell,
+    pending: Mutex<PendingMap>,
+}
+
+impl<S: TelemetrySink> Core<S> {
+    fn poll(&self) -> TelemetryResult<()> {
+        let now = self.clock.now_millis();
+        let due = self.take_due(&mut *self.lock()?, now);
+        self.deliver(due)
+    }
+
+    fn lock(&self) -> TelemetryResult<MutexGuard<'_, PendingMap>> {
+        self.pending
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))
+    }
+
+    fn take_due(&self, pending: &mut PendingMap, now: i64) -> Vec<(String, Vec<Vec<u8>>)> {
+        let due: Vec<String> = pending
+            .iter()
+            .filter(|(_, p)| elapsed_since(now, p.start) >= self.merge_timeout)
+            .map(|(topic, _)| topic.clone())
+            .collect();
+        due.into_iter()
+            .filter_map(|topic| pending.remove(&topic).map(|p| (topic, p.payloads)))
+            .collect()
+    }
+
+    /// Send one array per topic; every topic is attempted and the first
+    /// error is returned.
+    fn deliver(&self, merged: Vec<(String, Vec<Vec<u8>>)>) -> TelemetryResult<()> {
+        let mut result = Ok(());
+        for (topic, payloads) in merged {
+            let sent = self.inner.send(&topic, &merge_array(&payloads));
+            if result.is_ok() {
+                result = sent;
+            }
+        }
+        result
+    }
+}
+
+impl<S: TelemetrySink> Drop for Core<S> {
+    fn drop(&mut self) {
+        let pending = self
+            .pending
+            .get_mut()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let remaining = std::mem::take(pending)
+            .into_iter()
+            .map(|(topic, p)| (topic, p.payloads))
+            .collect();
+        if let Err(e) = self.deliver(remaining) {
+            log::warn!("MergeSink dropped with unsent payloads: {}", e);
+        }
+    }
+}
+
+/// A sink that merges JSON payloads per topic into one JSON array.
+///
+/// A topic's merged array is sent as soon as `merge_count` payloads have
+/// accumulated, or by the next `send`, `poll` or `flush` (or the poll thread
+/// started by `with_poll_interval`) once `merge_timeout` has passed since
+/// its first payload. The payloads are embedded verbatim, in arrival order.
+/// Payloads that are not valid JSON are forwarded immediately and may
+/// overtake JSON still pending on the same topic. Anything still held when
+/// the sink is dropped is sent then.
+pub struct MergeSink<S: TelemetrySink> {
+    // Declared first so the poll thread is joined before the core is dropped
+    poller: Option<AutoFlusher>,
+    core: Arc<Core<S>>,
+}
+
+impl<S: TelemetrySink> MergeSink<S> {
+    /// Merge up to `merge_count` payloads (at least one) per topic, sending
+    /// partial arrays once `merge_timeout` has passed.
+    pub fn new(inner: S, merge_count: usize, merge_timeout: Duration) -> Self {
+        Self {
+            poller: None,
+            core: Arc::new(Core {
+                inner,
+                merge_count: merge_count.max(1),
+                merge_timeout,
+                clock: ClockCell::default(),
+                pending: Mutex::new(BTreeMap::new()),
+            }),
+        }
+    }
+
+    /// Measure timeouts with `clock` instead of the system clock.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        self.core.clock.set(clock);
+        self
+    }
+
+    /// Payloads per merged array.
+    pub fn merge_count(&self) -> usize {
+        self.core.merge_count
+    }
+
+    /// Time after a topic's first payload when its partial array is due.
+    pub fn merge_timeout(&self) -> Duration {
+        self.core.merge_timeout
+    }
+
+    /// Interval of the poll thread, if one is running.
+    pub fn poll_interval(&self) -> Option<Duration> {
+        self.poller.as_ref().map(AutoFlusher::interval)
+    }
+
+    /// Payloads held across all topics.
+    pub fn pending_count(&self) -> usize {
+        self.core.pending.lock().map_or(0, |pending| {
+            pending.values().map(|p| p.payloads.len()).sum()
+        })
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.core.inner
+    }
+
+    /// Send every merged array whose timeout has passed.
+    pub fn poll(&self) -> TelemetryResult<()> {
+        self.core.poll()
+    }
+}
+
+impl<S: TelemetrySink + 'static> MergeSink<S> {
+    /// Also `poll` every `interval` from a background thread, so partial
+    /// arrays are sent on time when sends stop (see the crate docs).
+    ///
+    /// `None` stops a running thread; it is also stopped and joined when
+    /// the sink is dropped.
+    pub fn with_poll_interval(mut self, interval: Option<Duration>) -> Self {
+        self.poller = None;
+        self.poller = interval.map(|interval| {
+            let core = Arc::clone(&self.core);
+            AutoFlusher::spawn("merge", interval, move || core.poll())
+        });
+        self
+    }
+}
+
+/// Join JSON documents into one JSON array without re-encoding them.
+fn merge_array(payloads: &[Vec<u8>]) -> Vec<u8> {
+    let len = payloads.iter().map(|p| p.len() + 1).sum::<usize>() + 1;
+    let mut out = Vec::with_capacity(len);
+    out.push(b'[');
+    for (i, payload) in payloads.iter().enumerate() {
+        if i > 0 {
+            out.push(b',');
+        }
+        out.extend_from_slice(payload);
+    }
+    out.push(b']');
+    out
+}
+
+fn is_json(payload: &[u8]) -> bool {
+    serde_json::from_slice::<serde::de::IgnoredAny>(payload).is_ok()
+}
+
+impl<S: TelemetrySink> TelemetrySink for MergeSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("merge", &[self.core.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let core = &self.core;
+        if !is_json(payload) {
+            core.poll()?;
+            return core.inner.send(topic, payload);
+        }
+        let now = core.clock.now_millis();
+        let due = {
+            let mut pending = core.lock()?;
+            let mut due = core.take_due(&mut pending, now);
+            let entry = pending.entry(topic.to_string()).or_insert_with(|| Pending {
+                start: now,
+                payloads: Vec::new(),
+            });
+            entry.payloads.push(payload.to_vec());
+            if entry.payloads.len() >= core.merge_count {
+                if let Some(full) = pending.remove(topic) {
+                    due.push((topic.to_string(), full.payloads));
+                }
+            }
+            due
+        };
+        core.deliver(due)
+    }
+
+    /// Send every held array early, then flush the inner sink.
+    fn flush(&self) -> TelemetryResult<()> {
+        let all = std::mem::take(&mut *self.core.lock()?);
+        self.core
+            .deliver(all.into_iter().map(|(t, p)| (t, p.payloads)).collect())?;
+        self.core.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.core.inner.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemorySink, MockClock};
+    use serde_json::{json, Value};
+
+    fn sink(clock: &MockClock) -> MergeSink<InMemorySink> {
+        MergeSink::new(InMemorySink::new(), 3, Duration::from_millis(100))
+            .with_clock(Arc::new(clock.clone()))
+    }
+
+    fn sent(sink: &MergeSink<InMemorySink>) -> Vec<(String, Value)> {
+        sink.inner()
+            .records
+            .lock()
+            .expect("lock")
+            .iter()
+            .map(|(topic, payload)| {
+                (
+                    topic.clone(),
+                    serde_json::from_slice(payload).unwrap_or(Value::Null),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn count_threshold_sends_one_array() {
+        let clock = MockClock::new(0);
+        let sink = sink(&clock);
+        assert_eq!(sink.merge_count(), 3);
+
+        sink.send("temp", br#"{"v":1}"#).expect("send");
+        sink.send("other", b"10").expect("send");
+        sink.send("temp", br#"{"v":2}"#).expect("send");
+        assert!(sent(&sink).is_empty());
+        assert_eq!(sink.pending_count(), 3);
+
+        sink.send("temp", br#"{"v":3}"#).expect("send");
+        assert_eq!(
+            sent(&sink),
+            vec![("temp".to_string(), json!([{"v": 1}, {"v": 2}, {"v": 3}]))]
+        );
+        assert_eq!(sink.pending_count(), 1);
+    }
+
+    #[test]
+    fn timeout_sends_partial_array() {
+        let clock = MockClock::new(0);
+        let sink = sink(&clock);
+        sink.send("temp", b"1").expect("send");
+        clock.advance(Duration::from_millis(99));
+        sink.poll().expect("poll");
+        assert!(sent(&sink).is_empty());
+
+        clock.advance(Duration::from_millis(1));
+        sink.poll().expect("poll");
+        assert_eq!(sent(&sink), vec![("temp".to_string(), json!([1]))]);
+    }
+
+    #[test]
+    fn non_json_bypasses_merging() {
+        let clock = MockClock::new(0);
+        let sink = sink(&clock);
+        sink.send("temp", b"1").expect("send");
+        sink.send("temp", &[0xff, 0x00]).expect("send");
+
+        let records = sink.inner().records.lock().expect("lock").clone();
+        assert_eq!(records, vec![("temp".to_string(), vec![0xff, 0x00])]);
+        assert_eq!(sink.pending_count(), 1);
+    }
+
+    #[test]
+    fn flush_and_drop_send_held_arrays() {
+        let clock = MockClock::new(0);
+        let sink = sink(&clock);
+        sink.send("a", b"1").expect("send");
+        sink.flush().expect("flush");
+        assert_eq!(sent(&sink), vec![("a".to_string(), json!([1]))]);
+
+        let records = sink.inner().records_arc();
+        sink.send("b", b"2").expect("send");
+        drop(sink);
+        assert_eq!(records.lock().expect("lock").len(), 2);
+    }
+
+    #[test]
+    fn poll_thread_sends_partial_array_without_further_sends() {
+        let clock = MockClock::new(0);
+        let sink = sink(&clock).with_poll_interval(Some(Duration::from_millis(5)));
+        assert_eq!(sink.poll_interval(), Some(Duration::from_millis(5)));
+        sink.send("temp", b"1").expect("send");
+        clock.advance(Duration::from_millis(100));
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while sent(&sink).is_empty() {
+            assert!(std::time::Instant::now() < deadline, "array never sent");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(sent(&sink), vec![("temp".to_string(), json!([1]))]);
+    }
+}
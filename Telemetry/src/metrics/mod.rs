@@ -0,0 +1,407 @@
+//! In-process metric types rendered as telemetry.
+//!
+//! `Counter`, `Gauge` and `Histogram` accumulate values from any number of
+//! threads; a `MetricsRegistry` names them and emits one `TelemetryMessage`
+//! per metric on demand.
+//!
+//! **Why not hand-built JSON?** Every call site used to pick its own field
+//! names. Rendering through these types keeps the payload shape identical
+//! for all metrics of a kind, so dashboards can rely on it.
+
+use crate::{TelemetryClient, TelemetryError, TelemetryMessage, TelemetryResult};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Monotonically increasing count, e.g. requests served.
+#[derive(Debug, Default)]
+pub struct Counter {
+    value: AtomicU64,
+}
+
+impl Counter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one.
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    /// Add `n`.
+    pub fn add(&self, n: u64) {
+        self.value.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Total so far.
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+
+    /// `{"type":"counter","value":<total>}` on `topic`.
+    pub fn to_message(&self, topic: impl Into<String>) -> TelemetryMessage {
+        TelemetryMessage::new(topic, json!({"type": "counter", "value": self.get()}))
+    }
+}
+
+/// Value that can go up and down, e.g. queue depth or temperature.
+#[derive(Debug, Default)]
+pub struct Gauge {
+    /// `f64` bit pattern, so updates stay lock-free.
+    bits: AtomicU64,
+}
+
+impl Gauge {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the current value.
+    pub fn set(&self, value: f64) {
+        self.bits.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Add `delta` (negative to decrease).
+    pub fn add(&self, delta: f64) {
+        // fetch_update only fails if the closure returns None
+        let _ = self
+            .bits
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some((f64::from_bits(bits) + delta).to_bits())
+            });
+    }
+
+    /// Current value.
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.bits.load(Ordering::Relaxed))
+    }
+
+    /// `{"type":"gauge","value":<value>}` on `topic`.
+    pub fn to_message(&self, topic: impl Into<String>) -> TelemetryMessage {
+        TelemetryMessage::new(topic, json!({"type": "gauge", "value": self.get()}))
+    }
+}
+
+/// Point-in-time copy of a `Histogram`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramSnapshot {
+    /// Upper bounds of the finite buckets, ascending.
+    pub bounds: Vec<f64>,
+    /// Observations per bucket (not cumulative); the last entry counts
+    /// values above every bound.
+    pub counts: Vec<u64>,
+    pub count: u64,
+    pub sum: f64,
+}
+
+/// Distribution of observed values over fixed buckets, e.g. latencies.
+#[derive(Debug)]
+pub struct Histogram {
+    bounds: Vec<f64>,
+    state: Mutex<HistogramSnapshot>,
+}
+
+impl Histogram {
+    /// Buckets with the given upper bounds; they are sorted and deduplicated.
+    /// A value lands in the first bucket whose bound is `>=` it.
+    pub fn new(bounds: impl Into<Vec<f64>>) -> Self {
+        let mut bounds: Vec<f64> = bounds.into().into_iter().filter(|b| !b.is_nan()).collect();
+        bounds.sort_by(f64::total_cmp);
+        bounds.dedup();
+        let state = HistogramSnapshot {
+            bounds: bounds.clone(),
+            counts: vec![0; bounds.len() + 1],
+            count: 0,
+            sum: 0.0,
+        };
+        Self {
+            bounds,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Bucket upper bounds.
+    pub fn bounds(&self) -> &[f64] {
+        &self.bounds
+    }
+
+    /// Record one observation.
+    pub fn record(&self, value: f64) -> TelemetryResult<()> {
+        let bucket = self.bounds.partition_point(|&bound| bound < value);
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+        state.counts[bucket] += 1;
+        state.count += 1;
+        state.sum += value;
+        Ok(())
+    }
+
+    /// Copy of the current counts.
+    pub fn snapshot(&self) -> TelemetryResult<HistogramSnapshot> {
+        self.state
+            .lock()
+            .map(|state| state.clone())
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))
+    }
+
+    /// `{"type":"histogram","count":..,"sum":..,"buckets":[{"le":..,"count":..}]}`
+    /// on `topic`. Bucket counts are cumulative, Prometheus style, ending
+    /// with `"le":"+Inf"`.
+    pub fn to_message(&self, topic: impl Into<String>) -> TelemetryResult<TelemetryMessage> {
+        let snapshot = self.snapshot()?;
+        let mut cumulative = 0;
+        let buckets: Vec<Value> = snapshot
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(i, count)| {
+                cumulative += count;
+                let le = snapshot.bounds.get(i).map_or(json!("+Inf"), |b| json!(b));
+                json!({"le": le, "count": cumulative})
+            })
+            .collect();
+        Ok(TelemetryMessage::new(
+            topic,
+            json!({
+                "type": "histogram",
+                "count": snapshot.count,
+                "sum": snapshot.sum,
+                "buckets": buckets,
+            }),
+        ))
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Metric {
+    Counter(Arc<Counter>),
+    Gauge(Arc<Gauge>),
+    Histogram(Arc<Histogram>),
+}
+
+impl Metric {
+    fn kind(&self) -> &'static str {
+        match self {
+            Metric::Counter(_) => "counter",
+            Metric::Gauge(_) => "gauge",
+            Metric::Histogram(_) => "histogram",
+        }
+    }
+
+    fn to_message(&self, topic: String) -> TelemetryResult<TelemetryMessage> {
+        match self {
+            Metric::Counter(c) => Ok(c.to_message(topic)),
+            Metric::Gauge(g) => Ok(g.to_message(topic)),
+            Metric::Histogram(h) => h.to_message(topic),
+        }
+    }
+}
+
+/// Named collection of metrics.
+///
+/// Lookups create the metric on first use and return the same instance
+/// afterwards, so hot paths can keep the returned `Arc` and skip the map.
+#[derive(Debug, Default)]
+pub struct MetricsRegistry {
+    metrics: Mutex<BTreeMap<String, Metric>>,
+}
+
+impl MetricsRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counter named `name`.
+    pub fn counter(&self, name: &str) -> TelemetryResult<Arc<Counter>> {
+        match self.get_or_insert(name, || Metric::Counter(Arc::default()))? {
+            Metric::Counter(c) => Ok(c),
+            other => Err(Self::kind_mismatch(name, &other, "counter")),
+        }
+    }
+
+    /// Gauge named `name`.
+    pub fn gauge(&self, name: &str) -> TelemetryResult<Arc<Gauge>> {
+        match self.get_or_insert(name, || Metric::Gauge(Arc::default()))? {
+            Metric::Gauge(g) => Ok(g),
+            other => Err(Self::kind_mismatch(name, &other, "gauge")),
+        }
+    }
+
+    /// Histogram named `name`; `bounds` only apply when it is created.
+    pub fn histogram(&self, name: &str, bounds: &[f64]) -> TelemetryResult<Arc<Histogram>> {
+        match self.get_or_insert(name, || Metric::Histogram(Arc::new(Histogram::new(bounds))))? {
+            Metric::Histogram(h) => Ok(h),
+            other => Err(Self::kind_mismatch(name, &other, "histogram")),
+        }
+    }
+
+    /// Number of registered metrics.
+    pub fn len(&self) -> usize {
+        self.metrics.lock().map_or(0, |m| m.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// One message per metric, on `<prefix>/<name>` (or `<name>` when
+    /// `prefix` is empty), in name order.
+    pub fn messages(&self, prefix: &str) -> TelemetryResult<Vec<TelemetryMessage>> {
+        let metrics: Vec<(String, Metric)> = self
+            .metrics
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?
+            .iter()
+            .map(|(name, metric)| (name.clone(), metric.clone()))
+            .collect();
+        metrics
+            .into_iter()
+            .map(|(name, metric)| {
+                let topic = if prefix.is_empty() {
+                    name
+                } else {
+                    format!("{}/{}", prefix.trim_end_matches('/'), name)
+                };
+                metric.to_message(topic)
+            })
+            .collect()
+    }
+
+    /// Send every metric through `client`, stamped with its clock.
+    ///
+    /// Values are cumulative and are not reset. Every metric is attempted;
+    /// the first send error is returned.
+    pub fn flush_to(&self, client: &TelemetryClient, prefix: &str) -> TelemetryResult<()> {
+        let mut result = Ok(());
+        for mut msg in self.messages(prefix)? {
+            client.stamp(&mut msg);
+            if let Err(e) = client.send_message(&msg) {
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+
+    fn get_or_insert(
+        &self,
+        name: &str,
+        create: impl FnOnce() -> Metric,
+    ) -> TelemetryResult<Metric> {
+        let mut metrics = self
+            .metrics
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+        Ok(metrics
+            .entry(name.to_string())
+            .or_insert_with(create)
+            .clone())
+    }
+
+    fn kind_mismatch(name: &str, existing: &Metric, requested: &str) -> TelemetryError {
+        TelemetryError::new(format!(
+            "metric '{}' is a {}, not a {}",
+            name,
+            existing.kind(),
+            requested
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemorySink, MockClock};
+
+    fn emitted(sink: &InMemorySink) -> Vec<TelemetryMessage> {
+        sink.records
+            .lock()
+            .expect("lock")
+            .iter()
+            .map(|(_, payload)| serde_json::from_slice(payload).expect("message"))
+            .collect()
+    }
+
+    #[test]
+    fn counter_increments_from_many_threads() {
+        let registry = Arc::new(MetricsRegistry::new());
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let registry = Arc::clone(&registry);
+                std::thread::spawn(move || {
+                    let requests = registry.counter("requests").expect("counter");
+                    for _ in 0..250 {
+                        requests.inc();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().expect("join");
+        }
+
+        let sink = Arc::new(InMemorySink::new());
+        let client = TelemetryClient::new(sink.clone()).with_clock(Arc::new(MockClock::new(7)));
+        registry.flush_to(&client, "app").expect("flush");
+
+        let messages = emitted(&sink);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].topic, "app/requests");
+        assert_eq!(messages[0].timestamp, Some(7));
+        assert_eq!(
+            messages[0].payload,
+            json!({"type": "counter", "value": 1000})
+        );
+    }
+
+    #[test]
+    fn histogram_emits_cumulative_buckets() {
+        let registry = MetricsRegistry::new();
+        let latency = registry
+            .histogram("latency_ms", &[10.0, 100.0])
+            .expect("histogram");
+        for value in [5.0, 10.0, 50.0, 500.0] {
+            latency.record(value).expect("record");
+        }
+        registry.gauge("queue_depth").expect("gauge").set(3.5);
+
+        let sink = Arc::new(InMemorySink::new());
+        registry
+            .flush_to(&TelemetryClient::new(sink.clone()), "")
+            .expect("flush");
+
+        let messages = emitted(&sink);
+        assert_eq!(messages[0].topic, "latency_ms");
+        assert_eq!(
+            messages[0].payload,
+            json!({
+                "type": "histogram",
+                "count": 4,
+                "sum": 565.0,
+                "buckets": [
+                    {"le": 10.0, "count": 2},
+                    {"le": 100.0, "count": 3},
+                    {"le": "+Inf", "count": 4},
+                ],
+            })
+        );
+        assert_eq!(messages[1].payload, json!({"type": "gauge", "value": 3.5}));
+    }
+
+    #[test]
+    fn same_name_returns_same_metric_of_one_kind() {
+        let registry = MetricsRegistry::new();
+        registry.counter("hits").expect("counter").add(2);
+        registry.counter("hits").expect("counter").inc();
+
+        assert_eq!(registry.counter("hits").expect("counter").get(), 3);
+        let err = registry.gauge("hits").expect_err("kind mismatch");
+        assert_eq!(err.message(), "metric 'hits' is a counter, not a gauge");
+        assert_eq!(registry.len(), 1);
+    }
+}
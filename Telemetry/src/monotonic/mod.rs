@@ -0,0 +1,300 @@
+//! Strictly increasing message timestamps.
+//!
+//! **Why?** Producers on several threads stamp messages from a clock that
+//! can repeat a millisecond or step backwards. Consumers that sort, window
+//! or deduplicate by timestamp then see ties and reversals.
+//! `MonotonicStampSink` makes each timestamp later than the one before it.
+
+use crate::source::decode_message;
+use crate::{
+    layered_sink_name, Clock, SystemClock, TelemetryError, TelemetryResult, TelemetrySink,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Which messages a `MonotonicStampSink` keeps in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StampScope {
+    /// Every message is later than the previous one on any topic.
+    #[default]
+    Global,
+    /// Each topic has its own sequence of timestamps.
+    PerTopic,
+}
+
+/// How a `MonotonicStampSink` treats a message's existing timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StampMode {
+    /// Replace it with the current time.
+    #[default]
+    Overwrite,
+    /// Keep it if it is later than the previous timestamp; stamp messages
+    /// without one with the current time.
+    Validate,
+}
+
+/// A sink that gives every message a strictly increasing `timestamp`.
+///
+/// Whenever the chosen timestamp is not after the previous one (the clock
+/// has not advanced, or went backwards) it is bumped to one millisecond past
+/// the previous one. Payloads are decoded as `TelemetryMessage` JSON;
+/// anything else is wrapped in a new message (binary payloads
+/// base64-encoded, see `source::ENCODING_HEADER`). Sends are serialized so
+/// messages reach the inner sink in timestamp order, and a stamp only counts
+/// as the previous one once the inner sink has accepted it.
+pub struct MonotonicStampSink<S: TelemetrySink> {
+    inner: S,
+    scope: StampScope,
+    mode: StampMode,
+    clock: Arc<dyn Clock>,
+    /// Last timestamp per topic, or under `""` for `StampScope::Global`.
+    last: Mutex<HashMap<String, i64>>,
+}
+
+impl<S: TelemetrySink> MonotonicStampSink<S> {
+    /// Overwrite timestamps, ordered across all topics.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            scope: StampScope::default(),
+            mode: StampMode::default(),
+            clock: Arc::new(SystemClock),
+            last: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_scope(mut self, scope: StampScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: StampMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Stamp with `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn scope(&self) -> StampScope {
+        self.scope
+    }
+
+    pub fn mode(&self) -> StampMode {
+        self.mode
+    }
+
+    /// Last timestamp stamped for `topic` (any topic under
+    /// `StampScope::Global`).
+    pub fn last_timestamp(&self, topic: &str) -> Option<i64> {
+        self.last.lock().ok()?.get(self.key(topic)).copied()
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn key<'a>(&self, topic: &'a str) -> &'a str {
+        match self.scope {
+            StampScope::Global => "",
+            StampScope::PerTopic => topic,
+        }
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for MonotonicStampSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("monotonic_stamp", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let mut message = decode_message(topic, payload);
+        let mut last = self
+            .last
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+        let candidate = match (self.mode, message.timestamp) {
+            (StampMode::Validate, Some(own)) => own,
+            _ => self.clock.now_millis(),
+        };
+        let stamp = match last.get(self.key(topic)) {
+            Some(&prev) if candidate <= prev => prev + 1,
+            _ => candidate,
+        };
+        message.timestamp = Some(stamp);
+        let encoded = serde_json::to_vec(&message)
+            .map_err(|e| TelemetryError::Serialization(format!("stamped message: {}", e)))?;
+        self.inner.send(topic, &encoded)?;
+        last.insert(self.key(topic).to_string(), stamp);
+        Ok(())
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Fault, FaultInjectionSink, InMemorySink, MockClock, TelemetryMessage};
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn timestamps(sink: &InMemorySink) -> Vec<(String, i64)> {
+        sink.records
+            .lock()
+            .expect("lock")
+            .iter()
+            .map(|(topic, bytes)| {
+                let msg: TelemetryMessage = serde_json::from_slice(bytes).expect("message");
+                (topic.clone(), msg.timestamp.expect("stamped"))
+            })
+            .collect()
+    }
+
+    fn strictly_increasing(stamps: &[i64]) -> bool {
+        stamps.windows(2).all(|pair| pair[0] < pair[1])
+    }
+
+    #[test]
+    fn rapid_sends_are_bumped_past_a_stalled_clock() {
+        let clock = MockClock::new(5_000);
+        let sink = MonotonicStampSink::new(InMemorySink::new()).with_clock(Arc::new(clock.clone()));
+        for i in 0..3 {
+            sink.send("a", format!("{}", i).as_bytes()).expect("send");
+        }
+        clock.set(4_000);
+        sink.send("b", b"late").expect("send");
+        clock.set(9_000);
+        sink.send("a", b"later").expect("send");
+
+        let stamps: Vec<i64> = timestamps(sink.inner())
+            .into_iter()
+            .map(|(_, t)| t)
+            .collect();
+        assert_eq!(stamps, [5_000, 5_001, 5_002, 5_003, 9_000]);
+        assert_eq!(sink.last_timestamp("anything"), Some(9_000));
+    }
+
+    #[test]
+    fn per_topic_scope_orders_each_topic_separately() {
+        let clock = MockClock::new(100);
+        let sink = MonotonicStampSink::new(InMemorySink::new())
+            .with_scope(StampScope::PerTopic)
+            .with_clock(Arc::new(clock.clone()));
+        for topic in ["a", "b", "a", "b"] {
+            sink.send(topic, b"{}").expect("send");
+        }
+        clock.advance(Duration::from_millis(10));
+        sink.send("a", b"{}").expect("send");
+
+        assert_eq!(
+            timestamps(sink.inner()),
+            [
+                ("a".to_string(), 100),
+                ("b".to_string(), 100),
+                ("a".to_string(), 101),
+                ("b".to_string(), 101),
+                ("a".to_string(), 110),
+            ]
+        );
+        assert_eq!(sink.last_timestamp("b"), Some(101));
+    }
+
+    #[test]
+    fn validate_keeps_later_timestamps_and_fixes_stale_ones() {
+        let clock = MockClock::new(1_000);
+        let sink = MonotonicStampSink::new(InMemorySink::new())
+            .with_mode(StampMode::Validate)
+            .with_clock(Arc::new(clock.clone()));
+        for ts in [Some(2_000), Some(1_500), None, Some(3_000)] {
+            let mut msg = TelemetryMessage::new("t", json!(1));
+            msg.timestamp = ts;
+            sink.send("t", msg.to_json().as_bytes()).expect("send");
+        }
+
+        let stamps: Vec<i64> = timestamps(sink.inner())
+            .into_iter()
+            .map(|(_, t)| t)
+            .collect();
+        assert_eq!(stamps, [2_000, 2_001, 2_002, 3_000]);
+    }
+
+    #[test]
+    fn failed_send_does_not_advance_the_stamp() {
+        let clock = MockClock::new(5_000);
+        let sink = MonotonicStampSink::new(FaultInjectionSink::scripted([Fault::Fail(
+            TelemetryError::Transport("down".to_string()),
+        )]))
+        .with_clock(Arc::new(clock.clone()));
+
+        assert!(sink.send("t", b"1").is_err());
+        assert_eq!(sink.last_timestamp("t"), None);
+        sink.send("t", b"1").expect("retry");
+
+        let calls = sink.inner().calls();
+        let stamps: Vec<i64> = calls
+            .iter()
+            .map(|call| {
+                let msg: TelemetryMessage = serde_json::from_slice(&call.payload).expect("message");
+                msg.timestamp.expect("stamped")
+            })
+            .collect();
+        assert_eq!(stamps, [5_000, 5_000]);
+        assert_eq!(sink.last_timestamp("t"), Some(5_000));
+    }
+
+    #[test]
+    fn binary_payloads_survive_stamping() {
+        use base64::Engine;
+
+        let sink = MonotonicStampSink::new(InMemorySink::new());
+        sink.send("t", &[0x00, 0xff, 0x80]).expect("send");
+
+        let records = sink.inner().records.lock().expect("lock");
+        let msg: TelemetryMessage = serde_json::from_slice(&records[0].1).expect("message");
+        assert_eq!(msg.headers[crate::source::ENCODING_HEADER], "base64");
+        let encoded = msg.payload.as_str().expect("string payload");
+        assert_eq!(
+            base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .expect("base64"),
+            [0x00, 0xff, 0x80]
+        );
+    }
+
+    #[test]
+    fn concurrent_senders_get_strictly_increasing_timestamps() {
+        let sink = Arc::new(MonotonicStampSink::new(InMemorySink::new()));
+        let threads: Vec<_> = (0..4)
+            .map(|t| {
+                let sink = Arc::clone(&sink);
+                std::thread::spawn(move || {
+                    for i in 0..50 {
+                        sink.send("t", format!("{}-{}", t, i).as_bytes())
+                            .expect("send");
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().expect("join");
+        }
+
+        let stamps: Vec<i64> = timestamps(sink.inner())
+            .into_iter()
+            .map(|(_, t)| t)
+            .collect();
+        assert_eq!(stamps.len(), 200);
+        assert!(strictly_increasing(&stamps));
+    }
+}
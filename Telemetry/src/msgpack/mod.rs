@@ -0,0 +1,52 @@
+//! MessagePack encoding for `TelemetryMessage`.
+//!
+//! Like the CBOR encoding, the whole message is encoded as one map keyed by
+//! the JSON field names, so the two decode to the same structure. Prefer it
+//! when consumers already use MessagePack elsewhere.
+
+use crate::{TelemetryError, TelemetryMessage, TelemetryResult};
+
+impl TelemetryMessage {
+    /// Encode the message as MessagePack bytes.
+    pub fn to_msgpack(&self) -> TelemetryResult<Vec<u8>> {
+        rmp_serde::to_vec_named(self)
+            .map_err(|e| TelemetryError::Serialization(format!("MessagePack encode: {}", e)))
+    }
+
+    /// Decode a message from MessagePack bytes produced by `to_msgpack`.
+    pub fn from_msgpack(bytes: &[u8]) -> TelemetryResult<Self> {
+        rmp_serde::from_slice(bytes)
+            .map_err(|e| TelemetryError::Serialization(format!("MessagePack decode: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn round_trips_message_with_metadata() {
+        let msg = TelemetryMessage::builder()
+            .topic("sensors/temp")
+            .payload(json!({"value": 21.5, "tags": ["a", "b"], "ok": true, "n": null}))
+            .timestamp(1_700_000_000_000)
+            .header("service", "hvac")
+            .build()
+            .expect("build");
+
+        let bytes = msg.to_msgpack().expect("encode");
+
+        assert!(bytes.len() < msg.to_json().len());
+        assert_eq!(TelemetryMessage::from_msgpack(&bytes).expect("decode"), msg);
+    }
+
+    #[test]
+    fn truncated_bytes_are_a_serialization_error() {
+        let bytes = TelemetryMessage::new("t", json!(1))
+            .to_msgpack()
+            .expect("encode");
+        let err = TelemetryMessage::from_msgpack(&bytes[..bytes.len() - 1]).expect_err("short");
+        assert!(matches!(err, TelemetryError::Serialization(_)));
+    }
+}
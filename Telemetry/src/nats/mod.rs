@@ -0,0 +1,383 @@
+//! NATS transport for telemetry data.
+//!
+//! **Why feature-gated?** Only deployments whose event bus is NATS need it.
+//! Enable with `features = ["nats"]` in Cargo.toml.
+//!
+//! The sink speaks the NATS client text protocol (`CONNECT` / `PUB` /
+//! `PING`) directly over TCP. That is all a publisher needs, and it avoids
+//! pulling an async runtime into a synchronous sink.
+
+use crate::{TelemetryError, TelemetryResult, TelemetrySink};
+use std::any::Any;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Port used when the server URL does not name one.
+pub const DEFAULT_PORT: u16 = 4222;
+
+/// Translates a telemetry topic into a NATS subject.
+pub type SubjectMapper = Box<dyn Fn(&str) -> String + Send + Sync>;
+
+/// Default topic→subject mapping: `sensors/temp` becomes `sensors.temp`.
+pub fn default_subject(topic: &str) -> String {
+    topic.replace('/', ".")
+}
+
+/// Credentials sent in the `CONNECT` handshake.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum NatsAuth {
+    #[default]
+    None,
+    Token(String),
+    UserPassword {
+        user: String,
+        password: String,
+    },
+}
+
+/// Configuration for `NatsSink`.
+#[derive(Debug, Clone)]
+pub struct NatsSinkConfig {
+    /// Server address, e.g. `nats://localhost:4222`.
+    pub url: String,
+    pub auth: NatsAuth,
+    /// Connect and handshake timeout.
+    pub timeout: Duration,
+}
+
+impl NatsSinkConfig {
+    /// Unauthenticated configuration with a 5s timeout.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            auth: NatsAuth::None,
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Authenticate with a token.
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.auth = NatsAuth::Token(token.into());
+        self
+    }
+
+    /// Authenticate with a user name and password.
+    pub fn user_password(mut self, user: impl Into<String>, password: impl Into<String>) -> Self {
+        self.auth = NatsAuth::UserPassword {
+            user: user.into(),
+            password: password.into(),
+        };
+        self
+    }
+
+    /// Set the connect and handshake timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn socket_addr(&self) -> TelemetryResult<SocketAddr> {
+        let host = self.url.strip_prefix("nats://").unwrap_or(&self.url);
+        let host = host.trim_end_matches('/');
+        let with_port = if host
+            .rsplit_once(':')
+            .is_some_and(|(_, p)| p.parse::<u16>().is_ok())
+        {
+            host.to_string()
+        } else {
+            format!("{}:{}", host, DEFAULT_PORT)
+        };
+        with_port
+            .to_socket_addrs()
+            .map_err(|e| TelemetryError::Connection(format!("cannot resolve {}: {}", self.url, e)))?
+            .next()
+            .ok_or_else(|| TelemetryError::Connection(format!("no address for {}", self.url)))
+    }
+
+    fn connect_command(&self) -> String {
+        let mut options = serde_json::json!({
+            "verbose": false,
+            "pedantic": false,
+            "lang": "rust",
+            "version": env!("CARGO_PKG_VERSION"),
+        });
+        match &self.auth {
+            NatsAuth::None => {}
+            NatsAuth::Token(token) => options["auth_token"] = token.clone().into(),
+            NatsAuth::UserPassword { user, password } => {
+                options["user"] = user.clone().into();
+                options["pass"] = password.clone().into();
+            }
+        }
+        format!("CONNECT {}\r\n", options)
+    }
+}
+
+struct Connection {
+    writer: TcpStream,
+    reader: BufReader<TcpStream>,
+}
+
+impl Connection {
+    fn open(config: &NatsSinkConfig) -> TelemetryResult<Self> {
+        let addr = config.socket_addr()?;
+        let stream = TcpStream::connect_timeout(&addr, config.timeout)
+            .map_err(|e| TelemetryError::Connection(format!("NATS connect to {}: {}", addr, e)))?;
+        let io = |e: std::io::Error| TelemetryError::Connection(format!("NATS handshake: {}", e));
+        stream.set_read_timeout(Some(config.timeout)).map_err(io)?;
+        stream.set_nodelay(true).map_err(io)?;
+        let mut conn = Connection {
+            reader: BufReader::new(stream.try_clone().map_err(io)?),
+            writer: stream,
+        };
+
+        let info = conn.read_line().map_err(io)?;
+        if !info.starts_with("INFO") {
+            return Err(TelemetryError::Connection(format!(
+                "unexpected NATS greeting: {}",
+                info
+            )));
+        }
+        conn.writer
+            .write_all(config.connect_command().as_bytes())
+            .map_err(io)?;
+        conn.ping().map_err(|e| match e {
+            TelemetryError::Transport(msg) => TelemetryError::Connection(msg),
+            other => other,
+        })?;
+        Ok(conn)
+    }
+
+    fn read_line(&mut self) -> std::io::Result<String> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(line.trim_end().to_string())
+    }
+
+    fn publish(&mut self, subject: &str, payload: &[u8]) -> std::io::Result<()> {
+        let mut frame = format!("PUB {} {}\r\n", subject, payload.len()).into_bytes();
+        frame.extend_from_slice(payload);
+        frame.extend_from_slice(b"\r\n");
+        self.writer.write_all(&frame)
+    }
+
+    /// Round-trip a `PING` so any server `-ERR` for earlier commands surfaces.
+    fn ping(&mut self) -> TelemetryResult<()> {
+        let io = |e: std::io::Error| TelemetryError::Connection(format!("NATS ping: {}", e));
+        self.writer.write_all(b"PING\r\n").map_err(io)?;
+        loop {
+            let line = self.read_line().map_err(io)?;
+            match line.as_str() {
+                "PONG" => return Ok(()),
+                "PING" => self.writer.write_all(b"PONG\r\n").map_err(io)?,
+                "+OK" => {}
+                _ if line.starts_with("INFO") => {}
+                _ if line.starts_with("-ERR") => {
+                    return Err(TelemetryError::Transport(format!("NATS server: {}", line)))
+                }
+                _ => {
+                    return Err(TelemetryError::Transport(format!(
+                        "unexpected NATS reply: {}",
+                        line
+                    )))
+                }
+            }
+        }
+    }
+}
+
+/// A sink that publishes each payload to a NATS subject.
+///
+/// A broken connection is dropped and re-established on the next send.
+pub struct NatsSink {
+    config: NatsSinkConfig,
+    mapper: SubjectMapper,
+    conn: Mutex<Option<Connection>>,
+}
+
+impl NatsSink {
+    /// Connect to `url` without authentication.
+    pub fn new(url: impl Into<String>) -> TelemetryResult<Self> {
+        Self::with_config(NatsSinkConfig::new(url))
+    }
+
+    /// Connect using an explicit configuration.
+    pub fn with_config(config: NatsSinkConfig) -> TelemetryResult<Self> {
+        let conn = Connection::open(&config)?;
+        Ok(Self {
+            config,
+            mapper: Box::new(default_subject),
+            conn: Mutex::new(Some(conn)),
+        })
+    }
+
+    /// Replace the default `/`→`.` topic→subject translation.
+    pub fn with_subject_mapper<F>(mut self, mapper: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.mapper = Box::new(mapper);
+        self
+    }
+
+    /// Active configuration.
+    pub fn config(&self) -> &NatsSinkConfig {
+        &self.config
+    }
+
+    /// Subject a payload for `topic` is published to.
+    pub fn subject_for(&self, topic: &str) -> String {
+        (self.mapper)(topic)
+    }
+
+    /// Run `op` on the live connection, reconnecting first if needed.
+    ///
+    /// The connection is discarded if `op` fails so the next call starts
+    /// from a clean handshake.
+    fn with_connection<T>(
+        &self,
+        op: impl FnOnce(&mut Connection) -> TelemetryResult<T>,
+    ) -> TelemetryResult<T> {
+        let mut guard = self
+            .conn
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+        let mut conn = match guard.take() {
+            Some(conn) => conn,
+            None => Connection::open(&self.config)?,
+        };
+        let result = op(&mut conn);
+        if result.is_ok() {
+            *guard = Some(conn);
+        }
+        result
+    }
+}
+
+impl TelemetrySink for NatsSink {
+    fn sink_name(&self) -> &'static str {
+        "nats"
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let subject = self.subject_for(topic);
+        if subject.is_empty() || subject.contains(char::is_whitespace) {
+            return Err(TelemetryError::new(format!(
+                "invalid NATS subject '{}'",
+                subject
+            )));
+        }
+        self.with_connection(|conn| {
+            conn.publish(&subject, payload)
+                .map_err(|e| TelemetryError::Transport(format!("NATS publish: {}", e)))
+        })
+    }
+
+    /// Wait for the server to acknowledge everything published so far.
+    fn flush(&self) -> TelemetryResult<()> {
+        self.with_connection(Connection::ping)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    /// Minimal NATS server: greets, answers PINGs, and returns every line
+    /// (payload lines included) received before the client disconnects.
+    fn mock_server(reject_auth: bool) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("nats://{}", listener.local_addr().expect("addr"));
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("accept");
+            let mut reader = BufReader::new(stream.try_clone().expect("clone"));
+            stream
+                .write_all(b"INFO {\"server_id\":\"mock\"}\r\n")
+                .expect("info");
+            let mut lines = Vec::new();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                    break;
+                }
+                let line = line.trim_end().to_string();
+                if line == "PING" {
+                    let reply: &[u8] = if reject_auth {
+                        b"-ERR 'Authorization Violation'\r\n"
+                    } else {
+                        b"PONG\r\n"
+                    };
+                    stream.write_all(reply).expect("reply");
+                }
+                lines.push(line);
+            }
+            lines
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn default_mapping_converts_slashes_to_dots() {
+        assert_eq!(default_subject("sensors/temp"), "sensors.temp");
+        assert_eq!(default_subject("a/b/c"), "a.b.c");
+        assert_eq!(default_subject("plain"), "plain");
+    }
+
+    #[test]
+    fn publishes_to_mapped_subject_with_token() {
+        let (url, server) = mock_server(false);
+        let sink = NatsSink::with_config(NatsSinkConfig::new(url).token("s3cret")).expect("sink");
+
+        sink.send("sensors/temp", b"21.5").expect("send");
+        sink.flush().expect("flush");
+        let custom = sink.with_subject_mapper(|t| format!("room619.{}", t.replace('/', "_")));
+        custom.send("sensors/hum", b"40").expect("send");
+        drop(custom);
+
+        let lines = server.join().expect("server");
+        assert!(lines[0].starts_with("CONNECT "));
+        let connect: serde_json::Value =
+            serde_json::from_str(&lines[0]["CONNECT ".len()..]).expect("connect json");
+        assert_eq!(connect["auth_token"], "s3cret");
+        assert_eq!(&lines[2..4], ["PUB sensors.temp 4", "21.5"]);
+        assert_eq!(&lines[5..7], ["PUB room619.sensors_hum 2", "40"]);
+    }
+
+    #[test]
+    fn rejected_credentials_map_to_connection_error() {
+        let (url, server) = mock_server(true);
+        let err = NatsSink::with_config(NatsSinkConfig::new(url).user_password("u", "wrong"))
+            .err()
+            .expect("auth failure");
+        assert!(matches!(err, TelemetryError::Connection(_)));
+        assert!(err.message().contains("Authorization Violation"));
+
+        let lines = server.join().expect("server");
+        let connect: serde_json::Value =
+            serde_json::from_str(&lines[0]["CONNECT ".len()..]).expect("connect json");
+        assert_eq!(connect["user"], "u");
+        assert_eq!(connect["pass"], "wrong");
+    }
+
+    /// Runs against a real server when `NATS_URL` is set,
+    /// e.g. `NATS_URL=nats://127.0.0.1:4222 cargo test --features nats`.
+    #[test]
+    fn publishes_to_live_server() {
+        let Ok(url) = std::env::var("NATS_URL") else {
+            return;
+        };
+        let sink = NatsSink::new(url).expect("connect");
+        sink.send("room619/test", b"hello").expect("publish");
+        sink.flush().expect("flush");
+    }
+}
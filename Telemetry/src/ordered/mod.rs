@@ -0,0 +1,257 @@
+//! Per-topic ordered delivery on dedicated worker threads.
+//!
+//! `OrderedSink` hashes each topic to one of a fixed set of worker threads,
+//! so every send on a topic reaches the inner sink from the same thread and
+//! in submission order, however many threads call `send`.
+//!
+//! **Why not a lock around the inner sink?** A lock keeps one send at a time
+//! but lets waiting callers through in any order, and some transports
+//! (thread-bound client handles, per-thread sessions) additionally need all
+//! traffic for a stream to come from one thread.
+
+use crate::{layered_sink_name, ShutdownSink, TelemetryError, TelemetryResult, TelemetrySink};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::JoinHandle;
+
+enum Job {
+    Send(String, Vec<u8>),
+    /// Answered once every job queued before it has been processed.
+    Barrier(Sender<()>),
+}
+
+/// A sink that delivers each topic's messages in order from one worker
+/// thread per topic.
+///
+/// `send` only enqueues: it returns once the message is handed to its
+/// worker, and failures of the inner sink are logged and counted in
+/// `failed_count`. `flush` waits for everything queued so far to be
+/// delivered, then flushes the inner sink.
+pub struct OrderedSink<S: TelemetrySink + 'static> {
+    inner: Arc<S>,
+    /// Emptied by `shutdown`.
+    senders: RwLock<Vec<Sender<Job>>>,
+    threads: Mutex<Vec<JoinHandle<()>>>,
+    failed: Arc<AtomicU64>,
+}
+
+impl<S: TelemetrySink + 'static> OrderedSink<S> {
+    /// Start `workers` worker threads (at least one) delivering to `inner`.
+    pub fn new(inner: S, workers: usize) -> Self {
+        let inner = Arc::new(inner);
+        let failed = Arc::new(AtomicU64::new(0));
+        let (senders, threads) = (0..workers.max(1))
+            .map(|_| {
+                let (tx, rx) = channel::<Job>();
+                let inner = Arc::clone(&inner);
+                let failed = Arc::clone(&failed);
+                let thread = std::thread::spawn(move || {
+                    for job in rx {
+                        match job {
+                            Job::Send(topic, payload) => {
+                                if let Err(e) = inner.send(&topic, &payload) {
+                                    failed.fetch_add(1, Ordering::Relaxed);
+                                    log::warn!("ordered send on '{}' failed: {}", topic, e);
+                                }
+                            }
+                            Job::Barrier(done) => {
+                                let _ = done.send(());
+                            }
+                        }
+                    }
+                });
+                (tx, thread)
+            })
+            .unzip();
+        Self {
+            inner,
+            senders: RwLock::new(senders),
+            threads: Mutex::new(threads),
+            failed,
+        }
+    }
+
+    /// Number of worker threads, or zero once shut down.
+    pub fn worker_count(&self) -> usize {
+        self.senders.read().map_or(0, |senders| senders.len())
+    }
+
+    /// Sends the inner sink rejected.
+    pub fn failed_count(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Deliver everything queued, stop the workers and flush the inner sink.
+    ///
+    /// Later sends fail with `TelemetryError::Connection`. Calling it again
+    /// only flushes the inner sink.
+    pub fn shutdown(&self) -> TelemetryResult<()> {
+        // Dropping the senders ends each worker's loop once its queue is empty
+        self.senders
+            .write()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?
+            .clear();
+        let threads = std::mem::take(
+            &mut *self
+                .threads
+                .lock()
+                .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?,
+        );
+        for thread in threads {
+            thread
+                .join()
+                .map_err(|_| TelemetryError::new("ordered sink worker panicked"))?;
+        }
+        self.inner.flush()
+    }
+
+    fn shut_down() -> TelemetryError {
+        TelemetryError::Connection("ordered sink is shut down".into())
+    }
+}
+
+impl<S: TelemetrySink + 'static> TelemetrySink for OrderedSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("ordered", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let senders = self
+            .senders
+            .read()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+        if senders.is_empty() {
+            return Err(Self::shut_down());
+        }
+        let mut hasher = DefaultHasher::new();
+        topic.hash(&mut hasher);
+        let worker = &senders[(hasher.finish() % senders.len() as u64) as usize];
+        worker
+            .send(Job::Send(topic.to_string(), payload.to_vec()))
+            .map_err(|_| Self::shut_down())
+    }
+
+    /// Wait until every worker has delivered what was queued before this
+    /// call, then flush the inner sink.
+    fn flush(&self) -> TelemetryResult<()> {
+        let waits: Vec<_> = self
+            .senders
+            .read()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?
+            .iter()
+            .filter_map(|worker| {
+                let (done, wait) = channel();
+                worker.send(Job::Barrier(done)).ok().map(|()| wait)
+            })
+            .collect();
+        for wait in waits {
+            wait.recv()
+                .map_err(|_| TelemetryError::new("ordered sink worker stopped"))?;
+        }
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+impl<S: TelemetrySink + 'static> ShutdownSink for OrderedSink<S> {
+    fn close(self) -> TelemetryResult<()> {
+        self.shutdown()
+    }
+}
+
+impl<S: TelemetrySink + 'static> Drop for OrderedSink<S> {
+    fn drop(&mut self) {
+        if let Err(e) = self.shutdown() {
+            log::warn!("OrderedSink shutdown on drop failed: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::ThreadId;
+
+    /// Records each payload with the thread that delivered it.
+    #[derive(Default)]
+    struct ThreadRecordingSink {
+        seen: Mutex<Vec<(String, u64, ThreadId)>>,
+    }
+
+    impl TelemetrySink for ThreadRecordingSink {
+        fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+            let seq = std::str::from_utf8(payload)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .ok_or_else(|| TelemetryError::Serialization("not a sequence number".into()))?;
+            self.seen.lock().expect("lock").push((
+                topic.to_string(),
+                seq,
+                std::thread::current().id(),
+            ));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn concurrent_sends_on_one_topic_stay_in_order() {
+        let sink = Arc::new(OrderedSink::new(ThreadRecordingSink::default(), 4));
+        assert_eq!(sink.worker_count(), 4);
+        // Held while numbering and submitting, so submission order is the
+        // sequence order
+        let submit = Arc::new(Mutex::new(0u64));
+
+        let senders: Vec<_> = (0..8)
+            .map(|_| {
+                let sink = Arc::clone(&sink);
+                let submit = Arc::clone(&submit);
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        let mut seq = submit.lock().expect("lock");
+                        *seq += 1;
+                        sink.send("sensors/temp", seq.to_string().as_bytes())
+                            .expect("send");
+                    }
+                })
+            })
+            .collect();
+        for sender in senders {
+            sender.join().expect("sender");
+        }
+        sink.flush().expect("flush");
+
+        let seen = sink.inner().seen.lock().expect("lock");
+        let seqs: Vec<u64> = seen.iter().map(|(_, seq, _)| *seq).collect();
+        assert_eq!(seqs, (1..=800).collect::<Vec<_>>());
+        assert!(seen.iter().all(|(_, _, thread)| *thread == seen[0].2));
+        assert_ne!(seen[0].2, std::thread::current().id());
+    }
+
+    #[test]
+    fn failures_are_counted_and_shutdown_rejects_sends() {
+        let sink = OrderedSink::new(ThreadRecordingSink::default(), 2);
+        sink.send("a", b"1").expect("send");
+        sink.send("b", b"not a number").expect("queued");
+        sink.shutdown().expect("shutdown");
+
+        assert_eq!(sink.failed_count(), 1);
+        assert_eq!(sink.inner().seen.lock().expect("lock").len(), 1);
+        assert_eq!(sink.worker_count(), 0);
+        assert!(matches!(
+            sink.send("a", b"2"),
+            Err(TelemetryError::Connection(_))
+        ));
+        sink.flush().expect("flush after shutdown");
+    }
+}
@@ -0,0 +1,741 @@
+//! OpenTelemetry (OTLP) export for telemetry data.
+//!
+//! Each message becomes an OTLP log record or a gauge data point, exported
+//! to a collector with protobuf encoding over OTLP/HTTP or OTLP/gRPC.
+//!
+//! **Why not `opentelemetry-otlp`?** Its exporters sit behind the
+//! OpenTelemetry SDK's logger and meter providers, which batch in the
+//! background and hand export failures to a global error handler. A sink
+//! has to return each export's outcome (backpressure, rejected records) to
+//! its caller, so this one builds the same `opentelemetry-proto` requests
+//! the exporter would and sends them itself.
+//!
+//! **Why feature-gated?** The OTLP message types and the HTTP and gRPC
+//! clients are only needed by deployments that feed an OpenTelemetry
+//! collector. Enable with `features = ["otlp"]` in Cargo.toml.
+
+use crate::source::decode_message;
+use crate::{TelemetryError, TelemetryMessage, TelemetryRecord, TelemetryResult, TelemetrySink};
+use opentelemetry_proto::tonic::collector::logs::v1::logs_service_client::LogsServiceClient;
+use opentelemetry_proto::tonic::collector::logs::v1::{
+    ExportLogsServiceRequest, ExportLogsServiceResponse,
+};
+use opentelemetry_proto::tonic::collector::metrics::v1::metrics_service_client::MetricsServiceClient;
+use opentelemetry_proto::tonic::collector::metrics::v1::{
+    ExportMetricsServiceRequest, ExportMetricsServiceResponse,
+};
+use opentelemetry_proto::tonic::common::v1::{
+    any_value, AnyValue, ArrayValue, InstrumentationScope, KeyValue, KeyValueList,
+};
+use opentelemetry_proto::tonic::logs::v1::{LogRecord, ResourceLogs, ScopeLogs};
+use opentelemetry_proto::tonic::metrics::v1::{
+    metric, number_data_point, Gauge, Metric, NumberDataPoint, ResourceMetrics, ScopeMetrics,
+};
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use prost::Message;
+use serde_json::Value;
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::runtime::Runtime;
+use tonic::metadata::MetadataMap;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Status};
+
+/// Instrumentation scope used for exported metrics.
+pub const METRICS_SCOPE: &str = "room619.telemetry";
+
+/// What each message is exported as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OtlpSignal {
+    /// A log record whose instrumentation scope is the topic. The payload is
+    /// the body and its top-level fields are attributes.
+    #[default]
+    Logs,
+    /// A gauge named after the topic. The value is the payload itself or its
+    /// `value` field; the other top-level fields are attributes.
+    Metrics,
+}
+
+/// How exports reach the collector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OtlpProtocol {
+    /// `POST {endpoint}/v1/logs` or `/v1/metrics`, conventionally on port 4318.
+    #[default]
+    HttpProtobuf,
+    /// The `LogsService` or `MetricsService` `Export` RPC, conventionally on
+    /// port 4317.
+    Grpc,
+}
+
+/// Configuration for `OtlpSink`.
+#[derive(Debug, Clone)]
+pub struct OtlpSinkConfig {
+    /// Collector base URL, e.g. `http://localhost:4318`.
+    pub endpoint: String,
+    pub signal: OtlpSignal,
+    pub protocol: OtlpProtocol,
+    /// Per-request timeout (connect + transfer).
+    pub timeout: Duration,
+    /// Resource attributes attached to every export, e.g. `service.name`.
+    pub resource: BTreeMap<String, String>,
+    /// Extra headers sent with every request (e.g. an auth token).
+    pub headers: Vec<(String, String)>,
+}
+
+impl OtlpSinkConfig {
+    /// Export logs to `endpoint` with a 5s timeout and
+    /// `service.name = "unknown_service"`, the OpenTelemetry default.
+    pub fn new(endpoint: impl AsRef<str>) -> Self {
+        Self {
+            endpoint: endpoint.as_ref().trim_end_matches('/').to_string(),
+            signal: OtlpSignal::default(),
+            protocol: OtlpProtocol::default(),
+            timeout: Duration::from_secs(5),
+            resource: BTreeMap::from([("service.name".to_string(), "unknown_service".to_string())]),
+            headers: Vec::new(),
+        }
+    }
+
+    /// Choose between log records and metrics.
+    pub fn signal(mut self, signal: OtlpSignal) -> Self {
+        self.signal = signal;
+        self
+    }
+
+    /// Choose between OTLP/HTTP and OTLP/gRPC.
+    pub fn protocol(mut self, protocol: OtlpProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Set the per-request timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the `service.name` resource attribute.
+    pub fn service_name(self, name: impl Into<String>) -> Self {
+        self.resource_attribute("service.name", name)
+    }
+
+    /// Set a resource attribute.
+    pub fn resource_attribute(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.resource.insert(key.into(), value.into());
+        self
+    }
+
+    /// Add a header (gRPC metadata) sent with every request.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// gRPC client state; requests are driven on a private runtime.
+struct GrpcExporter {
+    runtime: Runtime,
+    channel: Channel,
+    metadata: MetadataMap,
+}
+
+impl GrpcExporter {
+    fn request<T>(&self, message: T) -> tonic::Request<T> {
+        let mut request = tonic::Request::new(message);
+        *request.metadata_mut() = self.metadata.clone();
+        request
+    }
+}
+
+enum Transport {
+    Http(reqwest::blocking::Client),
+    Grpc(Box<GrpcExporter>),
+}
+
+/// A sink exporting messages to an OpenTelemetry collector over OTLP/HTTP
+/// or OTLP/gRPC.
+///
+/// `send_batch` exports all records in one request. A collector applying
+/// backpressure (HTTP 429, 502, 503 or 504, or gRPC `RESOURCE_EXHAUSTED`,
+/// `UNAVAILABLE`, `ABORTED` or `DEADLINE_EXCEEDED`) yields
+/// `TelemetryError::RateLimited`, which OTLP defines as retryable; records
+/// the collector rejects are reported as `TelemetryError::Transport`.
+///
+/// With gRPC the sink drives tonic on a private runtime, so it must not be
+/// used from inside an async context.
+pub struct OtlpSink {
+    transport: Transport,
+    config: OtlpSinkConfig,
+}
+
+impl OtlpSink {
+    /// Export logs to `endpoint` with default settings.
+    pub fn new(endpoint: impl AsRef<str>) -> TelemetryResult<Self> {
+        Self::with_config(OtlpSinkConfig::new(endpoint))
+    }
+
+    /// Create a sink from an explicit configuration.
+    ///
+    /// Returns an error if a header name or value is invalid or the HTTP
+    /// or gRPC client cannot be initialised. A gRPC connection is made by
+    /// the first export.
+    pub fn with_config(config: OtlpSinkConfig) -> TelemetryResult<Self> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in &config.headers {
+            let name = reqwest::header::HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                TelemetryError::new(format!("invalid header name '{}': {}", name, e))
+            })?;
+            let value = reqwest::header::HeaderValue::from_str(value)
+                .map_err(|e| TelemetryError::new(format!("invalid header value: {}", e)))?;
+            headers.insert(name, value);
+        }
+        let transport = match config.protocol {
+            OtlpProtocol::HttpProtobuf => Transport::Http(
+                reqwest::blocking::Client::builder()
+                    .timeout(config.timeout)
+                    .default_headers(headers)
+                    .build()
+                    .map_err(|e| {
+                        TelemetryError::Connection(format!("HTTP client init failed: {}", e))
+                    })?,
+            ),
+            OtlpProtocol::Grpc => {
+                let endpoint = Endpoint::from_shared(config.endpoint.clone())
+                    .map_err(|e| {
+                        TelemetryError::Connection(format!("invalid OTLP endpoint: {}", e))
+                    })?
+                    .connect_timeout(config.timeout)
+                    .timeout(config.timeout);
+                let runtime = tokio::runtime::Builder::new_multi_thread()
+                    .worker_threads(1)
+                    .enable_all()
+                    .build()
+                    .map_err(|e| TelemetryError::new(format!("gRPC runtime: {}", e)))?;
+                // The lazy channel spawns its worker onto the current runtime
+                let channel = {
+                    let _entered = runtime.enter();
+                    endpoint.connect_lazy()
+                };
+                Transport::Grpc(Box::new(GrpcExporter {
+                    runtime,
+                    channel,
+                    metadata: MetadataMap::from_headers(headers),
+                }))
+            }
+        };
+        Ok(Self { transport, config })
+    }
+
+    /// Active configuration.
+    pub fn config(&self) -> &OtlpSinkConfig {
+        &self.config
+    }
+
+    /// URL exports are posted to; with gRPC, the collector endpoint.
+    pub fn url(&self) -> String {
+        if self.config.protocol == OtlpProtocol::Grpc {
+            return self.config.endpoint.clone();
+        }
+        let path = match self.config.signal {
+            OtlpSignal::Logs => "v1/logs",
+            OtlpSignal::Metrics => "v1/metrics",
+        };
+        format!("{}/{}", self.config.endpoint, path)
+    }
+
+    fn resource(&self) -> Resource {
+        Resource {
+            attributes: self
+                .config
+                .resource
+                .iter()
+                .map(|(key, value)| key_value(key, Value::String(value.clone())))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn export(&self, records: &[TelemetryRecord]) -> TelemetryResult<()> {
+        let messages = records
+            .iter()
+            .map(|(topic, payload)| decode_message(topic, payload));
+        let (rejected, message) = match self.config.signal {
+            OtlpSignal::Logs => {
+                let request = ExportLogsServiceRequest {
+                    resource_logs: vec![ResourceLogs {
+                        resource: Some(self.resource()),
+                        scope_logs: messages.map(log_scope).collect(),
+                        ..Default::default()
+                    }],
+                };
+                let response: ExportLogsServiceResponse = match &self.transport {
+                    Transport::Http(client) => self.post(client, request.encode_to_vec())?,
+                    Transport::Grpc(grpc) => self.call(grpc.runtime.block_on(
+                        LogsServiceClient::new(grpc.channel.clone()).export(grpc.request(request)),
+                    ))?,
+                };
+                response
+                    .partial_success
+                    .map(|p| (p.rejected_log_records, p.error_message))
+            }
+            OtlpSignal::Metrics => {
+                let request = ExportMetricsServiceRequest {
+                    resource_metrics: vec![ResourceMetrics {
+                        resource: Some(self.resource()),
+                        scope_metrics: vec![ScopeMetrics {
+                            scope: Some(scope(METRICS_SCOPE)),
+                            metrics: messages.map(gauge).collect::<TelemetryResult<_>>()?,
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    }],
+                };
+                let response: ExportMetricsServiceResponse = match &self.transport {
+                    Transport::Http(client) => self.post(client, request.encode_to_vec())?,
+                    Transport::Grpc(grpc) => self.call(
+                        grpc.runtime.block_on(
+                            MetricsServiceClient::new(grpc.channel.clone())
+                                .export(grpc.request(request)),
+                        ),
+                    )?,
+                };
+                response
+                    .partial_success
+                    .map(|p| (p.rejected_data_points, p.error_message))
+            }
+        }
+        .unwrap_or_default();
+        if rejected > 0 {
+            return Err(TelemetryError::Transport(format!(
+                "OTLP collector rejected {} record(s): {}",
+                rejected, message
+            )));
+        }
+        Ok(())
+    }
+
+    fn post<R: Message + Default>(
+        &self,
+        client: &reqwest::blocking::Client,
+        body: Vec<u8>,
+    ) -> TelemetryResult<R> {
+        let url = self.url();
+        let response = client
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, "application/x-protobuf")
+            .body(body)
+            .send()
+            .map_err(|e| {
+                TelemetryError::Connection(format!("OTLP export to {} failed: {}", url, e))
+            })?;
+        let status = response.status().as_u16();
+        if matches!(status, 429 | 502 | 503 | 504) {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| format!(", retry after {}s", v))
+                .unwrap_or_default();
+            return Err(TelemetryError::RateLimited(format!(
+                "OTLP collector {} returned HTTP {}{}",
+                url, status, retry_after
+            )));
+        }
+        if !response.status().is_success() {
+            return Err(TelemetryError::Transport(format!(
+                "OTLP collector {} returned HTTP {}",
+                url, status
+            )));
+        }
+        let body = response
+            .bytes()
+            .map_err(|e| TelemetryError::Connection(format!("OTLP response read failed: {}", e)))?;
+        Ok(R::decode(body.as_ref()).unwrap_or_default())
+    }
+
+    fn call<R>(&self, result: Result<tonic::Response<R>, Status>) -> TelemetryResult<R> {
+        result.map(tonic::Response::into_inner).map_err(|status| {
+            let message = format!(
+                "OTLP collector {} returned gRPC {:?}: {}",
+                self.config.endpoint,
+                status.code(),
+                status.message()
+            );
+            match status.code() {
+                Code::ResourceExhausted
+                | Code::Unavailable
+                | Code::Aborted
+                | Code::DeadlineExceeded => TelemetryError::RateLimited(message),
+                _ => TelemetryError::Transport(message),
+            }
+        })
+    }
+}
+
+impl TelemetrySink for OtlpSink {
+    fn sink_name(&self) -> &'static str {
+        "otlp"
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        self.export(&[(topic.to_string(), payload.to_vec())])
+    }
+
+    fn send_batch(&self, records: &[TelemetryRecord]) -> TelemetryResult<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        self.export(records)
+    }
+}
+
+fn scope(name: &str) -> InstrumentationScope {
+    InstrumentationScope {
+        name: name.to_string(),
+        ..Default::default()
+    }
+}
+
+/// Message timestamp, or now, in ns since the Unix epoch.
+fn time_unix_nano(msg: &TelemetryMessage) -> u64 {
+    match msg.timestamp {
+        Some(ms) => u64::try_from(ms).unwrap_or(0).saturating_mul(1_000_000),
+        None => now_unix_nano(),
+    }
+}
+
+fn now_unix_nano() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| u64::try_from(d.as_nanos()).unwrap_or(u64::MAX))
+}
+
+fn log_scope(msg: TelemetryMessage) -> ScopeLogs {
+    let attributes = match &msg.payload {
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(key, value)| key_value(key, value.clone()))
+            .collect(),
+        _ => Vec::new(),
+    };
+    ScopeLogs {
+        scope: Some(scope(&msg.topic)),
+        log_records: vec![LogRecord {
+            time_unix_nano: time_unix_nano(&msg),
+            observed_time_unix_nano: now_unix_nano(),
+            body: Some(any_value(Value::String(msg.payload.to_string()))),
+            attributes,
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+fn gauge(msg: TelemetryMessage) -> TelemetryResult<Metric> {
+    let (value, attributes) = match &msg.payload {
+        Value::Object(fields) => (
+            fields.get("value"),
+            fields
+                .iter()
+                .filter(|(key, _)| *key != "value")
+                .map(|(key, value)| key_value(key, value.clone()))
+                .collect(),
+        ),
+        other => (Some(other), Vec::new()),
+    };
+    let value = match value {
+        Some(Value::Number(n)) => match n.as_i64() {
+            Some(i) => number_data_point::Value::AsInt(i),
+            None => number_data_point::Value::AsDouble(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        _ => {
+            return Err(TelemetryError::Serialization(format!(
+                "topic '{}': metric payload needs a numeric value",
+                msg.topic
+            )))
+        }
+    };
+    Ok(Metric {
+        name: msg.topic.clone(),
+        data: Some(metric::Data::Gauge(Gauge {
+            data_points: vec![NumberDataPoint {
+                attributes,
+                time_unix_nano: time_unix_nano(&msg),
+                value: Some(value),
+                ..Default::default()
+            }],
+        })),
+        ..Default::default()
+    })
+}
+
+fn key_value(key: &str, value: Value) -> KeyValue {
+    KeyValue {
+        key: key.to_string(),
+        value: Some(any_value(value)),
+    }
+}
+
+fn any_value(value: Value) -> AnyValue {
+    let value = match value {
+        Value::Null => None,
+        Value::Bool(b) => Some(any_value::Value::BoolValue(b)),
+        Value::Number(n) => Some(match n.as_i64() {
+            Some(i) => any_value::Value::IntValue(i),
+            None => any_value::Value::DoubleValue(n.as_f64().unwrap_or(f64::NAN)),
+        }),
+        Value::String(s) => Some(any_value::Value::StringValue(s)),
+        Value::Array(items) => Some(any_value::Value::ArrayValue(ArrayValue {
+            values: items.into_iter().map(any_value).collect(),
+        })),
+        Value::Object(fields) => Some(any_value::Value::KvlistValue(KeyValueList {
+            values: fields
+                .into_iter()
+                .map(|(key, value)| key_value(&key, value))
+                .collect(),
+        })),
+    };
+    AnyValue { value }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::mock_server;
+    use opentelemetry_proto::tonic::collector::logs::v1::logs_service_server::{
+        LogsService, LogsServiceServer,
+    };
+    use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsPartialSuccess;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use tokio_stream::wrappers::TcpListenerStream;
+
+    fn attribute<'a>(attributes: &'a [KeyValue], key: &str) -> Option<&'a any_value::Value> {
+        attributes
+            .iter()
+            .find(|kv| kv.key == key)
+            .and_then(|kv| kv.value.as_ref())
+            .and_then(|v| v.value.as_ref())
+    }
+
+    #[test]
+    fn exports_log_record_with_scope_and_attributes() {
+        let (endpoint, collector) = mock_server(200);
+        let sink = OtlpSink::with_config(OtlpSinkConfig::new(&endpoint).service_name("gateway"))
+            .expect("sink");
+        let msg = TelemetryMessage::builder()
+            .topic("sensors/temp")
+            .payload(json!({"room": "lab", "celsius": 21.5}))
+            .timestamp(1_700_000_000_000)
+            .build()
+            .expect("message");
+
+        sink.send(&msg.topic, msg.to_json().as_bytes())
+            .expect("send");
+
+        let (request_line, _, body) = collector.join().expect("collector");
+        assert_eq!(request_line, "POST /v1/logs HTTP/1.1");
+        let request = ExportLogsServiceRequest::decode(body.as_slice()).expect("decode");
+        let resource_logs = &request.resource_logs[0];
+        let resource = resource_logs.resource.as_ref().expect("resource");
+        assert_eq!(
+            attribute(&resource.attributes, "service.name"),
+            Some(&any_value::Value::StringValue("gateway".into()))
+        );
+        let scope_logs = &resource_logs.scope_logs[0];
+        assert_eq!(
+            scope_logs.scope.as_ref().expect("scope").name,
+            "sensors/temp"
+        );
+        let record = &scope_logs.log_records[0];
+        assert_eq!(record.time_unix_nano, 1_700_000_000_000_000_000);
+        assert_eq!(
+            attribute(&record.attributes, "room"),
+            Some(&any_value::Value::StringValue("lab".into()))
+        );
+        assert_eq!(
+            attribute(&record.attributes, "celsius"),
+            Some(&any_value::Value::DoubleValue(21.5))
+        );
+    }
+
+    #[test]
+    fn exports_gauge_named_after_topic() {
+        let (endpoint, collector) = mock_server(200);
+        let sink =
+            OtlpSink::with_config(OtlpSinkConfig::new(&endpoint).signal(OtlpSignal::Metrics))
+                .expect("sink");
+
+        sink.send("pump/rpm", br#"{"value": 1200, "unit": "rpm"}"#)
+            .expect("send");
+
+        let (request_line, _, body) = collector.join().expect("collector");
+        assert_eq!(request_line, "POST /v1/metrics HTTP/1.1");
+        let request = ExportMetricsServiceRequest::decode(body.as_slice()).expect("decode");
+        let metric = &request.resource_metrics[0].scope_metrics[0].metrics[0];
+        assert_eq!(metric.name, "pump/rpm");
+        let Some(metric::Data::Gauge(gauge)) = &metric.data else {
+            panic!("expected gauge, got {:?}", metric.data);
+        };
+        let point = &gauge.data_points[0];
+        assert_eq!(point.value, Some(number_data_point::Value::AsInt(1200)));
+        assert_eq!(
+            attribute(&point.attributes, "unit"),
+            Some(&any_value::Value::StringValue("rpm".into()))
+        );
+        assert_eq!(attribute(&point.attributes, "value"), None);
+    }
+
+    #[test]
+    fn collector_backpressure_maps_to_rate_limited() {
+        let (endpoint, collector) = mock_server(429);
+        let sink = OtlpSink::new(&endpoint).expect("sink");
+
+        let err = sink.send("t", b"{}").expect_err("429 should fail");
+        collector.join().expect("collector");
+        assert!(matches!(err, TelemetryError::RateLimited(_)), "{:?}", err);
+    }
+
+    #[test]
+    fn non_numeric_metric_is_rejected_before_export() {
+        let sink =
+            OtlpSink::with_config(OtlpSinkConfig::new("http://unused").signal(OtlpSignal::Metrics))
+                .expect("sink");
+        let err = sink.send("t", br#"{"state": "on"}"#).expect_err("no value");
+        assert!(matches!(err, TelemetryError::Serialization(_)), "{:?}", err);
+    }
+
+    /// An export as seen by the gRPC collector, with its `authorization`.
+    type Export = (ExportLogsServiceRequest, Option<String>);
+
+    /// Logs collector capturing each export, answering with `status` or
+    /// rejecting `reject` records.
+    #[derive(Clone, Default)]
+    struct GrpcCollector {
+        received: Arc<Mutex<Vec<Export>>>,
+        status: Option<Code>,
+        reject: i64,
+    }
+
+    #[tonic::async_trait]
+    impl LogsService for GrpcCollector {
+        async fn export(
+            &self,
+            request: tonic::Request<ExportLogsServiceRequest>,
+        ) -> Result<tonic::Response<ExportLogsServiceResponse>, Status> {
+            let auth = request
+                .metadata()
+                .get("authorization")
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+            self.received
+                .lock()
+                .expect("lock")
+                .push((request.into_inner(), auth));
+            if let Some(code) = self.status {
+                return Err(Status::new(code, "busy"));
+            }
+            let partial_success = (self.reject > 0).then(|| ExportLogsPartialSuccess {
+                rejected_log_records: self.reject,
+                error_message: "bad record".into(),
+            });
+            Ok(tonic::Response::new(ExportLogsServiceResponse {
+                partial_success,
+            }))
+        }
+    }
+
+    fn serve_grpc(collector: GrpcCollector) -> (Runtime, String) {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .expect("runtime");
+        let listener = runtime
+            .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+            .expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        runtime.spawn(
+            tonic::transport::Server::builder()
+                .add_service(LogsServiceServer::new(collector))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        (runtime, format!("http://{}", addr))
+    }
+
+    #[test]
+    fn exports_log_record_over_grpc() {
+        let collector = GrpcCollector::default();
+        let (_server, endpoint) = serve_grpc(collector.clone());
+        let sink = OtlpSink::with_config(
+            OtlpSinkConfig::new(&endpoint)
+                .protocol(OtlpProtocol::Grpc)
+                .service_name("gateway")
+                .header("Authorization", "Bearer t0k3n"),
+        )
+        .expect("sink");
+        assert_eq!(sink.url(), endpoint);
+
+        sink.send("sensors/temp", br#"{"room": "lab"}"#)
+            .expect("send");
+
+        let received = collector.received.lock().expect("lock");
+        let (request, auth) = &received[0];
+        assert_eq!(auth.as_deref(), Some("Bearer t0k3n"));
+        let resource_logs = &request.resource_logs[0];
+        assert_eq!(
+            attribute(
+                &resource_logs
+                    .resource
+                    .as_ref()
+                    .expect("resource")
+                    .attributes,
+                "service.name"
+            ),
+            Some(&any_value::Value::StringValue("gateway".into()))
+        );
+        let scope_logs = &resource_logs.scope_logs[0];
+        assert_eq!(
+            scope_logs.scope.as_ref().expect("scope").name,
+            "sensors/temp"
+        );
+        assert_eq!(
+            attribute(&scope_logs.log_records[0].attributes, "room"),
+            Some(&any_value::Value::StringValue("lab".into()))
+        );
+    }
+
+    #[test]
+    fn grpc_backpressure_and_rejections_map_to_errors() {
+        let busy = GrpcCollector {
+            status: Some(Code::Unavailable),
+            ..GrpcCollector::default()
+        };
+        let (_server, endpoint) = serve_grpc(busy);
+        let sink =
+            OtlpSink::with_config(OtlpSinkConfig::new(&endpoint).protocol(OtlpProtocol::Grpc))
+                .expect("sink");
+        let err = sink.send("t", b"{}").expect_err("unavailable");
+        assert!(matches!(err, TelemetryError::RateLimited(_)), "{:?}", err);
+
+        let picky = GrpcCollector {
+            reject: 1,
+            ..GrpcCollector::default()
+        };
+        let (_server, endpoint) = serve_grpc(picky);
+        let sink =
+            OtlpSink::with_config(OtlpSinkConfig::new(&endpoint).protocol(OtlpProtocol::Grpc))
+                .expect("sink");
+        let err = sink.send("t", b"{}").expect_err("rejected");
+        assert!(
+            matches!(&err, TelemetryError::Transport(m) if m.contains("bad record")),
+            "{:?}",
+            err
+        );
+    }
+}
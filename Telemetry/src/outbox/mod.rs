@@ -0,0 +1,400 @@
+//! Persistent at-least-once outbox.
+//!
+//! `OutboxSink` writes every message to an append-only log before handing it
+//! to the inner sink and records an acknowledgement once delivery succeeds.
+//! Messages still unacknowledged when the process dies are redelivered the
+//! next time the outbox is opened.
+//!
+//! **Why at-least-once?** A crash between delivery and the ack record makes
+//! the message look undelivered, so it is sent again. Entry ids are local to
+//! the outbox and are not sent downstream; consumers that cannot tolerate
+//! duplicates need an id of their own in the payload.
+//!
+//! The log is JSON lines of `{"op":"put",...}` / `{"op":"ack","id":..}`
+//! records. Lines that fail to parse (e.g. a torn write) are skipped, and the
+//! log is compacted down to the pending entries on every open. Compaction
+//! starts the log with a `{"op":"seq","next":..}` record so ids are never
+//! reused, even once every entry has been acknowledged.
+
+use crate::{layered_sink_name, TelemetryError, TelemetryResult, TelemetrySink};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// A message that has been logged but not yet acknowledged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboxEntry {
+    pub id: u64,
+    pub topic: String,
+    pub payload: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum LogRecord {
+    Put {
+        id: u64,
+        topic: String,
+        payload: String,
+    },
+    Ack {
+        id: u64,
+    },
+    /// Lowest id not yet handed out.
+    Seq {
+        next: u64,
+    },
+}
+
+impl LogRecord {
+    fn put(entry: &OutboxEntry) -> Self {
+        LogRecord::Put {
+            id: entry.id,
+            topic: entry.topic.clone(),
+            payload: BASE64.encode(&entry.payload),
+        }
+    }
+}
+
+fn io_error(context: &str, e: std::io::Error) -> TelemetryError {
+    TelemetryError::Transport(format!("outbox {}: {}", context, e))
+}
+
+struct OutboxState {
+    log: File,
+    pending: BTreeMap<u64, OutboxEntry>,
+    next_id: u64,
+}
+
+impl OutboxState {
+    /// Append `record` and force it to disk before returning.
+    fn append(&mut self, record: &LogRecord) -> TelemetryResult<()> {
+        let mut line = serde_json::to_vec(record)
+            .map_err(|e| TelemetryError::Serialization(format!("outbox record: {}", e)))?;
+        line.push(b'\n');
+        self.log
+            .write_all(&line)
+            .and_then(|()| self.log.sync_data())
+            .map_err(|e| io_error("write", e))
+    }
+
+    /// Try to deliver `id`; record the ack and forget it on success.
+    fn deliver<S: TelemetrySink>(&mut self, inner: &S, id: u64) -> TelemetryResult<()> {
+        let Some(entry) = self.pending.get(&id) else {
+            return Ok(());
+        };
+        inner.send(&entry.topic, &entry.payload)?;
+        self.append(&LogRecord::Ack { id })?;
+        self.pending.remove(&id);
+        Ok(())
+    }
+}
+
+/// A sink that durably logs messages and redelivers unacknowledged ones.
+pub struct OutboxSink<S: TelemetrySink> {
+    inner: S,
+    path: PathBuf,
+    state: Mutex<OutboxState>,
+}
+
+impl<S: TelemetrySink> OutboxSink<S> {
+    /// Open (or create) the outbox log at `path` and redeliver any entries
+    /// left unacknowledged by a previous run.
+    ///
+    /// Entries that still fail to deliver stay pending; see `pending`.
+    pub fn open(inner: S, path: impl AsRef<Path>) -> TelemetryResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let (pending, next_id) = load(&path)?;
+        let log = compact(&path, &pending, next_id)?;
+        let sink = Self {
+            inner,
+            path,
+            state: Mutex::new(OutboxState {
+                log,
+                pending,
+                next_id,
+            }),
+        };
+        if let Err(e) = sink.redeliver() {
+            log::warn!("outbox redelivery incomplete: {}", e);
+        }
+        Ok(sink)
+    }
+
+    /// Entries logged but not yet acknowledged, oldest first.
+    pub fn pending(&self) -> TelemetryResult<Vec<OutboxEntry>> {
+        Ok(self.lock()?.pending.values().cloned().collect())
+    }
+
+    /// Location of the outbox log.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Retry every pending entry in order, stopping at the first failure so
+    /// delivery order is preserved.
+    pub fn redeliver(&self) -> TelemetryResult<()> {
+        let mut state = self.lock()?;
+        let ids: Vec<u64> = state.pending.keys().copied().collect();
+        for id in ids {
+            state.deliver(&self.inner, id)?;
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> TelemetryResult<MutexGuard<'_, OutboxState>> {
+        self.state
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))
+    }
+}
+
+/// Read the log, returning unacknowledged entries and the next free id.
+fn load(path: &Path) -> TelemetryResult<(BTreeMap<u64, OutboxEntry>, u64)> {
+    let mut pending = BTreeMap::new();
+    let mut next_id = 0;
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((pending, next_id)),
+        Err(e) => return Err(io_error("open", e)),
+    };
+    let mut reader = BufReader::new(file);
+    let mut line = Vec::new();
+    let mut line_no = 0;
+    loop {
+        line.clear();
+        if reader
+            .read_until(b'\n', &mut line)
+            .map_err(|e| io_error("read", e))?
+            == 0
+        {
+            break;
+        }
+        line_no += 1;
+        match serde_json::from_slice::<LogRecord>(&line) {
+            Ok(LogRecord::Put { id, topic, payload }) => match BASE64.decode(payload) {
+                Ok(payload) => {
+                    next_id = next_id.max(id + 1);
+                    pending.insert(id, OutboxEntry { id, topic, payload });
+                }
+                Err(e) => log::warn!("outbox line {}: bad payload, skipped: {}", line_no, e),
+            },
+            Ok(LogRecord::Ack { id }) => {
+                next_id = next_id.max(id + 1);
+                pending.remove(&id);
+            }
+            Ok(LogRecord::Seq { next }) => next_id = next_id.max(next),
+            Err(e) => log::warn!("outbox line {}: corrupt record, skipped: {}", line_no, e),
+        }
+    }
+    Ok((pending, next_id))
+}
+
+/// Rewrite the log to hold only `next_id` and `pending`, and reopen it for
+/// appending.
+///
+/// Writing to a temporary file and renaming keeps the old log intact if the
+/// process dies mid-compaction.
+fn compact(
+    path: &Path,
+    pending: &BTreeMap<u64, OutboxEntry>,
+    next_id: u64,
+) -> TelemetryResult<File> {
+    let tmp = path.with_extension("compact");
+    {
+        let mut file = File::create(&tmp).map_err(|e| io_error("compact", e))?;
+        let seq = LogRecord::Seq { next: next_id };
+        for record in std::iter::once(seq).chain(pending.values().map(LogRecord::put)) {
+            let mut line = serde_json::to_vec(&record)
+                .map_err(|e| TelemetryError::Serialization(format!("outbox record: {}", e)))?;
+            line.push(b'\n');
+            file.write_all(&line).map_err(|e| io_error("compact", e))?;
+        }
+        file.sync_all().map_err(|e| io_error("compact", e))?;
+    }
+    std::fs::rename(&tmp, path).map_err(|e| io_error("compact", e))?;
+    OpenOptions::new()
+        .append(true)
+        .open(path)
+        .map_err(|e| io_error("open", e))
+}
+
+impl<S: TelemetrySink> TelemetrySink for OutboxSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("outbox", &[self.inner.sink_name()])
+    }
+
+    /// Log the message, then deliver it.
+    ///
+    /// If delivery fails the error is returned but the message stays in the
+    /// outbox for `redeliver`, `flush` or the next `open`.
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let mut state = self.lock()?;
+        let entry = OutboxEntry {
+            id: state.next_id,
+            topic: topic.to_string(),
+            payload: payload.to_vec(),
+        };
+        state.append(&LogRecord::put(&entry))?;
+        state.next_id += 1;
+        let id = entry.id;
+        state.pending.insert(id, entry);
+        state.deliver(&self.inner, id)
+    }
+
+    /// Redeliver pending entries, then flush the inner sink.
+    fn flush(&self) -> TelemetryResult<()> {
+        self.redeliver()?;
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemorySink;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn outbox_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "telemetry-outbox-{}-{}.log",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    /// Records payloads while `up`, fails otherwise.
+    struct FlakySink {
+        up: AtomicBool,
+        memory: InMemorySink,
+    }
+
+    impl FlakySink {
+        fn new(up: bool) -> Self {
+            Self {
+                up: AtomicBool::new(up),
+                memory: InMemorySink::new(),
+            }
+        }
+    }
+
+    impl TelemetrySink for FlakySink {
+        fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+            if !self.up.load(Ordering::SeqCst) {
+                return Err(TelemetryError::Connection("down".into()));
+            }
+            self.memory.send(topic, payload)
+        }
+    }
+
+    fn topics(sink: &InMemorySink) -> Vec<String> {
+        sink.records
+            .lock()
+            .expect("lock")
+            .iter()
+            .map(|(t, _)| t.clone())
+            .collect()
+    }
+
+    #[test]
+    fn unacked_messages_are_replayed_after_crash() {
+        let path = outbox_path("crash");
+        {
+            let outbox = OutboxSink::open(FlakySink::new(true), &path).expect("open");
+            outbox.send("a", b"1").expect("delivered");
+            outbox.inner().up.store(false, Ordering::SeqCst);
+            assert!(outbox.send("b", b"2").is_err());
+            assert!(outbox.send("c", b"3").is_err());
+
+            let pending = outbox.pending().expect("pending");
+            let ids: Vec<u64> = pending.iter().map(|e| e.id).collect();
+            assert_eq!(ids, vec![1, 2]);
+            // Dropped here without ever recovering: a simulated crash.
+        }
+
+        let outbox = OutboxSink::open(InMemorySink::new(), &path).expect("reopen");
+        assert_eq!(topics(outbox.inner()), vec!["b", "c"]);
+        assert!(outbox.pending().expect("pending").is_empty());
+
+        outbox.send("d", b"4").expect("send");
+        drop(outbox);
+        let outbox = OutboxSink::open(InMemorySink::new(), &path).expect("reopen");
+        assert!(topics(outbox.inner()).is_empty());
+        std::fs::remove_file(path).expect("cleanup");
+    }
+
+    #[test]
+    fn failed_redelivery_keeps_entries_pending() {
+        let path = outbox_path("pending");
+        {
+            let outbox = OutboxSink::open(FlakySink::new(false), &path).expect("open");
+            assert!(outbox.send("a", b"1").is_err());
+        }
+        let outbox = OutboxSink::open(FlakySink::new(false), &path).expect("reopen");
+        assert_eq!(outbox.pending().expect("pending").len(), 1);
+
+        outbox.inner().up.store(true, Ordering::SeqCst);
+        outbox.flush().expect("flush redelivers");
+        assert_eq!(topics(&outbox.inner().memory), vec!["a"]);
+        assert!(outbox.pending().expect("pending").is_empty());
+        std::fs::remove_file(path).expect("cleanup");
+    }
+
+    #[test]
+    fn ids_are_not_reused_after_everything_is_acked() {
+        let path = outbox_path("seq");
+        {
+            let outbox = OutboxSink::open(InMemorySink::new(), &path).expect("open");
+            outbox.send("a", b"1").expect("send");
+            outbox.send("b", b"2").expect("send");
+        }
+        // Reopen twice: the second compaction sees only the seq record
+        drop(OutboxSink::open(InMemorySink::new(), &path).expect("reopen"));
+        let outbox = OutboxSink::open(FlakySink::new(false), &path).expect("reopen");
+        assert!(outbox.send("c", b"3").is_err());
+
+        let pending = outbox.pending().expect("pending");
+        assert_eq!(pending.iter().map(|e| e.id).collect::<Vec<_>>(), vec![2]);
+        std::fs::remove_file(path).expect("cleanup");
+    }
+
+    #[test]
+    fn corrupt_records_are_skipped() {
+        let path = outbox_path("corrupt");
+        {
+            let outbox = OutboxSink::open(FlakySink::new(false), &path).expect("open");
+            assert!(outbox.send("a", b"1").is_err());
+        }
+        let mut log = OpenOptions::new().append(true).open(&path).expect("log");
+        log.write_all(b"{\"op\":\"put\",\"id\":\ngarbage\n")
+            .expect("corrupt");
+        log.write_all(b"{\"op\":\"put\",\"id\":7,\"topic\":\"b\",\"payload\":\"Mg==\"}\n")
+            .expect("append");
+        log.write_all(b"{\"op\":\"put\",\"id\":8,\"topic\":\"c\",\"pay")
+            .expect("torn write");
+        drop(log);
+
+        let outbox = OutboxSink::open(InMemorySink::new(), &path).expect("reopen");
+        assert_eq!(topics(outbox.inner()), vec!["a", "b"]);
+        outbox.send("d", b"4").expect("send");
+        let records = outbox.inner().records.lock().expect("lock").clone();
+        assert_eq!(records[2], ("d".to_string(), b"4".to_vec()));
+        std::fs::remove_file(path).expect("cleanup");
+    }
+}
@@ -0,0 +1,279 @@
+//! Pooled sink.
+//!
+//! Spreads sends from many threads over several instances of a connection
+//! oriented sink (HTTP, gRPC, MQTT, ...) so one connection does not become
+//! the bottleneck.
+//!
+//! **Why a factory?** The pool has to open replacement connections when one
+//! fails, so it needs to know how to build a sink, not just hold a few.
+
+use crate::{TelemetryError, TelemetryResult, TelemetrySink};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, TryLockError};
+
+/// A sink that leases one of up to `max_connections` inner sinks per send.
+///
+/// Sends start at the next slot in round-robin order and take the first
+/// idle slot from there, waiting on the starting slot only if all are busy.
+/// Slots are filled lazily by the factory. A sink whose send or flush fails
+/// with a `Transport` or `Connection` error is flushed one last time,
+/// discarded and rebuilt on the slot's next use; other errors (a rejected
+/// payload, a rate limit) leave the connection in the pool.
+pub struct PooledSink<S, F>
+where
+    S: TelemetrySink,
+    F: Fn() -> TelemetryResult<S> + Send + Sync,
+{
+    factory: F,
+    slots: Vec<Mutex<Option<S>>>,
+    next: AtomicUsize,
+}
+
+impl<S, F> PooledSink<S, F>
+where
+    S: TelemetrySink,
+    F: Fn() -> TelemetryResult<S> + Send + Sync,
+{
+    /// Create an empty pool of at most `max_connections` sinks (at least one).
+    pub fn new(max_connections: usize, factory: F) -> Self {
+        Self {
+            factory,
+            slots: (0..max_connections.max(1))
+                .map(|_| Mutex::new(None))
+                .collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Maximum number of pooled sinks.
+    pub fn max_connections(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Number of slots currently holding a live sink.
+    pub fn active_connections(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| match slot.try_lock() {
+                Ok(sink) => sink.is_some(),
+                // A leased slot holds a live sink
+                Err(TryLockError::WouldBlock) => true,
+                Err(TryLockError::Poisoned(_)) => false,
+            })
+            .count()
+    }
+
+    /// Lease a slot: the first idle one from the round-robin position, or
+    /// the round-robin slot itself once it frees up.
+    fn lease(&self) -> TelemetryResult<MutexGuard<'_, Option<S>>> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let len = self.slots.len();
+        for offset in 0..len {
+            match self.slots[(start + offset) % len].try_lock() {
+                Ok(guard) => return Ok(guard),
+                Err(TryLockError::WouldBlock) => continue,
+                Err(TryLockError::Poisoned(e)) => {
+                    return Err(TelemetryError::new(format!("lock poisoned: {}", e)))
+                }
+            }
+        }
+        self.slots[start % len]
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))
+    }
+}
+
+impl<S, F> TelemetrySink for PooledSink<S, F>
+where
+    S: TelemetrySink,
+    F: Fn() -> TelemetryResult<S> + Send + Sync,
+{
+    fn sink_name(&self) -> &'static str {
+        "pooled"
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let mut slot = self.lease()?;
+        let sink = match slot.take() {
+            Some(sink) => sink,
+            None => (self.factory)()?,
+        };
+        let result = sink.send(topic, payload);
+        match &result {
+            Err(e) if is_broken(e) => discard(sink),
+            _ => *slot = Some(sink),
+        }
+        result
+    }
+
+    /// Flush every live sink, discarding those whose connection failed.
+    fn flush(&self) -> TelemetryResult<()> {
+        let mut result = Ok(());
+        for slot in &self.slots {
+            let mut slot = slot
+                .lock()
+                .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+            if let Some(Err(e)) = slot.as_ref().map(TelemetrySink::flush) {
+                if is_broken(&e) {
+                    *slot = None;
+                }
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Whether `e` means the connection itself is unusable.
+fn is_broken(e: &TelemetryError) -> bool {
+    matches!(
+        e.head(),
+        TelemetryError::Transport(_) | TelemetryError::Connection(_)
+    )
+}
+
+/// Drop a broken sink after giving it a chance to deliver what it holds.
+fn discard<S: TelemetrySink>(sink: S) {
+    if let Err(e) = sink.flush() {
+        log::debug!("discarded pooled sink failed to flush: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// What the sinks built by `factory` have done.
+    #[derive(Default)]
+    struct Probe {
+        created: AtomicUsize,
+        sends: Mutex<Vec<usize>>,
+        flushes: Mutex<Vec<usize>>,
+    }
+
+    impl Probe {
+        fn created(&self) -> usize {
+            self.created.load(Ordering::SeqCst)
+        }
+
+        fn sends(&self) -> Vec<usize> {
+            self.sends.lock().expect("lock").clone()
+        }
+    }
+
+    /// Test sink that counts its sends and can be told to fail.
+    struct CountingSink {
+        id: usize,
+        probe: Arc<Probe>,
+        fail: Option<TelemetryError>,
+    }
+
+    impl TelemetrySink for CountingSink {
+        fn send(&self, _topic: &str, _payload: &[u8]) -> TelemetryResult<()> {
+            if let Some(e) = &self.fail {
+                return Err(e.clone());
+            }
+            self.probe.sends.lock().expect("lock").push(self.id);
+            Ok(())
+        }
+
+        fn flush(&self) -> TelemetryResult<()> {
+            self.probe.flushes.lock().expect("lock").push(self.id);
+            Ok(())
+        }
+    }
+
+    /// Factory whose `nth` created sink (0-based) fails every send with the
+    /// given error.
+    fn factory(
+        probe: Arc<Probe>,
+        failing: Option<(usize, TelemetryError)>,
+    ) -> impl Fn() -> TelemetryResult<CountingSink> + Send + Sync {
+        move || {
+            let id = probe.created.fetch_add(1, Ordering::SeqCst);
+            Ok(CountingSink {
+                id,
+                probe: Arc::clone(&probe),
+                fail: failing
+                    .as_ref()
+                    .filter(|(nth, _)| *nth == id)
+                    .map(|(_, e)| e.clone()),
+            })
+        }
+    }
+
+    #[test]
+    fn sends_are_spread_across_pool_members() {
+        let probe = Arc::new(Probe::default());
+        let pool = PooledSink::new(3, factory(Arc::clone(&probe), None));
+        assert_eq!(pool.active_connections(), 0);
+
+        for _ in 0..6 {
+            pool.send("t", b"x").expect("send");
+        }
+
+        assert_eq!(probe.created(), 3);
+        assert_eq!(pool.active_connections(), 3);
+        assert_eq!(probe.sends(), vec![0, 1, 2, 0, 1, 2]);
+    }
+
+    #[test]
+    fn failing_member_is_flushed_and_replaced() {
+        let probe = Arc::new(Probe::default());
+        let broken = TelemetryError::Connection("broken".into());
+        let pool = PooledSink::new(2, factory(Arc::clone(&probe), Some((1, broken))));
+
+        pool.send("t", b"x").expect("slot 0");
+        assert!(pool.send("t", b"x").is_err());
+        assert_eq!(pool.active_connections(), 1);
+        assert_eq!(*probe.flushes.lock().expect("lock"), vec![1]);
+
+        pool.send("t", b"x").expect("slot 0 again");
+        pool.send("t", b"x").expect("slot 1 rebuilt");
+
+        assert_eq!(probe.created(), 3);
+        assert_eq!(pool.active_connections(), 2);
+        assert_eq!(probe.sends(), vec![0, 0, 2]);
+    }
+
+    #[test]
+    fn payload_errors_keep_the_connection() {
+        let probe = Arc::new(Probe::default());
+        let rejected = TelemetryError::Serialization("bad payload".into());
+        let pool = PooledSink::new(1, factory(Arc::clone(&probe), Some((0, rejected))));
+
+        for _ in 0..3 {
+            assert!(pool.send("t", b"x").is_err());
+        }
+
+        assert_eq!(probe.created(), 1);
+        assert_eq!(pool.active_connections(), 1);
+        assert!(probe.flushes.lock().expect("lock").is_empty());
+    }
+
+    #[test]
+    fn concurrent_sends_use_idle_members() {
+        let probe = Arc::new(Probe::default());
+        let pool = Arc::new(PooledSink::new(4, factory(Arc::clone(&probe), None)));
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let pool = Arc::clone(&pool);
+                std::thread::spawn(move || {
+                    for _ in 0..50 {
+                        pool.send("t", b"x").expect("send");
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().expect("join");
+        }
+
+        assert_eq!(probe.sends().len(), 400);
+        assert!(probe.created() <= pool.max_connections());
+    }
+}
@@ -0,0 +1,318 @@
+//! Prometheus text exposition sink.
+//!
+//! Keeps the latest numeric readings in memory so a `/metrics` handler can
+//! serve them to a scraper with `render`, instead of pushing them anywhere.
+//!
+//! **Why a sink?** Code already reporting readings through a
+//! `TelemetryClient` becomes scrapeable by swapping (or fanning out to) this
+//! sink, without a second instrumentation API.
+
+use crate::{TelemetryError, TelemetryResult, TelemetrySink};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::sync::{Mutex, MutexGuard};
+
+/// How a topic's samples combine into its metric.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrometheusKind {
+    /// Each sample replaces the previous value.
+    #[default]
+    Gauge,
+    /// Each sample is a non-negative increment added to a running total.
+    /// Rendered with a `_total` suffix.
+    Counter,
+}
+
+impl PrometheusKind {
+    fn type_name(self) -> &'static str {
+        match self {
+            Self::Gauge => "gauge",
+            Self::Counter => "counter",
+        }
+    }
+}
+
+type Labels = Vec<(String, String)>;
+
+struct Family {
+    kind: PrometheusKind,
+    series: BTreeMap<Labels, f64>,
+}
+
+/// A sink that turns numeric JSON payloads into Prometheus metrics.
+///
+/// The topic names the metric, with characters Prometheus does not allow
+/// replaced by `_` (`sensors/temp` becomes `sensors_temp`). The payload is
+/// either a bare number or an object whose `value` field is the sample;
+/// the object's other scalar fields become labels, each label set being its
+/// own series. Any other payload is rejected with
+/// `TelemetryError::Serialization`.
+#[derive(Default)]
+pub struct PrometheusTextSink {
+    namespace: Option<String>,
+    kinds: HashMap<String, PrometheusKind>,
+    families: Mutex<BTreeMap<String, Family>>,
+}
+
+impl PrometheusTextSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Prefix every metric name with `namespace_`.
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Treat samples on `topic` as `kind`; topics without a hint are gauges.
+    pub fn with_kind(mut self, topic: impl Into<String>, kind: PrometheusKind) -> Self {
+        self.kinds.insert(topic.into(), kind);
+        self
+    }
+
+    /// Kind applied to samples on `topic`.
+    pub fn kind(&self, topic: &str) -> PrometheusKind {
+        self.kinds.get(topic).copied().unwrap_or_default()
+    }
+
+    /// Number of distinct metrics seen so far.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget every metric.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Current metrics in the text exposition format, sorted by name and
+    /// label set.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for (name, family) in self.lock().iter() {
+            // Writing to a String cannot fail
+            let _ = writeln!(out, "# TYPE {} {}", name, family.kind.type_name());
+            for (labels, value) in &family.series {
+                out.push_str(name);
+                if !labels.is_empty() {
+                    let labels: Vec<String> = labels
+                        .iter()
+                        .map(|(k, v)| format!("{}=\"{}\"", k, escape_label_value(v)))
+                        .collect();
+                    let _ = write!(out, "{{{}}}", labels.join(","));
+                }
+                let _ = writeln!(out, " {}", format_value(*value));
+            }
+        }
+        out
+    }
+
+    /// The mutex only guards plain data, so a poisoned lock is still usable.
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, Family>> {
+        self.families
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn metric_name(&self, topic: &str, kind: PrometheusKind) -> String {
+        let mut name = match &self.namespace {
+            Some(namespace) => sanitize(&format!("{}_{}", namespace, topic), true),
+            None => sanitize(topic, true),
+        };
+        if kind == PrometheusKind::Counter && !name.ends_with("_total") {
+            name.push_str("_total");
+        }
+        name
+    }
+}
+
+/// Replace characters outside `[a-zA-Z0-9_]` (plus `:` in metric names)
+/// with `_`, and prefix a leading digit with `_`.
+fn sanitize(raw: &str, allow_colon: bool) -> String {
+    let mut out: String = raw
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || (allow_colon && c == ':') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert(0, '_');
+    }
+    out
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Split a payload into its sample value and sorted labels.
+fn parse_sample(topic: &str, payload: &[u8]) -> TelemetryResult<(f64, Labels)> {
+    let not_numeric =
+        || TelemetryError::Serialization(format!("'{}' payload is not a numeric sample", topic));
+    let value: serde_json::Value = serde_json::from_slice(payload).map_err(|_| not_numeric())?;
+    if let Some(n) = value.as_f64() {
+        return Ok((n, Vec::new()));
+    }
+    let object = value.as_object().ok_or_else(not_numeric)?;
+    let sample = object
+        .get("value")
+        .and_then(|v| v.as_f64())
+        .ok_or_else(not_numeric)?;
+    let mut labels: Labels = object
+        .iter()
+        .filter(|(key, _)| key.as_str() != "value")
+        .filter_map(|(key, v)| {
+            let text = match v {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Number(n) => n.to_string(),
+                serde_json::Value::Bool(b) => b.to_string(),
+                _ => return None,
+            };
+            Some((sanitize(key, false), text))
+        })
+        .collect();
+    labels.sort();
+    Ok((sample, labels))
+}
+
+impl TelemetrySink for PrometheusTextSink {
+    fn sink_name(&self) -> &'static str {
+        "prometheus"
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let (value, labels) = parse_sample(topic, payload)?;
+        let kind = self.kind(topic);
+        if kind == PrometheusKind::Counter && (value.is_nan() || value < 0.0) {
+            return Err(TelemetryError::new(format!(
+                "counter '{}' increment must be non-negative, got {}",
+                topic, value
+            )));
+        }
+        let name = self.metric_name(topic, kind);
+        let mut families = self.lock();
+        let family = families.entry(name.clone()).or_insert_with(|| Family {
+            kind,
+            series: BTreeMap::new(),
+        });
+        if family.kind != kind {
+            return Err(TelemetryError::new(format!(
+                "metric '{}' is already a {}",
+                name,
+                family.kind.type_name()
+            )));
+        }
+        let series = family.series.entry(labels).or_insert(0.0);
+        match kind {
+            PrometheusKind::Gauge => *series = value,
+            PrometheusKind::Counter => *series += value,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TelemetryClient;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn renders_gauges_and_counters() {
+        let sink = Arc::new(
+            PrometheusTextSink::new()
+                .with_namespace("room619")
+                .with_kind("http/requests", PrometheusKind::Counter),
+        );
+        let client = TelemetryClient::new(sink.clone());
+
+        client
+            .send_typed("sensors/temp", &json!({"value": 21.5, "room": "lab"}))
+            .expect("send");
+        client
+            .send_typed("sensors/temp", &json!({"value": 22.0, "room": "lab"}))
+            .expect("send");
+        client
+            .send_typed("sensors/temp", &json!({"value": 18, "room": "hall"}))
+            .expect("send");
+        client
+            .send_typed("http/requests", &json!({"value": 3, "code": 200}))
+            .expect("send");
+        client
+            .send_typed("http/requests", &json!({"value": 2, "code": 200}))
+            .expect("send");
+        client.send_typed("uptime", &json!(42)).expect("send");
+
+        assert_eq!(sink.len(), 3);
+        assert_eq!(
+            sink.render(),
+            "# TYPE room619_http_requests_total counter\n\
+             room619_http_requests_total{code=\"200\"} 5\n\
+             # TYPE room619_sensors_temp gauge\n\
+             room619_sensors_temp{room=\"hall\"} 18\n\
+             room619_sensors_temp{room=\"lab\"} 22\n\
+             # TYPE room619_uptime gauge\n\
+             room619_uptime 42\n"
+        );
+    }
+
+    #[test]
+    fn names_and_label_values_are_made_valid() {
+        let sink = PrometheusTextSink::new();
+        sink.send(
+            "9lives/cpu-load",
+            br#"{"value": 0.5, "host name": "a \"b\"\nc"}"#,
+        )
+        .expect("send");
+
+        assert_eq!(
+            sink.render(),
+            "# TYPE _9lives_cpu_load gauge\n\
+             _9lives_cpu_load{host_name=\"a \\\"b\\\"\\nc\"} 0.5\n"
+        );
+    }
+
+    #[test]
+    fn non_numeric_payloads_are_rejected() {
+        let sink = PrometheusTextSink::new();
+        for payload in [&b"not json"[..], br#"{"value": "hot"}"#, br#"{"temp": 1}"#] {
+            let err = sink.send("t", payload).expect_err("not numeric");
+            assert!(matches!(err, TelemetryError::Serialization(_)));
+        }
+        assert!(sink.is_empty());
+    }
+
+    #[test]
+    fn counters_reject_negative_increments() {
+        let sink = PrometheusTextSink::new().with_kind("jobs", PrometheusKind::Counter);
+        assert_eq!(sink.kind("jobs"), PrometheusKind::Counter);
+        assert_eq!(sink.kind("other"), PrometheusKind::Gauge);
+
+        sink.send("jobs", b"1").expect("send");
+        assert!(sink.send("jobs", b"-1").is_err());
+        assert!(sink.render().contains("jobs_total 1\n"));
+    }
+}
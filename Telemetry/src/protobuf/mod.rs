@@ -0,0 +1,142 @@
+//! Protobuf encoding for `TelemetryMessage`.
+//!
+//! **Why protobuf?** Protobuf-native backends otherwise have to bridge JSON,
+//! which is slower and loses type information at the boundary.
+//!
+//! The schema lives in `proto/telemetry.proto`. The message type below is
+//! written with `prost` derives rather than generated by a build script, so
+//! building the crate does not require `protoc`.
+
+use crate::{TelemetryError, TelemetryMessage, TelemetryResult};
+use prost::Message;
+use std::collections::BTreeMap;
+
+/// Prost representation of `room619.telemetry.v1.TelemetryMessage`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoTelemetryMessage {
+    #[prost(string, tag = "1")]
+    pub topic: String,
+    #[prost(string, tag = "2")]
+    pub payload_json: String,
+    #[prost(int64, optional, tag = "3")]
+    pub timestamp: Option<i64>,
+    #[prost(btree_map = "string, string", tag = "4")]
+    pub headers: BTreeMap<String, String>,
+    #[prost(uint32, tag = "5")]
+    pub priority: u32,
+    #[prost(uint32, optional, tag = "6")]
+    pub ttl_ms: Option<u32>,
+    #[prost(int64, optional, tag = "7")]
+    pub created_at: Option<i64>,
+}
+
+impl TelemetryMessage {
+    /// Encode the message as protobuf bytes.
+    ///
+    /// The payload is carried as a JSON string inside the protobuf message.
+    pub fn to_protobuf(&self) -> TelemetryResult<Vec<u8>> {
+        let payload_json = serde_json::to_string(&self.payload)
+            .map_err(|e| TelemetryError::Serialization(format!("payload to JSON: {}", e)))?;
+        let proto = ProtoTelemetryMessage {
+            topic: self.topic.clone(),
+            payload_json,
+            timestamp: self.timestamp,
+            headers: self.headers.clone(),
+            priority: u32::from(self.priority),
+            ttl_ms: self.ttl_ms,
+            created_at: self.created_at,
+        };
+        Ok(proto.encode_to_vec())
+    }
+
+    /// Decode a message from protobuf bytes produced by `to_protobuf`.
+    pub fn from_protobuf(bytes: &[u8]) -> TelemetryResult<Self> {
+        let proto = ProtoTelemetryMessage::decode(bytes)
+            .map_err(|e| TelemetryError::Serialization(format!("protobuf decode: {}", e)))?;
+        let payload = serde_json::from_str(&proto.payload_json)
+            .map_err(|e| TelemetryError::Serialization(format!("payload JSON: {}", e)))?;
+        let mut msg = TelemetryMessage::new(proto.topic, payload);
+        msg.timestamp = proto.timestamp;
+        msg.headers = proto.headers;
+        msg.priority = u8::try_from(proto.priority).unwrap_or(u8::MAX);
+        msg.ttl_ms = proto.ttl_ms;
+        msg.created_at = proto.created_at;
+        Ok(msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemorySink, TelemetryClient};
+    use std::sync::Arc;
+
+    /// Independent decoder for the schema, as a consumer would generate it.
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct ConsumerMessage {
+        #[prost(string, tag = "1")]
+        topic: String,
+        #[prost(string, tag = "2")]
+        payload_json: String,
+        #[prost(int64, optional, tag = "3")]
+        timestamp: Option<i64>,
+        #[prost(map = "string, string", tag = "4")]
+        headers: std::collections::HashMap<String, String>,
+    }
+
+    fn sample() -> TelemetryMessage {
+        TelemetryMessage::builder()
+            .topic("sensors/temp")
+            .payload(serde_json::json!({ "temp": 22.5, "unit": "C" }))
+            .timestamp(1_700_000_000_123)
+            .header("service", "hvac")
+            .build()
+            .expect("valid message")
+    }
+
+    #[test]
+    fn protobuf_round_trip() {
+        let msg = sample();
+        let bytes = msg.to_protobuf().expect("encode");
+        let decoded = TelemetryMessage::from_protobuf(&bytes).expect("decode");
+        assert_eq!(decoded, msg);
+    }
+
+    #[test]
+    fn wire_bytes_decode_with_standalone_type() {
+        let bytes = sample().to_protobuf().expect("encode");
+        let consumer = ConsumerMessage::decode(bytes.as_slice()).expect("decode");
+
+        assert_eq!(consumer.topic, "sensors/temp");
+        assert_eq!(consumer.timestamp, Some(1_700_000_000_123));
+        assert_eq!(
+            consumer.headers.get("service").map(String::as_str),
+            Some("hvac")
+        );
+        let payload: serde_json::Value =
+            serde_json::from_str(&consumer.payload_json).expect("json");
+        assert_eq!(payload["temp"], 22.5);
+    }
+
+    #[test]
+    fn client_sends_protobuf_bytes() {
+        let sink = InMemorySink::new();
+        let records = sink.records_arc();
+        let client = TelemetryClient::new(Arc::new(sink));
+
+        client.send_message_protobuf(&sample()).expect("send");
+
+        let records = records.lock().expect("lock");
+        assert_eq!(records[0].0, "sensors/temp");
+        assert_eq!(
+            TelemetryMessage::from_protobuf(&records[0].1).expect("decode"),
+            sample()
+        );
+    }
+
+    #[test]
+    fn garbage_bytes_are_a_serialization_error() {
+        let err = TelemetryMessage::from_protobuf(&[0xff, 0xff, 0xff]).expect_err("garbage");
+        assert!(matches!(err, TelemetryError::Serialization(_)));
+    }
+}
@@ -0,0 +1,603 @@
+//! Bounded queue in front of an asynchronous sink.
+//!
+//! `QueueSink` makes `send` a cheap enqueue and hands delivery to a
+//! background tokio task that drains into an `AsyncTelemetrySink`.
+//!
+//! **Why not a tokio channel?** `OverflowPolicy::DropOldest` needs to evict
+//! from the head of the queue, which `tokio::sync::mpsc` does not allow, and
+//! `Block` must work from plain threads without a runtime handle. A
+//! `Mutex<VecDeque>` with a `Condvar` for producers and a `Notify` for the
+//! worker covers all three policies.
+//!
+//! Payloads that are `TelemetryMessage` envelopes are queued by descending
+//! `priority` (FIFO within a priority), and envelopes whose `ttl_ms` has run
+//! out by the time the worker dequeues them are dropped instead of delivered.
+//!
+//! `send_with_ack` attaches a callback that receives the final outcome of one
+//! message, and `BlockingSink` lets a synchronous sink (e.g. a `RetrySink`)
+//! sit behind the queue.
+
+use crate::delivery::{insert_position, QueuedRecord};
+use crate::{
+    AsyncTelemetrySink, Clock, SinkFuture, SystemClock, TelemetryError, TelemetryResult,
+    TelemetrySink,
+};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+/// What `QueueSink::send` does when the queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Block the caller until the worker frees a slot.
+    ///
+    /// Do not use from a current-thread runtime: the blocked caller would
+    /// starve the worker that has to drain the queue.
+    Block,
+    /// Evict the oldest queued message of the lowest priority to make room.
+    DropOldest,
+    /// Reject the new message with `TelemetryError::RateLimited`.
+    #[default]
+    Reject,
+}
+
+/// Callback receiving the final delivery outcome of one queued message.
+pub type DeliveryAck = Box<dyn FnOnce(TelemetryResult<()>) + Send>;
+
+/// A queued record and the callback to notify once it is resolved.
+struct Entry {
+    record: QueuedRecord,
+    ack: Option<DeliveryAck>,
+}
+
+impl Entry {
+    fn resolve(self, result: TelemetryResult<()>) {
+        if let Some(ack) = self.ack {
+            ack(result);
+        }
+    }
+}
+
+struct QueueState {
+    /// Ordered by descending priority, FIFO within a priority.
+    items: VecDeque<Entry>,
+    closed: bool,
+}
+
+struct Shared {
+    state: Mutex<QueueState>,
+    /// Signalled when a slot frees up or the queue closes.
+    space: Condvar,
+    /// Signalled when an item is queued or the queue closes.
+    ready: Notify,
+    clock: Arc<dyn Clock>,
+}
+
+/// Counters shared with the worker task.
+#[derive(Default)]
+struct Stats {
+    failed: AtomicU64,
+    expired: AtomicU64,
+}
+
+/// A sink that queues payloads and delivers them from a background task.
+pub struct QueueSink {
+    shared: Arc<Shared>,
+    capacity: usize,
+    policy: OverflowPolicy,
+    dropped: AtomicU64,
+    stats: Arc<Stats>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl QueueSink {
+    /// Create a queue holding at most `capacity` messages and spawn its
+    /// worker on the current tokio runtime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside a tokio runtime.
+    pub fn new<S>(inner: S, capacity: usize, policy: OverflowPolicy) -> Self
+    where
+        S: AsyncTelemetrySink + 'static,
+    {
+        let capacity = capacity.max(1);
+        let shared = Arc::new(Shared {
+            state: Mutex::new(QueueState {
+                items: VecDeque::with_capacity(capacity),
+                closed: false,
+            }),
+            space: Condvar::new(),
+            ready: Notify::new(),
+            clock: Arc::new(SystemClock),
+        });
+        let stats = Arc::new(Stats::default());
+        let worker = tokio::spawn(drain(Arc::clone(&shared), inner, Arc::clone(&stats)));
+        Self {
+            shared,
+            capacity,
+            policy,
+            dropped: AtomicU64::new(0),
+            stats,
+            worker: Mutex::new(Some(worker)),
+        }
+    }
+
+    /// Maximum number of queued messages.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Active overflow policy.
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Number of messages currently waiting for the worker.
+    pub fn pending(&self) -> usize {
+        self.shared.state.lock().map(|s| s.items.len()).unwrap_or(0)
+    }
+
+    /// Messages evicted or rejected because the queue was full.
+    pub fn dropped_count(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Messages the inner sink failed to deliver.
+    pub fn failed_count(&self) -> u64 {
+        self.stats.failed.load(Ordering::Relaxed)
+    }
+
+    /// Messages discarded at dequeue because their TTL had run out.
+    pub fn expired_count(&self) -> u64 {
+        self.stats.expired.load(Ordering::Relaxed)
+    }
+
+    /// Enqueue like `send` and call `on_complete` exactly once with the
+    /// message's final outcome.
+    ///
+    /// The outcome is the inner sink's result (after any retries it makes
+    /// itself), or an error if the message is rejected, evicted or expires
+    /// while queued.
+    pub fn send_with_ack<F>(&self, topic: &str, payload: &[u8], on_complete: F)
+    where
+        F: FnOnce(TelemetryResult<()>) + Send + 'static,
+    {
+        // The error has already been passed to `on_complete`
+        let _ = self.push(topic, payload, Some(Box::new(on_complete)));
+    }
+
+    /// Stop accepting messages, deliver everything still queued, and flush
+    /// the inner sink.
+    pub async fn shutdown(&self) -> TelemetryResult<()> {
+        {
+            let mut state = self
+                .shared
+                .state
+                .lock()
+                .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+            state.closed = true;
+        }
+        self.shared.space.notify_all();
+        self.shared.ready.notify_one();
+
+        let worker = self
+            .worker
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?
+            .take();
+        match worker {
+            Some(handle) => handle
+                .await
+                .map_err(|e| TelemetryError::new(format!("queue worker failed: {}", e))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl QueueSink {
+    fn enqueue(
+        &self,
+        mut state: MutexGuard<'_, QueueState>,
+        topic: &str,
+        payload: &[u8],
+        ack: Option<DeliveryAck>,
+    ) {
+        let record = QueuedRecord::new(topic, payload, self.shared.clock.now_millis());
+        let at = insert_position(state.items.iter().map(|e| &e.record), record.priority);
+        state.items.insert(at, Entry { record, ack });
+        drop(state);
+        self.shared.ready.notify_one();
+    }
+
+    /// Enqueue according to the overflow policy.
+    ///
+    /// If the message is rejected, `ack` is called with the error before it
+    /// is returned. An evicted message's ack is called after the new one is
+    /// queued.
+    fn push(&self, topic: &str, payload: &[u8], ack: Option<DeliveryAck>) -> TelemetryResult<()> {
+        let reject = |e: TelemetryError, ack: Option<DeliveryAck>| {
+            if let Some(ack) = ack {
+                ack(Err(e.clone()));
+            }
+            Err(e)
+        };
+        let mut state = match self.shared.state.lock() {
+            Ok(state) => state,
+            Err(e) => return reject(TelemetryError::new(format!("lock poisoned: {}", e)), ack),
+        };
+        if state.closed {
+            return reject(TelemetryError::Connection("queue is shut down".into()), ack);
+        }
+        let mut evicted = None;
+        if state.items.len() >= self.capacity {
+            match self.policy {
+                OverflowPolicy::Block => {
+                    state = match self
+                        .shared
+                        .space
+                        .wait_while(state, |s| !s.closed && s.items.len() >= self.capacity)
+                    {
+                        Ok(state) => state,
+                        Err(e) => {
+                            return reject(
+                                TelemetryError::new(format!("lock poisoned: {}", e)),
+                                ack,
+                            )
+                        }
+                    };
+                    if state.closed {
+                        return reject(
+                            TelemetryError::Connection("queue is shut down".into()),
+                            ack,
+                        );
+                    }
+                }
+                OverflowPolicy::DropOldest => {
+                    // The tail holds the lowest priority; its oldest entry is
+                    // the first one of that priority.
+                    let lowest = state.items.back().map_or(0, |e| e.record.priority);
+                    let oldest = state
+                        .items
+                        .iter()
+                        .position(|e| e.record.priority == lowest)
+                        .unwrap_or(0);
+                    evicted = state.items.remove(oldest);
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                OverflowPolicy::Reject => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    return reject(
+                        TelemetryError::RateLimited(format!(
+                            "queue full ({} messages)",
+                            self.capacity
+                        )),
+                        ack,
+                    );
+                }
+            }
+        }
+        self.enqueue(state, topic, payload, ack);
+        if let Some(entry) = evicted {
+            entry.resolve(Err(TelemetryError::RateLimited(
+                "evicted from full queue".into(),
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Worker loop: deliver queued records until the queue is closed and empty.
+async fn drain<S: AsyncTelemetrySink>(shared: Arc<Shared>, inner: S, stats: Arc<Stats>) {
+    loop {
+        let next = match shared.state.lock() {
+            Ok(mut state) => match state.items.pop_front() {
+                Some(record) => Some(record),
+                None if state.closed => break,
+                None => None,
+            },
+            Err(_) => break,
+        };
+        match next {
+            Some(entry) => {
+                shared.space.notify_one();
+                let record = &entry.record;
+                if record.is_expired_at(shared.clock.now_millis()) {
+                    stats.expired.fetch_add(1, Ordering::Relaxed);
+                    log::debug!("QueueSink dropped expired message for '{}'", record.topic);
+                    entry.resolve(Err(TelemetryError::new("message expired before delivery")));
+                    continue;
+                }
+                let result = inner.send(&record.topic, &record.payload).await;
+                if let Err(e) = &result {
+                    stats.failed.fetch_add(1, Ordering::Relaxed);
+                    log::warn!("QueueSink failed to deliver to '{}': {}", record.topic, e);
+                }
+                entry.resolve(result);
+            }
+            // `notify_one` stores a permit, so a push between the check
+            // above and this await is not lost.
+            None => shared.ready.notified().await,
+        }
+    }
+    if let Err(e) = inner.flush().await {
+        log::warn!("QueueSink failed to flush inner sink: {}", e);
+    }
+}
+
+impl TelemetrySink for QueueSink {
+    fn sink_name(&self) -> &'static str {
+        "queue"
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        self.push(topic, payload, None)
+    }
+
+    /// Enqueue only if a slot is free; never blocks or evicts, whatever the
+    /// overflow policy.
+    fn try_send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<bool> {
+        let state = self
+            .shared
+            .state
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+        if state.closed {
+            return Err(TelemetryError::Connection("queue is shut down".into()));
+        }
+        if state.items.len() >= self.capacity {
+            return Ok(false);
+        }
+        self.enqueue(state, topic, payload, None);
+        Ok(true)
+    }
+}
+
+/// Adapts a synchronous sink to `AsyncTelemetrySink` by running each call
+/// on tokio's blocking thread pool.
+///
+/// Lets blocking transports and decorators such as `RetrySink` feed a
+/// `QueueSink` without stalling its worker.
+pub struct BlockingSink<S: TelemetrySink + 'static> {
+    inner: Arc<S>,
+}
+
+impl<S: TelemetrySink + 'static> BlockingSink<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+fn join_error(e: tokio::task::JoinError) -> TelemetryError {
+    TelemetryError::new(format!("blocking send failed: {}", e))
+}
+
+impl<S: TelemetrySink + 'static> AsyncTelemetrySink for BlockingSink<S> {
+    fn send<'a>(&'a self, topic: &'a str, payload: &'a [u8]) -> SinkFuture<'a> {
+        let inner = Arc::clone(&self.inner);
+        let topic = topic.to_string();
+        let payload = payload.to_vec();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || inner.send(&topic, &payload))
+                .await
+                .map_err(join_error)?
+        })
+    }
+
+    fn flush(&self) -> SinkFuture<'_> {
+        let inner = Arc::clone(&self.inner);
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || inner.flush())
+                .await
+                .map_err(join_error)?
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TelemetryMessage;
+    use std::time::Duration;
+    use tokio::sync::Semaphore;
+
+    /// Async sink that records payloads, optionally held back by a gate.
+    #[derive(Clone)]
+    struct GatedSink {
+        gate: Arc<Semaphore>,
+        started: Arc<AtomicU64>,
+        records: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl GatedSink {
+        fn closed() -> Self {
+            Self {
+                gate: Arc::new(Semaphore::new(0)),
+                started: Arc::new(AtomicU64::new(0)),
+                records: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+
+        fn open(&self) {
+            self.gate.add_permits(Semaphore::MAX_PERMITS / 2);
+        }
+
+        fn topics(&self) -> Vec<String> {
+            self.records.lock().expect("lock").clone()
+        }
+
+        /// Wait until the worker has pulled `n` messages off the queue.
+        async fn wait_started(&self, n: u64) {
+            while self.started.load(Ordering::SeqCst) < n {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        }
+    }
+
+    impl AsyncTelemetrySink for GatedSink {
+        fn send<'a>(&'a self, topic: &'a str, _payload: &'a [u8]) -> SinkFuture<'a> {
+            Box::pin(async move {
+                self.started.fetch_add(1, Ordering::SeqCst);
+                self.gate.acquire().await.expect("gate").forget();
+                self.records.lock().expect("lock").push(topic.to_string());
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn drop_oldest_evicts_head_of_queue() {
+        let inner = GatedSink::closed();
+        let sink = QueueSink::new(inner.clone(), 2, OverflowPolicy::DropOldest);
+
+        sink.send("a", b"").expect("send a");
+        inner.wait_started(1).await;
+        for topic in ["b", "c", "d"] {
+            sink.send(topic, b"").expect("send");
+        }
+        assert_eq!(sink.dropped_count(), 1);
+
+        inner.open();
+        sink.shutdown().await.expect("shutdown");
+        assert_eq!(inner.topics(), vec!["a", "c", "d"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn reject_returns_rate_limited_when_full() {
+        let inner = GatedSink::closed();
+        let sink = QueueSink::new(inner.clone(), 1, OverflowPolicy::Reject);
+
+        sink.send("a", b"").expect("send a");
+        inner.wait_started(1).await;
+        sink.send("b", b"").expect("send b");
+        let err = sink.send("c", b"").expect_err("queue full");
+        assert!(matches!(err, TelemetryError::RateLimited(_)));
+
+        inner.open();
+        sink.shutdown().await.expect("shutdown");
+        assert_eq!(inner.topics(), vec!["a", "b"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn block_waits_for_free_slot() {
+        let inner = GatedSink::closed();
+        let sink = Arc::new(QueueSink::new(inner.clone(), 1, OverflowPolicy::Block));
+
+        sink.send("a", b"").expect("send a");
+        inner.wait_started(1).await;
+        sink.send("b", b"").expect("send b");
+
+        let blocked = {
+            let sink = Arc::clone(&sink);
+            tokio::task::spawn_blocking(move || sink.send("c", b""))
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!blocked.is_finished());
+
+        inner.open();
+        blocked.await.expect("join").expect("send c");
+        sink.shutdown().await.expect("shutdown");
+        assert_eq!(inner.topics(), vec!["a", "b", "c"]);
+        assert_eq!(sink.dropped_count(), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn try_send_reports_full_queue_instead_of_blocking() {
+        let inner = GatedSink::closed();
+        let sink = QueueSink::new(inner.clone(), 1, OverflowPolicy::Block);
+
+        assert!(sink.try_send("a", b"").expect("try a"));
+        inner.wait_started(1).await;
+        assert!(sink.try_send("b", b"").expect("try b"));
+        assert!(!sink.try_send("c", b"").expect("queue full"));
+        assert_eq!(sink.pending(), 1);
+        assert_eq!(sink.dropped_count(), 0);
+
+        inner.open();
+        sink.shutdown().await.expect("shutdown");
+        assert_eq!(inner.topics(), vec!["a", "b"]);
+        assert!(sink.try_send("late", b"").is_err());
+    }
+
+    fn envelope(topic: &str, priority: u8) -> TelemetryMessage {
+        TelemetryMessage::builder()
+            .topic(topic)
+            .payload(serde_json::json!(null))
+            .priority(priority)
+            .build()
+            .expect("valid message")
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn high_priority_overtakes_queued_low_priority() {
+        let inner = GatedSink::closed();
+        let sink = QueueSink::new(inner.clone(), 8, OverflowPolicy::Reject);
+
+        sink.send("a", b"").expect("send a");
+        inner.wait_started(1).await;
+        for (topic, priority) in [("low", 0), ("mid", 5), ("high", 9), ("low2", 0)] {
+            let msg = envelope(topic, priority);
+            sink.send(topic, msg.to_json().as_bytes()).expect("send");
+        }
+
+        inner.open();
+        sink.shutdown().await.expect("shutdown");
+        assert_eq!(inner.topics(), vec!["a", "high", "mid", "low", "low2"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn expired_message_is_dropped_at_dequeue() {
+        let inner = GatedSink::closed();
+        let sink = QueueSink::new(inner.clone(), 8, OverflowPolicy::Reject);
+
+        sink.send("a", b"").expect("send a");
+        inner.wait_started(1).await;
+        let stale = TelemetryMessage::builder()
+            .topic("stale")
+            .payload(serde_json::json!(null))
+            .ttl(10)
+            .created_at(0)
+            .build()
+            .expect("valid message");
+        sink.send("stale", stale.to_json().as_bytes())
+            .expect("send stale");
+        let fresh = envelope("fresh", 0).with_ttl(60_000);
+        sink.send("fresh", fresh.to_json().as_bytes())
+            .expect("send fresh");
+
+        inner.open();
+        sink.shutdown().await.expect("shutdown");
+        assert_eq!(inner.topics(), vec!["a", "fresh"]);
+        assert_eq!(sink.expired_count(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn shutdown_drains_queue_and_rejects_new_sends() {
+        let inner = GatedSink::closed();
+        inner.open();
+        let sink = QueueSink::new(inner.clone(), 16, OverflowPolicy::Reject);
+
+        for i in 0..10 {
+            sink.send(&format!("t{}", i), b"").expect("send");
+        }
+        sink.shutdown().await.expect("shutdown");
+
+        assert_eq!(inner.topics().len(), 10);
+        assert_eq!(sink.pending(), 0);
+        assert!(sink.send("late", b"").is_err());
+    }
+}
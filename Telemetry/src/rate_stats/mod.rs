@@ -0,0 +1,215 @@
+//! Sliding-window send rate sink decorator.
+//!
+//! **Why not `MetricsRegistry` counters?** Those are cumulative since start;
+//! an operator (or an adaptive sampler) deciding whether to shed load needs
+//! the rate right now, over the last few seconds.
+
+use crate::clock::elapsed_since;
+use crate::{layered_sink_name, Clock, SystemClock, TelemetryResult, TelemetrySink};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Window used by `RateStatsSink::new`.
+pub const DEFAULT_RATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// Send rate averaged over a `RateStatsSink` window.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RateStats {
+    pub msgs_per_sec: f64,
+    pub bytes_per_sec: f64,
+}
+
+/// Messages and payload bytes delivered within one clock millisecond.
+#[derive(Debug, Clone, Copy)]
+struct Tick {
+    at: i64,
+    msgs: u64,
+    bytes: u64,
+}
+
+/// A sink that tracks its delivery rate over a sliding window.
+///
+/// Only sends the inner sink accepts are counted. Sends are grouped per
+/// clock millisecond, so memory is bounded by the window length rather than
+/// by traffic.
+pub struct RateStatsSink<S: TelemetrySink> {
+    inner: S,
+    window: Duration,
+    clock: Arc<dyn Clock>,
+    ticks: Mutex<VecDeque<Tick>>,
+}
+
+impl<S: TelemetrySink> RateStatsSink<S> {
+    /// Measure over `DEFAULT_RATE_WINDOW`.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            window: DEFAULT_RATE_WINDOW,
+            clock: Arc::new(SystemClock),
+            ticks: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Average over the last `window` instead; clamped to at least 1ms.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window.max(Duration::from_millis(1));
+        self
+    }
+
+    /// Measure the window with `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Rate over the window ending now.
+    pub fn current_rate(&self) -> RateStats {
+        let now = self.clock.now_millis();
+        let mut ticks = self
+            .ticks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.expire(&mut ticks, now);
+        let (msgs, bytes) = ticks
+            .iter()
+            .fold((0, 0), |(m, b), tick| (m + tick.msgs, b + tick.bytes));
+        let secs = self.window.as_secs_f64();
+        RateStats {
+            msgs_per_sec: msgs as f64 / secs,
+            bytes_per_sec: bytes as f64 / secs,
+        }
+    }
+
+    /// Drop ticks that have fallen out of the window ending at `now`.
+    fn expire(&self, ticks: &mut VecDeque<Tick>, now: i64) {
+        while ticks
+            .front()
+            .is_some_and(|tick| elapsed_since(now, tick.at) >= self.window)
+        {
+            ticks.pop_front();
+        }
+    }
+
+    fn record(&self, bytes: usize) {
+        let now = self.clock.now_millis();
+        let mut ticks = self
+            .ticks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.expire(&mut ticks, now);
+        match ticks.back_mut() {
+            Some(tick) if tick.at == now => {
+                tick.msgs += 1;
+                tick.bytes += bytes as u64;
+            }
+            _ => ticks.push_back(Tick {
+                at: now,
+                msgs: 1,
+                bytes: bytes as u64,
+            }),
+        }
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for RateStatsSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("rate_stats", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        self.inner.send(topic, payload)?;
+        self.record(payload.len());
+        Ok(())
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemorySink, MockClock, TelemetryError};
+
+    struct FailingSink;
+
+    impl TelemetrySink for FailingSink {
+        fn sink_name(&self) -> &'static str {
+            "failing"
+        }
+
+        fn send(&self, _topic: &str, _payload: &[u8]) -> TelemetryResult<()> {
+            Err(TelemetryError::new("down"))
+        }
+    }
+
+    fn sink(clock: &MockClock) -> RateStatsSink<InMemorySink> {
+        RateStatsSink::new(InMemorySink::new())
+            .with_window(Duration::from_secs(2))
+            .with_clock(Arc::new(clock.clone()))
+    }
+
+    #[test]
+    fn rate_is_averaged_over_the_window() {
+        let clock = MockClock::new(0);
+        let sink = sink(&clock);
+        // 5 messages of 10 bytes every 100ms: 50 msgs/s, 500 bytes/s
+        for _ in 0..20 {
+            clock.advance(Duration::from_millis(100));
+            for _ in 0..5 {
+                sink.send("t", &[0; 10]).expect("send");
+            }
+        }
+
+        let rate = sink.current_rate();
+        assert!((rate.msgs_per_sec - 50.0).abs() < 1e-9, "{:?}", rate);
+        assert!((rate.bytes_per_sec - 500.0).abs() < 1e-9, "{:?}", rate);
+        assert_eq!(sink.inner().records.lock().expect("lock").len(), 100);
+    }
+
+    #[test]
+    fn old_sends_leave_the_window() {
+        let clock = MockClock::new(0);
+        let sink = sink(&clock);
+        for _ in 0..40 {
+            sink.send("t", b"x").expect("send");
+        }
+        clock.advance(Duration::from_millis(1_000));
+        for _ in 0..10 {
+            sink.send("t", b"x").expect("send");
+        }
+        assert_eq!(sink.current_rate().msgs_per_sec, 25.0);
+
+        clock.advance(Duration::from_millis(1_000));
+        assert_eq!(sink.current_rate().msgs_per_sec, 5.0);
+
+        clock.advance(Duration::from_millis(1_000));
+        assert_eq!(sink.current_rate(), RateStats::default());
+    }
+
+    #[test]
+    fn failed_sends_are_not_counted() {
+        let clock = MockClock::new(0);
+        let sink = RateStatsSink::new(FailingSink)
+            .with_window(Duration::from_secs(1))
+            .with_clock(Arc::new(clock.clone()));
+        assert!(sink.send("t", b"x").is_err());
+        assert_eq!(sink.current_rate().msgs_per_sec, 0.0);
+        assert_eq!(sink.window(), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,275 @@
+//! Capture and replay of telemetry streams.
+//!
+//! `RecordingSink` appends every message to a file as one JSON line
+//! (`{"timestamp", "topic", "payload"}` with a base64 payload), and
+//! `Replayer` reads such a file back and re-sends it to any sink.
+//!
+//! **Why JSON lines?** The capture stays greppable and diffable when
+//! reproducing field issues, and a truncated file (e.g. after a crash) loses
+//! only its last partial line.
+
+use crate::{Clock, ShutdownSink, SystemClock, TelemetryError, TelemetryResult, TelemetrySink};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// One captured message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayRecord {
+    /// Capture time in milliseconds since the Unix epoch.
+    pub timestamp: i64,
+    pub topic: String,
+    pub payload: Vec<u8>,
+}
+
+/// On-disk form of a `ReplayRecord`.
+#[derive(Serialize, Deserialize)]
+struct RecordLine {
+    timestamp: i64,
+    topic: String,
+    payload: String,
+}
+
+fn io_error(context: &str, e: std::io::Error) -> TelemetryError {
+    TelemetryError::Transport(format!("{}: {}", context, e))
+}
+
+/// A sink that writes every message to a capture file.
+pub struct RecordingSink {
+    writer: Mutex<BufWriter<File>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl RecordingSink {
+    /// Create (or truncate) the capture file at `path`.
+    pub fn create(path: impl AsRef<Path>) -> TelemetryResult<Self> {
+        let file = File::create(path).map_err(|e| io_error("cannot create capture file", e))?;
+        Ok(Self {
+            writer: Mutex::new(BufWriter::new(file)),
+            clock: Arc::new(SystemClock),
+        })
+    }
+
+    /// Timestamp records with `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+}
+
+impl TelemetrySink for RecordingSink {
+    fn sink_name(&self) -> &'static str {
+        "recording"
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let line = RecordLine {
+            timestamp: self.clock.now_millis(),
+            topic: topic.to_string(),
+            payload: BASE64.encode(payload),
+        };
+        let mut encoded = serde_json::to_vec(&line)
+            .map_err(|e| TelemetryError::Serialization(format!("capture record: {}", e)))?;
+        encoded.push(b'\n');
+        self.writer
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?
+            .write_all(&encoded)
+            .map_err(|e| io_error("cannot write capture file", e))
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.writer
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?
+            .flush()
+            .map_err(|e| io_error("cannot flush capture file", e))
+    }
+}
+
+impl ShutdownSink for RecordingSink {
+    fn close(self) -> TelemetryResult<()> {
+        self.flush()
+    }
+}
+
+/// Pacing used by `Replayer::replay`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplaySpeed {
+    /// Wait between records as long as the original gap between them.
+    Realtime,
+    /// Send records back to back.
+    #[default]
+    AsFastAsPossible,
+}
+
+/// Reads a capture file and re-sends its records to a sink.
+pub struct Replayer {
+    records: Vec<ReplayRecord>,
+    speed: ReplaySpeed,
+    clock: Arc<dyn Clock>,
+}
+
+impl Replayer {
+    /// Load every record from the capture file at `path`.
+    pub fn open(path: impl AsRef<Path>) -> TelemetryResult<Self> {
+        let file = File::open(path).map_err(|e| io_error("cannot open capture file", e))?;
+        let mut records = Vec::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| io_error("cannot read capture file", e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parsed: RecordLine = serde_json::from_str(&line).map_err(|e| {
+                TelemetryError::Serialization(format!("capture line {}: {}", index + 1, e))
+            })?;
+            let payload = BASE64.decode(&parsed.payload).map_err(|e| {
+                TelemetryError::Serialization(format!("capture line {}: {}", index + 1, e))
+            })?;
+            records.push(ReplayRecord {
+                timestamp: parsed.timestamp,
+                topic: parsed.topic,
+                payload,
+            });
+        }
+        Ok(Self {
+            records,
+            speed: ReplaySpeed::default(),
+            clock: Arc::new(SystemClock),
+        })
+    }
+
+    /// Set the replay pacing.
+    pub fn with_speed(mut self, speed: ReplaySpeed) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Wait between records on `clock` (e.g. a `MockClock` in tests).
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Records loaded from the capture file.
+    pub fn records(&self) -> &[ReplayRecord] {
+        &self.records
+    }
+
+    /// Send every record to `target` in capture order.
+    ///
+    /// Stops at the first send error. Returns the number of records sent.
+    pub fn replay(&self, target: &dyn TelemetrySink) -> TelemetryResult<usize> {
+        let mut previous: Option<i64> = None;
+        for record in &self.records {
+            if let (ReplaySpeed::Realtime, Some(prev)) = (self.speed, previous) {
+                let gap = record.timestamp.saturating_sub(prev).max(0) as u64;
+                self.clock.sleep(Duration::from_millis(gap));
+            }
+            previous = Some(record.timestamp);
+            target.send(&record.topic, &record.payload)?;
+        }
+        target.flush()?;
+        Ok(self.records.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemorySink, MockClock};
+    use std::path::PathBuf;
+    use std::time::Instant;
+
+    fn capture_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("telemetry-{}-{}.jsonl", name, std::process::id()))
+    }
+
+    /// Record three messages 30ms apart on a mock clock.
+    fn record(path: &Path) {
+        let clock = MockClock::new(1_000);
+        let sink = RecordingSink::create(path)
+            .expect("create")
+            .with_clock(Arc::new(clock.clone()));
+        sink.send("sensors/temp", b"21.5").expect("send");
+        clock.advance(Duration::from_millis(30));
+        sink.send("sensors/hum", &[0x00, 0xff]).expect("send");
+        clock.advance(Duration::from_millis(30));
+        sink.send("sensors/temp", b"22.0").expect("send");
+        sink.close().expect("close");
+    }
+
+    #[test]
+    fn round_trip_preserves_topics_and_payloads() {
+        let path = capture_path("round-trip");
+        record(&path);
+
+        let replayer = Replayer::open(&path).expect("open");
+        assert_eq!(replayer.records()[2].timestamp, 1_060);
+        let target = InMemorySink::new();
+        assert_eq!(replayer.replay(&target).expect("replay"), 3);
+
+        let records = target.records.lock().expect("lock").clone();
+        assert_eq!(
+            records,
+            vec![
+                ("sensors/temp".to_string(), b"21.5".to_vec()),
+                ("sensors/hum".to_string(), vec![0x00, 0xff]),
+                ("sensors/temp".to_string(), b"22.0".to_vec()),
+            ]
+        );
+        std::fs::remove_file(path).expect("cleanup");
+    }
+
+    #[test]
+    fn realtime_replay_honours_original_spacing() {
+        let path = capture_path("realtime");
+        record(&path);
+
+        let start = Instant::now();
+        Replayer::open(&path)
+            .expect("open")
+            .with_speed(ReplaySpeed::Realtime)
+            .replay(&InMemorySink::new())
+            .expect("replay");
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(60));
+        assert!(elapsed < Duration::from_secs(2));
+
+        let clock = MockClock::new(0);
+        Replayer::open(&path)
+            .expect("open")
+            .with_speed(ReplaySpeed::Realtime)
+            .with_clock(Arc::new(clock.clone()))
+            .replay(&InMemorySink::new())
+            .expect("replay");
+        assert_eq!(clock.now_millis(), 60);
+        std::fs::remove_file(path).expect("cleanup");
+    }
+
+    #[test]
+    fn malformed_capture_reports_line() {
+        let path = capture_path("malformed");
+        std::fs::write(
+            &path,
+            "{\"timestamp\":1,\"topic\":\"t\",\"payload\":\"\"}\nnot json\n",
+        )
+        .expect("write");
+
+        let err = Replayer::open(&path).err().expect("parse error");
+        assert!(matches!(err, TelemetryError::Serialization(_)));
+        assert!(err.message().contains("line 2"));
+        assert!(Replayer::open(capture_path("missing")).is_err());
+        std::fs::remove_file(path).expect("cleanup");
+    }
+}
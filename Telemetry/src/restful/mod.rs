@@ -0,0 +1,276 @@
+//! Nested-document sink decorator for REST and document-store targets.
+//!
+//! Turns flat topics into JSON hierarchy: a payload sent to
+//! `sensors/temp/room1` becomes `{"sensors":{"temp":{"room1":<payload>}}}`.
+//!
+//! **Why nest?** Document stores and REST collectors index by path, so
+//! grouping readings under their topic segments lets one document describe
+//! a whole site instead of one row per reading.
+
+use crate::clock::elapsed_since;
+use crate::{
+    layered_sink_name, Clock, SystemClock, TelemetryError, TelemetryResult, TelemetrySink,
+};
+use serde_json::{Map, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Topic accumulated trees are sent to unless overridden.
+pub const DEFAULT_DOCUMENT_TOPIC: &str = "telemetry";
+
+/// How `RestfulSink` groups messages into documents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RestfulMode {
+    /// Send one nested document per message, on the message's own topic.
+    #[default]
+    PerMessage,
+    /// Merge messages into one tree and send it once `interval` has passed
+    /// since the first message merged into it, or on `flush`.
+    Accumulate { interval: Duration },
+}
+
+/// Wrap `payload` in one object level per non-empty segment of `topic`.
+pub fn nest(topic: &str, payload: Value) -> Value {
+    let mut doc = Value::Object(Map::new());
+    merge_at(&mut doc, topic, payload);
+    doc
+}
+
+/// Merge `payload` into `doc` at the path named by `topic`.
+///
+/// Intermediate levels are created as needed; a non-object value in the way
+/// is replaced. At the leaf, object payloads are merged key by key (new keys
+/// win) and anything else replaces the existing value.
+pub fn merge_at(doc: &mut Value, topic: &str, payload: Value) {
+    let mut node = doc;
+    for segment in topic.split('/').filter(|s| !s.is_empty()) {
+        if !node.is_object() {
+            *node = Value::Object(Map::new());
+        }
+        // Always an object by now; matching avoids a panicking accessor
+        let Value::Object(map) = node else {
+            return;
+        };
+        node = map.entry(segment).or_insert(Value::Null);
+    }
+    match (node, payload) {
+        (Value::Object(existing), Value::Object(incoming)) => existing.extend(incoming),
+        (node, payload) => *node = payload,
+    }
+}
+
+#[derive(Default)]
+struct Tree {
+    started: Option<i64>,
+    /// `Null` until the first message is merged.
+    doc: Value,
+}
+
+impl Tree {
+    fn take(&mut self) -> Option<Value> {
+        self.started = None;
+        match std::mem::take(&mut self.doc) {
+            Value::Null => None,
+            doc => Some(doc),
+        }
+    }
+}
+
+/// A sink that forwards JSON payloads as documents nested by topic.
+///
+/// Payloads that are not JSON are rejected with
+/// `TelemetryError::Serialization`.
+pub struct RestfulSink<S: TelemetrySink> {
+    inner: S,
+    mode: RestfulMode,
+    document_topic: String,
+    clock: Arc<dyn Clock>,
+    tree: Mutex<Tree>,
+}
+
+impl<S: TelemetrySink> RestfulSink<S> {
+    /// Nest payloads according to `mode`.
+    pub fn new(inner: S, mode: RestfulMode) -> Self {
+        Self {
+            inner,
+            mode,
+            document_topic: DEFAULT_DOCUMENT_TOPIC.to_string(),
+            clock: Arc::new(SystemClock),
+            tree: Mutex::new(Tree::default()),
+        }
+    }
+
+    /// Measure the accumulation interval with `clock`.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Send accumulated trees to `topic` instead of `telemetry`.
+    pub fn with_document_topic(mut self, topic: impl Into<String>) -> Self {
+        self.document_topic = topic.into();
+        self
+    }
+
+    /// Active grouping mode.
+    pub fn mode(&self) -> RestfulMode {
+        self.mode
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn emit(&self, doc: Option<Value>) -> TelemetryResult<()> {
+        match doc {
+            Some(doc) => self
+                .inner
+                .send(&self.document_topic, doc.to_string().as_bytes()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for RestfulSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("restful", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let value: Value = serde_json::from_slice(payload)
+            .map_err(|e| TelemetryError::Serialization(format!("payload is not JSON: {}", e)))?;
+        let interval = match self.mode {
+            RestfulMode::PerMessage => {
+                return self
+                    .inner
+                    .send(topic, nest(topic, value).to_string().as_bytes());
+            }
+            RestfulMode::Accumulate { interval } => interval,
+        };
+        let now = self.clock.now_millis();
+        let due = {
+            let mut tree = self
+                .tree
+                .lock()
+                .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+            let due = match tree.started {
+                Some(start) if elapsed_since(now, start) >= interval => tree.take(),
+                _ => None,
+            };
+            tree.started.get_or_insert(now);
+            merge_at(&mut tree.doc, topic, value);
+            due
+        };
+        self.emit(due)
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        let pending = self
+            .tree
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?
+            .take();
+        self.emit(pending)?;
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemorySink, MockClock};
+    use serde_json::json;
+
+    fn documents(sink: &RestfulSink<InMemorySink>) -> Vec<(String, Value)> {
+        sink.inner()
+            .records
+            .lock()
+            .expect("lock")
+            .iter()
+            .map(|(topic, payload)| {
+                (
+                    topic.clone(),
+                    serde_json::from_slice(payload).expect("document"),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nests_payload_under_topic_segments() {
+        let sink = RestfulSink::new(InMemorySink::new(), RestfulMode::PerMessage);
+        sink.send("sensors/temp/room1", br#"{"value":23}"#)
+            .expect("send");
+
+        assert_eq!(
+            documents(&sink),
+            vec![(
+                "sensors/temp/room1".to_string(),
+                json!({"sensors":{"temp":{"room1":{"value":23}}}})
+            )]
+        );
+    }
+
+    #[test]
+    fn merge_at_combines_leaves_and_siblings() {
+        let mut doc = nest("sensors/temp/room1", json!({"value": 23}));
+        merge_at(&mut doc, "sensors/temp/room1", json!({"unit": "C"}));
+        merge_at(&mut doc, "sensors/temp/room2", json!(19));
+        merge_at(&mut doc, "/sensors//hum/", json!({"value": 40}));
+
+        assert_eq!(
+            doc,
+            json!({"sensors": {
+                "temp": {"room1": {"value": 23, "unit": "C"}, "room2": 19},
+                "hum": {"value": 40}
+            }})
+        );
+    }
+
+    #[test]
+    fn accumulates_tree_until_interval_elapses() {
+        let clock = MockClock::new(0);
+        let sink = RestfulSink::new(
+            InMemorySink::new(),
+            RestfulMode::Accumulate {
+                interval: Duration::from_secs(10),
+            },
+        )
+        .with_clock(Arc::new(clock.clone()));
+
+        sink.send("sensors/temp/room1", br#"{"value":23}"#)
+            .expect("send");
+        sink.send("sensors/temp/room2", br#"{"value":19}"#)
+            .expect("send");
+        assert!(documents(&sink).is_empty());
+
+        clock.advance(Duration::from_secs(10));
+        sink.send("sensors/hum/room1", br#"{"value":40}"#)
+            .expect("send");
+        assert_eq!(
+            documents(&sink),
+            vec![(
+                DEFAULT_DOCUMENT_TOPIC.to_string(),
+                json!({"sensors":{"temp":{"room1":{"value":23},"room2":{"value":19}}}})
+            )]
+        );
+
+        sink.flush().expect("flush");
+        assert_eq!(
+            documents(&sink)[1].1,
+            json!({"sensors":{"hum":{"room1":{"value":40}}}})
+        );
+    }
+
+    #[test]
+    fn rejects_non_json_payload() {
+        let sink = RestfulSink::new(InMemorySink::new(), RestfulMode::PerMessage);
+        let err = sink.send("t", b"\xff").expect_err("not JSON");
+        assert!(matches!(err, TelemetryError::Serialization(_)));
+    }
+}
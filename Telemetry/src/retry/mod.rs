@@ -0,0 +1,170 @@
+//! Retrying sink decorator.
+//!
+//! Re-attempts failed sends against the inner sink a bounded number of times
+//! with a fixed backoff between attempts.
+
+use crate::{layered_sink_name, Clock, SystemClock, TelemetryResult, TelemetrySink};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A sink that retries failed sends on its inner sink.
+///
+/// **Why fixed backoff?** It keeps worst-case latency predictable
+/// (`max_attempts * backoff`), which matters for soft-real-time callers.
+pub struct RetrySink<S: TelemetrySink> {
+    inner: S,
+    max_attempts: u32,
+    backoff: Duration,
+    clock: Arc<dyn Clock>,
+}
+
+impl<S: TelemetrySink> RetrySink<S> {
+    /// Create a retrying sink making at most `max_attempts` attempts per send.
+    ///
+    /// A value of zero is treated as a single attempt.
+    pub fn new(inner: S, max_attempts: u32, backoff: Duration) -> Self {
+        Self {
+            inner,
+            max_attempts: max_attempts.max(1),
+            backoff,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Wait out the backoff through `clock`, e.g. a `MockClock` in tests.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for RetrySink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("retry", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let mut attempt = 1;
+        loop {
+            match self.inner.send(topic, payload) {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt >= self.max_attempts => {
+                    return Err(if attempt == 1 {
+                        e
+                    } else {
+                        e.context(format!("gave up after {} attempts", attempt))
+                    });
+                }
+                Err(e) => {
+                    log::debug!(
+                        "retry {}/{} for {}: {}",
+                        attempt,
+                        self.max_attempts,
+                        topic,
+                        e
+                    );
+                    attempt += 1;
+                    self.clock.sleep(self.backoff);
+                }
+            }
+        }
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BufferingSink, InMemorySink, MockClock, TelemetryError};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Fails the first `failures` sends, then succeeds.
+    struct FlakySink {
+        failures: u32,
+        calls: AtomicU32,
+    }
+
+    impl TelemetrySink for FlakySink {
+        fn send(&self, _topic: &str, _payload: &[u8]) -> TelemetryResult<()> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            if call < self.failures {
+                Err(TelemetryError::new("flaky"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[test]
+    fn retries_until_success() {
+        let sink = RetrySink::new(
+            FlakySink {
+                failures: 2,
+                calls: AtomicU32::new(0),
+            },
+            3,
+            Duration::ZERO,
+        );
+        assert!(sink.send("t", b"x").is_ok());
+        assert_eq!(sink.inner().calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let sink = RetrySink::new(
+            FlakySink {
+                failures: 5,
+                calls: AtomicU32::new(0),
+            },
+            3,
+            Duration::ZERO,
+        );
+        let err = sink.send("t", b"x").expect_err("gives up");
+        assert_eq!(sink.inner().calls.load(Ordering::SeqCst), 3);
+        assert_eq!(err.message(), "gave up after 3 attempts");
+        let source = std::error::Error::source(&err).expect("last attempt's error");
+        assert_eq!(source.to_string(), "TelemetryError: flaky");
+    }
+
+    #[test]
+    fn backoff_waits_on_injected_clock() {
+        let clock = MockClock::new(0);
+        let sink = RetrySink::new(
+            FlakySink {
+                failures: 2,
+                calls: AtomicU32::new(0),
+            },
+            3,
+            Duration::from_secs(10),
+        )
+        .with_clock(Arc::new(clock.clone()));
+
+        assert!(sink.send("t", b"x").is_ok());
+        assert_eq!(clock.now_millis(), 20_000);
+    }
+
+    #[test]
+    fn flush_reaches_buffered_inner_sink() {
+        let memory = InMemorySink::new();
+        let records = memory.records_arc();
+        let sink = RetrySink::new(BufferingSink::new(memory, 10), 3, Duration::ZERO);
+
+        sink.send("t", b"queued").expect("send");
+        assert!(records.lock().expect("lock").is_empty());
+
+        sink.flush().expect("flush");
+        assert_eq!(records.lock().expect("lock").len(), 1);
+    }
+}
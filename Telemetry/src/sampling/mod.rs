@@ -0,0 +1,363 @@
+//! Sampling sink decorator.
+//!
+//! Forwards only a subset of messages to the inner sink, which keeps the
+//! volume of high-frequency debug telemetry manageable while still showing
+//! trends.
+
+use crate::{layered_sink_name, TelemetryResult, TelemetrySink};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// How `SamplingSink` picks which messages to keep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SamplingStrategy {
+    /// Keep each message independently with the given probability in `[0, 1]`.
+    Probabilistic(f64),
+    /// Keep the first of every `n` messages (deterministic).
+    EveryN(usize),
+}
+
+/// Shared sampling decision logic.
+///
+/// **Why no `rand` dependency?** Sampling only needs a cheap, roughly uniform
+/// source; a xorshift generator in an atomic is enough and keeps the crate
+/// dependency-free for embedded builds.
+pub(crate) struct Sampler {
+    strategy: SamplingStrategy,
+    counter: AtomicU64,
+    rng_state: AtomicU64,
+}
+
+impl Sampler {
+    pub(crate) fn new(strategy: SamplingStrategy, seed: u64) -> Self {
+        let strategy = match strategy {
+            SamplingStrategy::Probabilistic(p) => {
+                SamplingStrategy::Probabilistic(p.clamp(0.0, 1.0))
+            }
+            SamplingStrategy::EveryN(n) => SamplingStrategy::EveryN(n.max(1)),
+        };
+        Self {
+            strategy,
+            counter: AtomicU64::new(0),
+            // xorshift must never be seeded with zero
+            rng_state: AtomicU64::new(seed.max(1)),
+        }
+    }
+
+    pub(crate) fn strategy(&self) -> SamplingStrategy {
+        self.strategy
+    }
+
+    /// Decide whether the next message should be kept.
+    pub(crate) fn should_keep(&self) -> bool {
+        match self.strategy {
+            SamplingStrategy::EveryN(n) => {
+                self.counter.fetch_add(1, Ordering::Relaxed) % n as u64 == 0
+            }
+            SamplingStrategy::Probabilistic(p) => {
+                let sample = (self.next_random() >> 11) as f64 / (1u64 << 53) as f64;
+                sample < p
+            }
+        }
+    }
+
+    fn next_random(&self) -> u64 {
+        let mut current = self.rng_state.load(Ordering::Relaxed);
+        loop {
+            let mut x = current;
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            match self.rng_state.compare_exchange_weak(
+                current,
+                x,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return x,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+}
+
+/// Seed derived from the system clock for non-reproducible sampling.
+pub(crate) fn time_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0x9e37_79b9_7f4a_7c15)
+}
+
+/// A sink that forwards a sampled subset of messages to an inner sink.
+///
+/// Dropped messages are not an error: `send` returns `Ok(())` for them.
+pub struct SamplingSink<S: TelemetrySink> {
+    inner: S,
+    sampler: Sampler,
+    sent: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl<S: TelemetrySink> SamplingSink<S> {
+    /// Create a sampling sink with the given strategy.
+    ///
+    /// `Probabilistic` rates are clamped to `[0, 1]`; `EveryN(0)` is treated
+    /// as `EveryN(1)`.
+    pub fn new(inner: S, strategy: SamplingStrategy) -> Self {
+        Self::with_seed(inner, strategy, time_seed())
+    }
+
+    /// Create a sampling sink with a fixed random seed.
+    ///
+    /// Useful in tests to make `Probabilistic` sampling reproducible.
+    pub fn with_seed(inner: S, strategy: SamplingStrategy, seed: u64) -> Self {
+        Self {
+            inner,
+            sampler: Sampler::new(strategy, seed),
+            sent: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Strategy in effect (after clamping).
+    pub fn strategy(&self) -> SamplingStrategy {
+        self.sampler.strategy()
+    }
+
+    /// Number of messages forwarded to the inner sink.
+    pub fn sent_count(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    /// Number of messages dropped by sampling.
+    pub fn dropped_count(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for SamplingSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("sampling", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        if !self.sampler.should_keep() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
+        self.inner.send(topic, payload)?;
+        self.sent.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+/// A sink that forwards the first `warmup` messages unconditionally, then
+/// samples the rest with `strategy`.
+///
+/// Useful to see every message of a startup burst without paying for full
+/// volume afterwards. Sampling starts fresh at the transition, so with
+/// `EveryN` the first message after the warmup is kept.
+pub struct AdaptiveSamplingSink<S: TelemetrySink> {
+    inner: S,
+    warmup: u64,
+    sampler: Sampler,
+    seen: AtomicU64,
+    sent: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl<S: TelemetrySink> AdaptiveSamplingSink<S> {
+    /// Forward `warmup` messages, then sample with `strategy` (clamped as
+    /// in `SamplingSink::new`).
+    pub fn new(inner: S, warmup: u64, strategy: SamplingStrategy) -> Self {
+        Self::with_seed(inner, warmup, strategy, time_seed())
+    }
+
+    /// Like `new`, with a fixed random seed for reproducible sampling.
+    pub fn with_seed(inner: S, warmup: u64, strategy: SamplingStrategy, seed: u64) -> Self {
+        Self {
+            inner,
+            warmup,
+            sampler: Sampler::new(strategy, seed),
+            seen: AtomicU64::new(0),
+            sent: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Number of messages forwarded before sampling starts.
+    pub fn warmup(&self) -> u64 {
+        self.warmup
+    }
+
+    /// Strategy used after the warmup (after clamping).
+    pub fn strategy(&self) -> SamplingStrategy {
+        self.sampler.strategy()
+    }
+
+    /// Whether the warmup is over and messages are being sampled.
+    pub fn is_sampling(&self) -> bool {
+        self.seen_count() >= self.warmup
+    }
+
+    /// Number of messages received, kept or not.
+    pub fn seen_count(&self) -> u64 {
+        self.seen.load(Ordering::Relaxed)
+    }
+
+    /// Number of messages forwarded to the inner sink.
+    pub fn sent_count(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    /// Number of messages dropped by sampling.
+    pub fn dropped_count(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for AdaptiveSamplingSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("adaptive_sampling", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let warming_up = self.seen.fetch_add(1, Ordering::Relaxed) < self.warmup;
+        if !warming_up && !self.sampler.should_keep() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
+        self.inner.send(topic, payload)?;
+        self.sent.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemorySink;
+
+    #[test]
+    fn every_n_keeps_exact_fraction() {
+        let inner = InMemorySink::new();
+        let records = inner.records_arc();
+        let sink = SamplingSink::new(inner, SamplingStrategy::EveryN(10));
+
+        for i in 0..100 {
+            sink.send("debug/trace", format!("{}", i).as_bytes())
+                .expect("send");
+        }
+
+        let records = records.lock().expect("lock");
+        assert_eq!(records.len(), 10);
+        assert_eq!(records[0].1, b"0".to_vec());
+        assert_eq!(records[1].1, b"10".to_vec());
+        assert_eq!(sink.sent_count(), 10);
+        assert_eq!(sink.dropped_count(), 90);
+    }
+
+    #[test]
+    fn probabilistic_extremes() {
+        let all =
+            SamplingSink::with_seed(InMemorySink::new(), SamplingStrategy::Probabilistic(1.0), 7);
+        let none =
+            SamplingSink::with_seed(InMemorySink::new(), SamplingStrategy::Probabilistic(0.0), 7);
+        for _ in 0..50 {
+            all.send("t", b"x").expect("send");
+            none.send("t", b"x").expect("send");
+        }
+        assert_eq!(all.sent_count(), 50);
+        assert_eq!(none.sent_count(), 0);
+        assert_eq!(none.dropped_count(), 50);
+    }
+
+    #[test]
+    fn probabilistic_rate_is_roughly_respected() {
+        let sink = SamplingSink::with_seed(
+            InMemorySink::new(),
+            SamplingStrategy::Probabilistic(0.25),
+            42,
+        );
+        for _ in 0..4000 {
+            sink.send("t", b"x").expect("send");
+        }
+        let sent = sink.sent_count();
+        assert!((800..1200).contains(&sent), "sent {} of 4000", sent);
+        assert_eq!(sink.sent_count() + sink.dropped_count(), 4000);
+    }
+
+    #[test]
+    fn strategy_is_clamped() {
+        let sink = SamplingSink::new(InMemorySink::new(), SamplingStrategy::Probabilistic(3.0));
+        assert_eq!(sink.strategy(), SamplingStrategy::Probabilistic(1.0));
+        let sink = SamplingSink::new(InMemorySink::new(), SamplingStrategy::EveryN(0));
+        assert_eq!(sink.strategy(), SamplingStrategy::EveryN(1));
+    }
+
+    #[test]
+    fn adaptive_forwards_warmup_then_every_n() {
+        let sink = AdaptiveSamplingSink::new(InMemorySink::new(), 20, SamplingStrategy::EveryN(10));
+        for i in 0..19 {
+            sink.send("boot", format!("{}", i).as_bytes())
+                .expect("send");
+        }
+        assert!(!sink.is_sampling());
+        for i in 19..120 {
+            sink.send("boot", format!("{}", i).as_bytes())
+                .expect("send");
+        }
+
+        assert!(sink.is_sampling());
+        assert_eq!(sink.warmup(), 20);
+        assert_eq!(sink.seen_count(), 120);
+        // All 20 warmup messages, then 1 in 10 of the 100 extras
+        assert_eq!(sink.sent_count(), 30);
+        assert_eq!(sink.dropped_count(), 90);
+        let records = sink.inner().records.lock().expect("lock");
+        assert_eq!(records[19].1, b"19".to_vec());
+        assert_eq!(records[20].1, b"20".to_vec());
+        assert_eq!(records[21].1, b"30".to_vec());
+    }
+
+    #[test]
+    fn adaptive_samples_extras_probabilistically() {
+        let sink = AdaptiveSamplingSink::with_seed(
+            InMemorySink::new(),
+            100,
+            SamplingStrategy::Probabilistic(0.25),
+            42,
+        );
+        for _ in 0..4100 {
+            sink.send("t", b"x").expect("send");
+        }
+        let sampled = sink.sent_count() - 100;
+        assert!(
+            (800..1200).contains(&sampled),
+            "sampled {} of 4000",
+            sampled
+        );
+        assert_eq!(sink.sent_count() + sink.dropped_count(), 4100);
+    }
+}
@@ -0,0 +1,188 @@
+//! Delayed sends.
+//!
+//! `TelemetryClient::send_after` emits a message once a delay has passed,
+//! e.g. a heartbeat or a deferred retry, and returns a `ScheduledSend`
+//! handle that can cancel it.
+//!
+//! With the `async` feature the wait runs on a tokio timer task; without
+//! it, on a dedicated thread. Either way the message goes through the
+//! client's normal send path (prefix, headers, limits and counters).
+
+use crate::{TelemetryClient, TelemetryError, TelemetryMessage, TelemetryResult};
+use std::time::Duration;
+
+fn cancelled() -> TelemetryError {
+    TelemetryError::new("scheduled send was cancelled")
+}
+
+/// Handle to a pending `send_after`.
+///
+/// Dropping the handle does not cancel the send.
+#[cfg(feature = "async")]
+pub struct ScheduledSend {
+    task: tokio::task::JoinHandle<TelemetryResult<()>>,
+}
+
+#[cfg(feature = "async")]
+impl ScheduledSend {
+    /// Prevent the send if it has not started yet.
+    pub fn cancel(&self) {
+        self.task.abort();
+    }
+
+    /// Whether the send has completed or been cancelled.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Wait for the send and return its outcome; a cancelled send is an
+    /// error.
+    pub async fn result(self) -> TelemetryResult<()> {
+        match self.task.await {
+            Ok(result) => result,
+            Err(e) if e.is_cancelled() => Err(cancelled()),
+            Err(e) => Err(TelemetryError::new(format!("scheduled send failed: {}", e))),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl TelemetryClient {
+    /// Send `msg` once `delay` has elapsed.
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn send_after(&self, msg: TelemetryMessage, delay: Duration) -> ScheduledSend {
+        let client = self.clone();
+        ScheduledSend {
+            task: tokio::spawn(async move {
+                tokio::time::sleep(delay).await;
+                client.send_message(&msg)
+            }),
+        }
+    }
+}
+
+/// Handle to a pending `send_after`.
+///
+/// Dropping the handle does not cancel the send.
+#[cfg(not(feature = "async"))]
+pub struct ScheduledSend {
+    cancel: std::sync::Arc<(std::sync::Mutex<bool>, std::sync::Condvar)>,
+    thread: std::thread::JoinHandle<TelemetryResult<()>>,
+}
+
+#[cfg(not(feature = "async"))]
+impl ScheduledSend {
+    /// Prevent the send if it has not started yet.
+    pub fn cancel(&self) {
+        let (flag, wake) = &*self.cancel;
+        if let Ok(mut cancelled) = flag.lock() {
+            *cancelled = true;
+            wake.notify_all();
+        }
+    }
+
+    /// Whether the send has completed or been cancelled.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Block until the send happens and return its outcome; a cancelled send
+    /// is an error.
+    pub fn wait(self) -> TelemetryResult<()> {
+        self.thread
+            .join()
+            .map_err(|_| TelemetryError::new("scheduled send panicked"))?
+    }
+}
+
+#[cfg(not(feature = "async"))]
+impl TelemetryClient {
+    /// Send `msg` from a background thread once `delay` has elapsed.
+    pub fn send_after(&self, msg: TelemetryMessage, delay: Duration) -> ScheduledSend {
+        use std::sync::{Arc, Condvar, Mutex};
+
+        let cancel = Arc::new((Mutex::new(false), Condvar::new()));
+        let client = self.clone();
+        let signal = Arc::clone(&cancel);
+        let thread = std::thread::spawn(move || {
+            let (flag, wake) = &*signal;
+            let guard = flag
+                .lock()
+                .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+            let (guard, _) = wake
+                .wait_timeout_while(guard, delay, |cancelled| !*cancelled)
+                .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+            if *guard {
+                return Err(cancelled());
+            }
+            drop(guard);
+            client.send_message(&msg)
+        });
+        ScheduledSend { cancel, thread }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemorySink;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn delivered(sink: &InMemorySink) -> usize {
+        sink.records.lock().expect("lock").len()
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn delivers_after_delay_unless_cancelled() {
+        let sink = Arc::new(InMemorySink::new());
+        let client = TelemetryClient::new(sink.clone());
+
+        let pending = client.send_after(
+            TelemetryMessage::new("heartbeat", json!(1)),
+            Duration::from_millis(100),
+        );
+        let cancelled = client.send_after(
+            TelemetryMessage::new("retry", json!(2)),
+            Duration::from_millis(100),
+        );
+        cancelled.cancel();
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(delivered(&sink), 0);
+
+        pending.result().await.expect("sent");
+        assert!(cancelled.result().await.is_err());
+        let records = sink.records.lock().expect("lock");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].0, "heartbeat");
+    }
+
+    #[cfg(not(feature = "async"))]
+    #[test]
+    fn delivers_after_delay_unless_cancelled() {
+        let sink = Arc::new(InMemorySink::new());
+        let client = TelemetryClient::new(sink.clone());
+
+        let pending = client.send_after(
+            TelemetryMessage::new("heartbeat", json!(1)),
+            Duration::from_millis(100),
+        );
+        let cancelled = client.send_after(
+            TelemetryMessage::new("retry", json!(2)),
+            Duration::from_millis(100),
+        );
+        cancelled.cancel();
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(delivered(&sink), 0);
+
+        pending.wait().expect("sent");
+        assert!(cancelled.wait().is_err());
+        let records = sink.records.lock().expect("lock");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].0, "heartbeat");
+    }
+}
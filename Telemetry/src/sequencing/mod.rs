@@ -0,0 +1,265 @@
+//! Per-topic sequence numbers for gap detection.
+//!
+//! `SequencingSink` stamps every message with a monotonically increasing
+//! `seq` header per topic; `SequenceTracker` lets consumers compare received
+//! values and notice dropped telemetry.
+//!
+//! **Why per topic?** Topics are often routed to different collectors, so a
+//! single global counter would show gaps on every consumer that subscribes
+//! to only part of the traffic.
+
+use crate::source::decode_message;
+use crate::{layered_sink_name, TelemetryError, TelemetryMessage, TelemetryResult, TelemetrySink};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Header carrying the per-topic sequence number.
+pub const SEQ_HEADER: &str = "seq";
+
+/// A sink that attaches a per-topic `seq` header before forwarding.
+///
+/// Payloads are decoded as `TelemetryMessage` JSON; anything else is wrapped
+/// in a new message so the header has somewhere to live (binary payloads
+/// base64-encoded, see `source::ENCODING_HEADER`). Sequence numbers
+/// start at 1 and are consumed even if the inner sink fails, so a failed
+/// send shows up downstream as a gap. Sends are serialized so messages reach
+/// the inner sink in sequence order.
+pub struct SequencingSink<S: TelemetrySink> {
+    inner: S,
+    counters: Mutex<HashMap<String, u64>>,
+}
+
+impl<S: TelemetrySink> SequencingSink<S> {
+    /// Wrap `inner` with per-topic sequence numbering.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            counters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Last sequence number assigned on `topic`, if any.
+    pub fn last_seq(&self, topic: &str) -> Option<u64> {
+        self.counters.lock().ok()?.get(topic).copied()
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for SequencingSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("sequencing", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let mut message = decode_message(topic, payload);
+        // Held across the forward so a later number cannot overtake this one
+        let mut counters = self
+            .counters
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+        let seq = counters.entry(topic.to_string()).or_insert(0);
+        *seq += 1;
+        let seq = *seq;
+        message
+            .headers
+            .insert(SEQ_HEADER.to_string(), seq.to_string());
+        let encoded = serde_json::to_vec(&message)
+            .map_err(|e| TelemetryError::Serialization(format!("sequenced message: {}", e)))?;
+        self.inner.send(topic, &encoded)
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+/// Outcome of feeding a sequence number to `SequenceTracker`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceCheck {
+    /// First value seen on the topic, or exactly the expected next value.
+    InOrder,
+    /// One or more values were skipped.
+    Gap { expected: u64, received: u64 },
+    /// A value at or below the last one seen (duplicate or reordered).
+    Stale { last: u64, received: u64 },
+}
+
+/// Consumer-side tracker that flags gaps in per-topic sequence numbers.
+///
+/// The first value seen on a topic is taken as the baseline, so consumers
+/// that subscribe late do not report the messages they never asked for.
+#[derive(Debug, Default)]
+pub struct SequenceTracker {
+    last: Mutex<HashMap<String, u64>>,
+    missing: AtomicU64,
+}
+
+impl SequenceTracker {
+    /// Create an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `seq` for `topic` and report whether it followed the last one.
+    pub fn observe(&self, topic: &str, seq: u64) -> TelemetryResult<SequenceCheck> {
+        let mut last = self
+            .last
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+        let check = match last.get(topic).copied() {
+            None => SequenceCheck::InOrder,
+            Some(prev) if seq == prev + 1 => SequenceCheck::InOrder,
+            Some(prev) if seq <= prev => {
+                return Ok(SequenceCheck::Stale {
+                    last: prev,
+                    received: seq,
+                })
+            }
+            Some(prev) => SequenceCheck::Gap {
+                expected: prev + 1,
+                received: seq,
+            },
+        };
+        last.insert(topic.to_string(), seq);
+        if let SequenceCheck::Gap { expected, received } = check {
+            self.missing
+                .fetch_add(received - expected, Ordering::Relaxed);
+        }
+        Ok(check)
+    }
+
+    /// Record the `seq` header of a received message.
+    ///
+    /// Returns an error if the header is missing or not a number.
+    pub fn observe_message(&self, message: &TelemetryMessage) -> TelemetryResult<SequenceCheck> {
+        let seq = message
+            .headers
+            .get(SEQ_HEADER)
+            .ok_or_else(|| TelemetryError::new(format!("missing '{}' header", SEQ_HEADER)))?
+            .parse::<u64>()
+            .map_err(|e| TelemetryError::new(format!("invalid '{}' header: {}", SEQ_HEADER, e)))?;
+        self.observe(&message.topic, seq)
+    }
+
+    /// Total number of sequence values missing across all topics.
+    pub fn missing_count(&self) -> u64 {
+        self.missing.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemorySink;
+
+    fn seqs(records: &[(String, Vec<u8>)], topic: &str) -> Vec<u64> {
+        records
+            .iter()
+            .filter(|(t, _)| t == topic)
+            .map(|(_, payload)| {
+                let msg: TelemetryMessage = serde_json::from_slice(payload).expect("decode");
+                msg.headers[SEQ_HEADER].parse().expect("seq")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sequences_are_independent_per_topic() {
+        let sink = SequencingSink::new(InMemorySink::new());
+        for i in 0..3 {
+            sink.send("a", format!("{}", i).as_bytes()).expect("send a");
+            sink.send("b", b"\"x\"").expect("send b");
+        }
+        sink.send("a", b"not json").expect("send a");
+
+        let records = sink.inner().records.lock().expect("lock").clone();
+        assert_eq!(seqs(&records, "a"), vec![1, 2, 3, 4]);
+        assert_eq!(seqs(&records, "b"), vec![1, 2, 3]);
+        assert_eq!(sink.last_seq("a"), Some(4));
+        assert_eq!(sink.last_seq("c"), None);
+    }
+
+    #[test]
+    fn concurrent_senders_deliver_in_sequence_order() {
+        let sink = std::sync::Arc::new(SequencingSink::new(InMemorySink::new()));
+        let threads: Vec<_> = (0..4)
+            .map(|t| {
+                let sink = std::sync::Arc::clone(&sink);
+                std::thread::spawn(move || {
+                    for i in 0..50 {
+                        sink.send("a", format!("{}", t * 100 + i).as_bytes())
+                            .expect("send");
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().expect("join");
+        }
+
+        let records = sink.inner().records.lock().expect("lock").clone();
+        assert_eq!(seqs(&records, "a"), (1..=200).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn existing_message_headers_are_preserved() {
+        let sink = SequencingSink::new(InMemorySink::new());
+        let msg = TelemetryMessage::builder()
+            .topic("a")
+            .payload(serde_json::json!(1))
+            .header("unit", "C")
+            .build()
+            .expect("build");
+        sink.send("a", msg.to_json().as_bytes()).expect("send");
+
+        let records = sink.inner().records.lock().expect("lock").clone();
+        let received: TelemetryMessage = serde_json::from_slice(&records[0].1).expect("decode");
+        assert_eq!(received.headers["unit"], "C");
+        assert_eq!(received.headers[SEQ_HEADER], "1");
+    }
+
+    #[test]
+    fn tracker_flags_skipped_sequence() {
+        let tracker = SequenceTracker::new();
+        assert_eq!(
+            tracker.observe("a", 1).expect("observe"),
+            SequenceCheck::InOrder
+        );
+        assert_eq!(
+            tracker.observe("b", 1).expect("observe"),
+            SequenceCheck::InOrder
+        );
+        assert_eq!(
+            tracker.observe("a", 2).expect("observe"),
+            SequenceCheck::InOrder
+        );
+        assert_eq!(
+            tracker.observe("a", 5).expect("observe"),
+            SequenceCheck::Gap {
+                expected: 3,
+                received: 5
+            }
+        );
+        assert_eq!(
+            tracker.observe("a", 4).expect("observe"),
+            SequenceCheck::Stale {
+                last: 5,
+                received: 4
+            }
+        );
+        assert_eq!(
+            tracker.observe("b", 2).expect("observe"),
+            SequenceCheck::InOrder
+        );
+        assert_eq!(tracker.missing_count(), 2);
+    }
+}
@@ -0,0 +1,256 @@
+//! Coordinated shutdown of a client's sinks.
+//!
+//! **Why?** A process stopping on SIGTERM has to flush the client's sink
+//! stack and close sinks that own threads or connections, in the right
+//! order, without hanging on a transport that no longer answers.
+//! `TelemetryClient::shutdown` does all of that behind one call.
+
+use crate::{ShutdownSink, TelemetryError, TelemetryResult, TelemetrySink};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub(crate) type ShutdownHook = Box<dyn FnOnce() -> TelemetryResult<()> + Send>;
+
+/// Outcome of a coordinated shutdown.
+#[derive(Debug, Default)]
+pub struct ShutdownReport {
+    /// Steps that completed successfully, in the order they ran.
+    pub completed: Vec<String>,
+    /// Steps that returned an error.
+    pub failed: Vec<(String, TelemetryError)>,
+    /// Steps still running when the timeout expired, or never started
+    /// because it already had.
+    pub timed_out: Vec<String>,
+}
+
+impl ShutdownReport {
+    /// Whether every step completed in time without error.
+    pub fn is_clean(&self) -> bool {
+        self.failed.is_empty() && self.timed_out.is_empty()
+    }
+}
+
+/// Registry of sinks to flush or close when the application shuts down.
+///
+/// Steps run one at a time in reverse registration order, so registering a
+/// sink before the decorators that wrap it closes the decorators (and their
+/// background threads) first. Each step runs on its own thread; one still
+/// running at the deadline is reported and abandoned.
+#[derive(Default)]
+pub struct ShutdownCoordinator {
+    steps: Mutex<Vec<(String, ShutdownHook)>>,
+}
+
+impl ShutdownCoordinator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Flush `sink` at shutdown; for sinks shared with a client stack.
+    pub fn register_flush(&self, name: impl Into<String>, sink: Arc<dyn TelemetrySink>) {
+        self.register_hook(name, move || sink.flush());
+    }
+
+    /// Take ownership of `sink` and `close` it at shutdown.
+    pub fn register_close<S>(&self, name: impl Into<String>, sink: S)
+    where
+        S: ShutdownSink + 'static,
+    {
+        self.register_hook(name, move || sink.close());
+    }
+
+    /// Run `hook` at shutdown.
+    pub fn register_hook<F>(&self, name: impl Into<String>, hook: F)
+    where
+        F: FnOnce() -> TelemetryResult<()> + Send + 'static,
+    {
+        self.steps
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push((name.into(), Box::new(hook)));
+    }
+
+    /// Number of registered steps not yet run.
+    pub fn len(&self) -> usize {
+        self.steps.lock().map_or(0, |steps| steps.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Run every registered step within `timeout` and clear the registry.
+    ///
+    /// A timeout too large to represent (e.g. `Duration::MAX`) waits for
+    /// every step to finish.
+    pub fn shutdown(&self, timeout: Duration) -> ShutdownReport {
+        run_steps(self.take_steps(), Instant::now().checked_add(timeout))
+    }
+
+    /// Registered steps in the order they should run.
+    pub(crate) fn take_steps(&self) -> Vec<(String, ShutdownHook)> {
+        let mut steps = std::mem::take(
+            &mut *self
+                .steps
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        );
+        steps.reverse();
+        steps
+    }
+}
+
+/// Run `steps` in order, each on its own thread, until `deadline` (or to
+/// completion if there is none).
+pub(crate) fn run_steps(
+    steps: Vec<(String, ShutdownHook)>,
+    deadline: Option<Instant>,
+) -> ShutdownReport {
+    let mut report = ShutdownReport::default();
+    let mut steps = steps.into_iter();
+    for (name, hook) in steps.by_ref() {
+        let remaining = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        if remaining.is_some_and(|remaining| remaining.is_zero()) {
+            report.timed_out.push(name);
+            break;
+        }
+        let (done, wait) = channel();
+        let spawned = std::thread::Builder::new()
+            .name(format!("shutdown-{}", name))
+            .spawn(move || {
+                let _ = done.send(hook());
+            });
+        if let Err(e) = spawned {
+            report.failed.push((
+                name,
+                TelemetryError::new(format!("cannot spawn shutdown thread: {}", e)),
+            ));
+            continue;
+        }
+        let outcome = match remaining {
+            Some(remaining) => wait.recv_timeout(remaining),
+            None => wait.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match outcome {
+            Ok(Ok(())) => report.completed.push(name),
+            Ok(Err(e)) => report.failed.push((name, e)),
+            Err(RecvTimeoutError::Timeout) => {
+                log::warn!("shutdown step '{}' did not finish in time", name);
+                report.timed_out.push(name);
+            }
+            Err(RecvTimeoutError::Disconnected) => report
+                .failed
+                .push((name, TelemetryError::new("shutdown step panicked"))),
+        }
+    }
+    report.timed_out.extend(steps.map(|(name, _)| name));
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BufferingSink, InMemorySink, TelemetryClient};
+
+    /// Sink whose flush blocks far longer than any test timeout.
+    struct HungSink;
+
+    impl TelemetrySink for HungSink {
+        fn sink_name(&self) -> &'static str {
+            "hung"
+        }
+
+        fn send(&self, _topic: &str, _payload: &[u8]) -> TelemetryResult<()> {
+            Ok(())
+        }
+
+        fn flush(&self) -> TelemetryResult<()> {
+            std::thread::sleep(Duration::from_secs(5));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn client_shutdown_flushes_buffered_messages() {
+        let inner = InMemorySink::new();
+        let records = inner.records_arc();
+        let client = TelemetryClient::new(Arc::new(BufferingSink::new(inner, 10)));
+        for i in 0..3 {
+            client
+                .send_binary("t", format!("{}", i).as_bytes())
+                .expect("send");
+        }
+        assert!(records.lock().expect("lock").is_empty());
+
+        let report = client.shutdown(Duration::from_secs(5));
+
+        assert!(report.is_clean(), "{:?}", report);
+        assert_eq!(report.completed, vec!["buffering(in_memory)".to_string()]);
+        assert_eq!(records.lock().expect("lock").len(), 3);
+    }
+
+    #[test]
+    fn steps_run_in_reverse_registration_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let coordinator = Arc::new(ShutdownCoordinator::new());
+        for name in ["transport", "decorator"] {
+            let log = Arc::clone(&log);
+            coordinator.register_hook(name, move || {
+                log.lock().expect("lock").push(name);
+                Ok(())
+            });
+        }
+        let inner = InMemorySink::new();
+        let records = inner.records_arc();
+        let buffer = BufferingSink::new(inner, 10);
+        buffer.send("t", b"x").expect("send");
+        coordinator.register_close("buffer", buffer);
+        coordinator.register_hook("broken", || Err(TelemetryError::new("boom")));
+        assert_eq!(coordinator.len(), 4);
+
+        let client = TelemetryClient::new(Arc::new(InMemorySink::new()))
+            .with_shutdown_coordinator(Arc::clone(&coordinator));
+        let report = client.shutdown(Duration::from_secs(5));
+
+        assert_eq!(
+            report.completed,
+            ["in_memory", "buffer", "decorator", "transport"]
+        );
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, "broken");
+        assert!(coordinator.is_empty());
+        assert_eq!(*log.lock().expect("lock"), ["decorator", "transport"]);
+        assert_eq!(records.lock().expect("lock").len(), 1);
+    }
+
+    #[test]
+    fn unrepresentable_timeout_waits_for_every_step() {
+        let coordinator = ShutdownCoordinator::new();
+        coordinator.register_hook("slow", || {
+            std::thread::sleep(Duration::from_millis(20));
+            Ok(())
+        });
+        let client = TelemetryClient::new(Arc::new(InMemorySink::new()));
+
+        let report = coordinator.shutdown(Duration::MAX);
+        assert_eq!(report.completed, ["slow"]);
+        assert!(client.shutdown(Duration::MAX).is_clean());
+    }
+
+    #[test]
+    fn hung_sink_is_reported_after_timeout() {
+        let coordinator = Arc::new(ShutdownCoordinator::new());
+        coordinator.register_hook("after", || Ok(()));
+        let client = TelemetryClient::new(Arc::new(HungSink))
+            .with_shutdown_coordinator(Arc::clone(&coordinator));
+
+        let started = Instant::now();
+        let report = client.shutdown(Duration::from_millis(50));
+
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(!report.is_clean());
+        assert!(report.completed.is_empty());
+        assert_eq!(report.timed_out, ["hung", "after"]);
+    }
+}
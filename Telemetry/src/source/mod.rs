@@ -0,0 +1,178 @@
+//! Receive-side telemetry abstraction.
+//!
+//! Telemetry is bidirectional: modules publish readings through a
+//! `TelemetrySink` and receive control commands through a `TelemetrySource`.
+//! Subscriptions use MQTT-style topic filters (`+` matches one level, `#`
+//! matches the remaining levels); see `crate::topic`.
+
+use crate::topic::{matches, validate_filter};
+use crate::{TelemetryError, TelemetryMessage, TelemetryResult};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+/// A source of incoming telemetry messages.
+pub trait TelemetrySource: Send + Sync {
+    /// Subscribe to messages whose topic matches `topic_filter`.
+    ///
+    /// **Why a channel?** A `Receiver` lets the caller choose between blocking
+    /// (`recv`), polling (`try_recv`) or timed waits without the source
+    /// dictating a threading model.
+    ///
+    /// Only messages published after the call are delivered.
+    fn subscribe(&self, topic_filter: &str) -> TelemetryResult<Receiver<TelemetryMessage>>;
+}
+
+type Subscriber = (String, Sender<TelemetryMessage>);
+
+/// An in-process source fed by `InMemorySink::with_source`.
+///
+/// Cloning is cheap and all clones share the same subscriber list.
+#[derive(Clone, Default)]
+pub struct InMemorySource {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+impl InMemorySource {
+    /// Create a source with no subscribers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deliver a payload to every subscriber whose filter matches `topic`.
+    ///
+    /// Payloads produced by `TelemetryClient::send_message` are decoded back
+    /// into the original message; other JSON payloads are wrapped as-is, and
+    /// non-JSON payloads become a (lossy) UTF-8 string.
+    pub fn publish(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let mut subscribers = self
+            .subscribers
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?;
+        if !subscribers.iter().any(|(f, _)| matches(f, topic)) {
+            return Ok(());
+        }
+        // Dropped receivers are pruned as we go.
+        subscribers.retain(|(filter, tx)| {
+            !matches(filter, topic) || tx.send(decode_message(topic, payload)).is_ok()
+        });
+        Ok(())
+    }
+}
+
+/// Header set to `"base64"` on messages whose payload string is the base64
+/// encoding of a binary payload.
+pub const ENCODING_HEADER: &str = "encoding";
+
+/// Decode a raw payload into a message, falling back to plain JSON or text.
+///
+/// Payloads that are not UTF-8 become a base64 string marked with
+/// `ENCODING_HEADER`, so binary data survives re-encoding unchanged.
+pub(crate) fn decode_message(topic: &str, payload: &[u8]) -> TelemetryMessage {
+    if let Ok(mut msg) = serde_json::from_slice::<TelemetryMessage>(payload) {
+        msg.topic = topic.to_string();
+        return msg;
+    }
+    if let Ok(value) = serde_json::from_slice::<serde_json::Value>(payload) {
+        return TelemetryMessage::new(topic, value);
+    }
+    match std::str::from_utf8(payload) {
+        Ok(text) => TelemetryMessage::new(topic, serde_json::Value::String(text.to_string())),
+        Err(_) => {
+            let mut msg =
+                TelemetryMessage::new(topic, serde_json::Value::String(BASE64.encode(payload)));
+            msg.headers
+                .insert(ENCODING_HEADER.to_string(), "base64".to_string());
+            msg
+        }
+    }
+}
+
+impl TelemetrySource for InMemorySource {
+    fn subscribe(&self, topic_filter: &str) -> TelemetryResult<Receiver<TelemetryMessage>> {
+        validate_filter(topic_filter)?;
+        let (tx, rx) = channel();
+        self.subscribers
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))?
+            .push((topic_filter.to_string(), tx));
+        Ok(rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemorySink, TelemetryClient, TelemetrySink};
+
+    #[test]
+    fn wildcard_matching() {
+        assert!(matches("cmd/+/reset", "cmd/motor/reset"));
+        assert!(!matches("cmd/+/reset", "cmd/motor/left/reset"));
+        assert!(matches("cmd/#", "cmd/motor/left/reset"));
+        assert!(matches("cmd/#", "cmd"));
+        assert!(!matches("cmd/motor", "cmd/motor/reset"));
+        assert!(!matches("cmd/+", "cmd"));
+    }
+
+    #[test]
+    fn invalid_filters_are_rejected() {
+        let source = InMemorySource::new();
+        assert!(source.subscribe("").is_err());
+        assert!(source.subscribe("cmd/#/reset").is_err());
+        assert!(source.subscribe("cmd/mo+").is_err());
+    }
+
+    #[test]
+    fn sink_sends_reach_matching_subscribers() {
+        let source = InMemorySource::new();
+        let sink = InMemorySink::new().with_source(source.clone());
+        let client = TelemetryClient::new(Arc::new(sink));
+
+        let motors = source.subscribe("cmd/+/reset").expect("subscribe");
+        let everything = source.subscribe("#").expect("subscribe");
+
+        let msg = TelemetryMessage::new("cmd/motor/reset", serde_json::json!({ "force": true }));
+        client.send_message(&msg).expect("send");
+        client.send_binary("cmd/motor/speed", b"42").expect("send");
+
+        let received = motors.try_recv().expect("reset command");
+        assert_eq!(received, msg);
+        assert!(motors.try_recv().is_err());
+
+        assert_eq!(
+            everything.try_recv().expect("first").topic,
+            "cmd/motor/reset"
+        );
+        let speed = everything.try_recv().expect("second");
+        assert_eq!(speed.payload, serde_json::json!(42));
+    }
+
+    #[test]
+    fn binary_payloads_are_base64_encoded() {
+        let text = decode_message("t", b"plain text");
+        assert_eq!(text.payload, serde_json::json!("plain text"));
+        assert!(!text.headers.contains_key(ENCODING_HEADER));
+
+        let bytes = [0x00, 0xff, 0xfe, 0x80];
+        let binary = decode_message("t", &bytes);
+        assert_eq!(binary.headers[ENCODING_HEADER], "base64");
+        let encoded = binary.payload.as_str().expect("string payload");
+        assert_eq!(BASE64.decode(encoded).expect("base64"), bytes);
+    }
+
+    #[test]
+    fn messages_before_subscription_are_not_delivered() {
+        let source = InMemorySource::new();
+        let sink = InMemorySink::new().with_source(source.clone());
+
+        sink.send("cmd/stop", b"\"early\"").expect("send");
+        let rx = source.subscribe("cmd/stop").expect("subscribe");
+        sink.send("cmd/stop", b"\"late\"").expect("send");
+
+        let received = rx.try_recv().expect("late message");
+        assert_eq!(received.payload, serde_json::json!("late"));
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,45 @@
+//! Helpers shared by the unit tests of the HTTP-based sinks.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread::JoinHandle;
+
+/// Request as seen by the mock server: (request line, headers, body).
+pub(crate) type Captured = (String, Vec<String>, Vec<u8>);
+
+/// Serve exactly one request with the given status and return what was received.
+pub(crate) fn mock_server(status: u16) -> (String, JoinHandle<Captured>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let base = format!("http://{}", listener.local_addr().expect("addr"));
+    let handle = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().expect("accept");
+        let mut reader = BufReader::new(stream.try_clone().expect("clone"));
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).expect("request line");
+        let mut headers = Vec::new();
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).expect("header");
+            let line = line.trim_end().to_string();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(len) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                content_length = len.trim().parse().expect("length");
+            }
+            headers.push(line);
+        }
+        let mut body = vec![0u8; content_length];
+        reader.read_exact(&mut body).expect("body");
+        let mut stream = stream;
+        write!(
+            stream,
+            "HTTP/1.1 {} Mock\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            status
+        )
+        .expect("respond");
+        (request_line.trim_end().to_string(), headers, body)
+    });
+    (base, handle)
+}
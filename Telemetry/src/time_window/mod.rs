@@ -0,0 +1,311 @@
+//! Time-windowed batching sink decorator.
+//!
+//! Collects everything sent during a fixed window and hands it to the inner
+//! sink as one `send_batch`, so a transport with a native batch operation
+//! makes one request per window however busy the topic is.
+//!
+//! **Why not `BufferingSink`?** That flushes once a count is reached, so the
+//! delay before delivery depends on traffic. Here batches are cut by time
+//! and the batch size varies instead.
+
+use crate::auto_flush::AutoFlusher;
+use crate::clock::{elapsed_since, ClockCell};
+use crate::{
+    layered_sink_name, Clock, TelemetryError, TelemetryRecord, TelemetryResult, TelemetrySink,
+};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+#[derive(Default)]
+struct Window {
+    /// When the first record of the open window arrived.
+    start: Option<i64>,
+    records: Vec<TelemetryRecord>,
+}
+
+impl Window {
+    fn take(&mut self) -> Vec<TelemetryRecord> {
+        self.start = None;
+        std::mem::take(&mut self.records)
+    }
+}
+
+/// State shared with the poll thread.
+struct Core<S: TelemetrySink> {
+    inner: S,
+    window: Duration,
+    clock: ClockCell,
+    state: Mutex<Window>,
+}
+
+impl<S: TelemetrySink> Core<S> {
+    fn poll(&self) -> TelemetryResult<()> {
+        let now = self.clock.now_millis();
+        let due = self.take_due(&mut *self.lock()?, now);
+        self.deliver(due)
+    }
+
+    fn lock(&self) -> TelemetryResult<MutexGuard<'_, Window>> {
+        self.state
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))
+    }
+
+    fn take_due(&self, state: &mut Window, now: i64) -> Vec<TelemetryRecord> {
+        match state.start {
+            Some(start) if elapsed_since(now, start) >= self.window => state.take(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn deliver(&self, batch: Vec<TelemetryRecord>) -> TelemetryResult<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        self.inner.send_batch(&batch)
+    }
+}
+
+impl<S: TelemetrySink> Drop for Core<S> {
+    fn drop(&mut self) {
+        let state = self
+            .state
+            .get_mut()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let remaining = state.take();
+        if let Err(e) = self.deliver(remaining) {
+            log::warn!("TimeWindowSink dropped with undelivered batch: {}", e);
+        }
+    }
+}
+
+/// A sink that delivers everything sent within each `window` as one batch.
+///
+/// The first send to an idle sink opens a window. Once it has elapsed the
+/// batch is delivered by the next `send`, `poll` or `flush`, or by the poll
+/// thread if `with_poll_interval` started one; a window with nothing in it
+/// produces no batch. Anything still held when the sink is dropped is
+/// delivered then.
+pub struct TimeWindowSink<S: TelemetrySink> {
+    // Declared first so the poll thread is joined before the core is dropped
+    poller: Option<AutoFlusher>,
+    core: Arc<Core<S>>,
+}
+
+impl<S: TelemetrySink> TimeWindowSink<S> {
+    /// Deliver one batch per `window`.
+    pub fn new(inner: S, window: Duration) -> Self {
+        Self {
+            poller: None,
+            core: Arc::new(Core {
+                inner,
+                window,
+                clock: ClockCell::default(),
+                state: Mutex::new(Window::default()),
+            }),
+        }
+    }
+
+    /// Measure windows with `clock` instead of the system clock.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        self.core.clock.set(clock);
+        self
+    }
+
+    /// Length of each window.
+    pub fn window(&self) -> Duration {
+        self.core.window
+    }
+
+    /// Interval of the poll thread, if one is running.
+    pub fn poll_interval(&self) -> Option<Duration> {
+        self.poller.as_ref().map(AutoFlusher::interval)
+    }
+
+    /// Records held for the open window.
+    pub fn pending_count(&self) -> usize {
+        self.core
+            .state
+            .lock()
+            .map_or(0, |state| state.records.len())
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.core.inner
+    }
+
+    /// Deliver the open window's batch if the window has elapsed.
+    pub fn poll(&self) -> TelemetryResult<()> {
+        self.core.poll()
+    }
+}
+
+impl<S: TelemetrySink + 'static> TimeWindowSink<S> {
+    /// Also `poll` every `interval` from a background thread, so the last
+    /// window is delivered on time when sends stop (see the crate docs).
+    ///
+    /// `None` stops a running thread; it is also stopped and joined when
+    /// the sink is dropped.
+    pub fn with_poll_interval(mut self, interval: Option<Duration>) -> Self {
+        self.poller = None;
+        self.poller = interval.map(|interval| {
+            let core = Arc::clone(&self.core);
+            AutoFlusher::spawn("time_window", interval, move || core.poll())
+        });
+        self
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for TimeWindowSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("time_window", &[self.core.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let core = &self.core;
+        let now = core.clock.now_millis();
+        let due = {
+            let mut state = core.lock()?;
+            let due = core.take_due(&mut state, now);
+            state.start.get_or_insert(now);
+            state.records.push((topic.to_string(), payload.to_vec()));
+            due
+        };
+        core.deliver(due)
+    }
+
+    /// Deliver the open window early, then flush the inner sink.
+    fn flush(&self) -> TelemetryResult<()> {
+        let partial = self.core.lock()?.take();
+        self.core.deliver(partial)?;
+        self.core.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.core.inner.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockClock;
+
+    /// Sink that records each `send_batch` call separately.
+    #[derive(Default)]
+    struct BatchSink {
+        batches: Arc<Mutex<Vec<Vec<TelemetryRecord>>>>,
+    }
+
+    impl TelemetrySink for BatchSink {
+        fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+            self.send_batch(&[(topic.to_string(), payload.to_vec())])
+        }
+
+        fn send_batch(&self, records: &[TelemetryRecord]) -> TelemetryResult<()> {
+            self.batches.lock().expect("lock").push(records.to_vec());
+            Ok(())
+        }
+    }
+
+    fn sink(clock: &MockClock) -> TimeWindowSink<BatchSink> {
+        TimeWindowSink::new(BatchSink::default(), Duration::from_millis(100))
+            .with_clock(Arc::new(clock.clone()))
+    }
+
+    fn batches(sink: &TimeWindowSink<BatchSink>) -> Vec<Vec<TelemetryRecord>> {
+        sink.inner().batches.lock().expect("lock").clone()
+    }
+
+    #[test]
+    fn window_is_delivered_as_one_batch() {
+        let clock = MockClock::new(0);
+        let sink = sink(&clock);
+        assert_eq!(sink.window(), Duration::from_millis(100));
+
+        for (i, topic) in ["a", "b", "a"].into_iter().enumerate() {
+            sink.send(topic, &[i as u8]).expect("send");
+            clock.advance(Duration::from_millis(30));
+        }
+        assert!(batches(&sink).is_empty());
+        assert_eq!(sink.pending_count(), 3);
+
+        clock.advance(Duration::from_millis(10));
+        sink.poll().expect("poll");
+        assert_eq!(
+            batches(&sink),
+            vec![vec![
+                ("a".to_string(), vec![0]),
+                ("b".to_string(), vec![1]),
+                ("a".to_string(), vec![2]),
+            ]]
+        );
+        assert_eq!(sink.pending_count(), 0);
+    }
+
+    #[test]
+    fn empty_window_produces_no_batch() {
+        let clock = MockClock::new(0);
+        let sink = sink(&clock);
+
+        clock.advance(Duration::from_millis(250));
+        sink.poll().expect("poll");
+        sink.flush().expect("flush");
+        assert!(batches(&sink).is_empty());
+
+        sink.send("a", b"x").expect("send");
+        clock.advance(Duration::from_millis(100));
+        sink.poll().expect("poll");
+        clock.advance(Duration::from_millis(300));
+        sink.poll().expect("poll");
+        assert_eq!(batches(&sink).len(), 1);
+    }
+
+    #[test]
+    fn next_send_closes_elapsed_window() {
+        let clock = MockClock::new(0);
+        let sink = sink(&clock);
+        sink.send("a", b"1").expect("send");
+        clock.advance(Duration::from_millis(100));
+        sink.send("a", b"2").expect("send");
+
+        assert_eq!(batches(&sink), vec![vec![("a".to_string(), b"1".to_vec())]]);
+        assert_eq!(sink.pending_count(), 1);
+    }
+
+    #[test]
+    fn drop_delivers_open_window() {
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let sink = TimeWindowSink::new(
+            BatchSink {
+                batches: Arc::clone(&batches),
+            },
+            Duration::from_secs(60),
+        );
+        sink.send("a", b"x").expect("send");
+        drop(sink);
+
+        assert_eq!(batches.lock().expect("lock").len(), 1);
+    }
+
+    #[test]
+    fn poll_thread_closes_window_without_further_sends() {
+        let clock = MockClock::new(0);
+        let sink = sink(&clock).with_poll_interval(Some(Duration::from_millis(5)));
+        assert_eq!(sink.poll_interval(), Some(Duration::from_millis(5)));
+        sink.send("t", b"last").expect("send");
+        clock.advance(Duration::from_millis(100));
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while batches(&sink).is_empty() {
+            assert!(std::time::Instant::now() < deadline, "window never closed");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(
+            batches(&sink),
+            vec![vec![("t".to_string(), b"last".to_vec())]]
+        );
+        assert_eq!(sink.with_poll_interval(None).poll_interval(), None);
+    }
+}
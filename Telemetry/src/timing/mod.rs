@@ -0,0 +1,237 @@
+//! Send latency measurement.
+//!
+//! Wrapping one layer of a sink stack in a `TimingSink` shows how long that
+//! layer (and everything below it) takes per send, so slow transports or
+//! expensive decorators can be singled out.
+
+use crate::metrics::Histogram;
+use crate::{layered_sink_name, TelemetryResult, TelemetrySink};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Number of recent sends `LatencySnapshot` percentiles are computed over.
+pub const DEFAULT_LATENCY_WINDOW: usize = 1024;
+
+/// Called with the topic and duration of every timed send.
+pub type LatencyCallback = Box<dyn Fn(&str, Duration) + Send + Sync>;
+
+/// Latency statistics over the most recent sends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LatencySnapshot {
+    /// Sends timed since creation, including those outside the window.
+    pub count: u64,
+    pub p50: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+#[derive(Default)]
+struct Samples {
+    count: u64,
+    recent: VecDeque<Duration>,
+}
+
+/// A sink that measures how long each `send` on the inner sink takes.
+///
+/// Failed sends are timed too. Every measurement is kept in a window of the
+/// last `DEFAULT_LATENCY_WINDOW` sends for `snapshot`, and optionally passed
+/// to a callback and recorded (in milliseconds) into a `Histogram`.
+pub struct TimingSink<S: TelemetrySink> {
+    inner: S,
+    window: usize,
+    samples: Mutex<Samples>,
+    on_latency: Option<LatencyCallback>,
+    histogram: Option<Arc<Histogram>>,
+}
+
+impl<S: TelemetrySink> TimingSink<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            window: DEFAULT_LATENCY_WINDOW,
+            samples: Mutex::new(Samples::default()),
+            on_latency: None,
+            histogram: None,
+        }
+    }
+
+    /// Compute percentiles over the last `window` sends (at least one).
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    /// Call `callback` with the topic and latency of every send.
+    pub fn on_latency<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&str, Duration) + Send + Sync + 'static,
+    {
+        self.on_latency = Some(Box::new(callback));
+        self
+    }
+
+    /// Record every latency, in milliseconds, into `histogram`.
+    pub fn with_histogram(mut self, histogram: Arc<Histogram>) -> Self {
+        self.histogram = Some(histogram);
+        self
+    }
+
+    /// Number of recent sends percentiles are computed over.
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Percentiles (nearest rank) and maximum over the current window.
+    pub fn snapshot(&self) -> LatencySnapshot {
+        let samples = self.lock();
+        let mut sorted: Vec<Duration> = samples.recent.iter().copied().collect();
+        sorted.sort_unstable();
+        LatencySnapshot {
+            count: samples.count,
+            p50: percentile(&sorted, 50),
+            p99: percentile(&sorted, 99),
+            max: sorted.last().copied().unwrap_or_default(),
+        }
+    }
+
+    /// The mutex only guards plain data, so a poisoned lock is still usable.
+    fn lock(&self) -> MutexGuard<'_, Samples> {
+        self.samples
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn record(&self, topic: &str, elapsed: Duration) {
+        {
+            let mut samples = self.lock();
+            samples.count += 1;
+            if samples.recent.len() >= self.window {
+                samples.recent.pop_front();
+            }
+            samples.recent.push_back(elapsed);
+        }
+        if let Some(histogram) = &self.histogram {
+            if let Err(e) = histogram.record(elapsed.as_secs_f64() * 1000.0) {
+                log::warn!("failed to record send latency: {}", e);
+            }
+        }
+        if let Some(callback) = &self.on_latency {
+            callback(topic, elapsed);
+        }
+    }
+}
+
+/// Nearest-rank percentile of an ascending slice; zero when empty.
+fn percentile(sorted: &[Duration], pct: usize) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = ((sorted.len() * pct + 99) / 100).max(1);
+    sorted[rank - 1]
+}
+
+impl<S: TelemetrySink> TelemetrySink for TimingSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("timing", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let started = Instant::now();
+        let result = self.inner.send(topic, payload);
+        self.record(topic, started.elapsed());
+        result
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemorySink, TelemetryError};
+
+    /// Sleeps for a per-topic delay, then fails on topic "fail".
+    struct SlowSink;
+
+    impl TelemetrySink for SlowSink {
+        fn send(&self, topic: &str, _payload: &[u8]) -> TelemetryResult<()> {
+            let delay = if topic == "slow" { 20 } else { 2 };
+            std::thread::sleep(Duration::from_millis(delay));
+            if topic == "fail" {
+                return Err(TelemetryError::new("down"));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn latency_covers_injected_delay() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let histogram = Arc::new(Histogram::new([5.0, 50.0]));
+        let recorded = Arc::clone(&seen);
+        let sink = TimingSink::new(SlowSink)
+            .with_histogram(Arc::clone(&histogram))
+            .on_latency(move |topic, elapsed| {
+                recorded
+                    .lock()
+                    .expect("lock")
+                    .push((topic.to_string(), elapsed))
+            });
+
+        sink.send("slow", b"x").expect("send");
+        assert!(sink.send("fail", b"x").is_err());
+
+        let seen = seen.lock().expect("lock");
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[0].0, "slow");
+        assert!(seen[0].1 >= Duration::from_millis(20), "{:?}", seen[0].1);
+        assert!(seen[1].1 >= Duration::from_millis(2), "{:?}", seen[1].1);
+
+        let buckets = histogram.snapshot().expect("snapshot");
+        assert_eq!(buckets.count, 2);
+        assert!(buckets.sum >= 22.0);
+    }
+
+    #[test]
+    fn snapshot_reports_percentiles_and_max() {
+        let sink = TimingSink::new(SlowSink);
+        assert_eq!(sink.snapshot(), LatencySnapshot::default());
+
+        for _ in 0..9 {
+            sink.send("fast", b"x").expect("send");
+        }
+        sink.send("slow", b"x").expect("send");
+
+        let stats = sink.snapshot();
+        assert_eq!(stats.count, 10);
+        assert!(stats.p50 >= Duration::from_millis(2));
+        assert!(stats.p50 < Duration::from_millis(20), "{:?}", stats);
+        assert!(stats.p99 >= Duration::from_millis(20));
+        assert_eq!(stats.p99, stats.max);
+    }
+
+    #[test]
+    fn window_keeps_only_recent_sends() {
+        let sink = TimingSink::new(InMemorySink::new()).with_window(3);
+        for _ in 0..5 {
+            sink.send("t", b"x").expect("send");
+        }
+        assert_eq!(sink.window(), 3);
+        assert_eq!(sink.snapshot().count, 5);
+        assert_eq!(sink.lock().recent.len(), 3);
+        assert_eq!(sink.inner().len(), 5);
+        assert_eq!(sink.sink_name(), "timing(in_memory)");
+    }
+}
@@ -0,0 +1,286 @@
+//! MQTT-style topic filters.
+//!
+//! Filters are `/`-separated like topics, with two wildcards: `+` matches
+//! exactly one level and `#` matches any number of remaining levels
+//! (including none), so `sensors/#` matches `sensors` itself. Subscriptions,
+//! routing and access control all share these rules.
+//!
+//! As in the MQTT spec, topics starting with `$` (e.g. `$SYS/broker/load`)
+//! are system topics: a filter whose first level is a wildcard does not
+//! match them. `TopicFilter::match_system_topics` turns that rule off.
+
+use crate::{TelemetryError, TelemetryResult};
+use std::fmt;
+
+/// Check that a filter is well formed.
+///
+/// `#` may only appear as the entire last level; `+` may only appear as an
+/// entire level.
+pub fn validate_filter(filter: &str) -> TelemetryResult<()> {
+    if filter.is_empty() {
+        return Err(TelemetryError::new("invalid topic filter: filter is empty"));
+    }
+    let levels: Vec<&str> = filter.split('/').collect();
+    for (i, level) in levels.iter().enumerate() {
+        if level.contains('#') && (*level != "#" || i != levels.len() - 1) {
+            return Err(TelemetryError::new(format!(
+                "invalid topic filter '{}': '#' must be the last level",
+                filter
+            )));
+        }
+        if level.contains('+') && *level != "+" {
+            return Err(TelemetryError::new(format!(
+                "invalid topic filter '{}': '+' must occupy a whole level",
+                filter
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `topic` matches `filter`.
+///
+/// The filter is not validated; use `TopicFilter` to reject malformed
+/// filters up front and to avoid re-splitting a filter on every match.
+pub fn matches(filter: &str, topic: &str) -> bool {
+    levels_match(filter.split('/'), topic, false)
+}
+
+fn levels_match<'f>(
+    filter: impl IntoIterator<Item = &'f str>,
+    topic: &str,
+    match_system_topics: bool,
+) -> bool {
+    let mut filter = filter.into_iter().peekable();
+    if !match_system_topics
+        && topic.starts_with('$')
+        && matches!(filter.peek(), Some(&"#") | Some(&"+"))
+    {
+        return false;
+    }
+    let mut topic_levels = topic.split('/');
+    for level in filter {
+        match level {
+            "#" => return true,
+            "+" => {
+                if topic_levels.next().is_none() {
+                    return false;
+                }
+            }
+            exact => {
+                if topic_levels.next() != Some(exact) {
+                    return false;
+                }
+            }
+        }
+    }
+    topic_levels.next().is_none()
+}
+
+/// A validated filter, split into levels once for repeated matching.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicFilter {
+    filter: String,
+    levels: Vec<String>,
+    match_system_topics: bool,
+}
+
+impl TopicFilter {
+    /// Parse `filter`, rejecting it if it is malformed.
+    pub fn new(filter: impl Into<String>) -> TelemetryResult<Self> {
+        let filter = filter.into();
+        validate_filter(&filter)?;
+        let levels = filter.split('/').map(str::to_string).collect();
+        Ok(Self {
+            filter,
+            levels,
+            match_system_topics: false,
+        })
+    }
+
+    /// Let leading wildcards match `$`-prefixed system topics too.
+    pub fn match_system_topics(mut self, enabled: bool) -> Self {
+        self.match_system_topics = enabled;
+        self
+    }
+
+    /// Whether `topic` matches this filter.
+    pub fn matches(&self, topic: &str) -> bool {
+        levels_match(
+            self.levels.iter().map(String::as_str),
+            topic,
+            self.match_system_topics,
+        )
+    }
+
+    /// The topic levels matched by each wildcard, in filter order, or
+    /// `None` if `topic` does not match.
+    ///
+    /// `+` captures one level; `#` captures all remaining levels joined by
+    /// `/`, which is empty when there are none.
+    pub fn captures<'t>(&self, topic: &'t str) -> Option<Vec<&'t str>> {
+        if !self.matches(topic) {
+            return None;
+        }
+        let mut captures = Vec::new();
+        let mut rest = topic;
+        for level in &self.levels {
+            if level == "#" {
+                captures.push(rest);
+                break;
+            }
+            let (head, tail) = rest.split_once('/').unwrap_or((rest, ""));
+            if level == "+" {
+                captures.push(head);
+            }
+            rest = tail;
+        }
+        Some(captures)
+    }
+
+    /// The filter as written.
+    pub fn as_str(&self) -> &str {
+        &self.filter
+    }
+}
+
+impl fmt::Display for TopicFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.filter)
+    }
+}
+
+/// Size and depth limits on publish topics.
+///
+/// Brokers differ in how long and deep a topic may be; a client enforcing
+/// the broker's limits fails fast with a clear error instead of a dropped
+/// connection. `None` means unlimited, the default for both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TopicPolicy {
+    /// Maximum topic length in bytes.
+    pub max_topic_len: Option<usize>,
+    /// Maximum number of `/`-separated levels.
+    pub max_segments: Option<usize>,
+}
+
+impl TopicPolicy {
+    /// Check `topic` against the limits.
+    pub fn check(&self, topic: &str) -> TelemetryResult<()> {
+        if let Some(max) = self.max_topic_len {
+            if topic.len() > max {
+                return Err(TelemetryError::new(format!(
+                    "topic '{}' is {} bytes, exceeding the limit of {}",
+                    topic,
+                    topic.len(),
+                    max
+                )));
+            }
+        }
+        if let Some(max) = self.max_segments {
+            let segments = topic.split('/').count();
+            if segments > max {
+                return Err(TelemetryError::new(format!(
+                    "topic '{}' has {} levels, exceeding the limit of {}",
+                    topic, segments, max
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_table() {
+        let cases = [
+            // Exact levels
+            ("sensors/temp", "sensors/temp", true),
+            ("sensors/temp", "sensors/hum", false),
+            ("sensors/temp", "sensors/temp/room1", false),
+            ("sensors/temp/room1", "sensors/temp", false),
+            // `+` matches exactly one level, including an empty one
+            ("sensors/+/room1", "sensors/temp/room1", true),
+            ("sensors/+/room1", "sensors/temp/a/room1", false),
+            ("sensors/+", "sensors", false),
+            ("sensors/+", "sensors/", true),
+            ("+/+", "a/b", true),
+            ("+", "a/b", false),
+            // `#` matches the remaining levels, including none
+            ("sensors/#", "sensors/temp/room1", true),
+            ("sensors/#", "sensors", true),
+            ("sensors/#", "sensor", false),
+            ("sensors/+/#", "sensors/temp", true),
+            ("#", "anything/at/all", true),
+            // Leading `/` introduces an empty first level
+            ("/sensors", "/sensors", true),
+            ("/sensors", "sensors", false),
+            ("+/sensors", "/sensors", true),
+            ("#", "/sensors", true),
+            // `$` system topics are hidden from leading wildcards
+            ("#", "$SYS/broker/load", false),
+            ("+/broker/load", "$SYS/broker/load", false),
+            ("$SYS/#", "$SYS/broker/load", true),
+            ("$SYS/+/load", "$SYS/broker/load", true),
+        ];
+        for (filter, topic, expected) in cases {
+            assert_eq!(
+                matches(filter, topic),
+                expected,
+                "matches({:?}, {:?})",
+                filter,
+                topic
+            );
+            let parsed = TopicFilter::new(filter).expect(filter);
+            assert_eq!(parsed.matches(topic), expected, "TopicFilter {:?}", filter);
+        }
+    }
+
+    #[test]
+    fn system_topic_rule_can_be_disabled() {
+        let filter = TopicFilter::new("#")
+            .expect("filter")
+            .match_system_topics(true);
+        assert!(filter.matches("$SYS/broker/load"));
+        assert!(TopicFilter::new("+/broker/load")
+            .expect("filter")
+            .match_system_topics(true)
+            .matches("$SYS/broker/load"));
+    }
+
+    #[test]
+    fn captures_wildcard_levels() {
+        let capture = |filter: &str, topic: &str| {
+            TopicFilter::new(filter)
+                .expect(filter)
+                .captures(topic)
+                .map(|c| c.join("|"))
+        };
+        assert_eq!(
+            capture("sensors/+/temp", "sensors/lab/temp").as_deref(),
+            Some("lab")
+        );
+        assert_eq!(capture("+/x/+", "a/x/b").as_deref(), Some("a|b"));
+        assert_eq!(capture("+/+", "a/").as_deref(), Some("a|"));
+        assert_eq!(
+            capture("logs/#", "logs/app/db/slow").as_deref(),
+            Some("app/db/slow")
+        );
+        assert_eq!(capture("logs/#", "logs").as_deref(), Some(""));
+        assert_eq!(capture("sensors/+", "sensors/a/b"), None);
+        assert_eq!(capture("+/load", "$SYS/load"), None);
+    }
+
+    #[test]
+    fn malformed_filters_are_rejected() {
+        for filter in ["", "sensors/#/temp", "sensors/te#", "sensors/te+mp", "#/"] {
+            assert!(TopicFilter::new(filter).is_err(), "{:?}", filter);
+        }
+        assert_eq!(
+            TopicFilter::new("sensors/#").expect("filter").to_string(),
+            "sensors/#"
+        );
+    }
+}
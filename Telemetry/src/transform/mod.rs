@@ -0,0 +1,257 @@
+//! Message transformation middleware.
+//!
+//! A `Transform` rewrites a topic and payload in place; `TransformSink` runs
+//! an ordered list of them before forwarding, so small edits such as
+//! redacting PII or tagging a correlation id do not each need a sink.
+
+use crate::source::decode_message;
+use crate::{layered_sink_name, TelemetryError, TelemetryResult, TelemetrySink};
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Header set by `AddCorrelationId`.
+pub const CORRELATION_ID_HEADER: &str = "correlation_id";
+
+/// An in-place edit of an outgoing message.
+///
+/// Returning an error aborts the send with that error.
+pub trait Transform: Send + Sync {
+    fn apply(&self, topic: &mut String, payload: &mut Vec<u8>) -> TelemetryResult<()>;
+}
+
+impl<F> Transform for F
+where
+    F: Fn(&mut String, &mut Vec<u8>) -> TelemetryResult<()> + Send + Sync,
+{
+    fn apply(&self, topic: &mut String, payload: &mut Vec<u8>) -> TelemetryResult<()> {
+        self(topic, payload)
+    }
+}
+
+/// A sink that runs its transforms, in the order they were added, before
+/// forwarding to the inner sink.
+pub struct TransformSink<S: TelemetrySink> {
+    inner: S,
+    transforms: Vec<Box<dyn Transform>>,
+}
+
+impl<S: TelemetrySink> TransformSink<S> {
+    /// Wrap `inner` with no transforms.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            transforms: Vec::new(),
+        }
+    }
+
+    /// Append a transform, run after those already added.
+    pub fn with_transform(mut self, transform: impl Transform + 'static) -> Self {
+        self.transforms.push(Box::new(transform));
+        self
+    }
+
+    /// Number of transforms.
+    pub fn len(&self) -> usize {
+        self.transforms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for TransformSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("transform", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let mut topic = topic.to_string();
+        let mut payload = payload.to_vec();
+        for transform in &self.transforms {
+            transform.apply(&mut topic, &mut payload)?;
+        }
+        self.inner.send(&topic, &payload)
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+/// Removes the named keys from a JSON payload, at any depth.
+///
+/// Payloads that are not JSON are rejected with
+/// `TelemetryError::Serialization` rather than forwarded unchecked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactFields(pub Vec<String>);
+
+impl RedactFields {
+    fn redact(&self, value: &mut Value) {
+        match value {
+            Value::Object(fields) => {
+                fields.retain(|key, _| !self.0.contains(key));
+                fields.values_mut().for_each(|v| self.redact(v));
+            }
+            Value::Array(items) => items.iter_mut().for_each(|v| self.redact(v)),
+            _ => {}
+        }
+    }
+}
+
+impl Transform for RedactFields {
+    fn apply(&self, _topic: &mut String, payload: &mut Vec<u8>) -> TelemetryResult<()> {
+        let mut value: Value = serde_json::from_slice(payload).map_err(|e| {
+            TelemetryError::Serialization(format!("cannot redact non-JSON payload: {}", e))
+        })?;
+        self.redact(&mut value);
+        *payload = serde_json::to_vec(&value)
+            .map_err(|e| TelemetryError::Serialization(format!("redacted payload: {}", e)))?;
+        Ok(())
+    }
+}
+
+type IdGenerator = Box<dyn Fn() -> String + Send + Sync>;
+
+/// Sets a `correlation_id` header on messages that do not have one.
+///
+/// Like `SequencingSink`, payloads that are not `TelemetryMessage` JSON are
+/// wrapped in a new message to carry the header. Generated ids combine the
+/// start time in milliseconds with a per-transform counter.
+pub struct AddCorrelationId {
+    generate: IdGenerator,
+}
+
+impl AddCorrelationId {
+    pub fn new() -> Self {
+        let start = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis());
+        let counter = AtomicU64::new(0);
+        Self::with_generator(move || {
+            format!("{:x}-{:x}", start, counter.fetch_add(1, Ordering::Relaxed))
+        })
+    }
+
+    /// Produce ids with `generate` instead, e.g. UUIDs.
+    pub fn with_generator(generate: impl Fn() -> String + Send + Sync + 'static) -> Self {
+        Self {
+            generate: Box::new(generate),
+        }
+    }
+}
+
+impl Default for AddCorrelationId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transform for AddCorrelationId {
+    fn apply(&self, topic: &mut String, payload: &mut Vec<u8>) -> TelemetryResult<()> {
+        let mut message = decode_message(topic, payload);
+        if message.headers.contains_key(CORRELATION_ID_HEADER) {
+            return Ok(());
+        }
+        message
+            .headers
+            .insert(CORRELATION_ID_HEADER.to_string(), (self.generate)());
+        *payload = serde_json::to_vec(&message)
+            .map_err(|e| TelemetryError::Serialization(format!("correlated message: {}", e)))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemorySink, TelemetryMessage};
+    use serde_json::json;
+
+    fn sent(sink: &TransformSink<InMemorySink>) -> Vec<(String, TelemetryMessage)> {
+        sink.inner()
+            .records
+            .lock()
+            .expect("lock")
+            .iter()
+            .map(|(topic, payload)| {
+                let msg = serde_json::from_slice(payload).expect("message");
+                (topic.clone(), msg)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn redacts_password_field() {
+        let sink = TransformSink::new(InMemorySink::new())
+            .with_transform(RedactFields(vec!["password".into()]));
+        let msg = TelemetryMessage::new(
+            "auth/login",
+            json!({"user": "ada", "password": "hunter2", "retry": {"password": "x"}}),
+        );
+
+        sink.send(&msg.topic, msg.to_json().as_bytes())
+            .expect("send");
+
+        let (_, received) = &sent(&sink)[0];
+        assert_eq!(received.payload, json!({"user": "ada", "retry": {}}));
+    }
+
+    #[test]
+    fn transforms_run_in_order() {
+        let prefix = |topic: &mut String, _: &mut Vec<u8>| -> TelemetryResult<()> {
+            topic.insert_str(0, "site1/");
+            Ok(())
+        };
+        let sink = TransformSink::new(InMemorySink::new())
+            .with_transform(AddCorrelationId::with_generator(|| "abc".into()))
+            .with_transform(prefix)
+            .with_transform(RedactFields(vec![CORRELATION_ID_HEADER.into()]));
+        assert_eq!(sink.len(), 3);
+
+        sink.send("temp", br#"{"value":1}"#).expect("send");
+
+        // The id was added, then stripped again by the later redaction
+        let (topic, received) = &sent(&sink)[0];
+        assert_eq!(topic, "site1/temp");
+        assert!(received.headers.is_empty());
+        assert_eq!(received.payload, json!({"value": 1}));
+    }
+
+    #[test]
+    fn correlation_id_is_added_once() {
+        let sink = TransformSink::new(InMemorySink::new())
+            .with_transform(AddCorrelationId::new())
+            .with_transform(AddCorrelationId::with_generator(|| "second".into()));
+
+        sink.send("t", br#"{"value":1}"#).expect("send");
+        sink.send("t", br#"{"value":2}"#).expect("send");
+
+        let ids: Vec<String> = sent(&sink)
+            .into_iter()
+            .map(|(_, msg)| msg.headers[CORRELATION_ID_HEADER].clone())
+            .collect();
+        assert_ne!(ids[0], ids[1]);
+        assert!(ids.iter().all(|id| id != "second"));
+    }
+
+    #[test]
+    fn failing_transform_aborts_send() {
+        let sink = TransformSink::new(InMemorySink::new())
+            .with_transform(RedactFields(vec!["password".into()]));
+
+        let err = sink.send("t", b"not json").expect_err("rejected");
+        assert!(matches!(err, TelemetryError::Serialization(_)));
+        assert!(sink.inner().records.lock().expect("lock").is_empty());
+    }
+}
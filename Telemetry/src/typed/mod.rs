@@ -0,0 +1,162 @@
+//! Strongly typed telemetry messages.
+//!
+//! `TypedMessage<T>` carries a topic together with any `Serialize` payload
+//! and serializes to exactly the same JSON as `TelemetryMessage`, so
+//! consumers cannot tell which one a producer used.
+//!
+//! **Why?** Building a `serde_json::Value` first costs an extra allocation
+//! per field and gives up compile-time checking of the payload shape.
+
+use crate::{TelemetryError, TelemetryMessage, TelemetryResult};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// A telemetry message whose payload is a concrete type.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TypedMessage<T> {
+    pub topic: String,
+    pub payload: T,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "is_default_priority")]
+    pub priority: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_ms: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<i64>,
+}
+
+fn is_default_priority(priority: &u8) -> bool {
+    *priority == 0
+}
+
+impl<T> TypedMessage<T> {
+    /// Create a message without timestamp or headers.
+    pub fn new(topic: impl Into<String>, payload: T) -> Self {
+        Self {
+            topic: topic.into(),
+            payload,
+            timestamp: None,
+            headers: BTreeMap::new(),
+            priority: 0,
+            ttl_ms: None,
+            created_at: None,
+        }
+    }
+
+    /// Add a header.
+    pub fn with_header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(key.into(), value.into());
+        self
+    }
+}
+
+impl<T: Serialize> TypedMessage<T> {
+    /// Convert into an untyped `TelemetryMessage`.
+    pub fn to_message(&self) -> TelemetryResult<TelemetryMessage> {
+        let payload = serde_json::to_value(&self.payload)
+            .map_err(|e| TelemetryError::Serialization(format!("typed payload: {}", e)))?;
+        Ok(TelemetryMessage {
+            topic: self.topic.clone(),
+            payload,
+            timestamp: self.timestamp,
+            headers: self.headers.clone(),
+            priority: self.priority,
+            ttl_ms: self.ttl_ms,
+            created_at: self.created_at,
+        })
+    }
+}
+
+impl<T: DeserializeOwned> TryFrom<TelemetryMessage> for TypedMessage<T> {
+    type Error = TelemetryError;
+
+    fn try_from(msg: TelemetryMessage) -> TelemetryResult<Self> {
+        let payload = serde_json::from_value(msg.payload)
+            .map_err(|e| TelemetryError::Serialization(format!("typed payload: {}", e)))?;
+        Ok(Self {
+            topic: msg.topic,
+            payload,
+            timestamp: msg.timestamp,
+            headers: msg.headers,
+            priority: msg.priority,
+            ttl_ms: msg.ttl_ms,
+            created_at: msg.created_at,
+        })
+    }
+}
+
+/// A payload tagged with its kind, encoded as `{"kind": ..., "data": ...}`.
+///
+/// Consumers can dispatch on `kind` without inspecting the shape of `data`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Envelope<T> {
+    pub kind: String,
+    pub data: T,
+}
+
+impl<T> Envelope<T> {
+    pub fn new(kind: impl Into<String>, data: T) -> Self {
+        Self {
+            kind: kind.into(),
+            data,
+        }
+    }
+}
+
+impl Envelope<Value> {
+    /// Decode `data` as the type registered for `kind`.
+    pub fn data_as<T: DeserializeOwned>(&self) -> TelemetryResult<T> {
+        T::deserialize(&self.data).map_err(|e| {
+            TelemetryError::Serialization(format!("'{}' envelope data: {}", self.kind, e))
+        })
+    }
+}
+
+/// Parse a payload sent with `TelemetryClient::send_tagged`, returning its
+/// kind and the data still undecoded.
+pub fn parse_envelope(payload: &[u8]) -> TelemetryResult<Envelope<Value>> {
+    serde_json::from_slice(payload)
+        .map_err(|e| TelemetryError::Serialization(format!("envelope: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Reading {
+        temp: f64,
+    }
+
+    #[test]
+    fn wire_format_matches_telemetry_message() {
+        let typed =
+            TypedMessage::new("sensors/temp", Reading { temp: 21.5 }).with_header("unit", "C");
+        let untyped = typed.to_message().expect("convert");
+        assert_eq!(
+            serde_json::to_string(&typed).expect("json"),
+            untyped.to_json()
+        );
+
+        let back: TypedMessage<Reading> = untyped.try_into().expect("typed");
+        assert_eq!(back, typed);
+    }
+
+    #[test]
+    fn mismatched_payload_fails_conversion() {
+        let msg = TelemetryMessage::new("t", serde_json::json!({ "humidity": 40 }));
+        let result: TelemetryResult<TypedMessage<Reading>> = msg.try_into();
+        assert!(matches!(result, Err(TelemetryError::Serialization(_))));
+    }
+
+    #[test]
+    fn parse_envelope_rejects_untagged_payload() {
+        let err = parse_envelope(br#"{"temp": 21.5}"#).expect_err("no kind");
+        assert!(matches!(err, TelemetryError::Serialization(_)));
+    }
+}
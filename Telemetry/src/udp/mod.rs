@@ -0,0 +1,166 @@
+//! UDP datagram transport for telemetry data.
+//!
+//! **Why UDP?** On a LAN where occasional loss is acceptable, one datagram
+//! per message has no connection setup, no head-of-line blocking and no
+//! retransmission delay.
+//! Enable with `features = ["udp"]` in Cargo.toml.
+//!
+//! Each datagram is framed as a big-endian `u16` topic length, the topic
+//! bytes, then the payload, so a single receiver port can demultiplex
+//! topics. Use `decode_datagram` on the receiving side.
+
+use crate::{TelemetryError, TelemetryResult, TelemetrySink};
+use std::any::Any;
+use std::net::{SocketAddr, UdpSocket};
+
+/// Largest datagram that fits a 1500-byte Ethernet MTU without IP
+/// fragmentation (1500 - 20 IPv4 header - 8 UDP header).
+pub const DEFAULT_MAX_DATAGRAM: usize = 1472;
+
+/// Bytes used by the topic length prefix.
+const TOPIC_LEN_BYTES: usize = 2;
+
+/// A sink that sends each payload as a single UDP datagram.
+pub struct UdpSink {
+    socket: UdpSocket,
+    target: SocketAddr,
+    max_datagram: usize,
+}
+
+impl UdpSink {
+    /// Bind an ephemeral local socket that sends to `target`.
+    pub fn new(target: SocketAddr) -> TelemetryResult<Self> {
+        let bind: SocketAddr = if target.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+        let socket = UdpSocket::bind(bind)
+            .map_err(|e| TelemetryError::Connection(format!("UDP bind failed: {}", e)))?;
+        Ok(Self {
+            socket,
+            target,
+            max_datagram: DEFAULT_MAX_DATAGRAM,
+        })
+    }
+
+    /// Override the maximum datagram size (header included).
+    ///
+    /// Raise it on jumbo-frame networks or lower it for tunnels.
+    pub fn with_max_datagram(mut self, max_datagram: usize) -> Self {
+        self.max_datagram = max_datagram;
+        self
+    }
+
+    /// Destination address.
+    pub fn target(&self) -> SocketAddr {
+        self.target
+    }
+
+    /// Maximum datagram size (header included).
+    pub fn max_datagram(&self) -> usize {
+        self.max_datagram
+    }
+}
+
+/// Frame `topic` and `payload` into a datagram.
+pub fn encode_datagram(topic: &str, payload: &[u8]) -> TelemetryResult<Vec<u8>> {
+    let topic_len = u16::try_from(topic.len()).map_err(|_| {
+        TelemetryError::Serialization(format!("topic of {} bytes is too long", topic.len()))
+    })?;
+    let mut datagram = Vec::with_capacity(TOPIC_LEN_BYTES + topic.len() + payload.len());
+    datagram.extend_from_slice(&topic_len.to_be_bytes());
+    datagram.extend_from_slice(topic.as_bytes());
+    datagram.extend_from_slice(payload);
+    Ok(datagram)
+}
+
+/// Split a received datagram into topic and payload.
+///
+/// Returns `None` if the datagram is truncated or the topic is not UTF-8.
+pub fn decode_datagram(datagram: &[u8]) -> Option<(&str, &[u8])> {
+    let len_bytes = datagram.get(..TOPIC_LEN_BYTES)?;
+    let topic_len = u16::from_be_bytes([len_bytes[0], len_bytes[1]]) as usize;
+    let rest = &datagram[TOPIC_LEN_BYTES..];
+    let topic = std::str::from_utf8(rest.get(..topic_len)?).ok()?;
+    Some((topic, &rest[topic_len..]))
+}
+
+impl TelemetrySink for UdpSink {
+    fn sink_name(&self) -> &'static str {
+        "udp"
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let datagram = encode_datagram(topic, payload)?;
+        if datagram.len() > self.max_datagram {
+            return Err(TelemetryError::Transport(format!(
+                "datagram of {} bytes exceeds limit of {} bytes",
+                datagram.len(),
+                self.max_datagram
+            )));
+        }
+        self.socket
+            .send_to(&datagram, self.target)
+            .map(|_| ())
+            .map_err(|e| TelemetryError::Transport(format!("UDP send failed: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn receiver() -> UdpSocket {
+        let socket = UdpSocket::bind("127.0.0.1:0").expect("bind receiver");
+        socket
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .expect("timeout");
+        socket
+    }
+
+    #[test]
+    fn datagram_carries_topic_header_and_payload() {
+        let rx = receiver();
+        let sink = UdpSink::new(rx.local_addr().expect("addr")).expect("sink");
+
+        sink.send("sensors/temp", b"21.5").expect("send");
+
+        let mut buf = [0u8; 2048];
+        let (n, _) = rx.recv_from(&mut buf).expect("recv");
+        let datagram = &buf[..n];
+        assert_eq!(&datagram[..2], &[0, 12]);
+        assert_eq!(&datagram[2..14], b"sensors/temp");
+        assert_eq!(&datagram[14..], b"21.5");
+        assert_eq!(
+            decode_datagram(datagram),
+            Some(("sensors/temp", &b"21.5"[..]))
+        );
+    }
+
+    #[test]
+    fn oversized_payload_is_rejected() {
+        let rx = receiver();
+        let sink = UdpSink::new(rx.local_addr().expect("addr"))
+            .expect("sink")
+            .with_max_datagram(64);
+
+        let exact = vec![0u8; 64 - 2 - 1];
+        sink.send("t", &exact).expect("fits exactly");
+
+        let err = sink.send("t", &[0u8; 62]).expect_err("too large");
+        assert!(matches!(err, TelemetryError::Transport(_)));
+        assert!(err.message().contains("65 bytes"));
+    }
+
+    #[test]
+    fn truncated_datagram_does_not_decode() {
+        assert_eq!(decode_datagram(&[0]), None);
+        assert_eq!(decode_datagram(&[0, 5, b'a']), None);
+    }
+}
@@ -0,0 +1,147 @@
+//! JSON Schema validating sink decorator.
+//!
+//! Rejects malformed sensor payloads at the edge, before they cost bandwidth
+//! or pollute downstream storage.
+
+use crate::{layered_sink_name, TelemetryError, TelemetryResult, TelemetrySink};
+use jsonschema::Validator;
+
+/// A sink that forwards only payloads matching a JSON Schema.
+///
+/// Payloads that are not JSON at all (e.g. from `send_binary`) are rejected
+/// by default; see `pass_non_json`.
+pub struct ValidatingSink<S: TelemetrySink> {
+    inner: S,
+    validator: Validator,
+    pass_non_json: bool,
+}
+
+impl<S: TelemetrySink> ValidatingSink<S> {
+    /// Validate payloads with an already compiled schema.
+    pub fn new(inner: S, validator: Validator) -> Self {
+        Self {
+            inner,
+            validator,
+            pass_non_json: false,
+        }
+    }
+
+    /// Compile `schema` and validate payloads against it.
+    pub fn from_schema(inner: S, schema: &serde_json::Value) -> TelemetryResult<Self> {
+        let validator = jsonschema::validator_for(schema)
+            .map_err(|e| TelemetryError::new(format!("invalid JSON schema: {}", e)))?;
+        Ok(Self::new(inner, validator))
+    }
+
+    /// Forward payloads that do not parse as JSON instead of rejecting them.
+    pub fn pass_non_json(mut self, pass: bool) -> Self {
+        self.pass_non_json = pass;
+        self
+    }
+
+    /// Access the wrapped sink.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn check(&self, payload: &[u8]) -> TelemetryResult<()> {
+        let value: serde_json::Value = match serde_json::from_slice(payload) {
+            Ok(value) => value,
+            Err(_) if self.pass_non_json => return Ok(()),
+            Err(e) => {
+                return Err(TelemetryError::Serialization(format!(
+                    "payload is not JSON: {}",
+                    e
+                )))
+            }
+        };
+        let errors: Vec<String> = self
+            .validator
+            .iter_errors(&value)
+            .map(|e| {
+                let path = e.instance_path.as_str();
+                let path = if path.is_empty() { "/" } else { path };
+                format!("{}: {}", path, e)
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(TelemetryError::Serialization(format!(
+                "schema validation failed: {}",
+                errors.join("; ")
+            )))
+        }
+    }
+}
+
+impl<S: TelemetrySink> TelemetrySink for ValidatingSink<S> {
+    fn sink_name(&self) -> &'static str {
+        layered_sink_name("validating", &[self.inner.sink_name()])
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        self.check(payload)?;
+        self.inner.send(topic, payload)
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        self.inner.flush()
+    }
+
+    fn health_check(&self) -> TelemetryResult<()> {
+        self.inner.health_check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemorySink;
+
+    fn temp_sink() -> ValidatingSink<InMemorySink> {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": { "temp": { "type": "number" } },
+            "required": ["temp"]
+        });
+        ValidatingSink::from_schema(InMemorySink::new(), &schema).expect("schema")
+    }
+
+    #[test]
+    fn valid_payload_is_forwarded() {
+        let sink = temp_sink();
+        sink.send("sensors/temp", br#"{"temp": 21.5}"#)
+            .expect("valid");
+        assert_eq!(sink.inner().records.lock().expect("lock").len(), 1);
+    }
+
+    #[test]
+    fn invalid_payloads_are_rejected_with_details() {
+        let sink = temp_sink();
+
+        let missing = sink
+            .send("sensors/temp", br#"{"humidity": 40}"#)
+            .expect_err("missing temp");
+        assert!(matches!(missing, TelemetryError::Serialization(_)));
+        assert!(missing.message().contains("temp"));
+
+        let wrong_type = sink
+            .send("sensors/temp", br#"{"temp": "hot"}"#)
+            .expect_err("wrong type");
+        assert!(matches!(wrong_type, TelemetryError::Serialization(_)));
+        assert!(wrong_type.message().contains("/temp"));
+
+        assert!(sink.inner().records.lock().expect("lock").is_empty());
+    }
+
+    #[test]
+    fn non_json_payloads_follow_flag() {
+        let sink = temp_sink();
+        assert!(sink.send("blob", &[0xff, 0x00]).is_err());
+
+        let sink = temp_sink().pass_non_json(true);
+        sink.send("blob", &[0xff, 0x00]).expect("passed through");
+        assert_eq!(sink.inner().records.lock().expect("lock").len(), 1);
+    }
+}
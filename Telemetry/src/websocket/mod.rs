@@ -0,0 +1,341 @@
+//! WebSocket transport for telemetry data.
+//!
+//! Streams telemetry to browser dashboards. Text frames carry a small JSON
+//! envelope (`{"topic": ..., "payload": ...}`) so the client can demultiplex;
+//! binary frames carry the raw payload.
+//!
+//! **Why feature-gated?** The WebSocket client and its TLS stack are only
+//! needed by deployments that feed a web UI.
+//! Enable with `features = ["websocket"]` in Cargo.toml.
+
+use crate::{ShutdownSink, TelemetryError, TelemetryResult, TelemetrySink};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::net::TcpStream;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+type Socket = WebSocket<MaybeTlsStream<TcpStream>>;
+
+/// Frame type used for each payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameMode {
+    /// JSON envelope with topic and payload in a text frame.
+    Text,
+    /// Raw payload bytes in a binary frame (topic is not transmitted).
+    Binary,
+}
+
+/// Configuration for `WebSocketSink`.
+#[derive(Debug, Clone)]
+pub struct WebSocketConfig {
+    /// `ws://` or `wss://` URL of the server.
+    pub url: String,
+    /// Frame type used for each payload.
+    pub mode: FrameMode,
+    /// Messages kept while the connection is down; further sends fail.
+    pub max_queued: usize,
+    /// Delay before the first reconnect attempt after a failure.
+    pub initial_backoff: Duration,
+    /// Upper bound for the exponentially growing reconnect delay.
+    pub max_backoff: Duration,
+}
+
+impl WebSocketConfig {
+    /// Text-mode configuration with a 100-message queue and 100ms..5s backoff.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            mode: FrameMode::Text,
+            max_queued: 100,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+
+    /// Select text or binary frames.
+    pub fn mode(mut self, mode: FrameMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Set the number of messages kept while reconnecting.
+    pub fn max_queued(mut self, max_queued: usize) -> Self {
+        self.max_queued = max_queued;
+        self
+    }
+
+    /// Set the reconnect backoff range.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+}
+
+struct Connection {
+    socket: Option<Socket>,
+    queue: VecDeque<Message>,
+    backoff: Duration,
+    next_attempt: Instant,
+}
+
+/// A sink that streams payloads over a WebSocket connection.
+///
+/// The connection is opened lazily on first send. While it is down, payloads
+/// are queued (up to `max_queued`) and `send` returns `Ok(())`; they are
+/// delivered in order once a reconnect succeeds. A send that would overflow
+/// the queue fails with `TelemetryError::Connection`.
+pub struct WebSocketSink {
+    config: WebSocketConfig,
+    connection: Mutex<Connection>,
+}
+
+impl WebSocketSink {
+    /// Create a text-mode sink for `url` with default settings.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::with_config(WebSocketConfig::new(url))
+    }
+
+    /// Create a sink from an explicit configuration.
+    pub fn with_config(config: WebSocketConfig) -> Self {
+        let backoff = config.initial_backoff;
+        Self {
+            config,
+            connection: Mutex::new(Connection {
+                socket: None,
+                queue: VecDeque::new(),
+                backoff,
+                next_attempt: Instant::now(),
+            }),
+        }
+    }
+
+    /// Active configuration.
+    pub fn config(&self) -> &WebSocketConfig {
+        &self.config
+    }
+
+    /// Number of messages waiting for a reconnect.
+    pub fn queued(&self) -> usize {
+        self.connection.lock().map(|c| c.queue.len()).unwrap_or(0)
+    }
+
+    fn frame(&self, topic: &str, payload: &[u8]) -> TelemetryResult<Message> {
+        match self.config.mode {
+            FrameMode::Binary => Ok(Message::Binary(payload.to_vec())),
+            FrameMode::Text => {
+                let payload =
+                    serde_json::from_slice::<serde_json::Value>(payload).unwrap_or_else(|_| {
+                        serde_json::Value::String(String::from_utf8_lossy(payload).into_owned())
+                    });
+                let envelope = serde_json::json!({ "topic": topic, "payload": payload });
+                serde_json::to_string(&envelope)
+                    .map(Message::Text)
+                    .map_err(|e| {
+                        TelemetryError::Serialization(format!("WebSocket envelope: {}", e))
+                    })
+            }
+        }
+    }
+
+    fn lock(&self) -> TelemetryResult<MutexGuard<'_, Connection>> {
+        self.connection
+            .lock()
+            .map_err(|e| TelemetryError::new(format!("lock poisoned: {}", e)))
+    }
+
+    /// Connect if disconnected and the backoff has elapsed.
+    fn ensure_connected(&self, conn: &mut Connection) -> TelemetryResult<()> {
+        if conn.socket.is_some() {
+            return Ok(());
+        }
+        let now = Instant::now();
+        if now < conn.next_attempt {
+            return Err(TelemetryError::Connection(format!(
+                "waiting to reconnect to {}",
+                self.config.url
+            )));
+        }
+        match tungstenite::connect(self.config.url.as_str()) {
+            Ok((socket, _response)) => {
+                conn.socket = Some(socket);
+                conn.backoff = self.config.initial_backoff;
+                Ok(())
+            }
+            Err(e) => {
+                conn.next_attempt = now + conn.backoff;
+                conn.backoff = (conn.backoff * 2).min(self.config.max_backoff);
+                Err(TelemetryError::Connection(format!(
+                    "connect to {} failed: {}",
+                    self.config.url, e
+                )))
+            }
+        }
+    }
+
+    /// Send queued frames in order; on failure the frame stays queued.
+    fn drain_queue(&self, conn: &mut Connection) -> TelemetryResult<()> {
+        while let Some(frame) = conn.queue.pop_front() {
+            let Some(socket) = conn.socket.as_mut() else {
+                conn.queue.push_front(frame);
+                return Err(TelemetryError::Connection("not connected".into()));
+            };
+            if let Err(e) = socket.send(frame.clone()) {
+                conn.queue.push_front(frame);
+                conn.socket = None;
+                conn.next_attempt = Instant::now();
+                return Err(TelemetryError::Connection(format!(
+                    "WebSocket send failed: {}",
+                    e
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TelemetrySink for WebSocketSink {
+    fn sink_name(&self) -> &'static str {
+        "websocket"
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn send(&self, topic: &str, payload: &[u8]) -> TelemetryResult<()> {
+        let frame = self.frame(topic, payload)?;
+        let mut conn = self.lock()?;
+        if conn.queue.len() >= self.config.max_queued && conn.socket.is_none() {
+            return Err(TelemetryError::Connection(format!(
+                "disconnected from {} and reconnect queue is full ({} messages)",
+                self.config.url, self.config.max_queued
+            )));
+        }
+        conn.queue.push_back(frame);
+        let delivered = self
+            .ensure_connected(&mut conn)
+            .and_then(|()| self.drain_queue(&mut conn));
+        match delivered {
+            Ok(()) => Ok(()),
+            Err(e) if conn.queue.len() <= self.config.max_queued => {
+                log::warn!("WebSocket: queued {} message(s): {}", conn.queue.len(), e);
+                Ok(())
+            }
+            Err(e) => {
+                conn.queue.pop_back();
+                Err(e)
+            }
+        }
+    }
+
+    fn flush(&self) -> TelemetryResult<()> {
+        let mut conn = self.lock()?;
+        if conn.queue.is_empty() {
+            return Ok(());
+        }
+        self.ensure_connected(&mut conn)?;
+        self.drain_queue(&mut conn)
+    }
+}
+
+impl ShutdownSink for WebSocketSink {
+    fn close(self) -> TelemetryResult<()> {
+        let flushed = self.flush();
+        let mut conn = self.lock()?;
+        if let Some(mut socket) = conn.socket.take() {
+            let _ = socket.close(None);
+            // Drive the closing handshake until the peer acknowledges.
+            while socket.read().is_ok() {}
+        }
+        flushed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    /// Accept one client and collect the frames it sends until it closes.
+    fn collecting_server() -> (String, JoinHandle<Vec<Message>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("ws://{}", listener.local_addr().expect("addr"));
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("accept");
+            let mut socket = tungstenite::accept(stream).expect("handshake");
+            let mut frames = Vec::new();
+            loop {
+                match socket.read() {
+                    Ok(Message::Close(_)) | Err(_) => break,
+                    Ok(frame) => frames.push(frame),
+                }
+            }
+            frames
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn text_frames_carry_topic_envelope() {
+        let (url, server) = collecting_server();
+        let sink = WebSocketSink::new(url);
+
+        sink.send("sensors/temp", br#"{"temp":21}"#).expect("send");
+        sink.send("logs/raw", b"not json").expect("send");
+        sink.close().expect("close");
+
+        let frames = server.join().expect("server");
+        assert_eq!(frames.len(), 2);
+        let first: serde_json::Value = match &frames[0] {
+            Message::Text(text) => serde_json::from_str(text).expect("json"),
+            other => panic!("expected text frame, got {:?}", other),
+        };
+        assert_eq!(
+            first,
+            serde_json::json!({ "topic": "sensors/temp", "payload": { "temp": 21 } })
+        );
+        let second: serde_json::Value = match &frames[1] {
+            Message::Text(text) => serde_json::from_str(text).expect("json"),
+            other => panic!("expected text frame, got {:?}", other),
+        };
+        assert_eq!(second["payload"], "not json");
+    }
+
+    #[test]
+    fn binary_frames_carry_raw_payload() {
+        let (url, server) = collecting_server();
+        let sink = WebSocketSink::with_config(WebSocketConfig::new(url).mode(FrameMode::Binary));
+
+        sink.send("blob", &[0, 1, 2, 255]).expect("send");
+        sink.close().expect("close");
+
+        let frames = server.join().expect("server");
+        assert_eq!(frames, vec![Message::Binary(vec![0, 1, 2, 255])]);
+    }
+
+    #[test]
+    fn queues_while_disconnected_and_rejects_overflow() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("ws://{}", listener.local_addr().expect("addr"));
+        drop(listener);
+
+        let sink = WebSocketSink::with_config(
+            WebSocketConfig::new(url)
+                .max_queued(2)
+                .backoff(Duration::from_secs(60), Duration::from_secs(60)),
+        );
+        sink.send("t", b"1").expect("queued");
+        sink.send("t", b"2").expect("queued");
+        assert_eq!(sink.queued(), 2);
+
+        let err = sink.send("t", b"3").expect_err("queue full");
+        assert!(matches!(err, TelemetryError::Connection(_)));
+        assert_eq!(sink.queued(), 2);
+        assert!(sink.flush().is_err());
+    }
+}
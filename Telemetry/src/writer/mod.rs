@@ -0,0 +1,156 @@
+//! `std::io::Write` adapter for building a payload in pieces.
+//!
+//! **Why?** Log chunks and reports are naturally produced with `write!` or
+//! by copying from a reader. `TopicWriter` collects those writes and sends
+//! the result as one payload, so callers need not assemble a `Vec` first.
+
+use crate::{TelemetryClient, TelemetryResult};
+use std::io;
+
+/// A writer that sends everything written to it as one payload on a topic.
+///
+/// Created by `TelemetryClient::payload_writer`. Bytes are held until
+/// `flush` (or `finish`), which sends them through the client like
+/// `send_binary` and starts a new payload. Anything still held when the
+/// writer is dropped is sent then; use `finish` to see the error instead of
+/// having it logged.
+pub struct TopicWriter<'a> {
+    client: &'a TelemetryClient,
+    topic: String,
+    buf: Vec<u8>,
+}
+
+impl<'a> TopicWriter<'a> {
+    pub(crate) fn new(client: &'a TelemetryClient, topic: String) -> Self {
+        Self {
+            client,
+            topic,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Full topic the payload is sent to, including the client's prefix.
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Bytes written since the last send.
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Send anything still held and consume the writer.
+    pub fn finish(mut self) -> TelemetryResult<()> {
+        self.send_pending()
+    }
+
+    fn send_pending(&mut self) -> TelemetryResult<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let payload = std::mem::take(&mut self.buf);
+        self.client
+            .send_raw("payload_writer", &self.topic, &payload)
+    }
+}
+
+impl io::Write for TopicWriter<'_> {
+    /// Fails without buffering anything if the payload would exceed the
+    /// client's size limit.
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.client
+            .check_payload_size(self.buf.len() + data.len())
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send_pending()
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+    }
+}
+
+impl Drop for TopicWriter<'_> {
+    fn drop(&mut self) {
+        if let Err(e) = self.send_pending() {
+            log::warn!("dropped payload for '{}': {}", self.topic, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{InMemorySink, TelemetryClient, TelemetryError, TelemetrySink};
+    use std::io::Write;
+    use std::sync::Arc;
+
+    fn client() -> (TelemetryClient, Arc<InMemorySink>) {
+        let sink = Arc::new(InMemorySink::new());
+        let client = TelemetryClient::new(Arc::clone(&sink) as Arc<dyn TelemetrySink>);
+        (client, sink)
+    }
+
+    #[test]
+    fn chunks_are_sent_as_one_payload_on_flush() {
+        let (client, sink) = client();
+        let mut writer = client.payload_writer("logs/app");
+        writer.write_all(b"line 1\n").expect("write");
+        writeln!(writer, "line {}", 2).expect("write");
+        writer.write_all(b"line 3\n").expect("write");
+        assert_eq!(writer.len(), 21);
+        assert!(sink.records.lock().expect("lock").is_empty());
+
+        writer.flush().expect("flush");
+        writer.write_all(b"next").expect("write");
+        drop(writer);
+
+        let records = sink.records.lock().expect("lock");
+        assert_eq!(
+            *records,
+            [
+                ("logs/app".to_string(), b"line 1\nline 2\nline 3\n".to_vec()),
+                ("logs/app".to_string(), b"next".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn finish_sends_with_client_prefix_and_empty_flush_sends_nothing() {
+        let (client, sink) = client();
+        let client = client.with_topic_prefix("edge/");
+        let mut writer = client.payload_writer("logs");
+        writer.flush().expect("empty flush");
+        assert_eq!(writer.topic(), "edge/logs");
+        writer.write_all(b"chunk").expect("write");
+        writer.finish().expect("finish");
+
+        let records = sink.records.lock().expect("lock");
+        assert_eq!(*records, [("edge/logs".to_string(), b"chunk".to_vec())]);
+        assert_eq!(client.metrics().messages_sent, 1);
+    }
+
+    #[test]
+    fn write_past_size_limit_fails_early() {
+        let sink = Arc::new(InMemorySink::new());
+        let client =
+            TelemetryClient::with_limits(Arc::clone(&sink) as Arc<dyn TelemetrySink>, Some(8));
+        let mut writer = client.payload_writer("t");
+        writer.write_all(b"12345").expect("within limit");
+
+        let err = writer.write_all(b"6789").expect_err("over limit");
+        let inner = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<TelemetryError>())
+            .expect("telemetry error");
+        assert!(inner.message().contains("exceeds limit"));
+
+        assert_eq!(writer.len(), 5);
+        writer.finish().expect("finish");
+        assert_eq!(sink.records.lock().expect("lock")[0].1, b"12345");
+    }
+}